@@ -0,0 +1,163 @@
+use crate::Image;
+use resvg::usvg::NodeExt;
+use resvg::{tiny_skia, usvg};
+
+/// Where lines sit relative to the grid of pixels, set with
+/// `Image::set_pixel_alignment`.
+///
+/// A line is centred on the points it is drawn between, so a line 1 unit
+/// wide down the whole-number coordinate x = 10 covers from 9.5 to 10.5:
+/// half of each of two columns of pixels. Rendered, that is two columns at
+/// half strength, rather than one crisp column.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PixelAlignment {
+    /// Draw everything exactly where it was asked for. This is the default.
+    #[default]
+    None,
+    /// Move shapes with strokes an odd number of pixels wide (1, 3, 5, ...)
+    /// half a pixel right and down when saving, so that horizontal and
+    /// vertical strokes along whole-number coordinates fill whole pixels.
+    HalfPixel,
+}
+
+impl Image {
+    /// Choose where lines sit relative to the grid of pixels (see
+    /// [`PixelAlignment`]).
+    ///
+    /// With `PixelAlignment::HalfPixel`, a horizontal or vertical line 1
+    /// unit wide along whole-number coordinates comes out as one solid row
+    /// or column of pixels, as in most raster drawing programs. This happens
+    /// when the image is saved or rendered (in PNG and SVG files alike), so
+    /// end points and everything else about the drawing stay in the
+    /// coordinates it was drawn with.
+    ///
+    /// The price is that those shapes are drawn half a pixel away from where
+    /// they were asked for, ends and all, so they no longer line up exactly
+    /// with anything that didn't move. A shape with both a fill and such a
+    /// stroke moves its fill too, so the fill's own edges are the ones that
+    /// land between pixels. Strokes an even or fractional number of pixels
+    /// wide don't move, and neither does anything else.
+    ///
+    /// ```rs
+    /// image.set_pixel_alignment(PixelAlignment::HalfPixel);
+    /// // A crisp column of pixels at x = 10.
+    /// image.draw_simple_line(10, 10, 180, 80, COLORS[7])?;
+    /// ```
+    pub fn set_pixel_alignment(&mut self, alignment: PixelAlignment) {
+        self.pixel_alignment = alignment;
+        self.mark_changed();
+    }
+
+    /// The alignment set by `set_pixel_alignment`.
+    pub fn pixel_alignment(&self) -> PixelAlignment {
+        self.pixel_alignment
+    }
+
+    /// Move the paths in `element` as the pixel alignment asks. The element
+    /// must already be in the tree being exported, so the transforms above
+    /// it are known.
+    pub(crate) fn align_to_pixels(&self, element: &usvg::Node) {
+        if self.pixel_alignment == PixelAlignment::None {
+            return;
+        }
+
+        let shift = tiny_skia::Transform::from_translate(0.5, 0.5);
+        for node in element.descendants() {
+            let transform = node.abs_transform();
+            let usvg::NodeKind::Path(ref mut path) = *node.borrow_mut() else {
+                continue;
+            };
+            let Some(ref stroke) = path.stroke else {
+                continue;
+            };
+            // How wide the stroke is in pixels, after any scaling.
+            let scale = (transform.sx * transform.sy - transform.kx * transform.ky)
+                .abs()
+                .sqrt();
+            let width = stroke.width.get() * scale;
+            if (width - width.round()).abs() > 1e-3 || width.round() % 2.0 != 1.0 {
+                continue;
+            }
+            let Some(inverse) = transform.invert() else {
+                continue;
+            };
+            // Shift in pixels, after everything else that moves the path.
+            path.transform = path
+                .transform
+                .pre_concat(inverse)
+                .pre_concat(shift)
+                .pre_concat(transform);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{StrokeStyle, COLORS};
+
+    /// How bright each of columns 6 to 14 is, halfway down a vertical line
+    /// `width` wide drawn at x = 10.
+    fn columns(alignment: PixelAlignment, width: f32) -> Vec<u8> {
+        let mut image = Image::new(20, 20);
+        image.set_pixel_alignment(alignment);
+        let style = StrokeStyle {
+            width,
+            ..StrokeStyle::default()
+        };
+        let end = image
+            .draw_styled_line(10, 2, 180, 16, COLORS[7], &style)
+            .unwrap();
+        assert_eq!(end, (10, 18), "end points don't move");
+        let pixmap = image.render().unwrap();
+        (6..15)
+            .map(|x| pixmap.pixel(x, 10).unwrap().red())
+            .collect()
+    }
+
+    #[test]
+    fn half_pixel_lines_fill_whole_columns() {
+        assert_eq!(
+            columns(PixelAlignment::HalfPixel, 1.0),
+            [0, 0, 0, 0, 255, 0, 0, 0, 0]
+        );
+        assert_eq!(
+            columns(PixelAlignment::HalfPixel, 3.0),
+            [0, 0, 0, 255, 255, 255, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn unaligned_lines_straddle_two_columns() {
+        let half = columns(PixelAlignment::None, 1.0);
+        assert_eq!(half[..3], [0, 0, 0]);
+        assert!((127..=128).contains(&half[3]) && (127..=128).contains(&half[4]));
+        assert_eq!(half[5..], [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn even_and_fractional_widths_stay_put() {
+        for width in [2.0, 1.5] {
+            assert_eq!(
+                columns(PixelAlignment::HalfPixel, width),
+                columns(PixelAlignment::None, width)
+            );
+        }
+    }
+
+    #[test]
+    fn the_drawing_itself_isnt_moved() {
+        let mut image = Image::new(20, 20);
+        image.set_pixel_alignment(PixelAlignment::HalfPixel);
+        assert_eq!(image.pixel_alignment(), PixelAlignment::HalfPixel);
+        image.draw_simple_line(10, 2, 180, 16, COLORS[7]).unwrap();
+        let first = image.render().unwrap();
+        assert_eq!(image.render().unwrap(), first, "rendering twice");
+
+        let node = image.element_node(image.last_element().unwrap()).unwrap();
+        let usvg::NodeKind::Path(ref path) = *node.borrow() else {
+            panic!("the line isn't a path");
+        };
+        assert_eq!(path.transform, tiny_skia::Transform::identity());
+    }
+}
@@ -0,0 +1,438 @@
+use crate::elements::node_bounds;
+use crate::recolor::recolor;
+use crate::{normalize_direction, BlendSpace, Color, Image};
+use resvg::usvg::NodeExt;
+use resvg::{tiny_skia, usvg};
+use std::rc::Rc;
+
+/// How many degrees of direction are counted together by
+/// `dominant_line_angle`.
+const ANGLE_BUCKET: i32 = 15;
+
+/// How many straight pieces each curve is split into to measure its length.
+const CURVE_STEPS: u32 = 16;
+
+/// A summary of what has been drawn on an image, from `Image::stats`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DrawStats {
+    /// The number of elements drawn (one per draw call that drew something).
+    pub element_count: usize,
+    /// The total length of every stroked line, in units. Curves are
+    /// measured by splitting them into short straight pieces, so their
+    /// lengths are very slightly too short.
+    pub ink_length: f32,
+    /// The area covered by everything drawn, as in `Image::content_bounds`.
+    pub bounds: Option<(i32, i32, i32, i32)>,
+    /// Every fill and stroke color used, in the order they first appear.
+    /// The background's color is not included.
+    pub colors: Vec<Color>,
+    /// A rough estimate of how much work rendering the image takes: the
+    /// total number of pixels covered by the bounds of each element, only
+    /// counting the parts inside the image. Overlapping elements are counted
+    /// once each, since each has to be drawn.
+    pub render_cost: u64,
+}
+
+impl Image {
+    /// Summarize everything drawn on the image in one call.
+    ///
+    /// ```rs
+    /// let mut image = Image::new(100, 100);
+    /// image.draw_simple_line(10, 10, 90, 50, COLORS[1])?;
+    /// image.draw_simple_line(10, 20, 90, 30, COLORS[4])?;
+    ///
+    /// let stats = image.stats();
+    /// assert_eq!(stats.element_count, 2);
+    /// assert_eq!(stats.ink_length, 80.0);
+    /// assert_eq!(stats.bounds, Some((10, 9, 60, 21)));
+    /// assert_eq!(stats.colors, vec![COLORS[1], COLORS[4]]);
+    /// // A 50x2 box and a 30x2 box.
+    /// assert_eq!(stats.render_cost, 160);
+    /// ```
+    pub fn stats(&self) -> DrawStats {
+        let mut ink_length = 0.0;
+        let mut colors = Vec::new();
+        let mut render_cost = 0;
+
+        for element in self.elements() {
+            for node in element.descendants() {
+                let usvg::NodeKind::Path(ref path) = *node.borrow() else {
+                    continue;
+                };
+
+                let paints = path.fill.iter().map(|fill| &fill.paint);
+                for paint in paints.chain(path.stroke.iter().map(|stroke| &stroke.paint)) {
+                    if let usvg::Paint::Color(color) = *paint {
+                        let color = Color::from_usvg(color);
+                        if !colors.contains(&color) {
+                            colors.push(color);
+                        }
+                    }
+                }
+
+                if path.stroke.is_some() {
+                    ink_length += path_length(&path.data, node.abs_transform());
+                }
+            }
+
+            let transform = self.coordinate_transform();
+            if let Some(bounds) = node_bounds(&element).and_then(|rect| rect.transform(transform)) {
+                let width =
+                    bounds.right().min(self.width as f32).ceil() - bounds.left().max(0.0).floor();
+                let height =
+                    bounds.bottom().min(self.height as f32).ceil() - bounds.top().max(0.0).floor();
+                render_cost += (width.max(0.0) * height.max(0.0)) as u64;
+            }
+        }
+
+        DrawStats {
+            element_count: self.elements.len(),
+            ink_length,
+            bounds: self.content_bounds(),
+            colors,
+            render_cost,
+        }
+    }
+
+    /// How much of the image shows `color`, in square units (pixels).
+    ///
+    /// Shapes' edges are anti-aliased, so a pixel on an edge is only partly
+    /// covered. Rather than counting whole pixels, each pixel counts for the
+    /// fraction of it that is covered by `color`, which gives a much closer
+    /// answer for curved and diagonal edges. Only what is visible counts:
+    /// anything hidden under other shapes is not included. The background
+    /// counts too, so measuring the background's color gives the area not
+    /// covered by anything else.
+    ///
+    /// Translucent shapes count for their opacity, and pictures made of
+    /// pixels (like the result of `morph`) never match.
+    ///
+    /// ```rs
+    /// let mut image = Image::new(100, 100);
+    /// image.draw_progress_arc(50, 50, 20, 40, 1.0, COLORS[4])?;
+    /// // A filled circle of radius 40.
+    /// let area = image.measure_area(COLORS[4]);
+    /// assert!((area - std::f32::consts::PI * 1600.0).abs() < 10.0);
+    /// ```
+    pub fn measure_area(&self, color: Color) -> f32 {
+        // Draw `color` in white and everything else in black, so the
+        // brightness of each pixel is how much of it is covered by `color`.
+        let mut image = self.clone();
+        image.blend_space = BlendSpace::Srgb;
+        let coverage = |other: Color| {
+            if other == color {
+                Color::white()
+            } else {
+                Color::black()
+            }
+        };
+        for node in std::iter::once(image.background()).chain(image.elements()) {
+            recolor(&node, &coverage);
+            hide_pictures(&node);
+        }
+
+        let Ok(pixmap) = image.render() else {
+            return 0.0;
+        };
+        pixmap
+            .pixels()
+            .iter()
+            .map(|pixel| f32::from(pixel.red()) / 255.0)
+            .sum()
+    }
+
+    /// The direction most lines on the image are drawn in, using the same
+    /// angles as `draw_simple_line` (0 is straight up, 90 is right, and so on).
+    ///
+    /// Every straight segment of every drawn line is counted, with directions
+    /// rounded to the nearest 15 degrees, and the most common direction is
+    /// returned. Ties go to the smallest angle. Filled shapes and curves are
+    /// ignored, and lines keep the direction they were drawn in, so a drawing
+    /// of mostly left-to-right lines gives 90, and right-to-left gives 270.
+    ///
+    /// Returns `None` if nothing on the image is a line.
+    ///
+    /// ```rs
+    /// image.draw_simple_line(10, 10, 90, 50, COLORS[1])?;
+    /// image.draw_simple_line(10, 20, 88, 50, COLORS[1])?;
+    /// image.draw_simple_line(10, 30, 0, 50, COLORS[1])?;
+    /// assert_eq!(image.dominant_line_angle(), Some(90));
+    /// ```
+    pub fn dominant_line_angle(&self) -> Option<i32> {
+        let mut counts = [0usize; (360 / ANGLE_BUCKET) as usize];
+
+        for element in self.elements() {
+            for node in element.descendants() {
+                let usvg::NodeKind::Path(ref path) = *node.borrow() else {
+                    continue;
+                };
+                if path.stroke.is_none() {
+                    continue;
+                }
+
+                let transform = node.abs_transform();
+                for (mut start, mut end) in line_segments(&path.data) {
+                    transform.map_point(&mut start);
+                    transform.map_point(&mut end);
+                    if let Some(angle) = segment_angle(start, end) {
+                        let bucket = normalize_direction(
+                            (angle / ANGLE_BUCKET as f32).round() as i32 * ANGLE_BUCKET,
+                        );
+                        counts[(bucket / ANGLE_BUCKET) as usize] += 1;
+                    }
+                }
+            }
+        }
+
+        let max = *counts.iter().max()?;
+        if max == 0 {
+            return None;
+        }
+        counts
+            .iter()
+            .position(|&count| count == max)
+            .map(|bucket| bucket as i32 * ANGLE_BUCKET)
+    }
+}
+
+/// Work out the same [`DrawStats`] as `Image::stats` from an SVG file saved
+/// by `save_svg`, such as a drawing saved earlier, so old drawings can be
+/// measured the same way as new ones.
+///
+/// The SVG is loaded with `Image::from_svg_str`, so every shape or group at
+/// the top of the drawing is one element, and bounds come out the same as
+/// from `stats`. A frame or watermark added when saving counts as an element
+/// (or two), since the SVG doesn't say which shapes are which. SVG files made
+/// by other programs can be measured too, though their element counts depend
+/// on how they are grouped.
+///
+/// Returns an error if the SVG can't be loaded, for the reasons given in
+/// `Image::from_svg_str`.
+///
+/// ```rs
+/// image.save_svg("drawing.svg")?;
+/// let stats = stats_from_svg(&std::fs::read_to_string("drawing.svg")?)?;
+/// assert_eq!(stats, image.stats());
+/// ```
+pub fn stats_from_svg(svg: &str) -> Result<DrawStats, String> {
+    Image::from_svg_str(svg).map(|image| image.stats())
+}
+
+/// Cover every picture inside `node` with a black rectangle.
+fn hide_pictures(node: &usvg::Node) {
+    let pictures: Vec<_> = node
+        .descendants()
+        .filter(|descendant| matches!(*descendant.borrow(), usvg::NodeKind::Image(_)))
+        .collect();
+
+    for picture in pictures {
+        let rect = match *picture.borrow() {
+            usvg::NodeKind::Image(ref image) => image.view_box.rect.to_rect(),
+            _ => continue,
+        };
+        let mut path = usvg::Path::new(Rc::new(tiny_skia::PathBuilder::from_rect(rect)));
+        path.transform = picture.borrow().transform();
+        path.fill = Some(usvg::Fill::default());
+        picture.insert_before(usvg::Node::new(usvg::NodeKind::Path(path)));
+        picture.detach();
+    }
+}
+
+/// The straight segments of a path, as (start, end) pairs.
+fn line_segments(path: &tiny_skia::Path) -> Vec<(tiny_skia::Point, tiny_skia::Point)> {
+    let mut segments = Vec::new();
+    let mut start = None;
+    let mut current = None;
+
+    for segment in path.segments() {
+        match segment {
+            tiny_skia::PathSegment::MoveTo(point) => {
+                start = Some(point);
+                current = Some(point);
+            }
+            tiny_skia::PathSegment::LineTo(point) => {
+                if let Some(from) = current {
+                    segments.push((from, point));
+                }
+                current = Some(point);
+            }
+            tiny_skia::PathSegment::QuadTo(_, point)
+            | tiny_skia::PathSegment::CubicTo(_, _, point) => current = Some(point),
+            tiny_skia::PathSegment::Close => {
+                if let (Some(from), Some(to)) = (current, start) {
+                    segments.push((from, to));
+                }
+                current = start;
+            }
+        }
+    }
+
+    segments
+}
+
+/// The direction from `start` to `end` in degrees, with 0 being straight up
+/// and increasing clockwise. Returns `None` if the points are the same.
+fn segment_angle(start: tiny_skia::Point, end: tiny_skia::Point) -> Option<f32> {
+    let (dx, dy) = (end.x - start.x, end.y - start.y);
+    if dx == 0.0 && dy == 0.0 {
+        return None;
+    }
+
+    // y points down the image, so "up" is negative y.
+    Some(dx.atan2(-dy).to_degrees())
+}
+
+/// The length of a path after applying `transform`.
+fn path_length(path: &tiny_skia::Path, transform: tiny_skia::Transform) -> f32 {
+    let point = |point: tiny_skia::Point| {
+        let mut point = point;
+        transform.map_point(&mut point);
+        point
+    };
+
+    let mut length = 0.0;
+    let mut start = tiny_skia::Point::zero();
+    let mut current = tiny_skia::Point::zero();
+    let mut add = |from: tiny_skia::Point, to: tiny_skia::Point| length += from.distance(to);
+
+    for segment in path.segments() {
+        match segment {
+            tiny_skia::PathSegment::MoveTo(to) => {
+                start = point(to);
+                current = start;
+            }
+            tiny_skia::PathSegment::LineTo(to) => {
+                let to = point(to);
+                add(current, to);
+                current = to;
+            }
+            tiny_skia::PathSegment::QuadTo(control, to) => {
+                let (control, to) = (point(control), point(to));
+                let from = current;
+                for step in 1..=CURVE_STEPS {
+                    let t = step as f32 / CURVE_STEPS as f32;
+                    let next = lerp(lerp(from, control, t), lerp(control, to, t), t);
+                    add(current, next);
+                    current = next;
+                }
+            }
+            tiny_skia::PathSegment::CubicTo(control1, control2, to) => {
+                let (control1, control2, to) = (point(control1), point(control2), point(to));
+                let from = current;
+                for step in 1..=CURVE_STEPS {
+                    let t = step as f32 / CURVE_STEPS as f32;
+                    let a = lerp(lerp(from, control1, t), lerp(control1, control2, t), t);
+                    let b = lerp(lerp(control1, control2, t), lerp(control2, to, t), t);
+                    let next = lerp(a, b, t);
+                    add(current, next);
+                    current = next;
+                }
+            }
+            tiny_skia::PathSegment::Close => {
+                add(current, start);
+                current = start;
+            }
+        }
+    }
+
+    length
+}
+
+fn lerp(a: tiny_skia::Point, b: tiny_skia::Point, t: f32) -> tiny_skia::Point {
+    tiny_skia::Point::from_xy(a.x + (b.x - a.x) * t, a.y + (b.y - a.y) * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Color, DrawStats, Image, COLORS};
+
+    #[test]
+    fn stats_sum_up_every_line() {
+        let mut image = Image::new(100, 100);
+        image.draw_simple_line(10, 10, 90, 50, COLORS[1]).unwrap();
+        image.draw_simple_line(10, 20, 90, 30, COLORS[4]).unwrap();
+
+        assert_eq!(
+            image.stats(),
+            DrawStats {
+                element_count: 2,
+                ink_length: 80.0,
+                bounds: Some((10, 9, 60, 21)),
+                colors: vec![COLORS[1], COLORS[4]],
+                // A 50x2 box and a 30x2 box.
+                render_cost: 160,
+            }
+        );
+    }
+
+    #[test]
+    fn curves_count_for_their_length_along_the_curve() {
+        let mut image = Image::new(100, 100);
+        image.draw_wave(10, 50, 20, 80, 1.0, COLORS[2]).unwrap();
+
+        // The length of one wavelength of 20 sin(2πx / 80), in small steps.
+        let steps = 10_000;
+        let wave = |i: i32| {
+            let x = 80.0 * i as f32 / steps as f32;
+            (x, 20.0 * (std::f32::consts::TAU * x / 80.0).sin())
+        };
+        let length: f32 = (0..steps)
+            .map(|i| {
+                let ((x0, y0), (x1, y1)) = (wave(i), wave(i + 1));
+                (x1 - x0).hypot(y1 - y0)
+            })
+            .sum();
+
+        let stats = image.stats();
+        assert!(length > 100.0);
+        assert!(
+            (stats.ink_length - length).abs() < length * 0.01,
+            "{} {length}",
+            stats.ink_length
+        );
+        assert_eq!(stats.colors, [COLORS[2]]);
+    }
+
+    #[test]
+    fn circles_measure_pi_r_squared() {
+        use std::f32::consts::PI;
+        let near = |area: f32, expected: f32| (area - expected).abs() < expected * 0.01;
+
+        let mut image = Image::new(100, 100);
+        // A filled circle of radius 40.
+        image
+            .draw_progress_arc(50, 50, 20, 40, 1.0, COLORS[4])
+            .unwrap();
+        let circle = image.measure_area(COLORS[4]);
+        assert!(near(circle, PI * 1600.0), "{circle}");
+        let background = image.measure_area(Color::black());
+        assert!(near(background, 10_000.0 - PI * 1600.0), "{background}");
+
+        // A ring on top hides part of the circle.
+        image.draw_ring(50, 50, 30, 20, COLORS[1]).unwrap();
+        let ring = image.measure_area(COLORS[1]);
+        assert!(near(ring, PI * 500.0), "{ring}");
+        let circle = image.measure_area(COLORS[4]);
+        assert!(near(circle, PI * 1100.0), "{circle}");
+
+        assert_eq!(image.measure_area(COLORS[2]), 0.0);
+    }
+
+    #[test]
+    fn mostly_horizontal_lines_point_sideways() {
+        let mut image = Image::new(100, 100);
+        assert_eq!(image.dominant_line_angle(), None);
+        image.draw_simple_line(10, 10, 90, 50, COLORS[1]).unwrap();
+        image.draw_simple_line(10, 20, 88, 50, COLORS[1]).unwrap();
+        image.draw_simple_line(10, 30, 0, 50, COLORS[1]).unwrap();
+        assert_eq!(image.dominant_line_angle(), Some(90));
+
+        let mut image = Image::new(100, 100);
+        for _ in 0..3 {
+            image.draw_simple_line(80, 10, 270, 50, COLORS[1]).unwrap();
+        }
+        image.draw_simple_line(80, 10, 359, 50, COLORS[1]).unwrap();
+        image.draw_simple_line(80, 10, 1, 50, COLORS[1]).unwrap();
+        assert_eq!(image.dominant_line_angle(), Some(270));
+    }
+}
@@ -0,0 +1,247 @@
+use crate::{f32_to_i32_saturating, i32_to_f32, Image, IntoColor, Rounding, StrokeStyle};
+use resvg::tiny_skia;
+
+/// The name of an anchor set with `Image::set_anchor`, for use wherever a
+/// point is expected (see [`IntoPoint`]).
+///
+/// ```rs
+/// image.set_anchor("roof_peak", 50, 10);
+/// image.draw_line_between(Named("roof_peak"), (10, 40), COLORS[1])?;
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Named<'a>(pub &'a str);
+
+/// Something that gives a point on an image: either an `(x, y)` tuple, or
+/// the [`Named`] anchor of a point set earlier with `Image::set_anchor`.
+pub trait IntoPoint {
+    /// The point on `image`, or an error naming an anchor it doesn't have.
+    fn into_point(self, image: &Image) -> Result<(i32, i32), String>;
+}
+
+impl IntoPoint for (i32, i32) {
+    fn into_point(self, _image: &Image) -> Result<(i32, i32), String> {
+        Ok(self)
+    }
+}
+
+impl IntoPoint for Named<'_> {
+    fn into_point(self, image: &Image) -> Result<(i32, i32), String> {
+        image
+            .anchor(self.0)
+            .ok_or_else(|| format!("There is no anchor named {:?}", self.0))
+    }
+}
+
+impl Image {
+    /// Remember the point (`x`, `y`) as `name`, so later drawing can refer to
+    /// it with `Named(name)` instead of passing the coordinates around.
+    /// Setting an anchor that already exists moves it.
+    ///
+    /// Anchors are in drawing coordinates, like the points lines are drawn
+    /// from, and move with the drawing when the canvas is resized. They are
+    /// kept by `to_scene_json` and `from_scene_json`.
+    ///
+    /// ```rs
+    /// let end = image.draw_simple_line(10, 90, 45, 60, COLORS[1])?;
+    /// image.set_anchor("roof_peak", end.0, end.1);
+    /// image.draw_simple_line_from(Named("roof_peak"), 135, 60, COLORS[1])?;
+    /// ```
+    pub fn set_anchor(&mut self, name: &str, x: i32, y: i32) {
+        self.anchors.insert(name.to_string(), (x, y));
+        self.mark_changed();
+    }
+
+    /// The point set as `name` by `set_anchor`, if there is one.
+    pub fn anchor(&self, name: &str) -> Option<(i32, i32)> {
+        self.anchors.get(name).copied()
+    }
+
+    /// Like `draw_simple_line`, but starting from any point, including a
+    /// [`Named`] anchor.
+    ///
+    /// Returns an error if the start is an anchor that hasn't been set, as
+    /// well as for anything `draw_simple_line` returns an error for.
+    pub fn draw_simple_line_from(
+        &mut self,
+        start: impl IntoPoint,
+        direction: i32,
+        length: i32,
+        color: impl IntoColor,
+    ) -> Result<(i32, i32), String> {
+        let (x, y) = start.into_point(self)?;
+        self.draw_simple_line(x, y, direction, length, color)
+    }
+
+    /// Like `draw_styled_line`, but starting from any point, including a
+    /// [`Named`] anchor.
+    pub fn draw_styled_line_from(
+        &mut self,
+        start: impl IntoPoint,
+        direction: i32,
+        length: i32,
+        color: impl IntoColor,
+        style: &StrokeStyle,
+    ) -> Result<(i32, i32), String> {
+        let (x, y) = start.into_point(self)?;
+        self.draw_styled_line(x, y, direction, length, color, style)
+    }
+
+    /// Draw a straight line from one point to another, either of which can
+    /// be a [`Named`] anchor.
+    ///
+    /// Returns an error naming the first anchor that hasn't been set.
+    ///
+    /// ```rs
+    /// image.set_anchor("left_eave", 10, 40);
+    /// image.set_anchor("roof_peak", 50, 10);
+    /// image.draw_line_between(Named("left_eave"), Named("roof_peak"), COLORS[1])?;
+    /// ```
+    pub fn draw_line_between(
+        &mut self,
+        from: impl IntoPoint,
+        to: impl IntoPoint,
+        color: impl IntoColor,
+    ) -> Result<(), String> {
+        let from = from.into_point(self)?;
+        let to = to.into_point(self)?;
+        self.draw_styled_polyline(&[from, to], color, &StrokeStyle::default())
+    }
+
+    /// Move every anchor by `transform`, to the nearest whole unit.
+    pub(crate) fn move_anchors(&mut self, transform: tiny_skia::Transform) {
+        for (x, y) in self.anchors.values_mut() {
+            let mut point = tiny_skia::Point::from_xy(i32_to_f32(*x), i32_to_f32(*y));
+            transform.map_point(&mut point);
+            *x = f32_to_i32_saturating(point.x, Rounding::Nearest);
+            *y = f32_to_i32_saturating(point.y, Rounding::Nearest);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::svg::SvgOptions;
+    use crate::{Anchor, LineStyle, COLORS};
+
+    /// The start and end of each straight piece drawn on `image`.
+    fn segments(image: &Image) -> Vec<((f32, f32), (f32, f32))> {
+        image
+            .to_segments(0.1)
+            .iter()
+            .map(|segment| (segment.start, segment.end))
+            .collect()
+    }
+
+    #[test]
+    fn anchors_can_be_set_and_moved() {
+        let mut image = Image::new(100, 100);
+        assert_eq!(image.anchor("peak"), None);
+        image.set_anchor("peak", 50, 10);
+        assert_eq!(image.anchor("peak"), Some((50, 10)));
+        image.set_anchor("peak", 40, 20);
+        assert_eq!(image.anchor("peak"), Some((40, 20)));
+        assert_eq!(Named("peak").into_point(&image), Ok((40, 20)));
+        assert_eq!((3, 4).into_point(&image), Ok((3, 4)));
+    }
+
+    #[test]
+    fn lines_from_anchors_match_lines_from_points() {
+        let dashed = StrokeStyle {
+            width: 3.0,
+            style: LineStyle::Dashed,
+            ..StrokeStyle::default()
+        };
+        let mut by_point = Image::new(100, 100);
+        by_point
+            .draw_simple_line(50, 10, 135, 40, COLORS[1])
+            .unwrap();
+        by_point
+            .draw_styled_line(10, 40, 90, 60, COLORS[4], &dashed)
+            .unwrap();
+
+        let mut by_name = Image::new(100, 100);
+        by_name.set_anchor("peak", 50, 10);
+        by_name.set_anchor("eave", 10, 40);
+        let end = by_name
+            .draw_simple_line_from(Named("peak"), 135, 40, COLORS[1])
+            .unwrap();
+        assert_eq!(end, (78, 38));
+        let end = by_name
+            .draw_styled_line_from(Named("eave"), 90, 60, COLORS[4], &dashed)
+            .unwrap();
+        assert_eq!(end, (70, 40));
+
+        assert_eq!(
+            by_name.svg_string(&SvgOptions::default()),
+            by_point.svg_string(&SvgOptions::default())
+        );
+    }
+
+    #[test]
+    fn lines_between_anchors_and_points() {
+        let mut image = Image::new(100, 100);
+        image.set_anchor("eave", 10, 40);
+        image.set_anchor("peak", 50, 10);
+        image
+            .draw_line_between(Named("eave"), Named("peak"), COLORS[1])
+            .unwrap();
+        image
+            .draw_line_between(Named("peak"), (90, 40), COLORS[1])
+            .unwrap();
+        image
+            .draw_line_between((90, 40), Named("eave"), COLORS[1])
+            .unwrap();
+        assert_eq!(
+            segments(&image),
+            [
+                ((10.0, 40.0), (50.0, 10.0)),
+                ((50.0, 10.0), (90.0, 40.0)),
+                ((90.0, 40.0), (10.0, 40.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_anchors_are_errors_and_draw_nothing() {
+        let mut image = Image::new(100, 100);
+        image.set_anchor("eave", 10, 40);
+        let error = image
+            .draw_simple_line_from(Named("peak"), 90, 10, COLORS[1])
+            .unwrap_err();
+        assert_eq!(error, r#"There is no anchor named "peak""#);
+        assert!(image
+            .draw_styled_line_from(Named("peak"), 90, 10, COLORS[1], &StrokeStyle::default())
+            .is_err());
+        // The first missing anchor is the one named.
+        let error = image
+            .draw_line_between(Named("ridge"), Named("peak"), COLORS[1])
+            .unwrap_err();
+        assert_eq!(error, r#"There is no anchor named "ridge""#);
+        let error = image
+            .draw_line_between(Named("eave"), Named("peak"), COLORS[1])
+            .unwrap_err();
+        assert_eq!(error, r#"There is no anchor named "peak""#);
+        assert!(image.element_ids().is_empty());
+    }
+
+    #[test]
+    fn anchors_move_with_the_canvas() {
+        let mut image = Image::new(100, 100);
+        image.set_anchor("peak", 50, 10);
+        image.grow_canvas(120, 140, Anchor::Center).unwrap();
+        assert_eq!(image.anchor("peak"), Some((60, 30)));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn anchors_are_kept_by_scenes() {
+        let mut image = Image::new(100, 100);
+        image.set_anchor("peak", 50, 10);
+        image.set_anchor("eave", -5, 400);
+        let json = image.to_scene_json();
+        let loaded = Image::from_scene_json(&json).unwrap();
+        assert_eq!(loaded.anchor("peak"), Some((50, 10)));
+        assert_eq!(loaded.anchor("eave"), Some((-5, 400)));
+    }
+}
@@ -0,0 +1,109 @@
+use crate::Image;
+use std::fmt::Write;
+
+impl Image {
+    /// The image as text that shows it in a terminal, `cols` characters wide,
+    /// for a quick look without opening a file.
+    ///
+    /// Each character is a block of color, the average of the part of the
+    /// image it covers, set with a 24-bit ANSI escape code, so the terminal
+    /// needs to support "truecolor" (most modern ones do). Characters in a
+    /// terminal are about twice as tall as they are wide, so there are about
+    /// half as many rows as the image's proportions would suggest. `cols` is
+    /// at most the image's width, so each character covers at least one
+    /// pixel, and there is always at least one row.
+    ///
+    /// Each line ends by resetting the colors, followed by a newline. Returns
+    /// an empty string if `cols` is 0.
+    ///
+    /// Returns an error if the image can't be rendered (see `rows`).
+    ///
+    /// ```rs
+    /// let mut image = Image::new(100, 100);
+    /// image.draw_simple_line(10, 10, 90, 50, COLORS[1])?;
+    /// print!("{}", image.to_ansi(40)?);
+    /// ```
+    pub fn to_ansi(&self, cols: u32) -> Result<String, String> {
+        let (width, height) = (u64::from(self.width), u64::from(self.height));
+        let cols = u64::from(cols).min(width);
+        if cols == 0 {
+            return Ok(String::new());
+        }
+        let rows = ((cols * height) as f64 / width as f64 / 2.0).round() as u64;
+        let rows = rows.clamp(1, height);
+
+        self.check_not_dry_run()?;
+
+        let mut output = String::new();
+        // The total red, green and blue of the pixels in each character of
+        // the current row, and how many pixels that is.
+        let mut sums = vec![[0u64; 4]; cols as usize];
+        let mut rendered = 0;
+        for (y, pixels) in (0..height).zip(self.rows()) {
+            rendered += 1;
+            for (x, color) in (0..width).zip(pixels) {
+                let sum = &mut sums[(x * cols / width) as usize];
+                sum[0] += u64::from(color.red);
+                sum[1] += u64::from(color.green);
+                sum[2] += u64::from(color.blue);
+                sum[3] += 1;
+            }
+
+            // Write out a row of characters once all its pixels are added up.
+            if (y + 1) * rows / height == y * rows / height && y + 1 < height {
+                continue;
+            }
+            for sum in &mut sums {
+                let count = sum[3].max(1);
+                let [red, green, blue] = [sum[0] / count, sum[1] / count, sum[2] / count];
+                let _ = write!(output, "\x1b[48;2;{red};{green};{blue}m ");
+                *sum = [0; 4];
+            }
+            output.push_str("\x1b[0m\n");
+        }
+        if rendered < height {
+            return Err("There isn't enough memory to render the image".into());
+        }
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Color, Image, StrokeStyle};
+
+    #[test]
+    fn each_character_is_the_average_color_under_it() {
+        let mut image = Image::new(4, 4);
+        let style = StrokeStyle {
+            width: 2.0,
+            ..StrokeStyle::default()
+        };
+        // The left half white, and a quarter of the right half white too.
+        image
+            .draw_styled_line(1, 0, 180, 4, Color::white(), &style)
+            .unwrap();
+        image
+            .draw_styled_line(2, 3, 90, 2, Color::white(), &StrokeStyle::default())
+            .unwrap();
+
+        // Two columns of two pixels, and a single row, as characters are
+        // twice as tall as they are wide.
+        assert_eq!(
+            image.to_ansi(2).unwrap(),
+            "\x1b[48;2;255;255;255m \x1b[48;2;63;63;63m \x1b[0m\n"
+        );
+        assert_eq!(image.to_ansi(0).unwrap(), "");
+    }
+
+    #[test]
+    fn there_are_never_more_characters_than_pixels() {
+        let image = Image::new(20, 10);
+        let ansi = image.to_ansi(100).unwrap();
+        let row = format!("{}\x1b[0m\n", "\x1b[48;2;0;0;0m ".repeat(20));
+        assert_eq!(ansi, row.repeat(5));
+
+        let image = Image::new(30, 1);
+        assert_eq!(image.to_ansi(3).unwrap().lines().count(), 1);
+    }
+}
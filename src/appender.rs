@@ -0,0 +1,262 @@
+use crate::{ElementId, Image, Renamed, SvgOptions};
+use std::fs::File;
+use std::io::{Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// The end of every SVG file written by `unsvg`.
+const CLOSING_TAG: &str = "</svg>\n";
+
+/// What the SVG writer puts before the elements when there are no clips,
+/// masks or other definitions.
+const EMPTY_DEFS: &str = "    <defs/>\n";
+
+/// Writes an SVG file a few elements at a time, for drawings that keep
+/// growing (like a long-running simulation), without rewriting the whole
+/// file each time.
+///
+/// `SvgAppender::create` writes an empty image, and each call to `append`
+/// adds whatever has been drawn on an [`Image`] since the last call. Only the
+/// new elements are written, just before the closing `</svg>` tag.
+///
+/// The file is a complete SVG after every call, so it can be opened at any
+/// time. Each append replaces the old closing tag with the new elements and a
+/// new closing tag in a single write, so if the program is stopped partway
+/// through, the file is either as it was or fully updated. (If the write
+/// itself fails, say because the disk is full, the new elements are cut off
+/// again, if possible.) In the unlikely event the file ends up without its
+/// closing tag, adding `</svg>` to the end fixes it.
+///
+/// For drawings without clips or masks, the finished file is exactly what
+/// `save_svg` would have written, without the frame or watermark (which are
+/// drawn on top of everything, so can't be appended to). Elements removed
+/// after they were appended stay in the file. Anything an element uses, like
+/// a clip, is written with each `append` that needs it, under an id of its
+/// own, and if a mask is set, each `append`'s elements are masked separately.
+/// The file then draws the same as the one `save_svg` would have written.
+///
+/// ```rs
+/// let mut image = Image::new(100, 100);
+/// let mut appender = SvgAppender::create("simulation.svg", 100, 100)?;
+/// for step in 0..1000 {
+///     image.draw_simple_line(50, 50, step, 40, COLORS[1])?;
+///     if step % 100 == 0 {
+///         appender.append(&image)?;
+///     }
+/// }
+/// appender.append(&image)?;
+/// appender.finish()?;
+/// ```
+pub struct SvgAppender {
+    file: File,
+    width: u32,
+    height: u32,
+    /// Where the closing tag starts in the file.
+    closing_tag_at: u64,
+    /// The newest element already written.
+    last_appended: Option<ElementId>,
+    /// An empty image the size of the file, which hands out the ids of the
+    /// clips and masks written, so that no two appends use the same id.
+    defs: Image,
+}
+
+impl std::fmt::Debug for SvgAppender {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SvgAppender")
+            .field("file", &self.file)
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .field("closing_tag_at", &self.closing_tag_at)
+            .field("last_appended", &self.last_appended)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SvgAppender {
+    /// Create (or replace) the file at `path` with an empty `width` by
+    /// `height` image, like `Image::new(width, height)`, ready for appending.
+    ///
+    /// Returns an error if the image would be too big (see `Image::try_new`),
+    /// or if the file can't be written.
+    pub fn create<P: AsRef<Path>>(path: P, width: u32, height: u32) -> Result<SvgAppender, String> {
+        let defs = Image::try_new(width, height)?;
+        let svg = defs.svg_string(&SvgOptions::default());
+        let header = svg
+            .strip_suffix(CLOSING_TAG)
+            .ok_or("An SVG file should end with a closing tag")?;
+
+        let mut file = File::create(path).map_err(|e| e.to_string())?;
+        file.write_all(svg.as_bytes()).map_err(|e| e.to_string())?;
+
+        Ok(SvgAppender {
+            file,
+            width,
+            height,
+            closing_tag_at: header.len() as u64,
+            last_appended: None,
+            defs,
+        })
+    }
+
+    /// Write everything drawn on `image` since the last call (or, the first
+    /// time, everything drawn on it) to the end of the file.
+    ///
+    /// Only elements newer than the last one appended are written, so this
+    /// should always be given the same image (or a clone of it) as it is
+    /// drawn on.
+    ///
+    /// Returns an error if `image` isn't the size the file was created with,
+    /// or if the file can't be written.
+    pub fn append(&mut self, image: &Image) -> Result<(), String> {
+        image.check_not_dry_run()?;
+        if image.get_dimensions() != (self.width, self.height) {
+            let (width, height) = image.get_dimensions();
+            return Err(format!(
+                "The image is {width}x{height}, but the file is {}x{}",
+                self.width, self.height
+            ));
+        }
+
+        let new: Vec<_> = image
+            .elements
+            .iter()
+            .filter(|&&(id, _)| Some(id) > self.last_appended)
+            .collect();
+        let Some(&&(newest, _)) = new.last() else {
+            return Ok(());
+        };
+
+        let mut tree = image.export_tree_of(None, new.iter().map(|(_, node)| node.clone()));
+        image.convert_text(&mut tree);
+        // Earlier appends may have written the same clips and masks already.
+        self.defs.rename_defs(&tree.root, &mut Renamed::default());
+        let svg = image.write_svg(&tree, &SvgOptions::default());
+
+        // Keep just the elements (and anything they use), which come between
+        // the opening `<svg ...>` tag and the closing tag.
+        let start = svg
+            .find("<svg")
+            .and_then(|start| svg[start..].find(">\n").map(|end| start + end + 2))
+            .ok_or("An SVG file should start with an <svg> tag")?;
+        let end = svg
+            .rfind(CLOSING_TAG)
+            .ok_or("An SVG file should end with a closing tag")?;
+        let content = svg[start..end]
+            .strip_prefix(EMPTY_DEFS)
+            .unwrap_or(&svg[start..end]);
+
+        let write = |file: &mut File, bytes: &[u8]| -> std::io::Result<()> {
+            file.seek(SeekFrom::Start(self.closing_tag_at))?;
+            file.write_all(bytes)
+        };
+        if let Err(error) = write(&mut self.file, format!("{content}{CLOSING_TAG}").as_bytes()) {
+            // Cut off whatever part of the new elements was written, so the
+            // file is complete again.
+            let restored = self
+                .file
+                .set_len(self.closing_tag_at)
+                .and_then(|()| write(&mut self.file, CLOSING_TAG.as_bytes()));
+            return Err(match restored {
+                Ok(()) => format!("Could not append to the file: {error}"),
+                Err(_) => format!(
+                    "Could not append to the file, which now needs </svg> adding to its end: {error}"
+                ),
+            });
+        }
+
+        self.closing_tag_at += content.len() as u64;
+        self.last_appended = Some(newest);
+        Ok(())
+    }
+
+    /// Finish writing the file, making sure everything written has reached
+    /// the disk.
+    pub fn finish(self) -> Result<(), String> {
+        self.file.sync_all().map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{render_tree, snapshot::parse_svg, MaskFit, StrokeStyle, COLORS, MAX_DIMENSION};
+
+    /// Draw `batches` of lines on `image`, appending each batch to a file,
+    /// and return what ends up in the file.
+    fn append_in_batches(name: &str, image: &mut Image, batches: i32) -> String {
+        let path = std::env::temp_dir().join(format!("unsvg-appender-{name}-test.svg"));
+        let (width, height) = image.get_dimensions();
+        let mut appender = SvgAppender::create(&path, width, height).unwrap();
+        for batch in 0..batches {
+            for line in 0..3 {
+                let direction = batch * 40 + line * 10;
+                image
+                    .draw_simple_line(50, 50, direction, 45, COLORS[1 + line as usize])
+                    .unwrap();
+            }
+            appender.append(image).unwrap();
+            // Appending with nothing new drawn writes nothing.
+            appender.append(image).unwrap();
+        }
+        appender.finish().unwrap();
+        let svg = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        svg
+    }
+
+    #[test]
+    fn batches_append_to_what_save_svg_writes() {
+        let mut image = Image::new(100, 100);
+        let svg = append_in_batches("plain", &mut image, 5);
+        assert_eq!(svg, image.svg_string(&SvgOptions::default()));
+    }
+
+    #[test]
+    fn files_too_big_for_an_image_are_an_error() {
+        let path = std::env::temp_dir().join("unsvg-appender-too-big-test.svg");
+        assert!(SvgAppender::create(&path, MAX_DIMENSION + 1, 100).is_err());
+        assert!(SvgAppender::create(&path, 100, 0).is_err());
+        assert!(!path.exists());
+    }
+
+    /// How many ids `svg` defines, and how many of them are different.
+    fn count_ids(svg: &str) -> (usize, usize) {
+        let mut ids: Vec<&str> = svg
+            .split(" id=\"")
+            .skip(1)
+            .map(|rest| &rest[..rest.find('"').unwrap()])
+            .collect();
+        let count = ids.len();
+        ids.sort_unstable();
+        ids.dedup();
+        (count, ids.len())
+    }
+
+    #[test]
+    fn batches_give_their_clips_ids_of_their_own() {
+        let mut image = Image::new(100, 100);
+        image
+            .push_clip_polygon(&[(10, 10), (90, 10), (90, 90), (10, 90)])
+            .unwrap();
+        let svg = append_in_batches("clips", &mut image, 4);
+        assert_eq!(count_ids(&svg), (4, 4), "{svg}");
+
+        let appended = render_tree(&parse_svg(&svg).unwrap()).unwrap();
+        assert!(appended == image.render().unwrap());
+    }
+
+    #[test]
+    fn batches_give_their_masks_ids_of_their_own() {
+        let mut mask = Image::new(100, 100);
+        let band = StrokeStyle {
+            width: 60.0,
+            ..StrokeStyle::default()
+        };
+        mask.draw_styled_line(0, 50, 90, 100, COLORS[15], &band)
+            .unwrap();
+        let mut image = Image::new(100, 100);
+        image.set_mask(&mask, MaskFit::Error).unwrap();
+        let svg = append_in_batches("masks", &mut image, 4);
+        assert_eq!(count_ids(&svg), (4, 4), "{svg}");
+        assert!(parse_svg(&svg).is_ok());
+    }
+}
@@ -0,0 +1,184 @@
+use crate::{render_tree, Image};
+use resvg::{tiny_skia, usvg};
+
+/// The color space that translucent shapes are blended in when rendering PNGs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BlendSpace {
+    /// Blend the stored sRGB values directly. This is what almost every SVG
+    /// renderer (including web browsers) does, and is the default.
+    #[default]
+    Srgb,
+    /// Blend in linear light, which is how light physically mixes. Overlapping
+    /// translucent shapes come out brighter than with `Srgb`, and without the
+    /// darkened band that appears where two bright colors fade into each other.
+    Linear,
+}
+
+impl Image {
+    /// Choose the color space that translucent shapes are blended in.
+    ///
+    /// This only affects PNG output. SVG files have no reliable way to ask
+    /// for linear blending, so SVGs are always displayed with sRGB blending.
+    ///
+    /// With `BlendSpace::Linear`, each draw call is rendered separately and
+    /// then blended onto the image, which makes rendering slower for images
+    /// with many draw calls.
+    ///
+    /// ```rs
+    /// let mut image = Image::new(100, 100);
+    /// image.set_blend_space(BlendSpace::Linear);
+    /// ```
+    pub fn set_blend_space(&mut self, space: BlendSpace) {
+        self.blend_space = space;
+        self.mark_changed();
+    }
+
+    pub(crate) fn render_linear(&self) -> Result<tiny_skia::Pixmap, String> {
+        let mut layers = self.linear_layers().into_iter();
+        let background = layers.next().ok_or("There is nothing to render")?;
+        render_layers_linear(&background, layers)
+    }
+
+    /// The background, each element, and then the frame and watermark (if
+    /// there are any), as separate trees for blending together in linear light.
+    pub(crate) fn linear_layers(&self) -> Vec<usvg::Tree> {
+        let mut layers: Vec<usvg::Tree> =
+            std::iter::once(self.export_tree_of(Some(self.background()), std::iter::empty()))
+                .chain(
+                    self.elements()
+                        .map(|element| self.export_tree_of(None, std::iter::once(element))),
+                )
+                .collect();
+        for layer in &mut layers {
+            self.convert_text(layer);
+        }
+
+        let mut overlays = self.export_tree_of(None, std::iter::empty());
+        self.finish_export(&mut overlays);
+        if overlays.root.has_children() {
+            layers.push(overlays);
+        }
+        layers
+    }
+}
+
+/// Render a background, then blend each layer onto it in linear light.
+pub(crate) fn render_layers_linear(
+    background: &usvg::Tree,
+    layers: impl Iterator<Item = usvg::Tree>,
+) -> Result<tiny_skia::Pixmap, String> {
+    let mut pixmap = render_tree(background)?;
+    let layers: Vec<_> = layers.map(|tree| resvg::Tree::from_usvg(&tree)).collect();
+    blend_layers_linear(&mut pixmap, tiny_skia::Transform::default(), &layers);
+    Ok(pixmap)
+}
+
+/// Render each layer with `transform`, and blend it onto `pixmap` in linear light.
+pub(crate) fn blend_layers_linear(
+    pixmap: &mut tiny_skia::Pixmap,
+    transform: tiny_skia::Transform,
+    layers: &[resvg::Tree],
+) {
+    let to_linear: Vec<f32> = (0..=255u8)
+        .map(|channel| srgb_to_linear(f32::from(channel) / 255.0))
+        .collect();
+
+    let mut layer = pixmap.clone();
+    for tree in layers {
+        layer.fill(tiny_skia::Color::TRANSPARENT);
+        tree.render(transform, &mut layer.as_mut());
+
+        for (dst, src) in pixmap
+            .data_mut()
+            .chunks_exact_mut(4)
+            .zip(layer.data().chunks_exact(4))
+        {
+            blend_linear(dst, src, &to_linear);
+        }
+    }
+}
+
+/// Blend a premultiplied source pixel over a premultiplied destination pixel,
+/// in linear light.
+fn blend_linear(dst: &mut [u8], src: &[u8], to_linear: &[f32]) {
+    let src_alpha = src[3];
+    if src_alpha == 0 {
+        return;
+    }
+
+    let src_a = f32::from(src_alpha) / 255.0;
+    let dst_a = f32::from(dst[3]) / 255.0;
+    let out_a = src_a + dst_a * (1.0 - src_a);
+
+    // Look up the linear value of a premultiplied channel.
+    let linear = |channel: u8, alpha: u8| {
+        if alpha == 0 {
+            0.0
+        } else {
+            let straight = (u32::from(channel) * 255 + u32::from(alpha) / 2) / u32::from(alpha);
+            to_linear[straight.min(255) as usize]
+        }
+    };
+
+    for i in 0..3 {
+        let premultiplied =
+            linear(src[i], src_alpha) * src_a + linear(dst[i], dst[3]) * dst_a * (1.0 - src_a);
+        let straight = linear_to_srgb(premultiplied / out_a);
+        dst[i] = (straight * out_a * 255.0).round().clamp(0.0, 255.0) as u8;
+    }
+    dst[3] = (out_a * 255.0).round().clamp(0.0, 255.0) as u8;
+}
+
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BlendSpace, Image, Rgba, StrokeStyle};
+
+    /// The color where half-transparent red and blue overlap, on black.
+    fn overlap(space: BlendSpace) -> (u8, u8, u8) {
+        let mut image = Image::new(20, 20);
+        image.set_blend_space(space);
+        let style = StrokeStyle {
+            width: 10.0,
+            ..StrokeStyle::default()
+        };
+        for color in [
+            Rgba::from_straight(255, 0, 0, 128),
+            Rgba::from_straight(0, 0, 255, 128),
+        ] {
+            image
+                .draw_styled_line(10, 0, 180, 20, color, &style)
+                .unwrap();
+        }
+        let pixel = image.render().unwrap().pixel(10, 10).unwrap();
+        (pixel.red(), pixel.green(), pixel.blue())
+    }
+
+    #[test]
+    fn linear_blending_is_lighter_than_srgb() {
+        let near = |(r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)| {
+            r1.abs_diff(r2) <= 2 && g1.abs_diff(g2) <= 2 && b1.abs_diff(b2) <= 2
+        };
+        // Each layer lets half of what is under it through: sRGB halves
+        // the stored values, while linear light halves the light itself.
+        let srgb = overlap(BlendSpace::Srgb);
+        assert!(near(srgb, (64, 0, 128)), "{srgb:?}");
+        let linear = overlap(BlendSpace::Linear);
+        assert!(near(linear, (137, 0, 188)), "{linear:?}");
+    }
+}
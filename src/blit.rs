@@ -0,0 +1,171 @@
+use crate::Image;
+use resvg::tiny_skia;
+
+impl Image {
+    /// Draw the image into an existing RGBA buffer, such as a game's or GUI's
+    /// framebuffer, with its top-left corner at (`dst_x`, `dst_y`) in the
+    /// buffer. This avoids saving a PNG just to load it again.
+    ///
+    /// `buffer` holds `buf_width` by `buf_height` pixels, row by row from the
+    /// top left, with 4 bytes (red, green, blue, alpha) per pixel. Its colors
+    /// are not premultiplied by alpha. The image is blended over what is
+    /// already in the buffer, so anything transparent in the image lets the
+    /// buffer show through, and any part of the image that falls outside the
+    /// buffer is cut off. The offset can be negative, to show only the
+    /// bottom or right of the image.
+    ///
+    /// The image is rendered as by `save_png` (including its blend space),
+    /// and then blended onto the buffer in sRGB.
+    ///
+    /// Returns an error if `buffer` is not exactly `buf_width` x `buf_height`
+    /// pixels, or for a dry-run image.
+    ///
+    /// ```rs
+    /// let mut framebuffer = vec![0; 640 * 480 * 4];
+    /// image.blit_into(&mut framebuffer, 640, 480, 20, 20)?;
+    /// ```
+    pub fn blit_into(
+        &self,
+        buffer: &mut [u8],
+        buf_width: u32,
+        buf_height: u32,
+        dst_x: i32,
+        dst_y: i32,
+    ) -> Result<(), String> {
+        let expected = u64::from(buf_width) * u64::from(buf_height) * 4;
+        if buffer.len() as u64 != expected {
+            return Err(format!(
+                "A {buf_width}x{buf_height} buffer needs {expected} bytes, but it has {}",
+                buffer.len()
+            ));
+        }
+        let pixmap = self.render()?;
+
+        // The rows and columns of the image that land inside the buffer.
+        let overlap = |offset: i32, size: u32, buf_size: u32| {
+            let first = (-i64::from(offset)).clamp(0, i64::from(size));
+            let end = (i64::from(buf_size) - i64::from(offset)).clamp(first, i64::from(size));
+            first as usize..end as usize
+        };
+        let columns = overlap(dst_x, self.width, buf_width);
+        let rows = overlap(dst_y, self.height, buf_height);
+
+        for y in rows {
+            let buf_y = (y as i64 + i64::from(dst_y)) as usize;
+            for x in columns.clone() {
+                let buf_x = (x as i64 + i64::from(dst_x)) as usize;
+                let index = (buf_y * buf_width as usize + buf_x) * 4;
+                let dst = &mut buffer[index..index + 4];
+                let src = pixmap.pixels()[y * self.width as usize + x];
+                dst.copy_from_slice(&source_over(src, [dst[0], dst[1], dst[2], dst[3]]));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Blend a premultiplied source pixel over a straight (not premultiplied)
+/// destination pixel, giving a straight pixel.
+fn source_over(src: tiny_skia::PremultipliedColorU8, dst: [u8; 4]) -> [u8; 4] {
+    let dst = tiny_skia::ColorU8::from_rgba(dst[0], dst[1], dst[2], dst[3]).premultiply();
+    let keep = 255 - u16::from(src.alpha());
+    let blend = |s: u8, d: u8| s + ((u16::from(d) * keep + 127) / 255) as u8;
+    let out = tiny_skia::PremultipliedColorU8::from_rgba(
+        blend(src.red(), dst.red()),
+        blend(src.green(), dst.green()),
+        blend(src.blue(), dst.blue()),
+        blend(src.alpha(), dst.alpha()),
+    )
+    .expect("blending premultiplied colors keeps them premultiplied")
+    .demultiply();
+    [out.red(), out.green(), out.blue(), out.alpha()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::COLORS;
+
+    const RED: [u8; 4] = [255, 0, 0, 255];
+    const BLACK: [u8; 4] = [0, 0, 0, 255];
+
+    /// Blit a 10x10 black image into a 20x20 red buffer at an offset, and
+    /// return the buffer's pixels.
+    fn blit_at(dst_x: i32, dst_y: i32) -> Vec<[u8; 4]> {
+        let image = Image::new(10, 10);
+        let mut buffer = RED.repeat(20 * 20);
+        image.blit_into(&mut buffer, 20, 20, dst_x, dst_y).unwrap();
+        buffer
+            .chunks(4)
+            .map(|pixel| pixel.try_into().unwrap())
+            .collect()
+    }
+
+    /// Which pixels of a 20x20 buffer should be black, when the image
+    /// covers `columns` and `rows` of it.
+    fn expected(columns: std::ops::Range<usize>, rows: std::ops::Range<usize>) -> Vec<[u8; 4]> {
+        (0..20 * 20)
+            .map(|index| {
+                let (x, y) = (index % 20, index / 20);
+                if columns.contains(&x) && rows.contains(&y) {
+                    BLACK
+                } else {
+                    RED
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn inside_the_buffer() {
+        assert_eq!(blit_at(5, 3), expected(5..15, 3..13));
+    }
+
+    #[test]
+    fn cut_off_at_every_edge() {
+        assert_eq!(blit_at(15, 15), expected(15..20, 15..20));
+        assert_eq!(blit_at(-5, -7), expected(0..5, 0..3));
+        assert_eq!(blit_at(-3, 14), expected(0..7, 14..20));
+        assert_eq!(blit_at(16, -9), expected(16..20, 0..1));
+    }
+
+    #[test]
+    fn entirely_outside_changes_nothing() {
+        let untouched = expected(0..0, 0..0);
+        for (x, y) in [(20, 0), (0, 20), (-10, 0), (0, -10), (i32::MAX, i32::MIN)] {
+            assert_eq!(blit_at(x, y), untouched, "at ({x}, {y})");
+        }
+    }
+
+    #[test]
+    fn drawing_lands_at_the_offset() {
+        let mut image = Image::new(10, 10);
+        image.draw_simple_line(0, 5, 90, 10, COLORS[7]).unwrap();
+        let mut buffer = RED.repeat(20 * 20);
+        image.blit_into(&mut buffer, 20, 20, -4, 2).unwrap();
+        let pixel = |x: usize, y: usize| &buffer[(y * 20 + x) * 4..][..4];
+        // The line is on rows 4 and 5 of the image, so rows 6 and 7 here.
+        assert!(pixel(3, 6)[1] > 64 && pixel(3, 7)[1] > 64);
+        assert_eq!(pixel(3, 9), BLACK);
+        assert_eq!(pixel(6, 6), RED);
+    }
+
+    #[test]
+    fn wrong_buffer_size() {
+        let image = Image::new(10, 10);
+        let mut buffer = vec![0; 20 * 20 * 4 - 1];
+        assert!(image.blit_into(&mut buffer, 20, 20, 0, 0).is_err());
+        assert!(buffer.iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn transparent_pixels_show_the_buffer() {
+        let src = tiny_skia::PremultipliedColorU8::from_rgba(0, 0, 0, 0).unwrap();
+        assert_eq!(source_over(src, RED), RED);
+        let src = tiny_skia::PremultipliedColorU8::from_rgba(0, 0, 128, 128).unwrap();
+        let [red, green, blue, alpha] = source_over(src, RED);
+        assert!((126..=128).contains(&red), "{red}");
+        assert_eq!((green, alpha), (0, 255));
+        assert!((127..=129).contains(&blue), "{blue}");
+    }
+}
@@ -0,0 +1,195 @@
+use crate::Image;
+use resvg::tiny_skia;
+
+/// The weights of a Gaussian blur with standard deviation `sigma`, from the
+/// centre outwards, adding up to 1 over both sides. The kernel reaches no
+/// further than `max_reach` pixels from the centre.
+fn gaussian_kernel(sigma: f32, max_reach: usize) -> Vec<f32> {
+    // Past three standard deviations, the weights are too small to matter.
+    let reach = ((sigma * 3.0).ceil() as usize).min(max_reach);
+    let mut kernel: Vec<f32> = (0..=reach)
+        .map(|distance| (-((distance * distance) as f32) / (2.0 * sigma * sigma)).exp())
+        .collect();
+
+    let total = kernel[0] + 2.0 * kernel[1..].iter().sum::<f32>();
+    for weight in &mut kernel {
+        *weight /= total;
+    }
+    kernel
+}
+
+/// Blur one line of pixels (a row, or a column) into `output`. `get(i)` is
+/// the `i`th pixel of the line, and pixels past the ends repeat the end pixels.
+fn blur_line(
+    kernel: &[f32],
+    length: usize,
+    get: impl Fn(usize) -> [f32; 4],
+    output: &mut [[f32; 4]],
+) {
+    for (i, out) in output.iter_mut().enumerate().take(length) {
+        let mut sum = get(i).map(|channel| channel * kernel[0]);
+        for (distance, &weight) in kernel.iter().enumerate().skip(1) {
+            let before = get(i.saturating_sub(distance));
+            let after = get((i + distance).min(length - 1));
+            for channel in 0..4 {
+                sum[channel] += (before[channel] + after[channel]) * weight;
+            }
+        }
+        *out = sum;
+    }
+}
+
+/// Apply a Gaussian blur with standard deviation `sigma` pixels to a pixmap,
+/// as a horizontal pass and then a vertical one.
+pub(crate) fn blur_pixmap(pixmap: &mut tiny_skia::Pixmap, sigma: f32) {
+    if sigma <= 0.0 {
+        return;
+    }
+    let (width, height) = (pixmap.width() as usize, pixmap.height() as usize);
+    let kernel = gaussian_kernel(sigma, width.max(height));
+
+    // Premultiplied colors blur correctly, without dark fringes around
+    // transparent areas.
+    let pixels: Vec<[f32; 4]> = pixmap
+        .pixels()
+        .iter()
+        .map(|pixel| [pixel.red(), pixel.green(), pixel.blue(), pixel.alpha()].map(f32::from))
+        .collect();
+
+    let mut across = vec![[0.0; 4]; pixels.len()];
+    for y in 0..height {
+        let row = y * width;
+        blur_line(
+            &kernel,
+            width,
+            |x| pixels[row + x],
+            &mut across[row..row + width],
+        );
+    }
+
+    let mut column = vec![[0.0; 4]; height];
+    for x in 0..width {
+        blur_line(&kernel, height, |y| across[y * width + x], &mut column);
+        for (y, &[red, green, blue, alpha]) in column.iter().enumerate() {
+            let channel = |value: f32| value.round().clamp(0.0, 255.0) as u8;
+            let alpha = channel(alpha);
+            pixmap.pixels_mut()[y * width + x] = tiny_skia::PremultipliedColorU8::from_rgba(
+                channel(red).min(alpha),
+                channel(green).min(alpha),
+                channel(blue).min(alpha),
+                alpha,
+            )
+            .expect("the color channels are no more than the alpha");
+        }
+    }
+}
+
+impl Image {
+    /// Save the image to a PNG file with a Gaussian blur, for soft
+    /// backgrounds or glowing effects.
+    ///
+    /// `radius` is the standard deviation of the blur in pixels (like the
+    /// CSS `blur()` filter): most of each pixel is spread over about
+    /// `radius` pixels around it, fading out by about three times `radius`.
+    /// Pixels past the edges of the image count as copies of the edge
+    /// pixels, so the edges don't fade to black. A radius of 0 saves the
+    /// image unblurred.
+    ///
+    /// Returns an error if `radius` is negative or isn't a number.
+    ///
+    /// ```rs
+    /// image.save_png_blurred("soft.png", 4.0)?;
+    /// ```
+    pub fn save_png_blurred<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+        radius: f32,
+    ) -> Result<(), String> {
+        if !(radius >= 0.0 && radius.is_finite()) {
+            return Err(format!(
+                "The blur radius must be 0 or more, but was {radius}"
+            ));
+        }
+
+        self.prepare_to_save()?;
+        let mut pixmap = self.render()?;
+        blur_pixmap(&mut pixmap, radius);
+        pixmap.save_png(path).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{StrokeStyle, COLORS};
+
+    #[test]
+    fn kernels_add_up_to_one() {
+        for sigma in [0.5, 1.0, 4.0] {
+            let kernel = gaussian_kernel(sigma, 100);
+            assert_eq!(kernel.len(), (sigma * 3.0).ceil() as usize + 1);
+            let total = kernel[0] + 2.0 * kernel[1..].iter().sum::<f32>();
+            assert!((total - 1.0).abs() < 1e-5, "{total}");
+            assert!(kernel.windows(2).all(|pair| pair[0] > pair[1]));
+        }
+        assert_eq!(gaussian_kernel(10.0, 4).len(), 5);
+    }
+
+    #[test]
+    fn blurring_spreads_light_without_losing_it() {
+        let mut pixmap = tiny_skia::Pixmap::new(21, 21).unwrap();
+        pixmap.fill(tiny_skia::Color::BLACK);
+        pixmap.pixels_mut()[10 * 21 + 10] =
+            tiny_skia::PremultipliedColorU8::from_rgba(255, 255, 255, 255).unwrap();
+        blur_pixmap(&mut pixmap, 1.0);
+
+        let red = |x: usize, y: usize| pixmap.pixels()[y * 21 + x].red();
+        // One pixel spread over a blob, brightest in the middle and the same
+        // all the way round.
+        assert!(red(10, 10) > red(11, 10) && red(11, 10) > red(12, 10));
+        assert_eq!(red(11, 10), red(9, 10));
+        assert_eq!(red(11, 10), red(10, 11));
+        assert_eq!(red(0, 0), 0);
+        let total: u32 = pixmap
+            .pixels()
+            .iter()
+            .map(|pixel| u32::from(pixel.red()))
+            .sum();
+        assert!(total.abs_diff(255) < 10, "{total}");
+        assert!(pixmap.pixels().iter().all(|pixel| pixel.alpha() == 255));
+    }
+
+    #[test]
+    fn blurred_pngs_keep_their_edges_bright() {
+        let mut image = Image::new(20, 20);
+        let style = StrokeStyle {
+            width: 20.0,
+            ..StrokeStyle::default()
+        };
+        image
+            .draw_styled_line(10, 0, 180, 20, COLORS[7], &style)
+            .unwrap();
+
+        let dir = std::env::temp_dir().join("unsvg-blur-test");
+        std::fs::create_dir_all(&dir).unwrap();
+        image
+            .save_png_blurred(dir.join("blurred.png"), 3.0)
+            .unwrap();
+        image.save_png_blurred(dir.join("sharp.png"), 0.0).unwrap();
+        image.save_png(dir.join("plain.png")).unwrap();
+        let blurred = tiny_skia::Pixmap::load_png(dir.join("blurred.png")).unwrap();
+        let same = std::fs::read(dir.join("sharp.png")).unwrap()
+            == std::fs::read(dir.join("plain.png")).unwrap();
+        assert!(image.save_png_blurred(dir.join("bad.png"), -1.0).is_err());
+        assert!(image
+            .save_png_blurred(dir.join("bad.png"), f32::NAN)
+            .is_err());
+        let bad_saved = dir.join("bad.png").exists();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // Past the edges are copies of the edge, so all white stays white.
+        assert!(blurred.pixels().iter().all(|pixel| pixel.red() == 255));
+        assert!(same);
+        assert!(!bad_saved);
+    }
+}
@@ -0,0 +1,375 @@
+use crate::{Image, MAX_DIMENSION};
+use resvg::{tiny_skia, usvg};
+use std::rc::Rc;
+
+/// An amount of space on each side of an image, in units.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Margins {
+    /// Space on the left side.
+    pub left: u32,
+    /// Space on the top side.
+    pub top: u32,
+    /// Space on the right side.
+    pub right: u32,
+    /// Space on the bottom side.
+    pub bottom: u32,
+}
+
+/// Where the existing drawing goes when an image is resized with
+/// `grow_canvas`: which part of the new canvas it is pinned to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Anchor {
+    /// The top-left corner stays where it is, so coordinates don't change.
+    #[default]
+    TopLeft,
+    /// Centred left to right, along the top edge.
+    Top,
+    /// In the top-right corner.
+    TopRight,
+    /// Centred top to bottom, along the left edge.
+    Left,
+    /// In the middle.
+    Center,
+    /// Centred top to bottom, along the right edge.
+    Right,
+    /// In the bottom-left corner.
+    BottomLeft,
+    /// Centred left to right, along the bottom edge.
+    Bottom,
+    /// In the bottom-right corner.
+    BottomRight,
+}
+
+impl Anchor {
+    /// How far across the spare space the drawing goes, from 0 (left or
+    /// top) to 2 (right or bottom), in halves.
+    fn halves(self) -> (i64, i64) {
+        match self {
+            Anchor::TopLeft => (0, 0),
+            Anchor::Top => (1, 0),
+            Anchor::TopRight => (2, 0),
+            Anchor::Left => (0, 1),
+            Anchor::Center => (1, 1),
+            Anchor::Right => (2, 1),
+            Anchor::BottomLeft => (0, 2),
+            Anchor::Bottom => (1, 2),
+            Anchor::BottomRight => (2, 2),
+        }
+    }
+}
+
+impl Image {
+    /// Change the size of the image to `new_width` by `new_height`, with
+    /// `anchor` choosing where the existing drawing sits on the new canvas.
+    ///
+    /// Everything already drawn moves by the same amount, keeping its
+    /// position relative to everything else, and the background, mask and
+    /// any pushed clips move with it. The background fills the new canvas.
+    /// After this, `content_bounds` and everything drawn use the new
+    /// coordinates. Points returned by earlier draw calls are in the old
+    /// coordinates, though, so they need moving too before drawing from them.
+    ///
+    /// The canvas can shrink as well as grow. Anything that no longer fits is
+    /// cut off by the edges, as usual, but is still there: growing the canvas
+    /// again brings it back. When centring leaves an odd number of units
+    /// spare, the drawing goes half a unit up or left of centre (rounding
+    /// down), so it stays on whole units.
+    ///
+    /// Returns an error if either new side is 0 or more than
+    /// [`MAX_DIMENSION`].
+    ///
+    /// ```rs
+    /// let mut image = Image::new(100, 100);
+    /// image.draw_simple_line(10, 10, 90, 50, COLORS[1])?;
+    /// image.grow_canvas(200, 150, Anchor::Center)?;
+    /// // The drawing moved right by 50 and down by 25.
+    /// assert_eq!(image.content_bounds(), Some((60, 34, 110, 36)));
+    /// ```
+    pub fn grow_canvas(
+        &mut self,
+        new_width: u32,
+        new_height: u32,
+        anchor: Anchor,
+    ) -> Result<(), String> {
+        if new_width == 0 || new_height == 0 {
+            return Err(format!(
+                "An image must be at least 1x1, but {new_width}x{new_height} was asked for"
+            ));
+        }
+        if new_width > MAX_DIMENSION || new_height > MAX_DIMENSION {
+            return Err(format!(
+                "An image can be at most {MAX_DIMENSION}x{MAX_DIMENSION}, but {new_width}x{new_height} was asked for"
+            ));
+        }
+
+        let (across, down) = anchor.halves();
+        let spare_x = i64::from(new_width) - i64::from(self.width);
+        let spare_y = i64::from(new_height) - i64::from(self.height);
+        let offset = (
+            (spare_x * across).div_euclid(2) as f32,
+            (spare_y * down).div_euclid(2) as f32,
+        );
+        self.resize_canvas(new_width, new_height, offset);
+        Ok(())
+    }
+
+    /// How far the drawing goes past each edge of the image, in whole units.
+    ///
+    /// This uses the same bounds as `content_bounds`, so it includes the full
+    /// width of thick strokes: a line drawn right along the top edge sticks
+    /// out by half its width, even though its centre is inside the image.
+    /// Clips are not taken into account.
+    ///
+    /// ```rs
+    /// let mut image = Image::new(100, 100);
+    /// let thick = StrokeStyle { width: 10.0, ..StrokeStyle::default() };
+    /// image.draw_styled_line(10, 0, 90, 80, COLORS[1], &thick)?;
+    /// assert_eq!(image.overflow(), Margins { top: 5, ..Margins::default() });
+    /// ```
+    pub fn overflow(&self) -> Margins {
+        let Some((min_x, min_y, max_x, max_y)) = self.content_bounds_on_image() else {
+            return Margins::default();
+        };
+        let past = |distance: i64| u32::try_from(distance.max(0)).unwrap_or(u32::MAX);
+
+        Margins {
+            left: past(-i64::from(min_x)),
+            top: past(-i64::from(min_y)),
+            right: past(i64::from(max_x) - i64::from(self.width)),
+            bottom: past(i64::from(max_y) - i64::from(self.height)),
+        }
+    }
+
+    /// Whether any of the drawing is cut off by the edges of the image; that
+    /// is, whether `overflow` is more than 0 on any side.
+    pub fn would_clip(&self) -> bool {
+        self.overflow() != Margins::default()
+    }
+
+    /// A copy of the image, grown just enough that nothing drawn on it is cut
+    /// off by its edges.
+    ///
+    /// Each side grows by exactly the `overflow` on that side, and the
+    /// drawing moves right and down by the growth on the left and top, so
+    /// everything keeps its position relative to everything else. The
+    /// background fills the new space. Anything drawn on the copy afterwards
+    /// uses the copy's coordinates, where the old (0, 0) is at
+    /// `(overflow().left, overflow().top)`.
+    ///
+    /// Neither side grows past [`MAX_DIMENSION`], so a drawing that goes
+    /// further out than that is still cut off.
+    ///
+    /// ```rs
+    /// let margins = image.overflow();
+    /// let expanded = image.expanded_to_fit();
+    /// assert!(!expanded.would_clip());
+    /// assert_eq!(
+    ///     expanded.get_dimensions(),
+    ///     (100 + margins.left + margins.right, 100 + margins.top + margins.bottom),
+    /// );
+    /// ```
+    pub fn expanded_to_fit(&self) -> Image {
+        let margins = self.overflow();
+        let mut image = self.clone();
+        image.resize_canvas(
+            self.width
+                .saturating_add(margins.left)
+                .saturating_add(margins.right)
+                .min(MAX_DIMENSION),
+            self.height
+                .saturating_add(margins.top)
+                .saturating_add(margins.bottom)
+                .min(MAX_DIMENSION),
+            (margins.left as f32, margins.top as f32),
+        );
+        image
+    }
+
+    /// Change the size of the image to `width` by `height`, moving everything
+    /// drawn on it by `offset`. The background is resized to fill the image,
+    /// and the mask, any pushed clips and the anchors move with the drawing.
+    pub(crate) fn resize_canvas(&mut self, width: u32, height: u32, offset: (f32, f32)) {
+        let Some(size) = usvg::Size::from_wh(width as f32, height as f32) else {
+            return;
+        };
+        let rect = size.to_non_zero_rect(0.0, 0.0);
+        let before = self.coordinate_transform();
+        self.width = width;
+        self.height = height;
+        self.tree.size = size;
+        self.tree.view_box.rect = rect;
+
+        if let usvg::NodeKind::Path(ref mut background) = *self.background().borrow_mut() {
+            background.data = Rc::new(tiny_skia::PathBuilder::from_rect(rect.to_rect()));
+        }
+
+        // The elements and clips are in drawing coordinates, which can
+        // themselves move on the image when its size changes.
+        let shift = tiny_skia::Transform::from_translate(offset.0, offset.1);
+        let transform = match self.coordinate_transform().invert() {
+            Some(after) => after.pre_concat(shift).pre_concat(before),
+            None => shift,
+        };
+        if !transform.is_identity() {
+            self.move_drawing(transform);
+            self.move_anchors(transform);
+            self.recount_memory();
+        }
+        if offset != (0.0, 0.0) {
+            self.move_mask(offset);
+        }
+        self.mark_changed();
+    }
+
+    /// Move everything drawn, and any pushed clips, by `transform`.
+    fn move_drawing(&mut self, transform: tiny_skia::Transform) {
+        for (_, node) in &mut self.elements {
+            let group = usvg::Node::new(usvg::NodeKind::Group(usvg::Group {
+                transform,
+                ..usvg::Group::default()
+            }));
+            node.insert_before(group.clone());
+            node.detach();
+            group.append(node.clone());
+            *node = group;
+        }
+
+        // Elements already drawn use the old clips (inside the moved group),
+        // so the moved clips are new definitions.
+        for index in 0..self.clips.len() {
+            let clip = self.clips[index].clone();
+            self.clips[index] = Rc::new(usvg::ClipPath {
+                id: self.new_def_id("clip"),
+                units: clip.units,
+                transform: transform.pre_concat(clip.transform),
+                clip_path: clip.clip_path.clone(),
+                root: clip.root.clone(),
+            });
+        }
+    }
+
+    /// Move the image's mask by `offset`.
+    fn move_mask(&mut self, offset: (f32, f32)) {
+        let transform = tiny_skia::Transform::from_translate(offset.0, offset.1);
+        if let Some(mask) = self.mask.clone() {
+            let root = usvg::Node::new(usvg::NodeKind::Group(usvg::Group::default()));
+            let content = usvg::Node::new(usvg::NodeKind::Group(usvg::Group {
+                transform,
+                ..usvg::Group::default()
+            }));
+            content.append(mask.root.make_deep_copy());
+            root.append(content);
+
+            self.mask = Some(Rc::new(usvg::Mask {
+                id: self.new_def_id("mask"),
+                units: mask.units,
+                content_units: mask.content_units,
+                rect: mask
+                    .rect
+                    .translate_to(mask.rect.x() + offset.0, mask.rect.y() + offset.1)
+                    .unwrap_or(mask.rect),
+                kind: mask.kind,
+                mask: mask.mask.clone(),
+                root,
+            }));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Anchor, Image, Margins, StrokeStyle, COLORS, MAX_DIMENSION};
+
+    #[test]
+    fn each_anchor_pins_the_drawing_to_its_part_of_the_canvas() {
+        // 101 units spare across and 51 down, so centring rounds down.
+        let anchors = [
+            (Anchor::TopLeft, (0, 0)),
+            (Anchor::Top, (50, 0)),
+            (Anchor::TopRight, (101, 0)),
+            (Anchor::Left, (0, 25)),
+            (Anchor::Center, (50, 25)),
+            (Anchor::Right, (101, 25)),
+            (Anchor::BottomLeft, (0, 51)),
+            (Anchor::Bottom, (50, 51)),
+            (Anchor::BottomRight, (101, 51)),
+        ];
+        for (anchor, (dx, dy)) in anchors {
+            let mut image = Image::new(100, 100);
+            image.draw_simple_line(10, 10, 90, 50, COLORS[1]).unwrap();
+            image.grow_canvas(201, 151, anchor).unwrap();
+            assert_eq!(image.get_dimensions(), (201, 151));
+            assert_eq!(
+                image.content_bounds(),
+                Some((10 + dx, 9 + dy, 60 + dx, 11 + dy)),
+                "{anchor:?}"
+            );
+            let pixmap = image.render().unwrap();
+            let (x, y) = (30 + dx as u32, 10 + dy as u32);
+            assert!(pixmap.pixel(x, y).unwrap().blue() > 64, "{anchor:?}");
+            assert_eq!(pixmap.pixel(x, y + 5).unwrap().blue(), 0, "{anchor:?}");
+        }
+    }
+
+    #[test]
+    fn shrinking_the_canvas_keeps_what_no_longer_fits() {
+        let mut image = Image::new(100, 100);
+        image.draw_simple_line(10, 10, 90, 50, COLORS[1]).unwrap();
+        image.grow_canvas(99, 50, Anchor::Center).unwrap();
+        // Half a unit left of centre, rounded down, is a whole unit left.
+        assert_eq!(image.content_bounds(), Some((9, -16, 59, -14)));
+        image.grow_canvas(100, 100, Anchor::Center).unwrap();
+        assert_eq!(image.content_bounds(), Some((9, 9, 59, 11)));
+
+        assert!(image.grow_canvas(0, 100, Anchor::Center).is_err());
+        assert!(image
+            .grow_canvas(MAX_DIMENSION + 1, 100, Anchor::Center)
+            .is_err());
+        assert_eq!(image.get_dimensions(), (100, 100));
+    }
+
+    /// Whether the pixel at (x, y) is mostly blue.
+    fn is_blue(image: &Image, x: u32, y: u32) -> bool {
+        image.render().unwrap().pixel(x, y).unwrap().blue() > 200
+    }
+
+    #[test]
+    fn thick_lines_along_the_top_edge_stick_out_by_half_their_width() {
+        let mut image = Image::new(100, 100);
+        image.draw_simple_line(10, 50, 90, 80, COLORS[1]).unwrap();
+        assert!(!image.would_clip());
+        assert_eq!(image.expanded_to_fit().get_dimensions(), (100, 100));
+
+        let thick = StrokeStyle {
+            width: 10.0,
+            ..StrokeStyle::default()
+        };
+        image
+            .draw_styled_line(10, 0, 90, 80, COLORS[1], &thick)
+            .unwrap();
+        assert_eq!(
+            image.overflow(),
+            Margins {
+                top: 5,
+                ..Margins::default()
+            }
+        );
+        assert!(image.would_clip());
+        // Only the half inside the image is drawn.
+        assert!(is_blue(&image, 50, 0) && is_blue(&image, 50, 4));
+        assert!(!is_blue(&image, 50, 5));
+
+        let expanded = image.expanded_to_fit();
+        assert_eq!(expanded.get_dimensions(), (100, 105));
+        assert!(!expanded.would_clip());
+        assert_eq!(expanded.overflow(), Margins::default());
+        assert_eq!(expanded.content_bounds(), Some((10, 0, 90, 56)));
+        assert!(is_blue(&expanded, 50, 0) && is_blue(&expanded, 50, 9));
+        assert!(!is_blue(&expanded, 50, 10));
+        // The thin line moved down from y = 50 to y = 55 with everything else.
+        let pixmap = expanded.render().unwrap();
+        let blue = |y| pixmap.pixel(50, y).unwrap().blue();
+        assert!(blue(54) > 64 && blue(55) > 64);
+        assert!(blue(49) == 0 && blue(50) == 0);
+    }
+}
@@ -0,0 +1,125 @@
+use crate::output::{encode_png, PngOptions};
+use crate::Image;
+use resvg::tiny_skia;
+
+/// The two colors of the checkerboard's cells, starting at the top left: white
+/// and light grey, as image editors show transparency.
+const CELL_COLORS: [[u8; 4]; 2] = [[255, 255, 255, 255], [204, 204, 204, 255]];
+
+impl Image {
+    /// Save the image to a PNG file drawn over a checkerboard of white and
+    /// light grey squares, each `cell` pixels wide, the way image editors
+    /// show transparency. This is for previewing: anywhere the image is
+    /// transparent (or partly transparent) shows the checkerboard through
+    /// it, while anything opaque covers it.
+    ///
+    /// Images only have transparent areas if their background is
+    /// transparent, as can happen with one loaded by `from_svg_file`. The
+    /// file itself is fully opaque.
+    ///
+    /// Returns an error if `cell` is 0, or for a dry-run image.
+    ///
+    /// ```rs
+    /// let image = Image::from_svg_file("sticker.svg")?;
+    /// image.save_png_with_transparency_grid("preview.png", 8)?;
+    /// ```
+    pub fn save_png_with_transparency_grid<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+        cell: u32,
+    ) -> Result<(), String> {
+        if cell == 0 {
+            return Err("The checkerboard's cells must be at least 1 pixel wide".into());
+        }
+        self.prepare_to_save()?;
+
+        let mut buffer: Vec<u8> = (0..self.height)
+            .flat_map(|y| {
+                (0..self.width).flat_map(move |x| CELL_COLORS[((x / cell + y / cell) % 2) as usize])
+            })
+            .collect();
+        self.blit_into(&mut buffer, self.width, self.height, 0, 0)?;
+
+        // The buffer is opaque, so it is the same premultiplied or not.
+        let size = tiny_skia::IntSize::from_wh(self.width, self.height)
+            .ok_or("An image must be at least 1x1".to_string())?;
+        let pixmap = tiny_skia::Pixmap::from_vec(buffer, size)
+            .ok_or("Could not create the checkerboard".to_string())?;
+        let png = encode_png(&pixmap, &PngOptions::default())?;
+        std::fs::write(path, png).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Save `image` over a checkerboard, and read the PNG back.
+    fn preview(image: &Image, cell: u32) -> tiny_skia::Pixmap {
+        let path = std::env::temp_dir().join(format!(
+            "unsvg-checkerboard-{:?}.png",
+            std::thread::current().id()
+        ));
+        image.save_png_with_transparency_grid(&path, cell).unwrap();
+        let pixmap = tiny_skia::Pixmap::load_png(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        pixmap
+    }
+
+    fn rgba(pixmap: &tiny_skia::Pixmap, x: u32, y: u32) -> [u8; 4] {
+        let pixel = pixmap.pixel(x, y).unwrap();
+        [pixel.red(), pixel.green(), pixel.blue(), pixel.alpha()]
+    }
+
+    #[test]
+    fn transparency_shows_the_checkerboard() {
+        // A see-through background, with an opaque square and a half
+        // see-through one.
+        let image = Image::from_svg_str(
+            r##"<svg width="40" height="20" viewBox="0 0 40 20" xmlns="http://www.w3.org/2000/svg">
+                <rect width="40" height="20" fill="#000000" fill-opacity="0"/>
+                <rect x="20" y="0" width="10" height="20" fill="#ff0000"/>
+                <rect x="30" y="0" width="10" height="20" fill="#000000" fill-opacity="0.5"/>
+            </svg>"##,
+        )
+        .unwrap();
+        let pixmap = preview(&image, 5);
+        assert_eq!((pixmap.width(), pixmap.height()), (40, 20));
+
+        // Cells alternate along rows and down columns, white first.
+        let [white, grey] = CELL_COLORS;
+        for (x, y, expected) in [(0, 0, white), (5, 0, grey), (0, 5, grey), (5, 5, white)] {
+            assert_eq!(rgba(&pixmap, x, y), expected, "at ({x}, {y})");
+        }
+        assert_eq!(rgba(&pixmap, 14, 19), grey);
+        assert_eq!(rgba(&pixmap, 19, 19), white);
+
+        assert_eq!(rgba(&pixmap, 25, 5), [255, 0, 0, 255]);
+        // Half black over white and over grey, on the same row.
+        let [red, ..] = rgba(&pixmap, 30, 0);
+        assert!((127..=128).contains(&red), "{red}");
+        let [red, ..] = rgba(&pixmap, 35, 0);
+        assert!((101..=103).contains(&red), "{red}");
+    }
+
+    #[test]
+    fn opaque_images_hide_it() {
+        let image = Image::new(20, 20);
+        let pixmap = preview(&image, 4);
+        assert!(pixmap.pixels().iter().all(|pixel| {
+            (pixel.red(), pixel.green(), pixel.blue(), pixel.alpha()) == (0, 0, 0, 255)
+        }));
+    }
+
+    #[test]
+    fn cells_must_be_at_least_a_pixel() {
+        let path = std::env::temp_dir().join("unsvg-checkerboard-zero.png");
+        assert!(Image::new(20, 20)
+            .save_png_with_transparency_grid(&path, 0)
+            .is_err());
+        assert!(!path.exists());
+        assert!(Image::new_dry_run(20, 20)
+            .save_png_with_transparency_grid(&path, 4)
+            .is_err());
+    }
+}
@@ -0,0 +1,202 @@
+use crate::Image;
+use resvg::tiny_skia;
+
+/// A 4x5 matrix that changes every color of an image, as used by
+/// `Image::save_png_recolored`. It works like SVG's `feColorMatrix`.
+///
+/// Each row gives one channel of the new color (red, green, blue, then
+/// alpha) from the old color's red, green, blue and alpha, plus a constant,
+/// with every channel going from 0.0 to 1.0. So the new red is
+/// `m[0][0] * r + m[0][1] * g + m[0][2] * b + m[0][3] * a + m[0][4]`, and
+/// so on. Results are clamped to 0.0 to 1.0.
+///
+/// A plain `[[f32; 5]; 4]` can be used anywhere a `ColorMatrix` is wanted.
+///
+/// ```rs
+/// // Swap the red and blue channels.
+/// let swap = ColorMatrix([
+///     [0.0, 0.0, 1.0, 0.0, 0.0],
+///     [0.0, 1.0, 0.0, 0.0, 0.0],
+///     [1.0, 0.0, 0.0, 0.0, 0.0],
+///     [0.0, 0.0, 0.0, 1.0, 0.0],
+/// ]);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColorMatrix(pub [[f32; 5]; 4]);
+
+impl ColorMatrix {
+    /// The matrix that leaves every color as it is.
+    pub fn identity() -> ColorMatrix {
+        ColorMatrix([
+            [1.0, 0.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0, 0.0],
+        ])
+    }
+
+    /// Shades of grey, keeping how bright each color looks (using the
+    /// same weights as SVG's `saturate` of 0).
+    pub fn grayscale() -> ColorMatrix {
+        let grey = [0.2126, 0.7152, 0.0722, 0.0, 0.0];
+        ColorMatrix([grey, grey, grey, [0.0, 0.0, 0.0, 1.0, 0.0]])
+    }
+
+    /// Browns, like an old photo (the same as CSS's `sepia(1)`).
+    pub fn sepia() -> ColorMatrix {
+        ColorMatrix([
+            [0.393, 0.769, 0.189, 0.0, 0.0],
+            [0.349, 0.686, 0.168, 0.0, 0.0],
+            [0.272, 0.534, 0.131, 0.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0, 0.0],
+        ])
+    }
+
+    /// Turn every hue around the color wheel by `degrees`, the same as
+    /// SVG's `hueRotate`. 120 degrees turns red towards green, green towards
+    /// blue, and blue towards red.
+    pub fn hue_rotate(degrees: f32) -> ColorMatrix {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        ColorMatrix([
+            [
+                0.213 + cos * 0.787 - sin * 0.213,
+                0.715 - cos * 0.715 - sin * 0.715,
+                0.072 - cos * 0.072 + sin * 0.928,
+                0.0,
+                0.0,
+            ],
+            [
+                0.213 - cos * 0.213 + sin * 0.143,
+                0.715 + cos * 0.285 + sin * 0.140,
+                0.072 - cos * 0.072 - sin * 0.283,
+                0.0,
+                0.0,
+            ],
+            [
+                0.213 - cos * 0.213 - sin * 0.787,
+                0.715 - cos * 0.715 + sin * 0.715,
+                0.072 + cos * 0.928 + sin * 0.072,
+                0.0,
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0, 0.0],
+        ])
+    }
+}
+
+impl From<[[f32; 5]; 4]> for ColorMatrix {
+    fn from(matrix: [[f32; 5]; 4]) -> ColorMatrix {
+        ColorMatrix(matrix)
+    }
+}
+
+/// Apply a color matrix to every pixel of a pixmap.
+fn recolor_pixmap(pixmap: &mut tiny_skia::Pixmap, matrix: &ColorMatrix) {
+    for pixel in pixmap.pixels_mut() {
+        let color = pixel.demultiply();
+        let old = [color.red(), color.green(), color.blue(), color.alpha()]
+            .map(|channel| f32::from(channel) / 255.0);
+        let new = matrix.0.map(|row| {
+            let value = row[..4]
+                .iter()
+                .zip(old)
+                .map(|(weight, channel)| weight * channel)
+                .sum::<f32>()
+                + row[4];
+            (value.clamp(0.0, 1.0) * 255.0).round() as u8
+        });
+        *pixel = tiny_skia::ColorU8::from_rgba(new[0], new[1], new[2], new[3]).premultiply();
+    }
+}
+
+impl Image {
+    /// Save the image to a PNG file, with every color changed by a
+    /// [`ColorMatrix`]. This can turn the image grey, sepia, shift its hues,
+    /// swap channels, and so on, in one step.
+    ///
+    /// The image is rendered as usual, and then the matrix is applied to
+    /// every pixel, using the colors as they are saved (in sRGB). Only the
+    /// PNG changes: the image itself is left as it is.
+    ///
+    /// ```rs
+    /// image.save_png_recolored("grey.png", ColorMatrix::grayscale())?;
+    /// image.save_png_recolored("shifted.png", ColorMatrix::hue_rotate(90.0))?;
+    /// ```
+    pub fn save_png_recolored<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+        matrix: impl Into<ColorMatrix>,
+    ) -> Result<(), String> {
+        self.prepare_to_save()?;
+        let mut pixmap = self.render()?;
+        recolor_pixmap(&mut pixmap, &matrix.into());
+        pixmap.save_png(path).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ColorMatrix;
+    use crate::{Image, COLORS};
+    use resvg::tiny_skia::Pixmap;
+
+    /// Stripes of every color, saved with `matrix` and read back.
+    fn recolored(name: &str, matrix: ColorMatrix) -> Pixmap {
+        let mut image = Image::new(32, 16);
+        for (x, &color) in (0..).step_by(2).zip(COLORS.iter()) {
+            image.draw_simple_line(x + 1, 0, 180, 16, color).unwrap();
+        }
+        let path = std::env::temp_dir().join(format!(
+            "unsvg-recolored-{name}-{:?}.png",
+            std::thread::current().id()
+        ));
+        image.save_png_recolored(&path, matrix).unwrap();
+        let pixmap = Pixmap::load_png(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        pixmap
+    }
+
+    #[test]
+    fn grayscale_makes_every_pixel_grey() {
+        let pixmap = recolored("grey", ColorMatrix::grayscale());
+        assert_eq!((pixmap.width(), pixmap.height()), (32, 16));
+        for pixel in pixmap.pixels() {
+            assert_eq!(pixel.red(), pixel.green(), "{pixel:?}");
+            assert_eq!(pixel.green(), pixel.blue(), "{pixel:?}");
+            assert_eq!(pixel.alpha(), 255);
+        }
+        // Still shades of grey, not one flat color.
+        let mut shades: Vec<u8> = pixmap.pixels().iter().map(|pixel| pixel.red()).collect();
+        shades.sort_unstable();
+        shades.dedup();
+        assert!(shades.len() > 8, "{shades:?}");
+    }
+
+    #[test]
+    fn matrices_move_channels_around() {
+        let plain = recolored("identity", ColorMatrix::identity());
+        let unturned = recolored("unturned", ColorMatrix::hue_rotate(0.0));
+        assert!(plain.pixels() == unturned.pixels());
+
+        let swapped = recolored(
+            "swapped",
+            [
+                [0.0, 0.0, 1.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0, 0.0, 0.0],
+                [1.0, 0.0, 0.0, 0.0, 0.0],
+                [0.0, 0.0, 0.0, 1.0, 0.0],
+            ]
+            .into(),
+        );
+        assert!(plain
+            .pixels()
+            .iter()
+            .any(|pixel| pixel.red() != pixel.blue()));
+        for (before, after) in plain.pixels().iter().zip(swapped.pixels()) {
+            assert_eq!(
+                (after.red(), after.green(), after.blue()),
+                (before.blue(), before.green(), before.red())
+            );
+        }
+    }
+}
@@ -0,0 +1,292 @@
+use crate::{Image, IntoColor};
+use resvg::{tiny_skia, usvg};
+use std::collections::BTreeMap;
+
+/// An edge between two neighbouring samples of a field, as (row, column,
+/// whether it goes right rather than down) of the sample it starts from.
+type Edge = (usize, usize, bool);
+
+/// Where a contour crosses each edge it crosses.
+type Crossings = BTreeMap<Edge, (f32, f32)>;
+
+/// The crossings each crossing is joined to by a contour.
+type Links = BTreeMap<Edge, Vec<Edge>>;
+
+impl Image {
+    /// Draw contour lines of a height field, like the lines on a topographic
+    /// map: for each of `levels`, a line through everywhere the field has
+    /// that value.
+    ///
+    /// `field[row][col]` is the height at one point of a grid stretched over
+    /// the whole image, with the first row along the top edge, the last row
+    /// along the bottom edge, and likewise for the columns. Between the
+    /// points, the height changes linearly, so more rows and columns give
+    /// smoother lines. Areas at exactly a level's height count as above it.
+    /// Where a square of the grid is ambiguous (two opposite corners above a
+    /// level and the other two below), the average of its corners decides
+    /// which pair is joined up. Squares with a corner that isn't finite are
+    /// skipped, leaving a gap.
+    ///
+    /// Each contour is drawn as one connected line, closed if it loops back
+    /// on itself, and every contour is drawn together as one element.
+    ///
+    /// Returns an error if the field has fewer than 2 rows or columns, if its
+    /// rows have different lengths, or if any level isn't finite.
+    ///
+    /// ```rs
+    /// // A hill in the middle of the image, with rings every 0.25 of height.
+    /// let field: Vec<Vec<f32>> = (0..=20)
+    ///     .map(|row| {
+    ///         (0..=20)
+    ///             .map(|col| {
+    ///                 let (x, y) = (col as f32 / 10.0 - 1.0, row as f32 / 10.0 - 1.0);
+    ///                 1.0 - (x * x + y * y).sqrt()
+    ///             })
+    ///             .collect()
+    ///     })
+    ///     .collect();
+    /// image.draw_contours(&field, &[0.25, 0.5, 0.75], COLORS[2])?;
+    /// ```
+    pub fn draw_contours(
+        &mut self,
+        field: &[Vec<f32>],
+        levels: &[f32],
+        color: impl IntoColor,
+    ) -> Result<(), String> {
+        let color = color.into_color()?;
+        let rows = field.len();
+        let cols = field.first().map_or(0, Vec::len);
+        if rows < 2 || cols < 2 {
+            return Err(format!(
+                "A field needs at least 2 rows and 2 columns, but it has {rows} rows and {cols} columns"
+            ));
+        }
+        if let Some(row) = field.iter().position(|row| row.len() != cols) {
+            return Err(format!(
+                "Every row of a field must be the same length, but row 0 has {cols} values and row {row} has {}",
+                field[row].len()
+            ));
+        }
+        if let Some(level) = levels.iter().find(|level| !level.is_finite()) {
+            return Err(format!(
+                "Contour levels must be finite, but one was {level}"
+            ));
+        }
+
+        let scale = (
+            self.width as f32 / (cols - 1) as f32,
+            self.height as f32 / (rows - 1) as f32,
+        );
+        let mut builder = tiny_skia::PathBuilder::new();
+        for &level in levels {
+            let (points, neighbours) = contour_segments(field, level, scale);
+            add_contours(&mut builder, &points, neighbours);
+        }
+
+        let Some(path) = builder.finish() else {
+            // No level crosses the field, so there is nothing to draw.
+            return Ok(());
+        };
+        let mut path = usvg::Path::new(path.into());
+        // The grid is laid out on the image, whatever coordinates are used.
+        path.transform = self.coordinate_transform().invert().unwrap_or_default();
+        path.stroke = Some(usvg::Stroke {
+            paint: color.paint(),
+            opacity: color.opacity(),
+            ..usvg::Stroke::default()
+        });
+        self.append_element(usvg::NodeKind::Path(path))?;
+        Ok(())
+    }
+}
+
+/// Find where `level` crosses the edges of `field`'s grid (scaled to the
+/// image by `scale`), and which of those crossings are joined by a contour.
+/// Each crossing is joined to at most two others, one in each square next
+/// to its edge.
+fn contour_segments(field: &[Vec<f32>], level: f32, scale: (f32, f32)) -> (Crossings, Links) {
+    let mut points = Crossings::new();
+    let mut neighbours = Links::new();
+
+    for row in 0..field.len() - 1 {
+        for col in 0..field[row].len() - 1 {
+            // Clockwise from the top-left corner.
+            let corners = [
+                field[row][col],
+                field[row][col + 1],
+                field[row + 1][col + 1],
+                field[row + 1][col],
+            ];
+            if corners.iter().any(|height| !height.is_finite()) {
+                continue;
+            }
+            let above = corners.map(|height| height >= level);
+
+            // The edges of the square, clockwise from the top, with the
+            // corners at each end.
+            let edges = [
+                ((row, col, true), (0, 1)),
+                ((row, col + 1, false), (1, 2)),
+                ((row + 1, col, true), (3, 2)),
+                ((row, col, false), (0, 3)),
+            ];
+            let mut crossed = Vec::new();
+            for (edge, (start, end)) in edges {
+                if above[start] == above[end] {
+                    continue;
+                }
+                let t = (level - corners[start]) / (corners[end] - corners[start]);
+                let (edge_row, edge_col, across) = edge;
+                let (x, y) = if across {
+                    (edge_col as f32 + t, edge_row as f32)
+                } else {
+                    (edge_col as f32, edge_row as f32 + t)
+                };
+                points.insert(edge, (x * scale.0, y * scale.1));
+                crossed.push(edge);
+            }
+
+            let pairs = match crossed[..] {
+                [a, b] => vec![(a, b)],
+                [top, right, bottom, left] => {
+                    // Two opposite corners are above the level and two are
+                    // below. Cut off the two corners that aren't joined to
+                    // the middle of the square.
+                    let middle = corners.iter().sum::<f32>() / 4.0 >= level;
+                    if middle == above[0] {
+                        vec![(top, right), (bottom, left)]
+                    } else {
+                        vec![(top, left), (right, bottom)]
+                    }
+                }
+                _ => Vec::new(),
+            };
+            for (a, b) in pairs {
+                neighbours.entry(a).or_default().push(b);
+                neighbours.entry(b).or_default().push(a);
+            }
+        }
+    }
+
+    (points, neighbours)
+}
+
+/// Add each contour through the crossings in `neighbours` to `builder` as one
+/// connected line: first those with loose ends (which run off the image or
+/// into a gap), starting from one end, and then those that loop back on
+/// themselves, which are closed.
+fn add_contours(builder: &mut tiny_skia::PathBuilder, points: &Crossings, mut neighbours: Links) {
+    let ends: Vec<Edge> = neighbours
+        .iter()
+        .filter(|(_, next)| next.len() == 1)
+        .map(|(&edge, _)| edge)
+        .collect();
+    let starts: Vec<Edge> = ends
+        .into_iter()
+        .chain(neighbours.keys().copied().collect::<Vec<_>>())
+        .collect();
+
+    for start in starts {
+        if neighbours.get(&start).is_none_or(Vec::is_empty) {
+            continue;
+        }
+
+        let (x, y) = points[&start];
+        builder.move_to(x, y);
+        let mut current = start;
+        while let Some(next) = neighbours.get_mut(&current).and_then(Vec::pop) {
+            // Use up the way back, too.
+            if let Some(back) = neighbours.get_mut(&next) {
+                if let Some(index) = back.iter().position(|&edge| edge == current) {
+                    back.swap_remove(index);
+                }
+            }
+            if next == start {
+                builder.close();
+                break;
+            }
+            let (x, y) = points[&next];
+            builder.line_to(x, y);
+            current = next;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Image, COLORS};
+
+    /// A cone, 1 high in the middle of the image and 0 at the middle of each
+    /// edge, sampled `samples + 1` times across and down.
+    fn hill(samples: usize) -> Vec<Vec<f32>> {
+        let half = samples as f32 / 2.0;
+        (0..=samples)
+            .map(|row| {
+                (0..=samples)
+                    .map(|col| {
+                        let (x, y) = (col as f32 / half - 1.0, row as f32 / half - 1.0);
+                        1.0 - x.hypot(y)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn hills_have_closed_rings_at_each_height() {
+        let mut image = Image::new(100, 100);
+        image.draw_contours(&hill(20), &[0.5], COLORS[2]).unwrap();
+        let segments = image.to_segments(0.1);
+        assert!(segments.len() > 8);
+
+        // One line, each piece starting where the last one ended, and
+        // coming back to where it started.
+        let near =
+            |a: (f32, f32), b: (f32, f32)| (a.0 - b.0).abs() < 1e-3 && (a.1 - b.1).abs() < 1e-3;
+        for pair in segments.windows(2) {
+            assert!(near(pair[0].end, pair[1].start), "{pair:?}");
+        }
+        assert!(near(segments[segments.len() - 1].end, segments[0].start));
+
+        // Half way up the hill is half way out from the middle.
+        for segment in &segments {
+            let distance = (segment.start.0 - 50.0).hypot(segment.start.1 - 50.0);
+            assert!((distance - 25.0).abs() < 1.0, "{segment:?}");
+        }
+
+        // Nothing crosses a level above the top of the hill.
+        let mut image = Image::new(100, 100);
+        image.draw_contours(&hill(20), &[2.0], COLORS[2]).unwrap();
+        assert_eq!(image.last_element(), None);
+    }
+
+    #[test]
+    fn slopes_have_straight_contours_where_they_cross_each_level() {
+        let mut image = Image::new(100, 50);
+        // Rising from 0 on the left to 4 on the right.
+        let field: Vec<Vec<f32>> = (0..3)
+            .map(|_| (0..5).map(|col| col as f32).collect())
+            .collect();
+        image.draw_contours(&field, &[1.0, 2.5], COLORS[2]).unwrap();
+        assert_eq!(image.element_ids().len(), 1);
+        let mut xs: Vec<f32> = image
+            .to_segments(0.1)
+            .iter()
+            .flat_map(|segment| {
+                assert_eq!(segment.start.0, segment.end.0, "{segment:?}");
+                [segment.start.0]
+            })
+            .collect();
+        xs.dedup();
+        assert_eq!(xs, [25.0, 62.5]);
+
+        assert!(image
+            .draw_contours(&[vec![1.0, 2.0]], &[1.0], COLORS[2])
+            .is_err());
+        assert!(image
+            .draw_contours(&[vec![1.0, 2.0], vec![1.0]], &[1.0], COLORS[2])
+            .is_err());
+        assert!(image.draw_contours(&field, &[f32::NAN], COLORS[2]).is_err());
+        assert_eq!(image.element_ids().len(), 1);
+    }
+}
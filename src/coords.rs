@@ -0,0 +1,317 @@
+use crate::{
+    f32_to_i32_saturating, get_end_coordinates_rounded, normalize_direction, u32_to_f32, Corner,
+    Image, IntoColor,
+};
+use resvg::tiny_skia;
+
+/// A point as fractions of the image's width and height.
+type Fractions = (f32, f32);
+
+impl Image {
+    /// Choose where (0, 0) is, and which way y goes, for everything drawn and
+    /// measured on the image.
+    ///
+    /// `origin` is the corner of the image at (0, 0). x always increases to
+    /// the right, and y increases upwards if `y_up` is true, or downwards if
+    /// it is false. The default is `(Corner::TopLeft, false)`, as in SVG;
+    /// `(Corner::BottomLeft, true)` gives the usual axes from maths, with the
+    /// whole image at positive coordinates. With other choices, part or all
+    /// of the image is at negative coordinates: with the origin at the top
+    /// left and y going up, the image is from y = -height to y = 0.
+    ///
+    /// Directions keep their meaning on the finished image: 0 is always
+    /// straight up the image and 90 is always to the right, whichever way y
+    /// goes. Every point given to or returned by a draw call, and every area
+    /// from `element_bounds`, `content_bounds` and `stats`, uses these
+    /// coordinates, as does clamping to the image with `set_clamp_to_bounds`.
+    /// Anything that describes the image's edges, like `overflow` and
+    /// [`Margins`](crate::Margins), still goes by the image's top, bottom,
+    /// left and right.
+    ///
+    /// This applies to the whole image, including anything already drawn,
+    /// so it is usually set once, before drawing anything.
+    ///
+    /// ```rs
+    /// let mut image = Image::new(100, 100);
+    /// image.set_coordinate_system(Corner::BottomLeft, true);
+    /// // A line from near the bottom-left corner, going up.
+    /// let end = image.draw_simple_line(10, 10, 0, 50, COLORS[1])?;
+    /// assert_eq!(end, (10, 60));
+    /// ```
+    pub fn set_coordinate_system(&mut self, origin: Corner, y_up: bool) {
+        self.coordinate_system = (origin, y_up);
+        self.mark_changed();
+    }
+
+    /// The origin and direction of y set by `set_coordinate_system`.
+    pub fn coordinate_system(&self) -> (Corner, bool) {
+        self.coordinate_system
+    }
+
+    /// The transform from drawing coordinates to the image's own coordinates,
+    /// with (0, 0) at the top left and y going down.
+    pub(crate) fn coordinate_transform(&self) -> tiny_skia::Transform {
+        let (origin, y_up) = self.coordinate_system;
+        let x = match origin {
+            Corner::TopLeft | Corner::BottomLeft => 0.0,
+            Corner::TopRight | Corner::BottomRight => self.width as f32,
+        };
+        let y = match origin {
+            Corner::TopLeft | Corner::TopRight => 0.0,
+            Corner::BottomLeft | Corner::BottomRight => self.height as f32,
+        };
+        let scale = self.units_per_step as f32;
+        let y_scale = if y_up { -scale } else { scale };
+        tiny_skia::Transform::from_row(scale, 0.0, 0.0, y_scale, x, y)
+    }
+
+    /// Whether two precise points end up on the same whole-number point once
+    /// they are rounded with the image's rounding (see `set_rounding`), as
+    /// happens to every point drawn. This helps find tight geometry that
+    /// would collapse, such as a line that would be drawn with no length.
+    ///
+    /// Points close together can still round apart if they are either side
+    /// of a halfway point, so this depends on where the points are, not just
+    /// how far apart they are.
+    ///
+    /// ```rs
+    /// assert!(image.would_collapse((10.1, 20.0), (10.4, 20.0)));
+    /// assert!(!image.would_collapse((10.1, 20.0), (12.1, 20.0)));
+    /// ```
+    pub fn would_collapse(&self, p1: (f32, f32), p2: (f32, f32)) -> bool {
+        let round = |(x, y): (f32, f32)| {
+            (
+                f32_to_i32_saturating(x, self.rounding),
+                f32_to_i32_saturating(y, self.rounding),
+            )
+        };
+        round(p1) == round(p2)
+    }
+
+    /// Like `draw_simple_line`, but with the start given as fractions of the
+    /// image's size and the length as a fraction of its diagonal, so the
+    /// same call draws the same layout on an image of any size.
+    ///
+    /// `x_pct` goes from 0.0 at the image's smallest x to 1.0 at its largest
+    /// x, and `y_pct` the same for y, so with the default coordinate system
+    /// (0.0, 0.0) is the top left and (1.0, 1.0) the bottom right.
+    /// `length_pct` is a fraction of the length of the image's diagonal, so
+    /// 1.0 reaches from corner to corner. Values outside 0.0 to 1.0 are fine,
+    /// and are off the image. The start and length are rounded to whole
+    /// units (see `set_rounding`) before drawing, like any other line.
+    ///
+    /// Returns where the line ends, both as fractions in the same way as the
+    /// start and in units (as `draw_simple_line` returns it), or an error if
+    /// any fraction is NaN or infinite.
+    ///
+    /// ```rs
+    /// let mut image = Image::new(200, 100);
+    /// // From the middle of the left edge, a quarter of the way across.
+    /// let (end_pct, end) = image.draw_line_pct(0.0, 0.5, 90, 0.25, COLORS[1])?;
+    /// assert_eq!(end, (56, 50));
+    /// assert_eq!(end_pct, (0.28, 0.5));
+    /// ```
+    pub fn draw_line_pct(
+        &mut self,
+        x_pct: f32,
+        y_pct: f32,
+        direction: i32,
+        length_pct: f32,
+        color: impl IntoColor,
+    ) -> Result<(Fractions, (i32, i32)), String> {
+        for (name, value) in [("x", x_pct), ("y", y_pct), ("length", length_pct)] {
+            if !value.is_finite() {
+                return Err(format!(
+                    "The {name} fraction must be a finite number, but it was {value}"
+                ));
+            }
+        }
+
+        let (min_x, min_y, _, _) = self.drawing_area();
+        let (width, height) = self.get_dimensions_in_steps();
+        let (width, height) = (u32_to_f32(width), u32_to_f32(height));
+        let x = min_x.saturating_add(f32_to_i32_saturating(x_pct * width, self.rounding));
+        let y = min_y.saturating_add(f32_to_i32_saturating(y_pct * height, self.rounding));
+        let length = f32_to_i32_saturating(length_pct * width.hypot(height), self.rounding);
+
+        let (end_x, end_y) = self.draw_simple_line(x, y, direction, length, color)?;
+        let end_pct = (
+            (end_x as f32 - min_x as f32) / width,
+            (end_y as f32 - min_y as f32) / height,
+        );
+        Ok((end_pct, (end_x, end_y)))
+    }
+
+    /// The area covered by the image in drawing coordinates, as
+    /// `(min_x, min_y, max_x, max_y)`.
+    pub(crate) fn drawing_area(&self) -> (i32, i32, i32, i32) {
+        let (width, height) = self.get_dimensions_in_steps();
+        let width = i32::try_from(width).unwrap_or(i32::MAX);
+        let height = i32::try_from(height).unwrap_or(i32::MAX);
+        let (origin, y_up) = self.coordinate_system;
+
+        let min_x = match origin {
+            Corner::TopLeft | Corner::BottomLeft => 0,
+            Corner::TopRight | Corner::BottomRight => -width,
+        };
+        let at_top = matches!(origin, Corner::TopLeft | Corner::TopRight);
+        // The image is at positive y when y goes away from the origin's edge.
+        let min_y = if at_top != y_up { 0 } else { -height };
+        (min_x, min_y, min_x + width, min_y + height)
+    }
+
+    /// Where a line from (`x`, `y`) ends, in drawing coordinates, with the
+    /// direction measured on the finished image. On an image with the
+    /// default coordinate system and rounding, this is exactly
+    /// `get_end_coordinates`, which goes through the same function.
+    pub(crate) fn line_end(&self, x: i32, y: i32, direction: i32, length: i32) -> (i32, i32) {
+        let (_, y_up) = self.coordinate_system;
+        // Turning y upside down mirrors directions top to bottom.
+        let direction = if y_up {
+            normalize_direction(180 - normalize_direction(direction))
+        } else {
+            direction
+        };
+        get_end_coordinates_rounded(x, y, direction, length, self.rounding)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{get_end_coordinates, Corner, Image, Rounding, COLORS};
+
+    #[test]
+    fn lines_end_where_get_end_coordinates_says() {
+        let starts = [(0, 0), (50, 50), (-37, 113), (70_000, -70_000)];
+        let lengths = (-60..=60).chain([-100_000, 1_000, 65_535, 65_536, 1 << 20]);
+        for length in lengths {
+            let mut image = Image::new(100, 100);
+            for (x, y) in starts {
+                for direction in 0..360 {
+                    assert_eq!(
+                        image.draw_simple_line(x, y, direction, length, COLORS[1]),
+                        Ok(get_end_coordinates(x, y, direction, length)),
+                        "from ({x}, {y}), direction {direction}, length {length}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn points_land_where_each_coordinate_system_puts_them() {
+        let corners = [
+            Corner::TopLeft,
+            Corner::TopRight,
+            Corner::BottomLeft,
+            Corner::BottomRight,
+        ];
+        for origin in corners {
+            for y_up in [false, true] {
+                let mut image = Image::new(100, 50);
+                image.set_coordinate_system(origin, y_up);
+                assert_eq!(image.coordinate_system(), (origin, y_up));
+
+                // 10 across and 20 along y from the origin, on the image.
+                let left = matches!(origin, Corner::TopLeft | Corner::BottomLeft);
+                let top = matches!(origin, Corner::TopLeft | Corner::TopRight);
+                let x = if left { 10 } else { -10 };
+                let y = if top != y_up { 20 } else { -20 };
+                let expected_x = if left { 10 } else { 90 };
+                let expected_y = if top { 20 } else { 30 };
+
+                // A short line up the image, to mark the point.
+                let end = image.draw_simple_line(x, y, 0, 4, COLORS[7]).unwrap();
+                assert_eq!(end, (x, if y_up { y + 4 } else { y - 4 }));
+
+                let context = format!("{origin:?}, y up {y_up}");
+                let pixmap = image.render().unwrap();
+                let lit: Vec<(i32, i32)> = (0..pixmap.height())
+                    .flat_map(|py| (0..pixmap.width()).map(move |px| (px, py)))
+                    .filter(|&(px, py)| pixmap.pixel(px, py).unwrap().red() > 64)
+                    .map(|(px, py)| (px as i32, py as i32))
+                    .collect();
+                let expected: Vec<(i32, i32)> = (expected_y - 4..expected_y)
+                    .flat_map(|py| [(expected_x - 1, py), (expected_x, py)])
+                    .collect();
+                assert_eq!(lit, expected, "{context}");
+
+                // Bounds come back in the same coordinates, covering the line
+                // and its stroke.
+                let (min_x, min_y, max_x, max_y) = image.content_bounds().unwrap();
+                let (low_y, high_y) = (y.min(end.1), y.max(end.1));
+                assert!(min_x < x && x < max_x, "{context}");
+                assert!(min_y <= low_y && high_y <= max_y, "{context}");
+                assert!(max_y - min_y <= 6, "{context}");
+            }
+        }
+    }
+
+    #[test]
+    fn points_a_fraction_of_a_pixel_apart_collapse() {
+        let image = Image::new(100, 100);
+        // 0.3 apart, across, down, and diagonally.
+        assert!(image.would_collapse((10.1, 20.0), (10.4, 20.0)));
+        assert!(image.would_collapse((10.0, 20.1), (10.0, 20.4)));
+        assert!(image.would_collapse((-5.2, -5.2), (-5.0, -4.99)));
+        assert!(image.would_collapse((3.0, 3.0), (3.0, 3.0)));
+        // 2 apart.
+        assert!(!image.would_collapse((10.1, 20.0), (12.1, 20.0)));
+        assert!(!image.would_collapse((10.0, 20.0), (10.0, 18.0)));
+        assert!(!image.would_collapse((-5.2, -5.2), (-3.8, -3.8)));
+    }
+
+    #[test]
+    fn collapsing_depends_on_the_rounding() {
+        // 0.3 apart, but either side of 10.5.
+        let (p1, p2) = ((10.4, 0.0), (10.7, 0.0));
+        let mut image = Image::new(100, 100);
+        assert!(!image.would_collapse(p1, p2));
+        image.set_rounding(Rounding::Truncate);
+        assert!(image.would_collapse(p1, p2));
+        assert!(!image.would_collapse((9.9, 0.0), (10.2, 0.0)));
+        image.set_rounding(Rounding::HalfToEven);
+        assert!(image.would_collapse((10.5, 0.0), (10.2, 0.0)));
+        assert!(!image.would_collapse((11.5, 0.0), (11.2, 0.0)));
+    }
+
+    #[test]
+    fn fractions_draw_the_same_layout_at_any_size() {
+        for scale in [1, 2, 5] {
+            let mut image = Image::new(200 * scale, 100 * scale);
+            let (end_pct, end) = image.draw_line_pct(0.0, 0.5, 90, 0.25, COLORS[1]).unwrap();
+            // A quarter of the diagonal, which is 223.6 on the smallest image.
+            let across = (223.6 * 0.25 * scale as f32).round() as i32;
+            assert_eq!(end, (across, 50 * scale as i32));
+            assert!((end_pct.0 - 0.28).abs() < 0.005, "{end_pct:?}");
+            assert_eq!(end_pct.1, 0.5);
+        }
+
+        // Corner to corner.
+        let mut image = Image::new(300, 300);
+        let (end_pct, end) = image.draw_line_pct(0.0, 1.0, 45, 1.0, COLORS[1]).unwrap();
+        assert_eq!((end_pct, end), ((1.0, 0.0), (300, 0)));
+    }
+
+    #[test]
+    fn fractions_go_from_the_smallest_coordinates_to_the_largest() {
+        let mut image = Image::new(200, 100);
+        image.set_coordinate_system(Corner::BottomLeft, true);
+        // With y going up, y_pct 0.0 is the bottom of the image.
+        let (_, end) = image.draw_line_pct(0.5, 0.0, 0, 0.1, COLORS[1]).unwrap();
+        assert_eq!(end, (100, 22));
+
+        image.set_coordinate_system(Corner::TopRight, false);
+        let (end_pct, end) = image.draw_line_pct(0.0, 0.0, 90, 0.5, COLORS[1]).unwrap();
+        assert_eq!(end, (-88, 0));
+        assert!((end_pct.0 - 0.56).abs() < 0.005, "{end_pct:?}");
+
+        let before = image.element_ids().len();
+        for bad in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+            assert!(image.draw_line_pct(bad, 0.0, 90, 0.5, COLORS[1]).is_err());
+            assert!(image.draw_line_pct(0.0, bad, 90, 0.5, COLORS[1]).is_err());
+            assert!(image.draw_line_pct(0.0, 0.0, 90, bad, COLORS[1]).is_err());
+        }
+        assert_eq!(image.element_ids().len(), before);
+    }
+}
@@ -0,0 +1,582 @@
+use crate::{Color, Image, COLORS, COLOR_NAMES};
+use resvg::usvg::NodeExt;
+use resvg::{tiny_skia, usvg};
+use std::fmt;
+
+/// How far apart two points (or two stroke widths) can be, in units, and
+/// still count as the same. Coordinates are kept to the nearest 1/256 of a
+/// unit, so this allows for a couple of those steps of difference.
+const TOLERANCE: f32 = 1.0 / 128.0;
+
+/// One difference between an image and a reference image, from
+/// [`diff_elements`].
+///
+/// Elements are numbered from 0, in the order they were drawn.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ElementDiff {
+    /// An element of the reference that nothing in the image matches.
+    Missing {
+        /// The number of the element in the reference.
+        expected: usize,
+        /// What the element is, such as "a blue line from (10, 10) to (60, 10)".
+        description: String,
+    },
+    /// An element of the image that nothing in the reference matches.
+    Extra {
+        /// The number of the element in the image.
+        actual: usize,
+        /// What the element is.
+        description: String,
+    },
+    /// An element of the image that matches one in the reference, but not
+    /// exactly.
+    Mismatched {
+        /// The number of the element in the image.
+        actual: usize,
+        /// The number of the matching element in the reference.
+        expected: usize,
+        /// How the elements differ, such as "is red, expected blue".
+        problems: Vec<String>,
+    },
+}
+
+impl fmt::Display for ElementDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ElementDiff::Missing {
+                expected,
+                ref description,
+            } => write!(
+                f,
+                "missing {description} (element {expected} of the reference)"
+            ),
+            ElementDiff::Extra {
+                actual,
+                ref description,
+            } => write!(f, "element {actual} is extra: {description}"),
+            ElementDiff::Mismatched {
+                actual,
+                expected,
+                ref problems,
+            } => {
+                write!(f, "element {actual} ")?;
+                if actual != expected {
+                    write!(f, "(element {expected} of the reference) ")?;
+                }
+                write!(f, "{}", problems.join(", and "))
+            }
+        }
+    }
+}
+
+/// What kind of thing an element is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Kind {
+    Line,
+    Polyline,
+    Shape,
+    Curve,
+    Group,
+    Text,
+    Picture,
+}
+
+impl Kind {
+    fn name(self) -> &'static str {
+        match self {
+            Kind::Line => "a line",
+            Kind::Polyline => "a line with several segments",
+            Kind::Shape => "a filled shape",
+            Kind::Curve => "a curve",
+            Kind::Group => "a group of shapes",
+            Kind::Text => "some text",
+            Kind::Picture => "a picture",
+        }
+    }
+}
+
+/// The parts of an element that are compared.
+struct Description {
+    kind: Kind,
+    colors: Vec<Color>,
+    width: Option<f32>,
+    points: Vec<(f32, f32)>,
+}
+
+/// Compare the elements of `image` with those of `reference`, for checking a
+/// drawing against the one it should be.
+///
+/// Each element is described by its kind (a line, a line with several
+/// segments, a filled shape, a curve, a group of shapes, text, or a
+/// picture), its colors, its stroke width, and its points (after any
+/// transforms). Points and widths within 1/128 of a unit count as the same.
+///
+/// Elements are paired up greedily, most alike first: pairs of the same
+/// kind come before pairs of different kinds, and then pairs whose points
+/// are closer together come first, with ties going to the elements drawn
+/// first. Each element of the reference then gives a `Mismatched` if it was
+/// paired with an element that differs from it, or a `Missing` if it
+/// wasn't paired at all, in the order they were drawn. Last come `Extra`s
+/// for anything in `image` left over. Elements that match exactly give
+/// nothing, so identical drawings give an empty list.
+///
+/// The result is always the same for the same images, and each difference
+/// can be printed for people to read.
+///
+/// ```rs
+/// let mut reference = Image::new(100, 100);
+/// reference.draw_simple_line(10, 10, 90, 50, COLORS[1])?;
+/// reference.draw_simple_line(10, 20, 90, 50, COLORS[1])?;
+///
+/// let mut image = Image::new(100, 100);
+/// image.draw_simple_line(10, 10, 90, 50, COLORS[4])?;
+///
+/// let diffs = diff_elements(&image, &reference);
+/// assert_eq!(diffs[0].to_string(), "element 0 is red, expected blue");
+/// assert_eq!(
+///     diffs[1].to_string(),
+///     "missing a blue line from (10, 20) to (60, 20) (element 1 of the reference)",
+/// );
+/// ```
+pub fn diff_elements(image: &Image, reference: &Image) -> Vec<ElementDiff> {
+    let actual: Vec<Description> = image
+        .elements
+        .iter()
+        .map(|(_, node)| describe(node))
+        .collect();
+    let expected: Vec<Description> = reference
+        .elements
+        .iter()
+        .map(|(_, node)| describe(node))
+        .collect();
+
+    let mut pairs: Vec<(bool, f32, usize, usize)> = Vec::new();
+    for (e, wanted) in expected.iter().enumerate() {
+        for (a, drawn) in actual.iter().enumerate() {
+            pairs.push((drawn.kind != wanted.kind, distance(drawn, wanted), e, a));
+        }
+    }
+    pairs.sort_by(|x, y| {
+        x.0.cmp(&y.0)
+            .then(x.1.total_cmp(&y.1))
+            .then(x.2.cmp(&y.2))
+            .then(x.3.cmp(&y.3))
+    });
+
+    let mut matched: Vec<Option<usize>> = vec![None; expected.len()];
+    let mut used = vec![false; actual.len()];
+    for (_, _, e, a) in pairs {
+        if matched[e].is_none() && !used[a] {
+            matched[e] = Some(a);
+            used[a] = true;
+        }
+    }
+
+    let mut diffs = Vec::new();
+    for (e, wanted) in expected.iter().enumerate() {
+        match matched[e] {
+            Some(a) => {
+                let problems = problems(&actual[a], wanted);
+                if !problems.is_empty() {
+                    diffs.push(ElementDiff::Mismatched {
+                        actual: a,
+                        expected: e,
+                        problems,
+                    });
+                }
+            }
+            None => diffs.push(ElementDiff::Missing {
+                expected: e,
+                description: wanted.to_string(),
+            }),
+        }
+    }
+    for (a, drawn) in actual.iter().enumerate() {
+        if !used[a] {
+            diffs.push(ElementDiff::Extra {
+                actual: a,
+                description: drawn.to_string(),
+            });
+        }
+    }
+    diffs
+}
+
+/// Describe an element by what it looks like.
+fn describe(element: &usvg::Node) -> Description {
+    let mut colors = Vec::new();
+    let mut width = None;
+    let mut points = Vec::new();
+    let mut shapes = 0;
+    let mut kind = Kind::Group;
+
+    for node in element.descendants() {
+        let transform = node.abs_transform();
+        let mut add_point = |mut point: tiny_skia::Point| {
+            transform.map_point(&mut point);
+            points.push((point.x, point.y));
+        };
+
+        match *node.borrow() {
+            usvg::NodeKind::Path(ref path) => {
+                shapes += 1;
+                let paints = path.fill.iter().map(|fill| &fill.paint);
+                for paint in paints.chain(path.stroke.iter().map(|stroke| &stroke.paint)) {
+                    if let usvg::Paint::Color(color) = *paint {
+                        let color = Color::from_usvg(color);
+                        if !colors.contains(&color) {
+                            colors.push(color);
+                        }
+                    }
+                }
+                if let Some(ref stroke) = path.stroke {
+                    // The width as drawn, after any scaling.
+                    let scale = (transform.sx * transform.sy - transform.kx * transform.ky)
+                        .abs()
+                        .sqrt();
+                    width = width.or(Some(stroke.width.get() * scale));
+                }
+
+                kind = path_kind(path);
+                for segment in path.data.segments() {
+                    match segment {
+                        tiny_skia::PathSegment::MoveTo(p) | tiny_skia::PathSegment::LineTo(p) => {
+                            add_point(p)
+                        }
+                        tiny_skia::PathSegment::QuadTo(p1, p) => {
+                            add_point(p1);
+                            add_point(p);
+                        }
+                        tiny_skia::PathSegment::CubicTo(p1, p2, p) => {
+                            add_point(p1);
+                            add_point(p2);
+                            add_point(p);
+                        }
+                        tiny_skia::PathSegment::Close => {}
+                    }
+                }
+            }
+            usvg::NodeKind::Text(_) => {
+                shapes += 1;
+                kind = Kind::Text;
+            }
+            usvg::NodeKind::Image(ref picture) => {
+                shapes += 1;
+                kind = Kind::Picture;
+                let rect = picture.view_box.rect;
+                add_point(tiny_skia::Point::from_xy(rect.left(), rect.top()));
+                add_point(tiny_skia::Point::from_xy(rect.right(), rect.bottom()));
+            }
+            usvg::NodeKind::Group(_) => {}
+        }
+    }
+
+    Description {
+        kind: if shapes == 1 { kind } else { Kind::Group },
+        colors,
+        width,
+        points,
+    }
+}
+
+fn path_kind(path: &usvg::Path) -> Kind {
+    let curved = path.data.segments().any(|segment| {
+        matches!(
+            segment,
+            tiny_skia::PathSegment::QuadTo(..) | tiny_skia::PathSegment::CubicTo(..)
+        )
+    });
+    if curved {
+        Kind::Curve
+    } else if path.fill.is_some() {
+        Kind::Shape
+    } else if path.data.len() == 2 {
+        Kind::Line
+    } else {
+        Kind::Polyline
+    }
+}
+
+/// How far apart two elements are: the furthest any side of the box around
+/// one element's points is from the same side of the other's.
+fn distance(a: &Description, b: &Description) -> f32 {
+    match (bounds(&a.points), bounds(&b.points)) {
+        (Some(a), Some(b)) => (0..4).map(|i| (a[i] - b[i]).abs()).fold(0.0, f32::max),
+        (None, None) => 0.0,
+        _ => f32::INFINITY,
+    }
+}
+
+fn bounds(points: &[(f32, f32)]) -> Option<[f32; 4]> {
+    let (&(x, y), rest) = points.split_first()?;
+    Some(
+        rest.iter()
+            .fold([x, y, x, y], |[left, top, right, bottom], &(x, y)| {
+                [left.min(x), top.min(y), right.max(x), bottom.max(y)]
+            }),
+    )
+}
+
+/// How `actual` differs from `expected`, or nothing if they match.
+fn problems(actual: &Description, expected: &Description) -> Vec<String> {
+    let mut problems = Vec::new();
+    if actual.kind != expected.kind {
+        problems.push(format!(
+            "is {}, expected {}",
+            actual.kind.name(),
+            expected.kind.name()
+        ));
+    }
+    if actual.colors != expected.colors {
+        problems.push(format!(
+            "is {}, expected {}",
+            color_list(&actual.colors),
+            color_list(&expected.colors)
+        ));
+    }
+    match (actual.width, expected.width) {
+        (Some(a), Some(e)) if (a - e).abs() > TOLERANCE => {
+            problems.push(format!("is {} wide, expected {}", number(a), number(e)))
+        }
+        _ => {}
+    }
+
+    if actual.points.len() != expected.points.len() {
+        problems.push(format!(
+            "has {} points, expected {}",
+            actual.points.len(),
+            expected.points.len()
+        ));
+    } else if let Some((index, (a, e))) = actual
+        .points
+        .iter()
+        .zip(&expected.points)
+        .enumerate()
+        .find(|(_, (a, e))| (a.0 - e.0).abs() > TOLERANCE || (a.1 - e.1).abs() > TOLERANCE)
+    {
+        let position = match (index, actual.kind) {
+            (0, Kind::Line) => "starts".to_string(),
+            (1, Kind::Line) => "ends".to_string(),
+            _ => format!("has point {index}"),
+        };
+        problems.push(format!(
+            "{position} at {}, expected {}",
+            point(*a),
+            point(*e)
+        ));
+    }
+    problems
+}
+
+impl fmt::Display for Description {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = self.kind.name();
+        let (article, noun) = name.split_once(' ').unwrap_or(("", name));
+        if self.colors.is_empty() {
+            write!(f, "{name}")?;
+        } else {
+            write!(f, "{article} {} {noun}", color_list(&self.colors))?;
+        }
+
+        match (self.kind, &self.points[..]) {
+            (Kind::Line, &[from, to]) => write!(f, " from {} to {}", point(from), point(to)),
+            (_, points) => match bounds(points) {
+                Some([left, top, right, bottom]) => write!(
+                    f,
+                    " between {} and {}",
+                    point((left, top)),
+                    point((right, bottom))
+                ),
+                None => Ok(()),
+            },
+        }
+    }
+}
+
+/// A color's name from `COLOR_NAMES`, or `#rrggbb` if it isn't one of `COLORS`.
+fn color_name(color: Color) -> String {
+    match COLORS.iter().position(|&other| other == color) {
+        Some(index) => COLOR_NAMES[index].to_string(),
+        None => format!("#{:02x}{:02x}{:02x}", color.red, color.green, color.blue),
+    }
+}
+
+fn color_list(colors: &[Color]) -> String {
+    match colors {
+        [] => "uncolored".to_string(),
+        [color] => color_name(*color),
+        [rest @ .., last] => {
+            let rest: Vec<String> = rest.iter().map(|&color| color_name(color)).collect();
+            format!("{} and {}", rest.join(", "), color_name(*last))
+        }
+    }
+}
+
+fn point((x, y): (f32, f32)) -> String {
+    format!("({}, {})", number(x), number(y))
+}
+
+/// A number rounded to 2 decimal places, without any trailing zeros.
+fn number(value: f32) -> String {
+    let rounded = (value * 100.0).round() / 100.0;
+    // Avoid printing "-0".
+    format!("{}", rounded + 0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff_elements, ElementDiff};
+    use crate::{FillRule, Image, StrokeStyle, COLORS};
+
+    fn messages(image: &Image, reference: &Image) -> Vec<String> {
+        diff_elements(image, reference)
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn the_same_drawing_in_any_order_has_no_differences() {
+        let mut reference = Image::new(100, 100);
+        reference
+            .draw_simple_line(10, 10, 90, 50, COLORS[1])
+            .unwrap();
+        reference.draw_ring(50, 50, 20, 10, COLORS[4]).unwrap();
+        reference
+            .fill_polygon(&[(0, 0), (20, 0), (0, 20)], COLORS[3], FillRule::NonZero)
+            .unwrap();
+
+        let mut image = Image::new(100, 100);
+        image
+            .fill_polygon(&[(0, 0), (20, 0), (0, 20)], COLORS[3], FillRule::NonZero)
+            .unwrap();
+        image.draw_ring(50, 50, 20, 10, COLORS[4]).unwrap();
+        image.draw_simple_line(10, 10, 90, 50, COLORS[1]).unwrap();
+
+        assert_eq!(diff_elements(&image, &reference), vec![]);
+        assert_eq!(diff_elements(&reference, &reference), vec![]);
+        assert_eq!(
+            diff_elements(&Image::new(10, 10), &Image::new(10, 10)),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn differences_say_what_was_expected() {
+        let mut reference = Image::new(100, 100);
+        reference
+            .draw_simple_line(10, 10, 90, 50, COLORS[1])
+            .unwrap();
+        reference
+            .draw_simple_line(10, 20, 90, 50, COLORS[1])
+            .unwrap();
+        reference
+            .draw_simple_line(10, 30, 90, 50, COLORS[1])
+            .unwrap();
+
+        let mut image = Image::new(100, 100);
+        // Matches the second line, but is longer and thicker.
+        let thick = StrokeStyle {
+            width: 3.0,
+            ..StrokeStyle::default()
+        };
+        image
+            .draw_styled_line(10, 20, 90, 55, COLORS[1], &thick)
+            .unwrap();
+        image.draw_simple_line(10, 10, 90, 50, COLORS[4]).unwrap();
+        image.draw_ring(80, 80, 10, 5, COLORS[2]).unwrap();
+
+        let diffs = diff_elements(&image, &reference);
+        assert_eq!(
+            diffs[0],
+            ElementDiff::Mismatched {
+                actual: 1,
+                expected: 0,
+                problems: vec!["is red, expected blue".to_string()],
+            }
+        );
+        assert_eq!(
+            messages(&image, &reference),
+            [
+                "element 1 (element 0 of the reference) is red, expected blue",
+                "element 0 (element 1 of the reference) is 3 wide, expected 1, and ends at (65, 20), expected (60, 20)",
+                "element 2 is a curve, expected a line, and is cyan, expected blue, and has 34 points, expected 2",
+            ]
+        );
+
+        // Left over elements, in the reference or the image.
+        let mut ring = Image::new(100, 100);
+        ring.draw_ring(80, 80, 10, 5, COLORS[2]).unwrap();
+        assert_eq!(
+            messages(&Image::new(100, 100), &reference)[2],
+            "missing a blue line from (10, 30) to (60, 30) (element 2 of the reference)"
+        );
+        assert_eq!(
+            messages(&ring, &Image::new(100, 100)),
+            ["element 0 is extra: a cyan curve between (70, 70) and (90, 90)"]
+        );
+    }
+
+    #[test]
+    fn kinds_and_point_counts_are_compared() {
+        let mut reference = Image::new(100, 100);
+        reference
+            .draw_styled_polyline(
+                &[(10, 10), (50, 50), (90, 10)],
+                COLORS[7],
+                &StrokeStyle::default(),
+            )
+            .unwrap();
+        let mut image = Image::new(100, 100);
+        image
+            .fill_polygon(
+                &[(10, 10), (50, 50), (90, 10), (50, 0)],
+                COLORS[7],
+                FillRule::NonZero,
+            )
+            .unwrap();
+        assert_eq!(
+            messages(&image, &reference),
+            ["element 0 is a filled shape, expected a line with several segments, and has 4 points, expected 3"]
+        );
+    }
+
+    #[test]
+    fn tiny_rounding_differences_are_ignored() {
+        let mut reference = Image::new(100, 100);
+        reference
+            .draw_simple_line(20, 10, 180, 50, COLORS[1])
+            .unwrap();
+
+        // The same line, worked out by reflecting a line across a diagonal,
+        // which can leave it a hair's breadth from whole units.
+        let mut image = Image::new(100, 100);
+        image.draw_simple_line(10, 20, 90, 50, COLORS[1]).unwrap();
+        let line = image.last_element().unwrap();
+        image.draw_reflected(line, (0, 0), (7, 7)).unwrap();
+        assert!(image.remove_element(line));
+        assert_eq!(diff_elements(&image, &reference), vec![]);
+
+        let within = StrokeStyle {
+            width: 1.005,
+            ..StrokeStyle::default()
+        };
+        let outside = StrokeStyle {
+            width: 1.02,
+            ..StrokeStyle::default()
+        };
+        let mut image = Image::new(100, 100);
+        image
+            .draw_styled_line(20, 10, 180, 50, COLORS[1], &within)
+            .unwrap();
+        assert_eq!(diff_elements(&image, &reference), vec![]);
+        let mut image = Image::new(100, 100);
+        image
+            .draw_styled_line(20, 10, 180, 50, COLORS[1], &outside)
+            .unwrap();
+        assert_eq!(
+            messages(&image, &reference),
+            ["element 0 is 1.02 wide, expected 1"]
+        );
+    }
+}
@@ -0,0 +1,124 @@
+use crate::Image;
+
+impl Image {
+    /// Creates an image that checks and records draw calls, but never
+    /// renders them. This is for quickly testing drawing logic, such as
+    /// checking the end points a program's lines reach, without paying for
+    /// pixels that will never be looked at.
+    ///
+    /// Every draw call does exactly what it does on an image from `new`, so
+    /// it returns the same end points and the same errors, including once
+    /// the drawing reaches a limit set with `set_memory_limit`.
+    ///
+    /// A dry-run image can't be saved or rendered: `save_png`, `save_svg`,
+    /// `png_bytes`, `export`, `snapshot`, `finalize`, `phash`, `rows` and the
+    /// rest return an error.
+    ///
+    /// ```rs
+    /// let mut image = Image::new_dry_run(100, 100);
+    /// let end = image.draw_simple_line(10, 10, 90, 50, COLORS[1])?;
+    /// assert_eq!(end, (60, 10));
+    /// assert_eq!(image.element_ids().len(), 1);
+    /// assert!(image.save_png("image.png").is_err());
+    /// ```
+    pub fn new_dry_run(width: u32, height: u32) -> Image {
+        let mut image = Image::new(width, height);
+        image.dry_run = true;
+        image
+    }
+
+    /// Whether the image was made by `new_dry_run`.
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Returns an error if the image is a dry run, so it has nothing to save.
+    pub(crate) fn check_not_dry_run(&self) -> Result<(), String> {
+        if self.dry_run {
+            return Err("A dry-run image only records draw calls, so it can't be saved".into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FillRule, Image, StrokeStyle, COLORS};
+
+    /// Draw the same things on `image` whatever kind it is, and describe
+    /// everything each draw call returned.
+    fn script(image: &mut Image) -> Vec<String> {
+        let mut results = Vec::new();
+        let mut end = (10, 10);
+        for (direction, length) in [(90, 40), (135, 25), (-30, 60), (400, 7), (180, -15)] {
+            let result = image.draw_simple_line(end.0, end.1, direction, length, COLORS[1]);
+            end = result.clone().unwrap_or(end);
+            results.push(format!("{result:?}"));
+        }
+        results.push(format!("{:?}", image.draw_simple_line(0, 0, 45, 10, 99)));
+        results.push(format!(
+            "{:?}",
+            image.draw_simple_line(0, 0, 45, 10, "mauve")
+        ));
+        let style = StrokeStyle {
+            width: -1.0,
+            ..StrokeStyle::default()
+        };
+        results.push(format!(
+            "{:?}",
+            image.draw_styled_line(5, 5, 0, 10, COLORS[2], &style)
+        ));
+        results.push(format!(
+            "{:?}",
+            image.fill_polygon(&[(0, 0), (50, 10)], COLORS[3], FillRule::NonZero)
+        ));
+
+        image.set_strict(true);
+        results.push(format!(
+            "{:?}",
+            image.draw_simple_line(50, 50, 90, 80, COLORS[1])
+        ));
+        image.set_strict(false);
+        image.set_clamp_to_bounds(true);
+        results.push(format!(
+            "{:?}",
+            image.draw_simple_line(50, 50, 90, 80, COLORS[1])
+        ));
+        image.set_clamp_to_bounds(false);
+
+        image.set_memory_limit(Some(image.estimated_memory_bytes() + 2000));
+        for _ in 0..20 {
+            let result = image.draw_simple_line(20, 20, 45, 30, COLORS[4]);
+            results.push(format!("{result:?}"));
+        }
+        image.set_memory_limit(None);
+
+        results.push(format!("{:?}", image.element_ids().len()));
+        results.push(format!("{:?}", image.content_bounds()));
+        results
+    }
+
+    #[test]
+    fn dry_run_returns_the_same_as_a_real_image() {
+        let real = script(&mut Image::new(100, 100));
+        let dry = script(&mut Image::new_dry_run(100, 100));
+        assert_eq!(real, dry);
+        assert!(real.iter().any(|result| result.contains("memory limit")));
+    }
+
+    #[test]
+    fn dry_run_images_cant_be_saved_or_rendered() {
+        let mut image = Image::new_dry_run(100, 100);
+        image.draw_simple_line(10, 10, 90, 50, COLORS[1]).unwrap();
+        let path = std::env::temp_dir().join("unsvg-dry-run-test.png");
+        assert!(image.save_png(&path).is_err());
+        assert!(image.save_svg(&path).is_err());
+        assert!(image.snapshot().is_err());
+        assert!(image.phash().is_err());
+        assert_eq!(image.rows().count(), 0);
+        assert!(image.to_ansi(40).is_err());
+        assert!(image.verify_render_consistency().is_err());
+        assert!(image.finalize().is_err());
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,419 @@
+use crate::{ElementId, Image};
+use resvg::usvg::NodeExt;
+use resvg::{tiny_skia, usvg};
+
+/// Convert a usvg stroke into the tiny-skia stroke it is rendered with.
+pub(crate) fn skia_stroke(stroke: &usvg::Stroke) -> tiny_skia::Stroke {
+    tiny_skia::Stroke {
+        width: stroke.width.get(),
+        miter_limit: stroke.miterlimit.get(),
+        line_cap: match stroke.linecap {
+            usvg::LineCap::Butt => tiny_skia::LineCap::Butt,
+            usvg::LineCap::Round => tiny_skia::LineCap::Round,
+            usvg::LineCap::Square => tiny_skia::LineCap::Square,
+        },
+        line_join: match stroke.linejoin {
+            usvg::LineJoin::Miter => tiny_skia::LineJoin::Miter,
+            usvg::LineJoin::Round => tiny_skia::LineJoin::Round,
+            usvg::LineJoin::Bevel => tiny_skia::LineJoin::Bevel,
+        },
+        dash: stroke
+            .dasharray
+            .as_ref()
+            .and_then(|dashes| tiny_skia::StrokeDash::new(dashes.clone(), stroke.dashoffset)),
+    }
+}
+
+/// A copy of a path with `f` applied to every point in it (including the
+/// control points of curves).
+pub(crate) fn map_points(
+    path: &tiny_skia::Path,
+    f: impl Fn(tiny_skia::Point) -> tiny_skia::Point,
+) -> Option<tiny_skia::Path> {
+    let mut builder = tiny_skia::PathBuilder::new();
+    for segment in path.segments() {
+        match segment {
+            tiny_skia::PathSegment::MoveTo(p) => {
+                let p = f(p);
+                builder.move_to(p.x, p.y);
+            }
+            tiny_skia::PathSegment::LineTo(p) => {
+                let p = f(p);
+                builder.line_to(p.x, p.y);
+            }
+            tiny_skia::PathSegment::QuadTo(p1, p) => {
+                let (p1, p) = (f(p1), f(p));
+                builder.quad_to(p1.x, p1.y, p.x, p.y);
+            }
+            tiny_skia::PathSegment::CubicTo(p1, p2, p) => {
+                let (p1, p2, p) = (f(p1), f(p2), f(p));
+                builder.cubic_to(p1.x, p1.y, p2.x, p2.y, p.x, p.y);
+            }
+            tiny_skia::PathSegment::Close => builder.close(),
+        }
+    }
+    builder.finish()
+}
+
+/// The area covered by a path, including the width of its stroke, after
+/// applying `transform` (which should already include the path's own transform).
+fn path_bounds(path: &usvg::Path, transform: tiny_skia::Transform) -> Option<tiny_skia::Rect> {
+    let mut rect = match path.fill {
+        Some(_) => (*path.data)
+            .clone()
+            .transform(transform)
+            .map(|fill| fill.bounds()),
+        None => None,
+    };
+
+    if let Some(ref stroke) = path.stroke {
+        let outline = path
+            .data
+            .stroke(&skia_stroke(stroke), 1.0)
+            .and_then(|outline| outline.transform(transform))
+            .map(|outline| outline.bounds());
+        rect = union(rect, outline);
+    }
+
+    rect
+}
+
+fn union(a: Option<tiny_skia::Rect>, b: Option<tiny_skia::Rect>) -> Option<tiny_skia::Rect> {
+    match (a, b) {
+        (Some(a), Some(b)) => tiny_skia::Rect::from_ltrb(
+            a.left().min(b.left()),
+            a.top().min(b.top()),
+            a.right().max(b.right()),
+            a.bottom().max(b.bottom()),
+        ),
+        (a, None) => a,
+        (None, b) => b,
+    }
+}
+
+/// The area covered by a node and everything inside it.
+pub(crate) fn node_bounds(node: &usvg::Node) -> Option<tiny_skia::Rect> {
+    node.descendants().fold(None, |bounds, descendant| {
+        let transform = descendant.abs_transform();
+        let rect = match *descendant.borrow() {
+            usvg::NodeKind::Path(ref path) => path_bounds(path, transform),
+            usvg::NodeKind::Image(ref image) => image.view_box.rect.to_rect().transform(transform),
+            usvg::NodeKind::Group(_) | usvg::NodeKind::Text(_) => None,
+        };
+        union(bounds, rect)
+    })
+}
+
+/// Round a rectangle outwards to whole units, as (min x, min y, max x, max y).
+fn to_int_bounds(rect: tiny_skia::Rect) -> (i32, i32, i32, i32) {
+    (
+        rect.left().floor() as i32,
+        rect.top().floor() as i32,
+        rect.right().ceil() as i32,
+        rect.bottom().ceil() as i32,
+    )
+}
+
+/// Check that a node from outside the crate is something that can be drawn.
+fn check_node(kind: &usvg::NodeKind) -> Result<(), String> {
+    if !kind.transform().is_finite() {
+        return Err("The node's transform must be finite".into());
+    }
+
+    match *kind {
+        usvg::NodeKind::Path(ref path) => {
+            if path.data.len() < 2 {
+                return Err("A path node needs at least two points".into());
+            }
+            if path
+                .data
+                .points()
+                .iter()
+                .any(|point| !(point.x.is_finite() && point.y.is_finite()))
+            {
+                return Err("Every point of a path node must be finite".into());
+            }
+            if let Some(ref stroke) = path.stroke {
+                if !stroke.width.get().is_finite() {
+                    return Err("A path node's stroke width must be finite".into());
+                }
+            }
+        }
+        usvg::NodeKind::Text(ref text) => {
+            if text.chunks.is_empty() {
+                return Err("A text node needs at least one chunk of text".into());
+            }
+        }
+        usvg::NodeKind::Group(_) | usvg::NodeKind::Image(_) => {}
+    }
+
+    Ok(())
+}
+
+impl Image {
+    /// Add a node built directly with `resvg`'s `usvg` types on top of the
+    /// image, for the rare things `unsvg` has no method for (like a filter
+    /// or an embedded picture).
+    ///
+    /// The node becomes an element like any other: it gets an [`ElementId`],
+    /// is inside any pushed clips, and works with `remove_element`,
+    /// `element_bounds`, `content_bounds` and so on. Any clip paths or masks
+    /// it uses are copied and given new ids, so they can't clash with the
+    /// image's own, and a group's id is cleared. Text is turned into paths
+    /// when the image is saved.
+    ///
+    /// This uses `resvg`'s types, so unlike the rest of `unsvg`, code using it
+    /// needs the same version of `resvg` as `unsvg`, and may need changes when
+    /// `unsvg` upgrades it.
+    ///
+    /// Returns an error if the node's transform isn't finite, if a path has
+    /// fewer than two points or any point that isn't finite, if a stroke's
+    /// width isn't finite, or if a text node has no text.
+    ///
+    /// ```rs
+    /// use resvg::{tiny_skia, usvg};
+    ///
+    /// let rect = tiny_skia::Rect::from_xywh(10.0, 10.0, 30.0, 20.0).unwrap();
+    /// let mut path = usvg::Path::new(tiny_skia::PathBuilder::from_rect(rect).into());
+    /// path.fill = Some(usvg::Fill::default());
+    /// let id = image.append_node(usvg::NodeKind::Path(path))?;
+    /// assert_eq!(image.element_bounds(id), Some((10, 10, 40, 30)));
+    /// ```
+    pub fn append_node(&mut self, node: usvg::NodeKind) -> Result<ElementId, String> {
+        check_node(&node)?;
+        let node = self.adopt(&usvg::Node::new(node));
+        self.append_element_node(node)
+    }
+
+    /// The id of the most recently drawn element, or `None` if nothing has
+    /// been drawn (or everything drawn has been removed).
+    ///
+    /// ```rs
+    /// image.draw_simple_line(10, 10, 90, 50, COLORS[1])?;
+    /// let line = image.last_element().unwrap();
+    /// ```
+    pub fn last_element(&self) -> Option<ElementId> {
+        self.elements.last().map(|&(id, _)| id)
+    }
+
+    /// The ids of everything drawn on the image, in the order it was drawn.
+    pub fn element_ids(&self) -> Vec<ElementId> {
+        self.elements.iter().map(|&(id, _)| id).collect()
+    }
+
+    /// Remove a drawn element from the image. Returns `false` if there is no
+    /// element with that id (for instance, because it was already removed).
+    pub fn remove_element(&mut self, id: ElementId) -> bool {
+        match self.elements.iter().position(|&(other, _)| other == id) {
+            Some(index) => {
+                let (_, node) = self.elements.remove(index);
+                self.element_bytes = self
+                    .element_bytes
+                    .saturating_sub(crate::memory::element_bytes(&node));
+                node.detach();
+                self.animations.retain(|&(other, _)| other != id);
+                self.mark_changed();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The node drawn for an element.
+    pub(crate) fn element_node(&self, id: ElementId) -> Option<usvg::Node> {
+        self.elements
+            .iter()
+            .find(|&&(other, _)| other == id)
+            .map(|(_, node)| node.clone())
+    }
+
+    /// The area an element covers on the image, as `(min_x, min_y, max_x, max_y)`.
+    ///
+    /// This includes the full width of strokes (and their caps and joins),
+    /// and is rounded outwards to whole units, so it is the smallest
+    /// rectangle of whole pixels that contains every pixel the element
+    /// touches. Clips and masks are not taken into account.
+    ///
+    /// Returns `None` if the element has been removed, or if it doesn't cover
+    /// any area at all.
+    ///
+    /// ```rs
+    /// image.draw_simple_line(10, 10, 90, 50, COLORS[1])?;
+    /// let bounds = image.element_bounds(image.last_element().unwrap());
+    /// // A line with the default width of 1 covers half a unit either side.
+    /// assert_eq!(bounds, Some((10, 9, 60, 11)));
+    /// ```
+    pub fn element_bounds(&self, id: ElementId) -> Option<(i32, i32, i32, i32)> {
+        self.element_node(id)
+            .and_then(|node| node_bounds(&node))
+            .map(to_int_bounds)
+    }
+
+    /// The area covered by everything drawn on the image, in the same form
+    /// as [`Image::element_bounds`]. The background is not included, so this
+    /// is `None` for an image with nothing drawn on it.
+    pub fn content_bounds(&self) -> Option<(i32, i32, i32, i32)> {
+        self.elements()
+            .fold(None, |bounds, node| union(bounds, node_bounds(&node)))
+            .map(to_int_bounds)
+    }
+
+    /// Like `element_bounds`, but in the image's own coordinates (with (0, 0)
+    /// at the top left and y going down), whatever `set_coordinate_system`
+    /// chose.
+    pub(crate) fn element_bounds_on_image(&self, id: ElementId) -> Option<(i32, i32, i32, i32)> {
+        self.element_node(id)
+            .and_then(|node| node_bounds(&node))
+            .and_then(|rect| rect.transform(self.coordinate_transform()))
+            .map(to_int_bounds)
+    }
+
+    /// Like `content_bounds`, but in the image's own coordinates.
+    pub(crate) fn content_bounds_on_image(&self) -> Option<(i32, i32, i32, i32)> {
+        self.elements()
+            .fold(None, |bounds, node| union(bounds, node_bounds(&node)))
+            .and_then(|rect| rect.transform(self.coordinate_transform()))
+            .map(to_int_bounds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FillRule, Image, StrokeStyle, COLORS};
+
+    /// The area of the pixels that aren't black, as
+    /// `(min_x, min_y, max_x, max_y)` of their edges.
+    fn drawn_area(image: &Image) -> Option<(i32, i32, i32, i32)> {
+        let pixmap = image.render().unwrap();
+        let width = pixmap.width() as i32;
+        let mut area: Option<(i32, i32, i32, i32)> = None;
+        for (index, pixel) in pixmap.pixels().iter().enumerate() {
+            if (pixel.red(), pixel.green(), pixel.blue()) == (0, 0, 0) {
+                continue;
+            }
+            let (x, y) = (index as i32 % width, index as i32 / width);
+            area = Some(match area {
+                None => (x, y, x + 1, y + 1),
+                Some((min_x, min_y, max_x, max_y)) => (
+                    min_x.min(x),
+                    min_y.min(y),
+                    max_x.max(x + 1),
+                    max_y.max(y + 1),
+                ),
+            });
+        }
+        area
+    }
+
+    #[test]
+    fn bounds_match_the_pixels_drawn() {
+        let wide = StrokeStyle {
+            width: 7.0,
+            ..StrokeStyle::default()
+        };
+        let mut images = vec![Image::new(100, 100); 5];
+        images[0]
+            .draw_simple_line(10, 10, 90, 50, COLORS[7])
+            .unwrap();
+        images[1]
+            .draw_simple_line(20, 80, 30, 60, COLORS[7])
+            .unwrap();
+        images[2]
+            .draw_styled_line(50, 50, 135, 30, COLORS[7], &wide)
+            .unwrap();
+        images[3]
+            .draw_styled_line(90, 20, 280, 45, COLORS[7], &wide)
+            .unwrap();
+        images[4]
+            .fill_polygon(&[(15, 30), (70, 5), (60, 90)], COLORS[7], FillRule::NonZero)
+            .unwrap();
+
+        for image in images {
+            let bounds = image.element_bounds(image.last_element().unwrap()).unwrap();
+            let drawn = drawn_area(&image).unwrap();
+            let within_a_pixel = [
+                (bounds.0, drawn.0),
+                (bounds.1, drawn.1),
+                (bounds.2, drawn.2),
+                (bounds.3, drawn.3),
+            ]
+            .iter()
+            .all(|(bound, edge)| bound.abs_diff(*edge) <= 1);
+            assert!(within_a_pixel, "bounds {bounds:?}, drawn {drawn:?}");
+        }
+    }
+
+    #[test]
+    fn removed_elements_have_no_bounds() {
+        let mut image = Image::new(100, 100);
+        assert_eq!(image.content_bounds(), None);
+        image.draw_simple_line(10, 10, 90, 50, COLORS[1]).unwrap();
+        let line = image.last_element().unwrap();
+        assert_eq!(image.element_bounds(line), Some((10, 9, 60, 11)));
+        image.draw_simple_line(10, 10, 180, 50, COLORS[1]).unwrap();
+        assert_eq!(image.content_bounds(), Some((9, 9, 60, 60)));
+
+        let copy = image.clone();
+        assert!(image.remove_element(line));
+        assert!(!image.remove_element(line));
+        assert_eq!(image.element_bounds(line), None);
+        assert_eq!(copy.element_bounds(line), Some((10, 9, 60, 11)));
+        assert_eq!(image.content_bounds(), Some((9, 10, 11, 60)));
+    }
+
+    #[test]
+    fn appended_nodes_are_elements_like_any_other() {
+        use resvg::{tiny_skia, usvg};
+
+        let filled_rect = |x, y, width, height| {
+            let rect = tiny_skia::Rect::from_xywh(x, y, width, height).unwrap();
+            let mut path = usvg::Path::new(tiny_skia::PathBuilder::from_rect(rect).into());
+            path.fill = Some(usvg::Fill::from_paint(COLORS[7].paint()));
+            usvg::NodeKind::Path(path)
+        };
+
+        let mut image = Image::new(100, 100);
+        image.draw_simple_line(10, 50, 90, 80, COLORS[1]).unwrap();
+        let id = image
+            .append_node(filled_rect(10.0, 10.0, 30.0, 20.0))
+            .unwrap();
+        assert_eq!(image.last_element(), Some(id));
+        assert_eq!(image.element_ids().len(), 2);
+        assert_eq!(image.element_bounds(id), Some((10, 10, 40, 30)));
+        assert_eq!(drawn_area(&image), Some((10, 10, 90, 51)));
+
+        // Removing it takes it off the image again.
+        assert!(image.remove_element(id));
+        assert_eq!(image.element_bounds(id), None);
+        assert_eq!(drawn_area(&image), Some((10, 49, 90, 51)));
+
+        // Appended nodes are inside pushed clips.
+        image
+            .push_clip_polygon(&[(0, 0), (20, 0), (20, 100), (0, 100)])
+            .unwrap();
+        image
+            .append_node(filled_rect(10.0, 10.0, 30.0, 20.0))
+            .unwrap();
+        image.pop_clip().unwrap();
+        assert_eq!(drawn_area(&image), Some((10, 10, 90, 51)));
+        let pixmap = image.render().unwrap();
+        assert_eq!(pixmap.pixel(15, 15).unwrap().red(), 255);
+        assert_eq!(pixmap.pixel(25, 15).unwrap().red(), 0);
+
+        // Nodes that can't be drawn are rejected, and nothing is added.
+        let ids = image.element_ids();
+        let bad_transform = usvg::Group {
+            transform: tiny_skia::Transform::from_translate(f32::NAN, 0.0),
+            ..usvg::Group::default()
+        };
+        assert!(image
+            .append_node(usvg::NodeKind::Group(bad_transform))
+            .is_err());
+        let mut rect = filled_rect(50.0, 50.0, 10.0, 10.0);
+        if let usvg::NodeKind::Path(ref mut path) = rect {
+            path.transform = tiny_skia::Transform::from_scale(f32::INFINITY, 1.0);
+        }
+        assert!(image.append_node(rect).is_err());
+        assert_eq!(image.element_ids(), ids);
+    }
+}
@@ -0,0 +1,271 @@
+use crate::Image;
+use resvg::usvg::NodeExt;
+use resvg::{tiny_skia, usvg};
+
+impl Image {
+    /// Save the image as an Encapsulated PostScript (EPS) file, for print
+    /// and vector tools that don't read SVG. The lines and shapes stay as
+    /// vector paths, so they can be scaled without losing detail.
+    ///
+    /// Each unit of the image is one point (1/72 of an inch) in the file.
+    /// Strokes keep their width, caps, joins and dashes, and solid fills are
+    /// drawn with their fill rule. PostScript can't do everything SVG can,
+    /// so some things are drawn differently or left out:
+    ///
+    /// - Everything is drawn fully opaque, since PostScript has no
+    ///   transparency.
+    /// - Gradients and patterns are left out.
+    /// - Pictures are left out.
+    /// - Clips and masks are ignored, so clipped shapes are drawn whole.
+    ///
+    /// Text (like a watermark) is saved as paths, as with `save_svg`.
+    ///
+    /// Returns an error if the file can't be written, or for a dry-run
+    /// image.
+    ///
+    /// ```rs
+    /// let mut image = Image::new(100, 100);
+    /// image.draw_simple_line(10, 10, 90, 50, COLORS[1])?;
+    /// image.save_eps("image.eps")?;
+    /// ```
+    pub fn save_eps<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), String> {
+        self.prepare_to_save()?;
+        std::fs::write(path, self.eps_string()).map_err(|e| e.to_string())
+    }
+
+    /// The contents of the EPS file `save_eps` writes.
+    fn eps_string(&self) -> String {
+        let tree = self.export_tree();
+        let mut eps = format!(
+            "%!PS-Adobe-3.0 EPSF-3.0\n\
+             %%BoundingBox: 0 0 {width} {height}\n\
+             %%Creator: unsvg\n\
+             %%EndComments\n\
+             save\n\
+             % SVG puts (0, 0) at the top left, with y going down.\n\
+             [1 0 0 -1 0 {height}] concat\n",
+            width = self.width,
+            height = self.height,
+        );
+
+        for node in tree.root.descendants() {
+            let usvg::NodeKind::Path(ref path) = *node.borrow() else {
+                continue;
+            };
+            if path.visibility == usvg::Visibility::Visible {
+                add_path(&mut eps, path, node.abs_transform());
+            }
+        }
+
+        eps.push_str("restore\nshowpage\n%%EOF\n");
+        eps
+    }
+}
+
+/// Add the PostScript that draws a path to `eps`.
+fn add_path(eps: &mut String, path: &usvg::Path, transform: tiny_skia::Transform) {
+    let fill = path.fill.as_ref().and_then(|fill| match fill.paint {
+        usvg::Paint::Color(color) => Some((color, fill.rule)),
+        _ => None,
+    });
+    let stroke = path.stroke.as_ref().and_then(|stroke| match stroke.paint {
+        usvg::Paint::Color(color) => Some((color, stroke)),
+        _ => None,
+    });
+    if fill.is_none() && stroke.is_none() {
+        return;
+    }
+
+    let tiny_skia::Transform {
+        sx,
+        ky,
+        kx,
+        sy,
+        tx,
+        ty,
+    } = transform;
+    eps.push_str(&format!("gsave\n[{sx} {ky} {kx} {sy} {tx} {ty}] concat\n"));
+    add_outline(eps, &path.data);
+
+    let fill_first = path.paint_order == usvg::PaintOrder::FillAndStroke;
+    if fill_first {
+        add_fill(eps, fill, stroke.is_some());
+    }
+    if let Some((color, stroke)) = stroke {
+        let cap = match stroke.linecap {
+            usvg::LineCap::Butt => 0,
+            usvg::LineCap::Round => 1,
+            usvg::LineCap::Square => 2,
+        };
+        let join = match stroke.linejoin {
+            usvg::LineJoin::Miter => 0,
+            usvg::LineJoin::Round => 1,
+            usvg::LineJoin::Bevel => 2,
+        };
+        let dashes = stroke.dasharray.as_ref().map_or_else(Vec::new, |dashes| {
+            dashes.iter().map(f32::to_string).collect()
+        });
+        eps.push_str(&format!(
+            "{}\n{} setlinewidth {cap} setlinecap {join} setlinejoin {} setmiterlimit\n[{}] {} setdash\n",
+            rgb(color),
+            stroke.width.get(),
+            stroke.miterlimit.get(),
+            dashes.join(" "),
+            stroke.dashoffset,
+        ));
+        // Keep the outline if it is filled after the stroke.
+        eps.push_str(if fill_first {
+            "stroke\n"
+        } else {
+            "gsave stroke grestore\n"
+        });
+    }
+    if !fill_first {
+        add_fill(eps, fill, false);
+    }
+    eps.push_str("grestore\n");
+}
+
+/// Add the PostScript that fills the current outline to `eps`, keeping the
+/// outline if it is stroked afterwards.
+fn add_fill(eps: &mut String, fill: Option<(usvg::Color, usvg::FillRule)>, keep: bool) {
+    let Some((color, rule)) = fill else {
+        return;
+    };
+    let operator = match rule {
+        usvg::FillRule::NonZero => "fill",
+        usvg::FillRule::EvenOdd => "eofill",
+    };
+    if keep {
+        eps.push_str(&format!("{}\ngsave {operator} grestore\n", rgb(color)));
+    } else {
+        eps.push_str(&format!("{}\n{operator}\n", rgb(color)));
+    }
+}
+
+/// Add the PostScript for a path's outline to `eps`, as a new path.
+fn add_outline(eps: &mut String, data: &tiny_skia::Path) {
+    eps.push_str("newpath\n");
+    let mut start = tiny_skia::Point::zero();
+    let mut current = start;
+    for segment in data.segments() {
+        match segment {
+            tiny_skia::PathSegment::MoveTo(p) => {
+                eps.push_str(&format!("{} {} moveto\n", p.x, p.y));
+                start = p;
+                current = p;
+            }
+            tiny_skia::PathSegment::LineTo(p) => {
+                eps.push_str(&format!("{} {} lineto\n", p.x, p.y));
+                current = p;
+            }
+            tiny_skia::PathSegment::QuadTo(p1, p) => {
+                // PostScript only has cubic curves, which can draw any
+                // quadratic one exactly.
+                let c1 = lerp(current, p1, 2.0 / 3.0);
+                let c2 = lerp(p, p1, 2.0 / 3.0);
+                eps.push_str(&format!(
+                    "{} {} {} {} {} {} curveto\n",
+                    c1.x, c1.y, c2.x, c2.y, p.x, p.y
+                ));
+                current = p;
+            }
+            tiny_skia::PathSegment::CubicTo(p1, p2, p) => {
+                eps.push_str(&format!(
+                    "{} {} {} {} {} {} curveto\n",
+                    p1.x, p1.y, p2.x, p2.y, p.x, p.y
+                ));
+                current = p;
+            }
+            tiny_skia::PathSegment::Close => {
+                eps.push_str("closepath\n");
+                current = start;
+            }
+        }
+    }
+}
+
+/// The point `t` of the way from `a` to `b`.
+fn lerp(a: tiny_skia::Point, b: tiny_skia::Point, t: f32) -> tiny_skia::Point {
+    tiny_skia::Point::from_xy(a.x + (b.x - a.x) * t, a.y + (b.y - a.y) * t)
+}
+
+/// The PostScript that sets the current color to `color`.
+fn rgb(color: usvg::Color) -> String {
+    let channel = |value: u8| f32::from(value) / 255.0;
+    format!(
+        "{} {} {} setrgbcolor",
+        channel(color.red),
+        channel(color.green),
+        channel(color.blue)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FillRule, Image, LineStyle, StrokeStyle, COLORS};
+
+    #[test]
+    fn eps_files_start_with_a_header_for_the_image_size() {
+        let image = Image::new(120, 80);
+        let eps = image.eps_string();
+        let header: Vec<&str> = eps.lines().take(7).collect();
+        assert_eq!(
+            header,
+            [
+                "%!PS-Adobe-3.0 EPSF-3.0",
+                "%%BoundingBox: 0 0 120 80",
+                "%%Creator: unsvg",
+                "%%EndComments",
+                "save",
+                "% SVG puts (0, 0) at the top left, with y going down.",
+                "[1 0 0 -1 0 80] concat",
+            ]
+        );
+        assert!(eps.ends_with("restore\nshowpage\n%%EOF\n"));
+
+        let path =
+            std::env::temp_dir().join(format!("unsvg-eps-{:?}.eps", std::thread::current().id()));
+        image.save_eps(&path).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), eps);
+        std::fs::remove_file(&path).unwrap();
+
+        let dry_run = Image::new_dry_run(120, 80);
+        assert!(dry_run.save_eps(&path).is_err());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn eps_files_draw_each_path() {
+        let mut image = Image::new(100, 100);
+        let dashed = StrokeStyle {
+            width: 2.0,
+            style: LineStyle::Dashed,
+            ..StrokeStyle::default()
+        };
+        image
+            .draw_styled_line(10, 10, 90, 50, COLORS[1], &dashed)
+            .unwrap();
+        image
+            .fill_polygon(&[(0, 0), (20, 0), (0, 20)], COLORS[4], FillRule::EvenOdd)
+            .unwrap();
+        let eps = image.eps_string();
+
+        // The black background, filled.
+        assert!(eps.contains(
+            "newpath\n0 0 moveto\n100 0 lineto\n100 100 lineto\n0 100 lineto\nclosepath\n0 0 0 setrgbcolor\nfill\n"
+        ));
+        // The blue dashed line.
+        assert!(eps.contains("newpath\n10 10 moveto\n60 10 lineto\n0 0 1 setrgbcolor\n2 setlinewidth 0 setlinecap 0 setlinejoin 4 setmiterlimit\n["));
+        let dashes: Vec<&str> = eps
+            .lines()
+            .filter(|line| line.ends_with("setdash"))
+            .collect();
+        assert_eq!(dashes.len(), 1);
+        assert_eq!(dashes[0], "[8 6] 0 setdash");
+        // The red triangle, filled with the even-odd rule.
+        assert!(eps.contains("closepath\n1 0 0 setrgbcolor\neofill\n"));
+        assert_eq!(eps.matches("gsave\n").count(), 3);
+        assert_eq!(eps.matches("grestore\n").count(), 3);
+    }
+}
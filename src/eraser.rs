@@ -0,0 +1,447 @@
+use crate::segments::flatten;
+use crate::{i32_to_f32, ElementId, Image};
+use resvg::usvg::NodeExt;
+use resvg::{tiny_skia, usvg};
+use std::rc::Rc;
+
+/// How far curves touched by the eraser can stray from the original curve
+/// once they have been split into straight pieces, in units.
+const CURVE_TOLERANCE: f32 = 0.1;
+
+/// Pieces of line shorter than this (in units) left over after erasing are
+/// dropped, rather than leaving specks from rounding errors.
+const MIN_PIECE: f32 = 0.001;
+
+type Point = tiny_skia::Point;
+
+/// Everything within `radius` of the line from `start` to `end`.
+pub(crate) struct Capsule {
+    pub(crate) start: Point,
+    pub(crate) end: Point,
+    pub(crate) radius: f32,
+}
+
+impl Image {
+    /// Erase everything under a line, as if rubbing it out, taking the same
+    /// starting point, direction and length as `draw_simple_line` and
+    /// returning the same end point.
+    ///
+    /// Rather than painting over lines in the background's color, this
+    /// removes the erased parts of them from the image: a line crossing the
+    /// eraser is split into the parts on either side of it, and anything
+    /// erased completely is removed along with its element. So the saved SVG
+    /// has nothing left of what was erased, and changing the background
+    /// later doesn't bring it back.
+    ///
+    /// The eraser covers everything within `width / 2` of the line from the
+    /// starting point to the end point, including round ends. A line is cut
+    /// wherever its centre goes under the eraser, so the ends left behind
+    /// keep their caps, and can stick into the erased area by up to half
+    /// their width. Curves that the eraser touches are turned into short
+    /// straight pieces. The pieces of a dashed line keep their dashes where
+    /// they were. Only lines are erased: filled shapes, text and pictures
+    /// aren't changed.
+    ///
+    /// Returns an error if `width` isn't more than 0, or if strict mode (see
+    /// `set_strict`) rejects the eraser's line, as it would for
+    /// `draw_simple_line`.
+    ///
+    /// ```rs
+    /// image.draw_simple_line(10, 50, 90, 80, COLORS[1])?;
+    /// // Cut a gap 10 units wide out of the middle of the line.
+    /// image.erase_line(50, 40, 180, 20, 10.0)?;
+    /// assert_eq!(image.element_ids().len(), 1);
+    ///
+    /// // Erasing the rest removes the line altogether.
+    /// image.erase_line(0, 50, 90, 100, 10.0)?;
+    /// assert!(image.element_ids().is_empty());
+    /// ```
+    pub fn erase_line(
+        &mut self,
+        x: i32,
+        y: i32,
+        direction: i32,
+        length: i32,
+        width: f32,
+    ) -> Result<(i32, i32), String> {
+        if !(width > 0.0 && width.is_finite()) {
+            return Err(format!(
+                "An eraser must be more than 0 wide, but it was {width}"
+            ));
+        }
+        self.check_strict_line(x, y, direction, length)?;
+
+        let end = self.line_end(x, y, direction, length);
+        let (x, y) = self.clamp_point((x, y));
+        let (end_x, end_y) = self.clamp_point(end);
+        let eraser = Capsule {
+            start: Point::from_xy(i32_to_f32(x), i32_to_f32(y)),
+            end: Point::from_xy(i32_to_f32(end_x), i32_to_f32(end_y)),
+            radius: width / 2.0,
+        };
+
+        let mut emptied: Vec<ElementId> = Vec::new();
+        for (id, element) in &mut self.elements {
+            let paths: Vec<_> = element
+                .descendants()
+                .filter(|node| matches!(*node.borrow(), usvg::NodeKind::Path(_)))
+                .collect();
+            let (mut erased, mut gone) = (false, false);
+            for path in paths {
+                match erase_path(&path, &eraser) {
+                    Erased::Nothing => {}
+                    Erased::Part(rest) => {
+                        erased = true;
+                        if rest.is_empty() {
+                            continue;
+                        }
+                        // The other pieces need a group to go in, to stay
+                        // part of the element.
+                        if path == *element {
+                            let group =
+                                usvg::Node::new(usvg::NodeKind::Group(usvg::Group::default()));
+                            element.insert_before(group.clone());
+                            path.detach();
+                            group.append(path.clone());
+                            *element = group;
+                        }
+                        let mut previous = path;
+                        for piece in rest {
+                            previous.insert_after(piece.clone());
+                            previous = piece;
+                        }
+                    }
+                    // A path that is the whole element is removed with its id, below.
+                    Erased::All if path == *element => (erased, gone) = (true, true),
+                    Erased::All => {
+                        erased = true;
+                        path.detach();
+                    }
+                }
+            }
+
+            let drawn = || {
+                element
+                    .descendants()
+                    .any(|node| !matches!(*node.borrow(), usvg::NodeKind::Group(_)))
+            };
+            if gone || (erased && !drawn()) {
+                emptied.push(*id);
+            }
+        }
+        for id in emptied {
+            self.remove_element(id);
+        }
+        self.recount_memory();
+        self.mark_changed();
+
+        Ok((end_x, end_y))
+    }
+}
+
+/// How much of a path the eraser erased.
+enum Erased {
+    Nothing,
+    /// Some of the path is left. A dashed path is split into a path for
+    /// each piece, so that each piece's dashes can start where they were:
+    /// the first piece is left in the path, and the rest are here, to go
+    /// after it.
+    Part(Vec<usvg::Node>),
+    All,
+}
+
+/// Cut the parts of a stroked path under the eraser out of it. If nothing is
+/// left, the path is left as it was, for the caller to remove.
+fn erase_path(node: &usvg::Node, eraser: &Capsule) -> Erased {
+    let transform = node.abs_transform();
+    let data = match *node.borrow() {
+        usvg::NodeKind::Path(ref path) if path.stroke.is_some() && path.fill.is_none() => {
+            path.data.clone()
+        }
+        _ => return Erased::Nothing,
+    };
+
+    let mut lines = Vec::new();
+    flatten(&data, CURVE_TOLERANCE, |from, to| lines.push((from, to)));
+
+    let mut erased = false;
+    // Each piece left, and how far along its part of the path it starts.
+    let mut pieces: Vec<(tiny_skia::PathBuilder, f32)> = Vec::new();
+    let mut pen = None;
+    // How far along the part of the path being erased `from` is, in the
+    // path's own units, as dashes are measured.
+    let (mut along, mut last) = (0.0, None);
+    for (from, to) in lines {
+        if last != Some(from) {
+            along = 0.0;
+        }
+        last = Some(to);
+        let step = from.distance(to);
+
+        // The eraser is in drawing coordinates, but how far along a line a
+        // point is stays the same under the path's transform.
+        let (mut start, mut end) = (from, to);
+        transform.map_point(&mut start);
+        transform.map_point(&mut end);
+        let length = start.distance(end);
+
+        let kept = match eraser.covered(start, end) {
+            Some((cut_start, cut_end)) => {
+                erased = true;
+                vec![(0.0, cut_start), (cut_end, 1.0)]
+            }
+            None => vec![(0.0, 1.0)],
+        };
+        for (t0, t1) in kept {
+            if (t1 - t0) * length < MIN_PIECE {
+                continue;
+            }
+            let (piece_start, piece_end) = (point_at(from, to, t0), point_at(from, to, t1));
+            match pieces.last_mut() {
+                Some((builder, _)) if pen == Some(piece_start) => {
+                    builder.line_to(piece_end.x, piece_end.y);
+                }
+                _ => {
+                    let mut builder = tiny_skia::PathBuilder::new();
+                    builder.move_to(piece_start.x, piece_start.y);
+                    builder.line_to(piece_end.x, piece_end.y);
+                    pieces.push((builder, along + step * t0));
+                }
+            }
+            pen = Some(piece_end);
+        }
+        along += step;
+    }
+
+    if !erased {
+        return Erased::Nothing;
+    }
+    let pieces: Vec<(tiny_skia::Path, f32)> = pieces
+        .into_iter()
+        .filter_map(|(builder, start)| Some((builder.finish()?, start)))
+        .collect();
+    let usvg::NodeKind::Path(ref mut path) = *node.borrow_mut() else {
+        return Erased::Nothing;
+    };
+
+    let dashed = path
+        .stroke
+        .as_ref()
+        .is_some_and(|stroke| stroke.dasharray.is_some());
+    if !dashed {
+        let mut builder = tiny_skia::PathBuilder::new();
+        for (piece, _) in &pieces {
+            builder.push_path(piece);
+        }
+        let Some(data) = builder.finish() else {
+            return Erased::All;
+        };
+        path.data = Rc::new(data);
+        return Erased::Part(Vec::new());
+    }
+
+    let mut pieces = pieces.into_iter().map(|(data, start)| {
+        let mut piece = path.clone();
+        piece.data = Rc::new(data);
+        if let Some(ref mut stroke) = piece.stroke {
+            stroke.dashoffset += start;
+        }
+        piece
+    });
+    let Some(first) = pieces.next() else {
+        return Erased::All;
+    };
+    let rest = pieces
+        .map(|mut piece| {
+            piece.id.clear();
+            usvg::Node::new(usvg::NodeKind::Path(piece))
+        })
+        .collect();
+    *path = first;
+    Erased::Part(rest)
+}
+
+/// The point `t` of the way from `from` to `to`, landing exactly on the ends
+/// for 0 and 1 so that pieces of a line still join up.
+fn point_at(from: Point, to: Point, t: f32) -> Point {
+    if t <= 0.0 {
+        from
+    } else if t >= 1.0 {
+        to
+    } else {
+        Point::from_xy(from.x + (to.x - from.x) * t, from.y + (to.y - from.y) * t)
+    }
+}
+
+impl Capsule {
+    /// The part of the line from `a` to `b` inside the capsule, as how far
+    /// along the line it starts and ends (from 0 to 1). Returns `None` if the
+    /// line only touches the capsule or misses it altogether.
+    pub(crate) fn covered(&self, a: Point, b: Point) -> Option<(f32, f32)> {
+        // The capsule is a rectangle along its centre line, with a circle at
+        // each end. It is convex, so the line crosses it in one piece, which
+        // spans every part crossing one of those three shapes.
+        let pieces = [
+            circle_crossing(a, b, self.start, self.radius),
+            circle_crossing(a, b, self.end, self.radius),
+            self.rectangle_crossing(a, b),
+        ];
+        let (start, end) =
+            pieces
+                .into_iter()
+                .flatten()
+                .reduce(|(start, end), (other_start, other_end)| {
+                    (start.min(other_start), end.max(other_end))
+                })?;
+
+        let (start, end) = (start.max(0.0), end.min(1.0));
+        (start < end).then_some((start, end))
+    }
+
+    /// Where the line from `a` to `b` crosses the rectangle along the
+    /// capsule's centre line.
+    fn rectangle_crossing(&self, a: Point, b: Point) -> Option<(f32, f32)> {
+        let length = self.start.distance(self.end);
+        if length == 0.0 {
+            return None;
+        }
+        let along = Point::from_xy(
+            (self.end.x - self.start.x) / length,
+            (self.end.y - self.start.y) / length,
+        );
+        let across = Point::from_xy(-along.y, along.x);
+
+        // How far along and across the centre line a point of the line is,
+        // as `at + change * t`.
+        let offset = Point::from_xy(a.x - self.start.x, a.y - self.start.y);
+        let direction = Point::from_xy(b.x - a.x, b.y - a.y);
+        let dot = |p: Point, q: Point| p.x * q.x + p.y * q.y;
+
+        let (along_start, along_end) =
+            between(dot(offset, along), dot(direction, along), 0.0, length)?;
+        let (across_start, across_end) = between(
+            dot(offset, across),
+            dot(direction, across),
+            -self.radius,
+            self.radius,
+        )?;
+
+        let (start, end) = (along_start.max(across_start), along_end.min(across_end));
+        (start <= end).then_some((start, end))
+    }
+}
+
+/// Where the (endless) line through `a` and `b` is within `radius` of
+/// `centre`, as multiples of the way from `a` to `b`.
+fn circle_crossing(a: Point, b: Point, centre: Point, radius: f32) -> Option<(f32, f32)> {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let (fx, fy) = (a.x - centre.x, a.y - centre.y);
+
+    // |a + t(b - a) - centre|² = radius², as a quadratic in t.
+    let qa = dx * dx + dy * dy;
+    let qb = 2.0 * (fx * dx + fy * dy);
+    let qc = fx * fx + fy * fy - radius * radius;
+    if qa == 0.0 {
+        // The line is a single point.
+        return (qc <= 0.0).then_some((f32::NEG_INFINITY, f32::INFINITY));
+    }
+
+    let discriminant = qb * qb - 4.0 * qa * qc;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let root = discriminant.sqrt();
+    Some(((-qb - root) / (2.0 * qa), (-qb + root) / (2.0 * qa)))
+}
+
+/// Where `at + change * t` is between `min` and `max`.
+fn between(at: f32, change: f32, min: f32, max: f32) -> Option<(f32, f32)> {
+    if change == 0.0 {
+        return (min <= at && at <= max).then_some((f32::NEG_INFINITY, f32::INFINITY));
+    }
+    let (t0, t1) = ((min - at) / change, (max - at) / change);
+    Some((t0.min(t1), t0.max(t1)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Image, LineStyle, StrokeStyle, COLORS};
+
+    /// Whether each pixel along the row `y` isn't black.
+    fn lit_row(image: &Image, y: u32) -> Vec<bool> {
+        let pixmap = image.render().unwrap();
+        (0..pixmap.width())
+            .map(|x| pixmap.pixel(x, y).unwrap().blue() > 64)
+            .collect()
+    }
+
+    fn thick(style: LineStyle) -> StrokeStyle {
+        StrokeStyle {
+            width: 4.0,
+            style,
+            ..StrokeStyle::default()
+        }
+    }
+
+    #[test]
+    fn erasing_cuts_lines_and_removes_what_is_left_of_nothing() {
+        let mut image = Image::new(100, 100);
+        let style = thick(LineStyle::Solid);
+        image
+            .draw_styled_line(10, 50, 90, 80, COLORS[1], &style)
+            .unwrap();
+        image
+            .draw_styled_line(10, 20, 90, 80, COLORS[1], &style)
+            .unwrap();
+
+        // A gap out of the middle of the lower line.
+        assert_eq!(image.erase_line(50, 40, 180, 20, 10.0), Ok((50, 60)));
+        assert_eq!(image.element_ids().len(), 2);
+        let row = lit_row(&image, 50);
+        assert!(row[20] && row[80]);
+        assert!(!row[45..55].iter().any(|&lit| lit), "{row:?}");
+        assert!(lit_row(&image, 20)[50]);
+
+        // Rubbing out all of the upper line removes it.
+        image.erase_line(0, 20, 90, 100, 10.0).unwrap();
+        assert_eq!(image.element_ids().len(), 1);
+        assert!(!lit_row(&image, 20).iter().any(|&lit| lit));
+
+        // Missing everything changes nothing.
+        image.erase_line(10, 90, 90, 80, 4.0).unwrap();
+        assert_eq!(image.element_ids().len(), 1);
+    }
+
+    #[test]
+    fn pieces_of_dashed_lines_keep_their_dashes_where_they_were() {
+        let mut image = Image::new(100, 100);
+        let style = thick(LineStyle::Dashed);
+        image
+            .draw_styled_line(4, 50, 90, 92, COLORS[1], &style)
+            .unwrap();
+        let before = lit_row(&image, 50);
+
+        image.erase_line(50, 40, 180, 20, 6.0).unwrap();
+        assert_eq!(image.element_ids().len(), 1);
+        let after = lit_row(&image, 50);
+        assert_eq!(after[..45], before[..45]);
+        assert_eq!(after[56..], before[56..]);
+        assert!(!after[47..=53].iter().any(|&lit| lit), "{after:?}");
+        // The dashes after the gap really are there.
+        assert!(after[56..].iter().any(|&lit| lit) && after[56..].iter().any(|&lit| !lit));
+    }
+
+    #[test]
+    fn strict_mode_checks_the_eraser() {
+        let mut image = Image::new(100, 100);
+        image.draw_simple_line(10, 50, 90, 80, COLORS[1]).unwrap();
+        image.set_strict(true);
+        assert!(image.erase_line(50, 40, 180, 70, 10.0).is_err());
+        assert!(image.erase_line(50, 40, 180, 0, 10.0).is_err());
+        assert!(lit_row(&image, 50)[50]);
+
+        image.set_strict(false);
+        image.erase_line(50, 40, 180, 70, 10.0).unwrap();
+        assert!(!lit_row(&image, 50)[50]);
+    }
+}
@@ -0,0 +1,320 @@
+use crate::output::{encode_png, OutputFormat, PngOptions};
+use crate::text::text_node;
+use crate::{Color, Image, SvgOptions};
+use resvg::usvg::NodeExt;
+use resvg::{tiny_skia, usvg};
+use std::path::Path;
+
+/// The color overlays are drawn in: bright magenta, which stands out against
+/// most drawings.
+const OVERLAY_COLOR: Color = Color {
+    red: 255,
+    green: 0,
+    blue: 255,
+};
+
+/// The size of the text in overlays.
+const LABEL_SIZE: f32 = 8.0;
+
+/// How far apart the ticks on the rulers are, and how long they are. Every
+/// fifth tick is longer, and every tenth is labelled.
+const TICK_SPACING: u32 = 10;
+const TICK_LENGTH: f32 = 4.0;
+const LONG_TICK_LENGTH: f32 = 8.0;
+
+/// Extra information drawn on top of an image when it is exported, to help
+/// with debugging a drawing. Overlays are only added to the exported file,
+/// never to the image itself.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Overlays {
+    /// Label each element with its position in `element_ids` (starting from
+    /// 0), at the top-left corner of the area it covers.
+    pub element_numbers: bool,
+    /// Draw rulers along the top and left edges, with a tick every 10 units
+    /// and a label every 100.
+    pub rulers: bool,
+}
+
+/// Everything about how to export an image, bundled together so the same
+/// settings can be used in several places. Use one with `Image::export`.
+///
+/// There are presets for the common cases, and since every field is public,
+/// a profile can be built (or a preset adjusted) field by field:
+///
+/// ```rs
+/// let profile = ExportProfile {
+///     overlays: Overlays {
+///         rulers: true,
+///         ..Overlays::default()
+///     },
+///     ..ExportProfile::print(300)
+/// };
+/// image.export("drawing.png", &profile)?;
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExportProfile {
+    /// The kind of file to write.
+    pub format: OutputFormat,
+    /// How to write the file, if it is an SVG.
+    pub svg: SvgOptions,
+    /// How to write the file, if it is a PNG.
+    pub png: PngOptions,
+    /// What to draw on top of the image.
+    pub overlays: Overlays,
+}
+
+impl ExportProfile {
+    /// An SVG that is as small as possible, for putting on a website: no
+    /// line breaks or indentation, and colors as CSS classes.
+    pub fn web() -> ExportProfile {
+        ExportProfile {
+            format: OutputFormat::Svg,
+            svg: SvgOptions {
+                css_classes: true,
+                minify: true,
+            },
+            png: PngOptions::default(),
+            overlays: Overlays::default(),
+        }
+    }
+
+    /// A PNG that records its resolution, so it prints at the right size:
+    /// each pixel is `1 / dpi` inches wide.
+    pub fn print(dpi: u32) -> ExportProfile {
+        ExportProfile {
+            format: OutputFormat::Png,
+            svg: SvgOptions::default(),
+            png: PngOptions {
+                dpi: Some(dpi),
+                ..PngOptions::default()
+            },
+            overlays: Overlays::default(),
+        }
+    }
+
+    /// A readable SVG with every element numbered and rulers along the edges,
+    /// for working out what was drawn where.
+    pub fn debug() -> ExportProfile {
+        ExportProfile {
+            format: OutputFormat::Svg,
+            svg: SvgOptions::default(),
+            png: PngOptions::default(),
+            overlays: Overlays {
+                element_numbers: true,
+                rulers: true,
+            },
+        }
+    }
+}
+
+impl Image {
+    /// Save the image to a file as described by `profile`.
+    ///
+    /// The file is written as the profile's format, whatever extension
+    /// `path` has.
+    ///
+    /// ```rs
+    /// image.export("drawing.svg", &ExportProfile::web())?;
+    /// image.export("drawing.png", &ExportProfile::print(300))?;
+    /// image.export("drawing-debug.svg", &ExportProfile::debug())?;
+    /// ```
+    pub fn export<P: AsRef<Path>>(&self, path: P, profile: &ExportProfile) -> Result<(), String> {
+        self.prepare_to_save()?;
+        let overlay = self.overlay_node(&profile.overlays);
+        if let Some(overlay) = &overlay {
+            self.check_fonts(overlay)?;
+        }
+        let contents = match profile.format {
+            OutputFormat::Svg => {
+                let mut tree = self.export_tree();
+                if let Some(overlay) = overlay {
+                    tree.root.append(overlay);
+                    self.convert_text(&mut tree);
+                }
+                self.write_svg(&tree, &profile.svg).into_bytes()
+            }
+            OutputFormat::Png => {
+                let mut pixmap = self.render()?;
+                if let Some(overlay) = overlay {
+                    let mut tree = usvg::Tree {
+                        size: self.tree.size,
+                        view_box: self.tree.view_box,
+                        root: usvg::Node::new(usvg::NodeKind::Group(usvg::Group::default())),
+                    };
+                    tree.root.append(overlay);
+                    self.convert_text(&mut tree);
+                    resvg::Tree::from_usvg(&tree)
+                        .render(tiny_skia::Transform::default(), &mut pixmap.as_mut());
+                }
+                encode_png(&pixmap, &profile.png)?
+            }
+        };
+        std::fs::write(path, contents).map_err(|e| e.to_string())
+    }
+
+    /// A group with everything `overlays` asks for, or `None` if it asks for
+    /// nothing.
+    fn overlay_node(&self, overlays: &Overlays) -> Option<usvg::Node> {
+        if !overlays.element_numbers && !overlays.rulers {
+            return None;
+        }
+
+        let group = usvg::Node::new(usvg::NodeKind::Group(usvg::Group {
+            id: "overlay".to_string(),
+            ..usvg::Group::default()
+        }));
+        let fill = usvg::Fill::from_paint(OVERLAY_COLOR.paint());
+        let (width, height) = (self.width as f32, self.height as f32);
+
+        if overlays.rulers {
+            let mut builder = tiny_skia::PathBuilder::new();
+            let mut labels = Vec::new();
+            for (index, position) in (0..self.width.max(self.height))
+                .step_by(TICK_SPACING as usize)
+                .enumerate()
+                .skip(1)
+            {
+                let length = if index % 5 == 0 {
+                    LONG_TICK_LENGTH
+                } else {
+                    TICK_LENGTH
+                };
+                let position_f32 = position as f32;
+                if position < self.width {
+                    builder.move_to(position_f32, 0.0);
+                    builder.line_to(position_f32, length);
+                }
+                if position < self.height {
+                    builder.move_to(0.0, position_f32);
+                    builder.line_to(length, position_f32);
+                }
+                if index % 10 == 0 {
+                    labels.push(position);
+                }
+            }
+            if let Some(path) = builder.finish() {
+                let mut path = usvg::Path::new(path.into());
+                path.stroke = Some(usvg::Stroke {
+                    paint: OVERLAY_COLOR.paint(),
+                    ..usvg::Stroke::default()
+                });
+                group.append_kind(usvg::NodeKind::Path(path));
+            }
+
+            for position in labels {
+                let (text, position) = (position.to_string(), position as f32);
+                let below_tick = LONG_TICK_LENGTH + LABEL_SIZE;
+                let beside_tick = LONG_TICK_LENGTH + 2.0;
+                let points = [
+                    (position < width).then_some((position + 2.0, below_tick)),
+                    (position < height).then_some((beside_tick, position + LABEL_SIZE * 0.35)),
+                ];
+                for point in points.into_iter().flatten() {
+                    let start = usvg::TextAnchor::Start;
+                    if let Some(label) = text_node(&text, point, LABEL_SIZE, start, fill.clone()) {
+                        group.append(label);
+                    }
+                }
+            }
+        }
+
+        if overlays.element_numbers {
+            for (number, id) in self.element_ids().into_iter().enumerate() {
+                let Some((min_x, min_y, _, _)) = self.element_bounds_on_image(id) else {
+                    continue;
+                };
+                // Keep the label on the image, even if the element isn't.
+                let x = (min_x as f32).clamp(0.0, width) + 1.0;
+                let y = (min_y as f32).clamp(0.0, height) + LABEL_SIZE;
+                let start = usvg::TextAnchor::Start;
+                let label = text_node(&number.to_string(), (x, y), LABEL_SIZE, start, fill.clone());
+                if let Some(label) = label {
+                    group.append(label);
+                }
+            }
+        }
+
+        Some(group)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::snapshot::parse_svg;
+    use crate::{render_tree, ExportProfile, Image, COLORS};
+
+    /// Export the image with `profile` and read back the file.
+    fn export(image: &Image, name: &str, profile: &ExportProfile) -> Vec<u8> {
+        let path = std::env::temp_dir().join(format!("unsvg-export-{name}"));
+        image.export(&path, profile).unwrap();
+        let contents = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        contents
+    }
+
+    fn image() -> Image {
+        let mut image = Image::new(120, 80);
+        image.draw_simple_line(20, 40, 90, 80, COLORS[1]).unwrap();
+        image.draw_simple_line(60, 20, 180, 50, COLORS[4]).unwrap();
+        image
+    }
+
+    #[test]
+    fn web_svgs_are_one_line_and_look_the_same() {
+        let image = image();
+        let svg = String::from_utf8(export(&image, "web.svg", &ExportProfile::web())).unwrap();
+        assert!(!svg.contains('\n'), "{svg}");
+        assert!(svg.contains("class="), "{svg}");
+        let rendered = render_tree(&parse_svg(&svg).unwrap()).unwrap();
+        assert!(rendered == image.render().unwrap());
+    }
+
+    #[test]
+    fn print_pngs_record_their_resolution() {
+        let image = image();
+        let png = export(&image, "print.png", &ExportProfile::print(300));
+        let decoder = png::Decoder::new(png.as_slice());
+        let reader = decoder.read_info().unwrap();
+        let dimensions = reader.info().pixel_dims.unwrap();
+        // 300 pixels per inch is 11811 per metre.
+        assert_eq!((dimensions.xppu, dimensions.yppu), (11_811, 11_811));
+        assert_eq!(dimensions.unit, png::Unit::Meter);
+        assert_eq!((reader.info().width, reader.info().height), (120, 80));
+
+        let plain = image.png_bytes().unwrap();
+        let reader = png::Decoder::new(plain.as_slice()).read_info().unwrap();
+        assert!(reader.info().pixel_dims.is_none());
+    }
+
+    #[test]
+    fn overlays_are_drawn_on_the_file_but_not_the_image() {
+        let image = image();
+        let magenta = |pixmap: &resvg::tiny_skia::Pixmap, x, y| {
+            let pixel = pixmap.pixel(x, y).unwrap();
+            pixel.red() > 64 && pixel.blue() > 64 && pixel.green() == 0
+        };
+
+        let debug = ExportProfile::debug();
+        let svg = String::from_utf8(export(&image, "debug.svg", &debug)).unwrap();
+        assert!(svg.contains("id=\"overlay\""), "{svg}");
+        let rendered = render_tree(&parse_svg(&svg).unwrap()).unwrap();
+        // A tick every 10 units along the top and left edges.
+        assert!(magenta(&rendered, 10, 1) && magenta(&rendered, 1, 10));
+        assert!(!magenta(&rendered, 15, 1));
+
+        let png = export(
+            &image,
+            "debug.png",
+            &ExportProfile {
+                format: crate::OutputFormat::Png,
+                ..debug
+            },
+        );
+        let pixmap = resvg::tiny_skia::Pixmap::decode_png(&png).unwrap();
+        assert!(magenta(&pixmap, 10, 1) && magenta(&pixmap, 1, 10));
+
+        let plain = image.render().unwrap();
+        assert!(!magenta(&plain, 10, 1));
+        assert_eq!(image.element_ids().len(), 2);
+    }
+}
@@ -0,0 +1,144 @@
+use crate::{ElementId, Image, ImageSnapshot};
+use std::sync::Arc;
+
+/// An area as `(min_x, min_y, max_x, max_y)`, as from `Image::element_bounds`.
+type Bounds = (i32, i32, i32, i32);
+
+/// An image that can no longer be drawn on, made by `Image::finalize`.
+///
+/// A finalized image can be saved and queried, but has no drawing methods,
+/// so a later stage of a program can't change it by mistake: trying to is a
+/// compile error. It can be cloned cheaply (clones share the same data) and
+/// sent between threads, so one finished image can be handed to several
+/// threads to save in different formats.
+#[derive(Clone, Debug)]
+pub struct FinalizedImage {
+    snapshot: ImageSnapshot,
+    bounds: Arc<[(ElementId, Option<Bounds>)]>,
+    content_bounds: Option<(i32, i32, i32, i32)>,
+}
+
+impl FinalizedImage {
+    /// Get the size of the image as a tuple of (width, height).
+    pub fn get_dimensions(&self) -> (u32, u32) {
+        self.snapshot.get_dimensions()
+    }
+
+    /// Save the image to a PNG file, exactly as `Image::save_png` would have.
+    pub fn save_png<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), String> {
+        self.snapshot.save_png(path)
+    }
+
+    /// Save the image to an SVG file.
+    pub fn save_svg<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), String> {
+        self.snapshot.save_svg(path)
+    }
+
+    /// The image as the contents of an SVG file.
+    pub fn svg(&self) -> &str {
+        self.snapshot.svg()
+    }
+
+    /// A snapshot of the image, sharing its data.
+    pub fn snapshot(&self) -> ImageSnapshot {
+        self.snapshot.clone()
+    }
+
+    /// The ids of everything drawn on the image, in the order it was drawn.
+    pub fn element_ids(&self) -> Vec<ElementId> {
+        self.bounds.iter().map(|&(id, _)| id).collect()
+    }
+
+    /// The area an element covers, as described in `Image::element_bounds`.
+    pub fn element_bounds(&self, id: ElementId) -> Option<(i32, i32, i32, i32)> {
+        self.bounds
+            .iter()
+            .find(|&&(other, _)| other == id)
+            .and_then(|&(_, bounds)| bounds)
+    }
+
+    /// The area covered by everything drawn on the image, as described in
+    /// `Image::content_bounds`.
+    pub fn content_bounds(&self) -> Option<(i32, i32, i32, i32)> {
+        self.content_bounds
+    }
+}
+
+impl Image {
+    /// Finish drawing, turning the image into a [`FinalizedImage`] that can
+    /// be saved and queried but not drawn on.
+    ///
+    /// Returns an error for a dry-run image, which has nothing to save.
+    ///
+    /// ```rs
+    /// let image = image.finalize()?;
+    /// image.save_png("done.png")?;
+    /// // image.draw_simple_line(...) no longer compiles.
+    /// ```
+    pub fn finalize(self) -> Result<FinalizedImage, String> {
+        Ok(FinalizedImage {
+            snapshot: self.snapshot()?,
+            bounds: self
+                .element_ids()
+                .into_iter()
+                .map(|id| (id, self.element_bounds(id)))
+                .collect(),
+            content_bounds: self.content_bounds(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BlendSpace, Image, Rgba, StrokeStyle, COLORS};
+
+    /// Save a file with `save` and read back the bytes.
+    fn saved(name: &str, save: impl FnOnce(&std::path::Path) -> Result<(), String>) -> Vec<u8> {
+        let path = std::env::temp_dir().join(format!("unsvg_finalize_{name}"));
+        save(&path).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn finalized_images_save_what_the_image_would_have() {
+        for space in [BlendSpace::Srgb, BlendSpace::Linear] {
+            let mut image = Image::new(30, 20);
+            image.set_blend_space(space);
+            let style = StrokeStyle {
+                width: 8.0,
+                ..StrokeStyle::default()
+            };
+            image
+                .draw_styled_line(15, 0, 180, 20, Rgba::from_straight(255, 0, 0, 128), &style)
+                .unwrap();
+            image
+                .draw_styled_line(0, 10, 90, 30, Rgba::from_straight(0, 0, 255, 128), &style)
+                .unwrap();
+            image.draw_simple_line(2, 2, 90, 20, COLORS[2]).unwrap();
+
+            let png = saved(&format!("{space:?}_image.png"), |path| image.save_png(path));
+            let svg = saved(&format!("{space:?}_image.svg"), |path| image.save_svg(path));
+            let ids = image.element_ids();
+            let bounds: Vec<_> = ids.iter().map(|&id| image.element_bounds(id)).collect();
+            let content_bounds = image.content_bounds();
+
+            let image = image.finalize().unwrap();
+            assert_eq!(
+                saved(&format!("{space:?}_final.png"), |path| image.save_png(path)),
+                png,
+                "{space:?}"
+            );
+            assert_eq!(
+                saved(&format!("{space:?}_final.svg"), |path| image.save_svg(path)),
+                svg,
+                "{space:?}"
+            );
+            assert_eq!(image.element_ids(), ids);
+            let final_bounds: Vec<_> = ids.iter().map(|&id| image.element_bounds(id)).collect();
+            assert_eq!(final_bounds, bounds);
+            assert_eq!(image.content_bounds(), content_bounds);
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use crate::{u32_to_f32, Color, Image};
+use resvg::{tiny_skia, usvg};
+use std::rc::Rc;
+
+/// How far the control points of a rounded corner are from its ends, as a
+/// fraction of the radius, so the corner is close to a quarter circle.
+const CORNER_HANDLE: f32 = 0.552_284_8;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Frame {
+    width: u32,
+    color: Color,
+    corner_radius: u32,
+}
+
+/// Add a rectangle with corners rounded to `radius` (or as close as fits) to
+/// `builder`.
+fn push_rounded_rect(builder: &mut tiny_skia::PathBuilder, rect: tiny_skia::Rect, radius: f32) {
+    let radius = radius.min(rect.width() / 2.0).min(rect.height() / 2.0);
+    if radius <= 0.0 {
+        builder.push_rect(rect);
+        return;
+    }
+
+    let (left, top, right, bottom) = (rect.left(), rect.top(), rect.right(), rect.bottom());
+    let handle = radius * (1.0 - CORNER_HANDLE);
+    builder.move_to(left + radius, top);
+    builder.line_to(right - radius, top);
+    builder.cubic_to(
+        right - handle,
+        top,
+        right,
+        top + handle,
+        right,
+        top + radius,
+    );
+    builder.line_to(right, bottom - radius);
+    builder.cubic_to(
+        right,
+        bottom - handle,
+        right - handle,
+        bottom,
+        right - radius,
+        bottom,
+    );
+    builder.line_to(left + radius, bottom);
+    builder.cubic_to(
+        left + handle,
+        bottom,
+        left,
+        bottom - handle,
+        left,
+        bottom - radius,
+    );
+    builder.line_to(left, top + radius);
+    builder.cubic_to(left, top + handle, left + handle, top, left + radius, top);
+    builder.close();
+}
+
+impl Image {
+    /// Give the image a solid border `width` units wide, just inside its
+    /// edges, whenever it is saved. With a `corner_radius`, the inside edge
+    /// of the border has rounded corners, like a picture mount.
+    ///
+    /// Like the watermark, the frame is part of the canvas rather than an
+    /// element: it is added on top of everything (except the watermark) each
+    /// time the image is saved, so it can't be drawn over or removed by
+    /// accident. It isn't affected by the image's mask. Setting a frame
+    /// replaces the old one, and a width of 0 removes it.
+    ///
+    /// ```rs
+    /// // A 10 unit white border, with rounded corners inside it.
+    /// image.set_frame(10, COLORS[7], 20);
+    /// ```
+    pub fn set_frame(&mut self, width: u32, color: Color, corner_radius: u32) {
+        self.frame = (width > 0).then_some(Frame {
+            width,
+            color,
+            corner_radius,
+        });
+        self.mark_changed();
+    }
+
+    /// The frame, in place on the image.
+    pub(crate) fn frame_node(&self) -> Option<usvg::Node> {
+        let frame = self.frame?;
+        let (width, height) = (u32_to_f32(self.width), u32_to_f32(self.height));
+        let border = u32_to_f32(frame.width);
+
+        let mut builder = tiny_skia::PathBuilder::new();
+        builder.push_rect(tiny_skia::Rect::from_xywh(0.0, 0.0, width, height)?);
+        // A frame wider than half the image has no inside, and covers it all.
+        if let Some(inside) =
+            tiny_skia::Rect::from_ltrb(border, border, width - border, height - border)
+                .filter(|inside| inside.width() > 0.0 && inside.height() > 0.0)
+        {
+            push_rounded_rect(&mut builder, inside, u32_to_f32(frame.corner_radius));
+        }
+
+        let mut path = usvg::Path::new(Rc::new(builder.finish()?));
+        path.fill = Some(usvg::Fill {
+            paint: frame.color.paint(),
+            rule: usvg::FillRule::EvenOdd,
+            ..usvg::Fill::default()
+        });
+        Some(usvg::Node::new(usvg::NodeKind::Path(path)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FillRule, Image, COLORS};
+
+    const RED: [u8; 3] = [255, 0, 0];
+    const BLUE: [u8; 3] = [0, 0, 255];
+    const BLACK: [u8; 3] = [0, 0, 0];
+
+    /// The color of each of `points` when the image is rendered.
+    fn colors(image: &Image, points: &[(u32, u32)]) -> Vec<[u8; 3]> {
+        let pixmap = image.render().unwrap();
+        points
+            .iter()
+            .map(|&(x, y)| {
+                let pixel = pixmap.pixel(x, y).unwrap();
+                [pixel.red(), pixel.green(), pixel.blue()]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn the_border_is_just_inside_the_canvas() {
+        let mut image = Image::new(100, 80);
+        image.set_frame(5, COLORS[4], 0);
+        let border = [
+            (0, 40),
+            (4, 40),
+            (99, 40),
+            (95, 40),
+            (50, 0),
+            (50, 4),
+            (50, 79),
+            (50, 75),
+        ];
+        assert_eq!(colors(&image, &border), [RED; 8]);
+        let inside = [(5, 40), (94, 40), (50, 5), (50, 74), (50, 40)];
+        assert_eq!(colors(&image, &inside), [BLACK; 5]);
+        assert!(image.element_ids().is_empty());
+    }
+
+    #[test]
+    fn the_frame_is_drawn_over_what_is_drawn_after_it() {
+        let mut image = Image::new(100, 80);
+        image.set_frame(5, COLORS[4], 0);
+        image
+            .fill_polygon(
+                &[(0, 0), (100, 0), (100, 80), (0, 80)],
+                COLORS[1],
+                FillRule::NonZero,
+            )
+            .unwrap();
+        assert_eq!(
+            colors(&image, &[(2, 40), (97, 40), (50, 2), (50, 77), (50, 40)]),
+            [RED, RED, RED, RED, BLUE]
+        );
+    }
+
+    #[test]
+    fn rounded_corners_round_the_inside_edge() {
+        let mut image = Image::new(100, 80);
+        image.set_frame(5, COLORS[4], 0);
+        let corners = [(5, 5), (94, 5), (5, 74), (94, 74)];
+        assert_eq!(colors(&image, &corners), [BLACK; 4]);
+
+        image.set_frame(5, COLORS[4], 20);
+        assert_eq!(colors(&image, &corners), [RED; 4]);
+        // The outside of the frame is still square, and the middle of each
+        // side is unchanged.
+        assert_eq!(colors(&image, &[(0, 0), (99, 79)]), [RED; 2]);
+        assert_eq!(colors(&image, &[(5, 40), (50, 5), (50, 40)]), [BLACK; 3]);
+    }
+
+    #[test]
+    fn setting_a_frame_replaces_the_old_one() {
+        let mut image = Image::new(100, 80);
+        image.set_frame(5, COLORS[4], 0);
+        image.set_frame(2, COLORS[1], 0);
+        assert_eq!(
+            colors(&image, &[(0, 40), (1, 40), (2, 40), (4, 40)]),
+            [BLUE, BLUE, BLACK, BLACK]
+        );
+    }
+
+    #[test]
+    fn a_width_of_0_removes_the_frame() {
+        let mut image = Image::new(100, 80);
+        let plain = image.png_bytes().unwrap();
+        image.set_frame(5, COLORS[4], 10);
+        assert_ne!(image.png_bytes().unwrap(), plain);
+        image.set_frame(0, COLORS[4], 10);
+        assert_eq!(image.png_bytes().unwrap(), plain);
+        assert_eq!(colors(&image, &[(0, 0), (2, 40)]), [BLACK; 2]);
+    }
+}
@@ -0,0 +1,261 @@
+//! Ready-made generative drawings, built from `draw_simple_line`.
+//!
+//! Everything here is deterministic: the same arguments always draw exactly
+//! the same lines, on any computer, so results can be compared and tested.
+
+use crate::{Image, IntoColor};
+
+/// The longest string `l_system` will expand to, in symbols. A few rules
+/// applied many times can easily grow past what could ever be drawn.
+const MAX_L_SYSTEM_LENGTH: usize = 10_000_000;
+
+/// A small, fast pseudo-random number generator (SplitMix64). It is not
+/// suitable for anything secret, but always gives the same numbers from the
+/// same seed.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+/// Draw a random walk of `steps` lines, each `step_len` units long, starting
+/// at `start`. Each line starts where the last one ended, and points in a
+/// random whole-number direction from 0 to 359 degrees.
+///
+/// The directions come from `seed`, so the same seed always draws the same
+/// walk; use a different seed for a different walk. Returns where the walk
+/// ended up.
+///
+/// ```rs
+/// use unsvg::generate::random_walk;
+///
+/// let mut image = Image::new(200, 200);
+/// let end = random_walk(&mut image, (100, 100), 500, 5, 6991, COLORS[7])?;
+/// ```
+pub fn random_walk(
+    image: &mut Image,
+    start: (i32, i32),
+    steps: u32,
+    step_len: i32,
+    seed: u64,
+    color: impl IntoColor,
+) -> Result<(i32, i32), String> {
+    let color = color.into_color()?;
+    let mut random = SplitMix64(seed);
+
+    let mut position = start;
+    for _ in 0..steps {
+        let direction = (random.next() % 360) as i32;
+        position = image.draw_simple_line(position.0, position.1, direction, step_len, color)?;
+    }
+    Ok(position)
+}
+
+/// Expand and draw an L-system, a set of rewriting rules that can describe
+/// plants, fractals and space-filling curves.
+///
+/// Starting from `axiom`, every symbol that has a rule is replaced by that
+/// rule's text, `iterations` times over (if a symbol has more than one rule,
+/// the first is used). The result is then drawn by a turtle that starts at
+/// `start` facing up (direction 0), reading one symbol at a time:
+///  - `F` and `G` draw a line `step` units forward,
+///  - `f` moves `step` units forward without drawing (stopping at the edge
+///    of the image with `set_clamp_to_bounds`, as lines do),
+///  - `+` turns `angle` degrees clockwise, and `-` turns anticlockwise,
+///  - `[` remembers the turtle's position and direction, and `]` goes back to
+///    the most recently remembered one,
+///  - anything else is ignored, so it can be used in rules as a placeholder.
+///
+/// Each line is an element of its own. Returns where the turtle ended up.
+/// Returns an error if a `]` has no matching `[`, if the expanded string
+/// would be more than ten million symbols long, or if drawing any of the
+/// lines fails (such as in strict mode, see `set_strict`). Nothing is drawn
+/// if there is an error.
+///
+/// ```rs
+/// use unsvg::generate::l_system;
+///
+/// // A fractal plant.
+/// let rules = [('X', "F+[[X]-X]-F[-FX]+X"), ('F', "FF")];
+/// l_system(&mut image, "X", &rules, 5, 25, 3, (100, 400), COLORS[3])?;
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn l_system(
+    image: &mut Image,
+    axiom: &str,
+    rules: &[(char, &str)],
+    iterations: u32,
+    angle: i32,
+    step: i32,
+    start: (i32, i32),
+    color: impl IntoColor,
+) -> Result<(i32, i32), String> {
+    let color = color.into_color()?;
+
+    let mut symbols = axiom.to_string();
+    for _ in 0..iterations {
+        let mut next = String::with_capacity(symbols.len());
+        for symbol in symbols.chars() {
+            match rules.iter().find(|&&(from, _)| from == symbol) {
+                Some((_, to)) => next.push_str(to),
+                None => next.push(symbol),
+            }
+            if next.len() > MAX_L_SYSTEM_LENGTH {
+                return Err(format!(
+                    "The L-system grew to more than {MAX_L_SYSTEM_LENGTH} symbols: use fewer iterations"
+                ));
+            }
+        }
+        symbols = next;
+    }
+
+    let mut depth = 0usize;
+    for symbol in symbols.chars() {
+        match symbol {
+            '[' => depth += 1,
+            ']' => {
+                depth = depth
+                    .checked_sub(1)
+                    .ok_or("The L-system has a ']' without a matching '['")?;
+            }
+            _ => {}
+        }
+    }
+
+    // The lines are drawn on a copy of the image's settings first, so that
+    // nothing is drawn if one of them fails.
+    let (width, height) = image.get_dimensions();
+    let mut turtle = image.blank_like(width, height)?;
+    let (mut position, mut direction) = (start, 0);
+    let mut saved = Vec::new();
+    for symbol in symbols.chars() {
+        match symbol {
+            'F' | 'G' => {
+                position =
+                    turtle.draw_simple_line(position.0, position.1, direction, step, color)?;
+            }
+            'f' => {
+                let end = turtle.line_end(position.0, position.1, direction, step);
+                position = turtle.clamp_point(end);
+            }
+            '+' => direction = crate::normalize_direction(direction.wrapping_add(angle)),
+            '-' => direction = crate::normalize_direction(direction.wrapping_sub(angle)),
+            '[' => saved.push((position, direction)),
+            ']' => {
+                (position, direction) = saved
+                    .pop()
+                    .ok_or("The L-system has a ']' without a matching '['")?;
+            }
+            _ => {}
+        }
+    }
+
+    for (_, node) in std::mem::take(&mut turtle.elements) {
+        image.append_element_node(node)?;
+    }
+    Ok(position)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::COLORS;
+
+    /// Where each line drawn on `image` starts and ends.
+    fn lines(image: &Image) -> Vec<((f32, f32), (f32, f32))> {
+        image
+            .to_segments(0.1)
+            .into_iter()
+            .map(|segment| (segment.start, segment.end))
+            .collect()
+    }
+
+    #[test]
+    fn l_systems_draw_a_line_for_each_step_forward() {
+        let mut image = Image::new(100, 100);
+        let end = l_system(&mut image, "F+F+F+F", &[], 0, 90, 20, (40, 60), 1).unwrap();
+        assert_eq!(end, (40, 60));
+        assert_eq!(
+            lines(&image),
+            [
+                ((40.0, 60.0), (40.0, 40.0)),
+                ((40.0, 40.0), (60.0, 40.0)),
+                ((60.0, 40.0), (60.0, 60.0)),
+                ((60.0, 60.0), (40.0, 60.0)),
+            ]
+        );
+        assert_eq!(image.element_ids().len(), 4);
+    }
+
+    #[test]
+    fn l_systems_expand_their_rules() {
+        let mut image = Image::new(100, 100);
+        let rules = [('F', "F+F-F-F+F")];
+        let end = l_system(&mut image, "F", &rules, 1, 90, 10, (10, 50), 1).unwrap();
+        assert_eq!(end, (10, 20));
+        assert_eq!(
+            lines(&image),
+            [
+                ((10.0, 50.0), (10.0, 40.0)),
+                ((10.0, 40.0), (20.0, 40.0)),
+                ((20.0, 40.0), (20.0, 30.0)),
+                ((20.0, 30.0), (10.0, 30.0)),
+                ((10.0, 30.0), (10.0, 20.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn l_systems_go_back_to_where_they_branched() {
+        let mut image = Image::new(100, 100);
+        let end = l_system(&mut image, "F[+F]F", &[], 0, 90, 10, (50, 50), 1).unwrap();
+        assert_eq!(end, (50, 30));
+        assert_eq!(
+            lines(&image),
+            [
+                ((50.0, 50.0), (50.0, 40.0)),
+                ((50.0, 40.0), (60.0, 40.0)),
+                ((50.0, 40.0), (50.0, 30.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn l_systems_move_without_drawing() {
+        let mut image = Image::new(100, 100);
+        l_system(&mut image, "FfF", &[], 0, 90, 10, (50, 50), 1).unwrap();
+        assert_eq!(
+            lines(&image),
+            [((50.0, 50.0), (50.0, 40.0)), ((50.0, 30.0), (50.0, 20.0))]
+        );
+
+        // Moves stop at the edge, like lines do.
+        let mut image = Image::new(100, 100);
+        image.set_clamp_to_bounds(true);
+        let end = l_system(&mut image, "ff+F", &[], 0, 90, 10, (50, 15), 1).unwrap();
+        assert_eq!(end, (60, 0));
+        assert_eq!(lines(&image), [((50.0, 0.0), (60.0, 0.0))]);
+    }
+
+    #[test]
+    fn l_systems_draw_nothing_if_they_fail() {
+        let mut image = Image::new(100, 100);
+        image.draw_simple_line(10, 10, 90, 10, COLORS[1]).unwrap();
+        assert!(l_system(&mut image, "F]F", &[], 0, 90, 10, (50, 50), 1).is_err());
+        assert!(l_system(&mut image, "X", &[('X', "F[F]]")], 2, 90, 10, (50, 50), 1).is_err());
+        assert_eq!(image.element_ids().len(), 1);
+
+        // The fifth line would leave the image.
+        image.set_strict(true);
+        assert!(l_system(&mut image, "FFFFFF", &[], 0, 90, 20, (50, 90), 1).is_err());
+        assert_eq!(image.element_ids().len(), 1);
+        l_system(&mut image, "FFFF", &[], 0, 90, 20, (50, 90), 1).unwrap();
+        assert_eq!(image.element_ids().len(), 5);
+    }
+}
@@ -0,0 +1,538 @@
+use crate::Image;
+
+impl Image {
+    /// A number that changes whenever the image does, for caching things
+    /// worked out from it (like a rendered preview) and noticing when they
+    /// are out of date.
+    ///
+    /// Every method that changes the image changes its generation: drawing,
+    /// removing elements, erasing, recoloring, resizing the canvas, and all
+    /// the `set_` methods. A call that returns an error leaves the generation
+    /// as it was. A call that leaves the image looking the same (like setting
+    /// an option to the value it already had) may still change it, so two
+    /// different generations mean the image *might* have changed, while the
+    /// same generation means it certainly hasn't.
+    ///
+    /// A new image starts at generation 0. A clone starts with the same
+    /// generation as the original, and the two then count separately, so
+    /// compare generations of the same image, not of an image and its clone.
+    ///
+    /// ```rs
+    /// let seen = image.generation();
+    /// image.draw_simple_line(10, 10, 90, 50, COLORS[1])?;
+    /// assert_ne!(image.generation(), seen);
+    /// ```
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Record that the image has changed.
+    pub(crate) fn mark_changed(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::generate::{l_system, random_walk};
+    use crate::text::test_font;
+    use crate::{
+        Anchor, BlendSpace, Color, Corner, FillRule, Image, MaskFit, PixelAlignment, Rounding,
+        SaveOptions, StrokeStyle, COLORS,
+    };
+    use resvg::usvg::{self, fontdb};
+
+    type Mutator = Box<dyn Fn(&mut Image) -> Result<(), String>>;
+
+    /// An image with one line on it, so there is an element to work on.
+    fn drawn() -> Image {
+        let mut image = Image::new(100, 100);
+        image.draw_simple_line(20, 50, 90, 60, COLORS[1]).unwrap();
+        image
+    }
+
+    fn last(image: &Image) -> crate::ElementId {
+        image.last_element().unwrap()
+    }
+
+    /// A call to every public method that changes an image, each one
+    /// arranged so that it succeeds on `drawn()`.
+    fn mutators() -> Vec<(&'static str, Mutator)> {
+        let style = StrokeStyle::default();
+        let square = [(10, 10), (90, 10), (90, 90), (10, 90)];
+        let field = vec![
+            vec![0.0, 0.0, 0.0],
+            vec![0.0, 1.0, 0.0],
+            vec![0.0, 0.0, 0.0],
+        ];
+        let mask = Image::new(100, 100);
+        let mut mutators: Vec<(&'static str, Mutator)> = vec![
+            (
+                "set_pixel_alignment",
+                Box::new(|image| {
+                    image.set_pixel_alignment(PixelAlignment::HalfPixel);
+                    Ok(())
+                }),
+            ),
+            (
+                "set_anchor",
+                Box::new(|image| {
+                    image.set_anchor("here", 10, 10);
+                    Ok(())
+                }),
+            ),
+            (
+                "draw_simple_line_from",
+                Box::new(|image| {
+                    image
+                        .draw_simple_line_from((10, 10), 90, 20, COLORS[1])
+                        .map(drop)
+                }),
+            ),
+            (
+                "draw_styled_line_from",
+                Box::new(move |image| {
+                    image
+                        .draw_styled_line_from((10, 10), 90, 20, COLORS[1], &style)
+                        .map(drop)
+                }),
+            ),
+            (
+                "draw_line_between",
+                Box::new(|image| image.draw_line_between((10, 10), (30, 30), COLORS[1])),
+            ),
+            (
+                "set_blend_space",
+                Box::new(|image| {
+                    image.set_blend_space(BlendSpace::Linear);
+                    Ok(())
+                }),
+            ),
+            (
+                "grow_canvas",
+                Box::new(|image| image.grow_canvas(120, 120, Anchor::Center)),
+            ),
+            (
+                "draw_contours",
+                Box::new(move |image| image.draw_contours(&field, &[0.5], COLORS[2])),
+            ),
+            (
+                "set_coordinate_system",
+                Box::new(|image| {
+                    image.set_coordinate_system(Corner::BottomLeft, true);
+                    Ok(())
+                }),
+            ),
+            (
+                "draw_line_pct",
+                Box::new(|image| image.draw_line_pct(0.5, 0.5, 90, 0.2, COLORS[1]).map(drop)),
+            ),
+            ("dilate", Box::new(|image| image.dilate(2))),
+            (
+                "append_node",
+                Box::new(|image| {
+                    let rect = usvg::Rect::from_xywh(10.0, 10.0, 20.0, 20.0).unwrap();
+                    let mut path =
+                        usvg::Path::new(resvg::tiny_skia::PathBuilder::from_rect(rect).into());
+                    path.fill = Some(usvg::Fill::from_paint(COLORS[7].paint()));
+                    image.append_node(usvg::NodeKind::Path(path)).map(drop)
+                }),
+            ),
+            (
+                "remove_element",
+                Box::new(|image| {
+                    let id = last(image);
+                    assert!(image.remove_element(id));
+                    Ok(())
+                }),
+            ),
+            (
+                "erase_line",
+                Box::new(|image| image.erase_line(50, 50, 0, 20, 4.0).map(drop)),
+            ),
+            (
+                "set_frame",
+                Box::new(|image| {
+                    image.set_frame(2, Color::white(), 0);
+                    Ok(())
+                }),
+            ),
+            (
+                "draw_grid_of",
+                Box::new(|image| {
+                    image.draw_grid_of(2, 2, 1, |cell, _, _| {
+                        cell.draw_simple_line(5, 5, 90, 10, COLORS[3]).map(drop)
+                    })
+                }),
+            ),
+            (
+                "draw_simple_line_hue_by_direction",
+                Box::new(|image| {
+                    image
+                        .draw_simple_line_hue_by_direction(10, 10, 90, 20)
+                        .map(drop)
+                }),
+            ),
+            (
+                "set_direction_hue",
+                Box::new(|image| {
+                    image.set_direction_hue(0.5, 0.5);
+                    Ok(())
+                }),
+            ),
+            (
+                "set_save_options",
+                Box::new(|image| {
+                    image.set_save_options(SaveOptions {
+                        warn_invisible: true,
+                    });
+                    Ok(())
+                }),
+            ),
+            (
+                "draw_simple_line",
+                Box::new(|image| image.draw_simple_line(10, 10, 90, 20, COLORS[1]).map(drop)),
+            ),
+            (
+                "draw_styled_line",
+                Box::new(move |image| {
+                    image
+                        .draw_styled_line(10, 10, 90, 20, COLORS[1], &style)
+                        .map(drop)
+                }),
+            ),
+            (
+                "push_clip_polygon",
+                Box::new(move |image| image.push_clip_polygon(&square)),
+            ),
+            (
+                "set_clamp_to_bounds",
+                Box::new(|image| {
+                    image.set_clamp_to_bounds(true);
+                    Ok(())
+                }),
+            ),
+            (
+                "set_rounding",
+                Box::new(|image| {
+                    image.set_rounding(Rounding::Truncate);
+                    Ok(())
+                }),
+            ),
+            (
+                "set_mask",
+                Box::new({
+                    let mask = mask.clone();
+                    move |image| image.set_mask(&mask, MaskFit::Scale)
+                }),
+            ),
+            (
+                "clear_mask",
+                Box::new(|image| {
+                    image.clear_mask();
+                    Ok(())
+                }),
+            ),
+            (
+                "with_mask",
+                Box::new(move |image| {
+                    image.with_mask(&mask, MaskFit::Scale, |masked| {
+                        masked.draw_simple_line(10, 10, 90, 20, COLORS[1]).map(drop)
+                    })
+                }),
+            ),
+            (
+                "set_memory_limit",
+                Box::new(|image| {
+                    image.set_memory_limit(Some(1 << 20));
+                    Ok(())
+                }),
+            ),
+            (
+                "map_region_colors",
+                Box::new(|image| image.map_region_colors((0, 0, 50, 50), |_| COLORS[4])),
+            ),
+            (
+                "draw_cell_grid",
+                Box::new(|image| {
+                    image.draw_cell_grid((0, 0), 2, 2, 10, &|_, _| Some(COLORS[3]), None)
+                }),
+            ),
+            (
+                "draw_center_guides",
+                Box::new(|image| image.draw_center_guides(COLORS[15]).map(drop)),
+            ),
+            (
+                "draw_connected_dots",
+                Box::new(|image| {
+                    image.draw_connected_dots(&[(10, 10), (50, 50)], 4, COLORS[1], COLORS[4])
+                }),
+            ),
+            (
+                "draw_convex_hull",
+                Box::new(move |image| image.draw_convex_hull(&square, COLORS[2]).map(drop)),
+            ),
+            (
+                "draw_diagonals",
+                Box::new(|image| image.draw_diagonals(COLORS[15]).map(drop)),
+            ),
+            (
+                "draw_multicolor_path",
+                Box::new(|image| {
+                    image.draw_multicolor_path(
+                        &[(10, 10), (50, 10), (50, 50)],
+                        &[COLORS[1], COLORS[4]],
+                    )
+                }),
+            ),
+            (
+                "draw_progress_arc",
+                Box::new(|image| image.draw_progress_arc(50, 50, 30, 4, 0.5, COLORS[3])),
+            ),
+            (
+                "draw_ribbon",
+                Box::new(|image| image.draw_ribbon(&[(10, 10), (90, 90)], &[2.0, 6.0], COLORS[2])),
+            ),
+            (
+                "draw_ring",
+                Box::new(|image| image.draw_ring(50, 50, 30, 20, COLORS[4])),
+            ),
+            (
+                "draw_styled_polyline",
+                Box::new(move |image| image.draw_styled_polyline(&square, COLORS[1], &style)),
+            ),
+            (
+                "draw_varying_dashed_line",
+                Box::new(move |image| {
+                    image
+                        .draw_varying_dashed_line(10, 10, 90, 50, COLORS[1], &style, |_| (4.0, 2.0))
+                        .map(drop)
+                }),
+            ),
+            (
+                "draw_wave",
+                Box::new(|image| image.draw_wave(10, 50, 10, 20, 2.0, COLORS[3])),
+            ),
+            (
+                "fill_polygon",
+                Box::new(move |image| image.fill_polygon(&square, COLORS[2], FillRule::NonZero)),
+            ),
+            (
+                "animate_translation",
+                Box::new(|image| image.animate_translation(last(image), (10, 0), 1.0, true)),
+            ),
+            (
+                "set_strict",
+                Box::new(|image| {
+                    image.set_strict(true);
+                    Ok(())
+                }),
+            ),
+            (
+                "enable_timestamps",
+                Box::new(|image| {
+                    image.enable_timestamps();
+                    Ok(())
+                }),
+            ),
+            (
+                "draw_reflected",
+                Box::new(|image| {
+                    image
+                        .draw_reflected(last(image), (50, 0), (50, 100))
+                        .map(drop)
+                }),
+            ),
+            (
+                "repeat_element",
+                Box::new(|image| {
+                    image
+                        .repeat_element(last(image), 3, 10, (0, 0), 1.0)
+                        .map(drop)
+                }),
+            ),
+            (
+                "repeat_last",
+                Box::new(|image| image.repeat_last(3, 0, (5, 0), 1.0).map(drop)),
+            ),
+            ("snap_to_grid", Box::new(|image| image.snap_to_grid(10))),
+            (
+                "set_watermark",
+                Box::new(|image| {
+                    image.set_watermark("draft", Corner::BottomRight, 0.5);
+                    Ok(())
+                }),
+            ),
+            (
+                "clear_watermark",
+                Box::new(|image| {
+                    image.clear_watermark();
+                    Ok(())
+                }),
+            ),
+            (
+                "random_walk",
+                Box::new(|image| random_walk(image, (50, 50), 10, 5, 6991, COLORS[7]).map(drop)),
+            ),
+            (
+                "l_system",
+                Box::new(|image| {
+                    l_system(image, "F+F", &[], 1, 90, 10, (50, 50), COLORS[3]).map(drop)
+                }),
+            ),
+        ];
+
+        #[cfg(feature = "json")]
+        mutators.push((
+            "draw_geojson",
+            Box::new(|image| {
+                let line = r#"{"type": "LineString", "coordinates": [[0, 0], [2, 3]]}"#;
+                image.draw_geojson(line, |(x, y)| (x as i32 * 10, y as i32 * 10), COLORS[3])
+            }),
+        ));
+
+        mutators.push((
+            "set_font_data",
+            Box::new(|image| image.set_font_data([test_font()])),
+        ));
+        mutators.push((
+            "set_font_database",
+            Box::new(|image| {
+                image.set_font_database(fontdb::Database::new());
+                Ok(())
+            }),
+        ));
+        mutators
+    }
+
+    #[test]
+    fn every_mutator_changes_the_generation() {
+        for (name, mutate) in mutators() {
+            let mut image = drawn();
+            let seen = image.generation();
+            mutate(&mut image).unwrap_or_else(|e| panic!("{name} failed: {e}"));
+            assert_ne!(image.generation(), seen, "{name} kept the generation");
+        }
+    }
+
+    #[test]
+    fn pop_clip_changes_the_generation() {
+        let mut image = drawn();
+        image
+            .push_clip_polygon(&[(0, 0), (50, 0), (0, 50)])
+            .unwrap();
+        let seen = image.generation();
+        image.pop_clip().unwrap();
+        assert_ne!(image.generation(), seen);
+    }
+
+    #[test]
+    fn errors_keep_the_generation() {
+        let style = StrokeStyle::default();
+        let errors: Vec<(&str, Mutator)> = vec![
+            (
+                "draw_simple_line",
+                Box::new(|image| image.draw_simple_line(50, 50, 90, 500, COLORS[1]).map(drop)),
+            ),
+            (
+                "draw_styled_line",
+                Box::new(move |image| {
+                    let style = StrokeStyle {
+                        width: -1.0,
+                        ..style
+                    };
+                    image
+                        .draw_styled_line(10, 10, 90, 20, COLORS[1], &style)
+                        .map(drop)
+                }),
+            ),
+            (
+                "grow_canvas",
+                Box::new(|image| image.grow_canvas(0, 10, Anchor::TopLeft)),
+            ),
+            (
+                "draw_contours",
+                Box::new(|image| {
+                    image.draw_contours(&[vec![1.0, 2.0], vec![1.0]], &[1.0], COLORS[2])
+                }),
+            ),
+            (
+                "erase_line",
+                Box::new(|image| image.erase_line(50, 50, 0, 20, 0.0).map(drop)),
+            ),
+            (
+                "push_clip_polygon",
+                Box::new(|image| image.push_clip_polygon(&[(0, 0), (9, 9)])),
+            ),
+            ("pop_clip", Box::new(|image| image.pop_clip())),
+            (
+                "set_mask",
+                Box::new(|image| image.set_mask(&Image::new(50, 50), MaskFit::Error)),
+            ),
+            (
+                "with_mask",
+                Box::new(|image| {
+                    image.with_mask(&Image::new(100, 100), MaskFit::Scale, |masked| {
+                        masked.draw_simple_line(10, 10, 90, 20, COLORS[1])?;
+                        Err("changed my mind".into())
+                    })
+                }),
+            ),
+            (
+                "map_region_colors",
+                Box::new(|image| image.map_region_colors((10, 10, 10, 50), |_| COLORS[4])),
+            ),
+            (
+                "draw_cell_grid",
+                Box::new(|image| image.draw_cell_grid((0, 0), 0, 2, 10, &|_, _| None, None)),
+            ),
+            (
+                "draw_convex_hull",
+                Box::new(|image| image.draw_convex_hull(&[(10, 10)], COLORS[2]).map(drop)),
+            ),
+            (
+                "draw_progress_arc",
+                Box::new(|image| image.draw_progress_arc(50, 50, 30, 0, 0.5, COLORS[3])),
+            ),
+            (
+                "draw_ribbon",
+                Box::new(|image| image.draw_ribbon(&[(10, 10)], &[2.0], COLORS[2])),
+            ),
+            (
+                "draw_wave",
+                Box::new(|image| image.draw_wave(10, 50, 10, 0, 2.0, COLORS[3])),
+            ),
+            (
+                "animate_translation",
+                Box::new(|image| image.animate_translation(last(image), (10, 0), 0.0, true)),
+            ),
+            ("snap_to_grid", Box::new(|image| image.snap_to_grid(0))),
+            ("set_font_data", Box::new(|image| image.set_font_data([]))),
+            (
+                "l_system",
+                Box::new(|image| {
+                    l_system(image, "F]F", &[], 1, 90, 10, (50, 50), COLORS[3]).map(drop)
+                }),
+            ),
+        ];
+
+        for (name, mutate) in errors {
+            // Strict, so that lines off the edge are errors.
+            let mut image = drawn();
+            image.set_strict(true);
+            let seen = image.generation();
+            assert!(mutate(&mut image).is_err(), "{name} didn't fail");
+            assert_eq!(image.generation(), seen, "{name} changed the generation");
+        }
+    }
+
+    #[test]
+    fn clones_count_separately() {
+        let mut image = drawn();
+        let clone = image.clone();
+        assert_eq!(clone.generation(), image.generation());
+        image.draw_simple_line(10, 10, 90, 20, COLORS[1]).unwrap();
+        assert_ne!(clone.generation(), image.generation());
+        assert_eq!(Image::new(10, 10).generation(), 0);
+    }
+}
@@ -0,0 +1,313 @@
+use crate::{i32_to_f32, Image, IntoColor};
+use resvg::{tiny_skia, usvg};
+use serde_json::Value;
+
+impl Image {
+    /// Draw the lines and polygons from GeoJSON data, such as roads, rivers
+    /// and borders, placing each point with `projection`. This needs the
+    /// `json` feature.
+    ///
+    /// `projection` turns a GeoJSON position, as `(longitude, latitude)` (or
+    /// whatever `x` and `y` the data uses), into a point on the image. Any
+    /// altitude is ignored.
+    ///
+    /// `data` can be a `FeatureCollection`, a single `Feature`, or a bare
+    /// geometry. `LineString`s are drawn as lines, and `Polygon`s as closed
+    /// outlines (including their holes), along with their `Multi` versions
+    /// and anything in a `GeometryCollection`. Points are skipped, and so are
+    /// features with no geometry. Everything is drawn 1 unit wide in
+    /// `color`, with each feature (or bare geometry) as one element.
+    ///
+    /// Returns an error if `data` isn't GeoJSON, or if a geometry is broken
+    /// (such as a line with only one position). Nothing is drawn if there is
+    /// an error.
+    ///
+    /// ```rs
+    /// let data = r#"{
+    ///     "type": "Feature",
+    ///     "properties": {},
+    ///     "geometry": {
+    ///         "type": "Polygon",
+    ///         "coordinates": [[[150.0, -33.0], [151.0, -33.0], [151.0, -34.0], [150.0, -33.0]]]
+    ///     }
+    /// }"#;
+    /// // Each degree is 50 units, with north at the top.
+    /// image.draw_geojson(data, |(lon, lat)| {
+    ///     (((lon - 150.0) * 50.0) as i32 + 10, ((-33.0 - lat) * 50.0) as i32 + 10)
+    /// }, COLORS[3])?;
+    /// ```
+    pub fn draw_geojson(
+        &mut self,
+        data: &str,
+        projection: impl Fn((f64, f64)) -> (i32, i32),
+        color: impl IntoColor,
+    ) -> Result<(), String> {
+        let color = color.into_color()?;
+        let value: Value =
+            serde_json::from_str(data).map_err(|e| format!("Invalid GeoJSON: {e}"))?;
+
+        let geometries = match member_str(&value, "type")? {
+            "FeatureCollection" => {
+                let features = value
+                    .get("features")
+                    .and_then(Value::as_array)
+                    .ok_or("A GeoJSON FeatureCollection needs a list of \"features\"")?;
+                features
+                    .iter()
+                    .map(feature_geometry)
+                    .collect::<Result<Vec<_>, _>>()?
+            }
+            "Feature" => vec![feature_geometry(&value)?],
+            _ => vec![Some(&value)],
+        };
+
+        let mut paths = Vec::new();
+        for geometry in geometries.into_iter().flatten() {
+            let mut builder = tiny_skia::PathBuilder::new();
+            add_geometry(&mut builder, geometry, &projection)?;
+            paths.extend(builder.finish());
+        }
+        for data in &paths {
+            self.check_strict_bounds("GeoJSON drawing", data.bounds())?;
+        }
+
+        for data in paths {
+            let mut path = usvg::Path::new(data.into());
+            path.stroke = Some(usvg::Stroke {
+                paint: color.paint(),
+                opacity: color.opacity(),
+                ..usvg::Stroke::default()
+            });
+            self.append_element(usvg::NodeKind::Path(path))?;
+        }
+        Ok(())
+    }
+}
+
+/// A member of a GeoJSON object that must be a string.
+fn member_str<'a>(value: &'a Value, name: &str) -> Result<&'a str, String> {
+    value
+        .get(name)
+        .and_then(Value::as_str)
+        .ok_or_else(|| format!("A GeoJSON object needs a \"{name}\" string"))
+}
+
+/// The geometry of a GeoJSON feature, or `None` if it has none.
+fn feature_geometry(feature: &Value) -> Result<Option<&Value>, String> {
+    let kind = member_str(feature, "type")?;
+    if kind != "Feature" {
+        return Err(format!(
+            "A GeoJSON FeatureCollection can only hold features, but it had a {kind}"
+        ));
+    }
+    Ok(feature
+        .get("geometry")
+        .filter(|geometry| !geometry.is_null()))
+}
+
+/// Add the lines of a GeoJSON geometry to `builder`.
+fn add_geometry(
+    builder: &mut tiny_skia::PathBuilder,
+    geometry: &Value,
+    projection: &impl Fn((f64, f64)) -> (i32, i32),
+) -> Result<(), String> {
+    let kind = member_str(geometry, "type")?;
+    if kind == "GeometryCollection" {
+        let geometries = geometry
+            .get("geometries")
+            .and_then(Value::as_array)
+            .ok_or("A GeoJSON GeometryCollection needs a list of \"geometries\"")?;
+        for geometry in geometries {
+            add_geometry(builder, geometry, projection)?;
+        }
+        return Ok(());
+    }
+
+    let coordinates = geometry
+        .get("coordinates")
+        .ok_or_else(|| format!("A GeoJSON {kind} needs \"coordinates\""))?;
+    match kind {
+        "Point" | "MultiPoint" => {}
+        "LineString" => add_line(builder, coordinates, false, projection)?,
+        "MultiLineString" => {
+            for line in list(coordinates, kind)? {
+                add_line(builder, line, false, projection)?;
+            }
+        }
+        "Polygon" => add_polygon(builder, coordinates, projection)?,
+        "MultiPolygon" => {
+            for polygon in list(coordinates, kind)? {
+                add_polygon(builder, polygon, projection)?;
+            }
+        }
+        _ => return Err(format!("Unknown GeoJSON geometry type \"{kind}\"")),
+    }
+    Ok(())
+}
+
+/// Add every ring of a GeoJSON polygon to `builder`.
+fn add_polygon(
+    builder: &mut tiny_skia::PathBuilder,
+    rings: &Value,
+    projection: &impl Fn((f64, f64)) -> (i32, i32),
+) -> Result<(), String> {
+    for ring in list(rings, "Polygon")? {
+        add_line(builder, ring, true, projection)?;
+    }
+    Ok(())
+}
+
+/// Add a line through GeoJSON positions to `builder`, closing it if it is
+/// the ring of a polygon.
+fn add_line(
+    builder: &mut tiny_skia::PathBuilder,
+    positions: &Value,
+    closed: bool,
+    projection: &impl Fn((f64, f64)) -> (i32, i32),
+) -> Result<(), String> {
+    let positions = list(positions, "LineString")?;
+    let needed = if closed { 3 } else { 2 };
+    if positions.len() < needed {
+        return Err(format!(
+            "A GeoJSON {} needs at least {needed} positions, but it had {}",
+            if closed { "polygon ring" } else { "line" },
+            positions.len()
+        ));
+    }
+
+    for (i, position) in positions.iter().enumerate() {
+        let (x, y) = projection(position_xy(position)?);
+        let (x, y) = (i32_to_f32(x), i32_to_f32(y));
+        if i == 0 {
+            builder.move_to(x, y);
+        } else {
+            builder.line_to(x, y);
+        }
+    }
+    if closed {
+        builder.close();
+    }
+    Ok(())
+}
+
+/// The list of coordinates of a GeoJSON geometry.
+fn list<'a>(value: &'a Value, kind: &str) -> Result<&'a Vec<Value>, String> {
+    value
+        .as_array()
+        .ok_or_else(|| format!("The coordinates of a GeoJSON {kind} must be lists"))
+}
+
+/// The first two numbers of a GeoJSON position.
+fn position_xy(position: &Value) -> Result<(f64, f64), String> {
+    match position.as_array().map(Vec::as_slice) {
+        Some([x, y, ..]) => match (x.as_f64(), y.as_f64()) {
+            (Some(x), Some(y)) => Ok((x, y)),
+            _ => Err(format!(
+                "A GeoJSON position must be numbers, but it was {position}"
+            )),
+        },
+        _ => Err(format!(
+            "A GeoJSON position must have at least 2 numbers, but it was {position}"
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Image, COLORS};
+
+    /// Ten units per degree, from (0, 0).
+    fn projection((x, y): (f64, f64)) -> (i32, i32) {
+        ((x * 10.0) as i32, (y * 10.0) as i32)
+    }
+
+    /// The segments drawn, as whole-number `(start, end)` pairs.
+    fn segments(image: &Image) -> Vec<((i32, i32), (i32, i32))> {
+        let round = |(x, y): (f32, f32)| (x.round() as i32, y.round() as i32);
+        image
+            .to_segments(0.1)
+            .iter()
+            .map(|segment| (round(segment.start), round(segment.end)))
+            .collect()
+    }
+
+    #[test]
+    fn features_are_drawn_one_element_each() {
+        let data = r#"{
+            "type": "FeatureCollection",
+            "features": [
+                {"type": "Feature", "properties": {}, "geometry":
+                    {"type": "LineString", "coordinates": [[1, 1], [5, 1, 300], [5, 5]]}},
+                {"type": "Feature", "properties": {}, "geometry": null},
+                {"type": "Feature", "properties": {}, "geometry":
+                    {"type": "Point", "coordinates": [3, 3]}},
+                {"type": "Feature", "properties": {}, "geometry":
+                    {"type": "Polygon", "coordinates": [
+                        [[6, 6], [9, 6], [9, 9], [6, 6]],
+                        [[7, 7], [8, 7], [8, 8], [7, 7]]
+                    ]}},
+                {"type": "Feature", "properties": {}, "geometry":
+                    {"type": "GeometryCollection", "geometries": [
+                        {"type": "MultiLineString", "coordinates": [[[0, 9], [1, 9]], [[0, 8], [1, 8]]]},
+                        {"type": "MultiPoint", "coordinates": [[2, 2]]}
+                    ]}}
+            ]
+        }"#;
+        let mut image = Image::new(100, 100);
+        image.draw_geojson(data, projection, COLORS[3]).unwrap();
+        // Nothing for the feature with no geometry, or the point.
+        assert_eq!(image.element_ids().len(), 3);
+        assert_eq!(
+            segments(&image),
+            [
+                ((10, 10), (50, 10)),
+                ((50, 10), (50, 50)),
+                ((60, 60), (90, 60)),
+                ((90, 60), (90, 90)),
+                ((90, 90), (60, 60)),
+                ((70, 70), (80, 70)),
+                ((80, 70), (80, 80)),
+                ((80, 80), (70, 70)),
+                ((0, 90), (10, 90)),
+                ((0, 80), (10, 80)),
+            ]
+        );
+        assert!(image
+            .to_segments(0.1)
+            .iter()
+            .all(|segment| segment.color == COLORS[3] && segment.width == 1.0));
+
+        // A bare geometry is one element too.
+        let mut image = Image::new(100, 100);
+        let line = r#"{"type": "LineString", "coordinates": [[0, 0], [2, 3]]}"#;
+        image.draw_geojson(line, projection, COLORS[3]).unwrap();
+        assert_eq!(segments(&image), [((0, 0), (20, 30))]);
+    }
+
+    #[test]
+    fn broken_geojson_draws_nothing() {
+        let mut image = Image::new(100, 100);
+        let broken = [
+            "not json",
+            r#"{"coordinates": [[0, 0], [1, 1]]}"#,
+            r#"{"type": "Curve", "coordinates": [[0, 0], [1, 1]]}"#,
+            r#"{"type": "LineString"}"#,
+            r#"{"type": "LineString", "coordinates": [[0, 0]]}"#,
+            r#"{"type": "LineString", "coordinates": [[0, 0], ["a", 1]]}"#,
+            r#"{"type": "Polygon", "coordinates": [[[0, 0], [1, 1]]]}"#,
+            r#"{"type": "FeatureCollection"}"#,
+            // The first feature is fine, but nothing is drawn from it.
+            r#"{"type": "FeatureCollection", "features": [
+                {"type": "Feature", "geometry": {"type": "LineString", "coordinates": [[0, 0], [1, 1]]}},
+                {"type": "LineString", "coordinates": [[0, 0], [1, 1]]}
+            ]}"#,
+        ];
+        for data in broken {
+            assert!(
+                image.draw_geojson(data, projection, COLORS[3]).is_err(),
+                "{data}"
+            );
+        }
+        assert_eq!(image.last_element(), None);
+    }
+}
@@ -0,0 +1,327 @@
+//! Geometry helpers that work on the crate's integer coordinates.
+
+/// Twice the signed area of the triangle `o`, `a`, `b`.
+///
+/// Computed in `i64` so that any pair of `i32` points is handled without overflow.
+fn cross(o: (i32, i32), a: (i32, i32), b: (i32, i32)) -> i64 {
+    let (ox, oy) = (o.0 as i64, o.1 as i64);
+    (a.0 as i64 - ox) * (b.1 as i64 - oy) - (a.1 as i64 - oy) * (b.0 as i64 - ox)
+}
+
+/// Computes the convex hull of a set of points using Andrew's monotone chain.
+///
+/// The hull starts at the point with the smallest x (then smallest y)
+/// coordinate and walks around the outside of the points. Points that lie on
+/// an edge of the hull are not included, and duplicate points are ignored, so
+/// the result contains no repeated points:
+///  - no points gives an empty hull,
+///  - a single distinct point gives a hull of just that point,
+///  - points that are all collinear give the two extreme points.
+///
+/// ```rs
+/// let hull = convex_hull(&[(0, 0), (10, 0), (5, 5), (10, 10), (0, 10)]);
+/// assert_eq!(hull, vec![(0, 0), (10, 0), (10, 10), (0, 10)]);
+/// ```
+pub fn convex_hull(points: &[(i32, i32)]) -> Vec<(i32, i32)> {
+    let mut points = points.to_vec();
+    points.sort_unstable();
+    points.dedup();
+
+    if points.len() < 3 {
+        return points;
+    }
+
+    let mut lower: Vec<(i32, i32)> = Vec::with_capacity(points.len());
+    for &p in &points {
+        while lower.len() >= 2 && cross(lower[lower.len() - 2], lower[lower.len() - 1], p) <= 0 {
+            lower.pop();
+        }
+        lower.push(p);
+    }
+
+    let mut upper: Vec<(i32, i32)> = Vec::with_capacity(points.len());
+    for &p in points.iter().rev() {
+        while upper.len() >= 2 && cross(upper[upper.len() - 2], upper[upper.len() - 1], p) <= 0 {
+            upper.pop();
+        }
+        upper.push(p);
+    }
+
+    // The last point of each chain is the first point of the other one.
+    lower.pop();
+    upper.pop();
+    lower.extend(upper);
+    lower
+}
+
+/// The distance from `p` to the line segment between `a` and `b`.
+fn distance_to_segment(p: (i32, i32), a: (i32, i32), b: (i32, i32)) -> f32 {
+    let (px, py) = (p.0 as f32, p.1 as f32);
+    let (ax, ay) = (a.0 as f32, a.1 as f32);
+    let (bx, by) = (b.0 as f32, b.1 as f32);
+
+    let (dx, dy) = (bx - ax, by - ay);
+    let length_squared = dx * dx + dy * dy;
+    if length_squared == 0.0 {
+        return (px - ax).hypot(py - ay);
+    }
+
+    let t = (((px - ax) * dx + (py - ay) * dy) / length_squared).clamp(0.0, 1.0);
+    (px - (ax + t * dx)).hypot(py - (ay + t * dy))
+}
+
+/// Simplifies a polyline using the Ramer–Douglas–Peucker algorithm.
+///
+/// Any point that is within `tolerance` units of the simplified line is
+/// removed. The first and last points are always kept, so a polyline with
+/// fewer than three points is returned unchanged. A negative (or NaN)
+/// tolerance is treated as `0.0`, which only removes points that lie exactly
+/// on the simplified line.
+///
+/// ```rs
+/// let line = simplify_polyline(&[(0, 0), (5, 1), (10, 0), (20, 0)], 2.0);
+/// assert_eq!(line, vec![(0, 0), (20, 0)]);
+/// ```
+pub fn simplify_polyline(points: &[(i32, i32)], tolerance: f32) -> Vec<(i32, i32)> {
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+
+    let tolerance = tolerance.max(0.0);
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[points.len() - 1] = true;
+
+    // An explicit stack rather than recursion, so very long polylines can't
+    // overflow the call stack.
+    let mut ranges = vec![(0, points.len() - 1)];
+    while let Some((start, end)) = ranges.pop() {
+        let mut furthest = start;
+        let mut furthest_distance = 0.0;
+        for i in start + 1..end {
+            let distance = distance_to_segment(points[i], points[start], points[end]);
+            if distance > furthest_distance {
+                furthest = i;
+                furthest_distance = distance;
+            }
+        }
+
+        if furthest != start && furthest_distance > tolerance {
+            keep[furthest] = true;
+            ranges.push((start, furthest));
+            ranges.push((furthest, end));
+        }
+    }
+
+    points
+        .iter()
+        .zip(keep)
+        .filter_map(|(&p, keep)| keep.then_some(p))
+        .collect()
+}
+
+/// The circle through three points, as `(centre_x, centre_y, radius)`, or
+/// `None` if the points are on one straight line (including when any two
+/// of them are the same), so no circle goes through them all.
+///
+/// The calculation is done in `f64`, relative to the first point, so points
+/// that are very nearly on a line still give an accurate (if very large)
+/// circle.
+///
+/// ```rs
+/// let (x, y, radius) = circle_through((0, 0), (10, 0), (0, 10)).unwrap();
+/// assert_eq!((x, y, radius), (5.0, 5.0, 50f32.sqrt()));
+/// assert_eq!(circle_through((0, 0), (5, 5), (10, 10)), None);
+/// ```
+pub fn circle_through(p1: (i32, i32), p2: (i32, i32), p3: (i32, i32)) -> Option<(f32, f32, f32)> {
+    // Exact, so collinear points are always caught.
+    let twice_area = cross(p1, p2, p3);
+    if twice_area == 0 {
+        return None;
+    }
+
+    let (bx, by) = (
+        (i64::from(p2.0) - i64::from(p1.0)) as f64,
+        (i64::from(p2.1) - i64::from(p1.1)) as f64,
+    );
+    let (cx, cy) = (
+        (i64::from(p3.0) - i64::from(p1.0)) as f64,
+        (i64::from(p3.1) - i64::from(p1.1)) as f64,
+    );
+    let d = 2.0 * twice_area as f64;
+    let (b_squared, c_squared) = (bx * bx + by * by, cx * cx + cy * cy);
+    let ux = (cy * b_squared - by * c_squared) / d;
+    let uy = (bx * c_squared - cx * b_squared) / d;
+
+    Some((
+        (f64::from(p1.0) + ux) as f32,
+        (f64::from(p1.1) + uy) as f32,
+        ux.hypot(uy) as f32,
+    ))
+}
+
+/// The directions of the two lines from `from` that just touch a circle,
+/// using the same directions as `draw_simple_line` (0 is straight up, 90 is
+/// right, and so on), rounded to the nearest degree.
+///
+/// The first direction is the tangent anticlockwise of the direction to the
+/// centre, and the second is the one clockwise of it, each from 0 to 359.
+/// If `from` is on the circle, the two directions are opposite ways along
+/// the one tangent there.
+///
+/// Returns `None` if `from` is inside the circle, so no line from it only
+/// touches the circle, or if the radius is negative, or if `from` is the
+/// centre.
+///
+/// ```rs
+/// // A circle of radius 10, 20 units to the right.
+/// let tangents = tangent_directions((0, 0), (20, 0), 10);
+/// assert_eq!(tangents, Some((60, 120)));
+/// ```
+pub fn tangent_directions(
+    from: (i32, i32),
+    circle_center: (i32, i32),
+    radius: i32,
+) -> Option<(i32, i32)> {
+    let dx = (i64::from(circle_center.0) - i64::from(from.0)) as f64;
+    let dy = (i64::from(circle_center.1) - i64::from(from.1)) as f64;
+    let distance = dx.hypot(dy);
+    let radius = f64::from(radius);
+    if radius < 0.0 || distance == 0.0 || distance < radius {
+        return None;
+    }
+
+    // y points down the image, so "up" is negative y.
+    let towards_centre = dx.atan2(-dy).to_degrees();
+    // The tangents touch where the radius meets them at a right angle.
+    let spread = (radius / distance).min(1.0).asin().to_degrees();
+    let heading = |degrees: f64| (degrees.round() as i32).rem_euclid(360);
+
+    Some((
+        heading(towards_centre - spread),
+        heading(towards_centre + spread),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hull_leaves_out_inside_points() {
+        let points = [(0, 0), (10, 0), (5, 5), (10, 10), (0, 10)];
+        assert_eq!(
+            convex_hull(&points),
+            vec![(0, 0), (10, 0), (10, 10), (0, 10)]
+        );
+    }
+
+    #[test]
+    fn hull_of_degenerate_points() {
+        assert_eq!(convex_hull(&[]), vec![]);
+        assert_eq!(convex_hull(&[(3, 3), (3, 3)]), vec![(3, 3)]);
+        assert_eq!(
+            convex_hull(&[(0, 0), (1, 1), (2, 2), (2, 2)]),
+            vec![(0, 0), (2, 2)]
+        );
+        assert_eq!(
+            convex_hull(&[(0, 0), (0, 5), (0, 10), (5, 0), (10, 0)]),
+            vec![(0, 0), (10, 0), (0, 10)]
+        );
+    }
+
+    #[test]
+    fn simplify_drops_points_within_tolerance() {
+        let points = [(0, 0), (5, 1), (10, 0), (20, 0)];
+        assert_eq!(simplify_polyline(&points, 2.0), vec![(0, 0), (20, 0)]);
+        assert_eq!(simplify_polyline(&points, 0.5), points);
+    }
+
+    #[test]
+    fn simplify_degenerate_polylines() {
+        assert_eq!(simplify_polyline(&[], 1.0), vec![]);
+        assert_eq!(simplify_polyline(&[(4, 4)], 1.0), vec![(4, 4)]);
+        assert_eq!(
+            simplify_polyline(&[(0, 0), (0, 0), (0, 0)], 0.0),
+            vec![(0, 0), (0, 0)]
+        );
+        assert_eq!(
+            simplify_polyline(&[(0, 0), (5, 5), (0, 0)], 1.0),
+            vec![(0, 0), (5, 5), (0, 0)]
+        );
+        assert_eq!(
+            simplify_polyline(&[(0, 0), (5, 5), (10, 10)], f32::NAN),
+            vec![(0, 0), (10, 10)]
+        );
+    }
+
+    #[test]
+    fn circles_through_hand_worked_points() {
+        assert_eq!(
+            circle_through((0, 0), (10, 0), (0, 10)),
+            Some((5.0, 5.0, 50f32.sqrt()))
+        );
+        // Points on the circle x² + y² = 25, in any order.
+        for points in [[(3, 4), (-3, 4), (0, -5)], [(0, -5), (-3, 4), (3, 4)]] {
+            let [p1, p2, p3] = points;
+            assert_eq!(circle_through(p1, p2, p3), Some((0.0, 0.0, 5.0)));
+        }
+
+        assert_eq!(circle_through((0, 0), (5, 5), (10, 10)), None);
+        assert_eq!(circle_through((0, 0), (10, 10), (5, 5)), None);
+        assert_eq!(circle_through((1, 1), (1, 1), (5, 3)), None);
+        assert_eq!(circle_through((2, 7), (2, 7), (2, 7)), None);
+    }
+
+    #[test]
+    fn nearly_straight_points_give_huge_exact_circles() {
+        // The centre (1000, y) is as far from (0, 0) as from (1000, 1), so
+        // 1000² + y² = (1 - y)², and y = -499999.5.
+        assert_eq!(
+            circle_through((0, 0), (1000, 1), (2000, 0)),
+            Some((1000.0, -499_999.5, 500_000.5))
+        );
+        // The same, far from (0, 0), where f32 alone would lose the answer.
+        let far = 1_000_000_000;
+        let (x, y, radius) = circle_through((far, 0), (far + 1000, 1), (far + 2000, 0)).unwrap();
+        assert_eq!((x, y, radius), (1_000_001_000.0, -499_999.5, 500_000.5));
+        assert_eq!(
+            circle_through((far, far), (far + 1, far + 1), (-far, -far)),
+            None
+        );
+    }
+
+    #[test]
+    fn tangents_from_hand_worked_points() {
+        // The radius is half the distance, so each tangent is 30 degrees
+        // either side of the centre.
+        assert_eq!(tangent_directions((0, 0), (20, 0), 10), Some((60, 120)));
+        assert_eq!(tangent_directions((0, 0), (0, -20), 10), Some((330, 30)));
+        // The circle touches both axes, at (10, 0) to the right and (0, 10)
+        // below.
+        assert_eq!(tangent_directions((0, 0), (10, 10), 10), Some((90, 180)));
+        // On the circle, both ways along the one tangent: (3, 4) is 143.13
+        // degrees from straight up, and the tangent is at right angles.
+        assert_eq!(tangent_directions((0, 0), (3, 4), 5), Some((53, 233)));
+        assert_eq!(tangent_directions((10, 0), (0, 0), 10), Some((180, 0)));
+        // A circle with no size is touched by the line straight to it.
+        assert_eq!(tangent_directions((0, 0), (0, 20), 0), Some((180, 180)));
+        // Far away, both tangents are almost the direction to the centre.
+        assert_eq!(
+            tangent_directions((0, 0), (1_000_000, 0), 1),
+            Some((90, 90))
+        );
+        assert_eq!(
+            tangent_directions((i32::MIN, 0), (i32::MAX, 0), 5),
+            Some((90, 90))
+        );
+    }
+
+    #[test]
+    fn no_tangents_from_inside_the_circle() {
+        assert_eq!(tangent_directions((20, 1), (20, 0), 10), None);
+        assert_eq!(tangent_directions((3, 4), (0, 0), 6), None);
+        assert_eq!(tangent_directions((5, 5), (5, 5), 0), None);
+        assert_eq!(tangent_directions((0, 0), (20, 0), -1), None);
+    }
+}
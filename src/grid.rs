@@ -0,0 +1,328 @@
+use crate::{u32_to_f32, Image};
+use resvg::usvg::NodeExt;
+use resvg::{tiny_skia, usvg};
+use std::rc::Rc;
+
+impl Image {
+    /// Draw a grid of small pictures, calling `f` once for each cell with the
+    /// cell's column and row (counting from 0 at the top-left).
+    ///
+    /// The image is split into `cols` by `rows` equal slots, and each cell is
+    /// its slot shrunk by `cell_padding` units on every side. `f` draws on an
+    /// image the size of the cell, with (0, 0) at the cell's top-left corner
+    /// (or whichever corner `set_coordinate_system` chose), and anything it
+    /// draws outside the cell is cut off. The cells draw the
+    /// way this image does (with the same rounding, strictness, coordinate
+    /// system, units per step and so on), but their backgrounds are not
+    /// drawn. This is handy for drawing the same figure with different
+    /// parameters side by side.
+    ///
+    /// Everything drawn in the grid becomes a single element. If the image
+    /// doesn't divide evenly, the leftover units are at the right and bottom.
+    ///
+    /// Returns an error if there are no cells, if the padding leaves the cells
+    /// with no area, or if `f` returns an error for any cell (in which case
+    /// nothing is drawn).
+    ///
+    /// ```rs
+    /// let mut image = Image::new(300, 300);
+    /// image.draw_grid_of(3, 3, 5, |cell, col, row| {
+    ///     let k = (row * 3 + col + 1) as i32;
+    ///     for i in 0..k {
+    ///         cell.draw_simple_line(10, 10 + i * 8, 90, 60, COLORS[4])?;
+    ///     }
+    ///     Ok(())
+    /// })?;
+    /// ```
+    pub fn draw_grid_of<F>(
+        &mut self,
+        cols: u32,
+        rows: u32,
+        cell_padding: u32,
+        mut f: F,
+    ) -> Result<(), String>
+    where
+        F: FnMut(&mut Image, u32, u32) -> Result<(), String>,
+    {
+        if cols == 0 || rows == 0 {
+            return Err(format!(
+                "A grid needs at least 1 cell, but it was {cols}x{rows}"
+            ));
+        }
+        let (slot_width, slot_height) = (self.width / cols, self.height / rows);
+        let padding = cell_padding.saturating_mul(2);
+        if slot_width <= padding || slot_height <= padding {
+            return Err(format!(
+                "A padding of {cell_padding} leaves no room in {slot_width}x{slot_height} cells"
+            ));
+        }
+        let (width, height) = (slot_width - padding, slot_height - padding);
+
+        // Every cell is the same size, so they can all share one clip.
+        let rect = tiny_skia::Rect::from_xywh(0.0, 0.0, u32_to_f32(width), u32_to_f32(height))
+            .ok_or("Could not create the cells' clip")?;
+        let mut path = usvg::Path::new(Rc::new(tiny_skia::PathBuilder::from_rect(rect)));
+        path.fill = Some(usvg::Fill::default());
+        let clip = usvg::ClipPath {
+            id: self.new_def_id("clip"),
+            ..usvg::ClipPath::default()
+        };
+        clip.root.append_kind(usvg::NodeKind::Path(path));
+        let clip = Rc::new(clip);
+
+        // The cells are laid out in the image's own units, with y going down,
+        // and each brings its own copy of the image's coordinate system.
+        let grid = usvg::Node::new(usvg::NodeKind::Group(usvg::Group {
+            transform: self.coordinate_transform().invert().unwrap_or_default(),
+            ..usvg::Group::default()
+        }));
+        for row in 0..rows {
+            for col in 0..cols {
+                let mut cell = self.blank_like(width, height)?;
+                f(&mut cell, col, row).map_err(|e| format!("In cell ({col}, {row}): {e}"))?;
+                self.next_element_id = cell.next_element_id;
+                if cell.elements.is_empty() {
+                    continue;
+                }
+
+                let origin = (
+                    u32_to_f32(col * slot_width + cell_padding),
+                    u32_to_f32(row * slot_height + cell_padding),
+                );
+                grid.append(self.adopt_cell(&cell, origin, &clip));
+            }
+        }
+
+        self.append_element_node(grid)?;
+        Ok(())
+    }
+
+    /// A copy of everything drawn on `cell`, moved so that its top-left corner
+    /// is at `origin` and clipped by `clip`.
+    fn adopt_cell(
+        &mut self,
+        cell: &Image,
+        origin: (f32, f32),
+        clip: &Rc<usvg::ClipPath>,
+    ) -> usvg::Node {
+        // The clip is in the group's own coordinates, so it moves with the cell.
+        let group = usvg::Node::new(usvg::NodeKind::Group(usvg::Group {
+            transform: tiny_skia::Transform::from_translate(origin.0, origin.1),
+            clip_path: Some(clip.clone()),
+            ..usvg::Group::default()
+        }));
+        let content = cell.export_tree_of(None, cell.elements());
+        for node in content.root.children() {
+            group.append(self.adopt(&node));
+        }
+
+        group
+    }
+}
+
+impl Image {
+    /// Lay out thumbnails of several images in a grid, `cols` across and as
+    /// many rows as needed, filling each row from left to right. This is
+    /// handy for looking over many drawings at once.
+    ///
+    /// Each cell is `cell_size` (width, height) units, with `padding` units
+    /// between the cells and around the edge of the sheet. Each image is
+    /// scaled, keeping its shape, to the largest size that fits its cell, and
+    /// centred in it. The thumbnails include everything that would be saved,
+    /// including backgrounds, frames and watermarks, and each is a single
+    /// element of the sheet. Images that blend in linear light (see
+    /// [`BlendSpace`](crate::BlendSpace)) are blended in sRGB on the sheet.
+    ///
+    /// Returns an error if there are no images, if `cols` is 0, if the cells
+    /// have no area, or if the sheet would be too big.
+    ///
+    /// ```rs
+    /// // Four drawings in a 2x2 grid of 100x100 cells, 10 units apart.
+    /// let sheet = Image::contact_sheet(&drawings, 2, (100, 100), 10)?;
+    /// assert_eq!(sheet.get_dimensions(), (230, 230));
+    /// sheet.save_png("submissions.png")?;
+    /// ```
+    pub fn contact_sheet(
+        images: &[Image],
+        cols: u32,
+        cell_size: (u32, u32),
+        padding: u32,
+    ) -> Result<Image, String> {
+        if images.is_empty() {
+            return Err("A contact sheet needs at least one image".into());
+        }
+        if cols == 0 {
+            return Err("A contact sheet needs at least 1 column".into());
+        }
+        let (cell_width, cell_height) = cell_size;
+        if cell_width == 0 || cell_height == 0 {
+            return Err(format!(
+                "The cells of a contact sheet must be at least 1x1, but they were {cell_width}x{cell_height}"
+            ));
+        }
+
+        let cols = cols.min(u32::try_from(images.len()).unwrap_or(u32::MAX));
+        let rows = u32::try_from(images.len().div_ceil(cols as usize)).unwrap_or(u32::MAX);
+        let side = |cells: u32, size: u32| {
+            cells
+                .checked_mul(size.checked_add(padding)?)?
+                .checked_add(padding)
+        };
+        let (Some(width), Some(height)) = (side(cols, cell_width), side(rows, cell_height)) else {
+            return Err(format!(
+                "A contact sheet of {cols}x{rows} cells of {cell_width}x{cell_height} is too big"
+            ));
+        };
+
+        let mut sheet = Image::try_new(width, height)?;
+        for (index, image) in (0..).zip(images) {
+            let (col, row) = (index % cols, index / cols);
+            let cell = (
+                u32_to_f32(padding + col * (cell_width + padding)),
+                u32_to_f32(padding + row * (cell_height + padding)),
+            );
+            let thumbnail = sheet.thumbnail(image, cell, cell_size)?;
+            sheet.append_element_node(thumbnail)?;
+        }
+        Ok(sheet)
+    }
+
+    /// Everything `image` would save, scaled down to fit a cell of `size`
+    /// with its top-left corner at `cell`, and centred in it.
+    fn thumbnail(
+        &mut self,
+        image: &Image,
+        cell: (f32, f32),
+        size: (u32, u32),
+    ) -> Result<usvg::Node, String> {
+        let (width, height) = (u32_to_f32(image.width), u32_to_f32(image.height));
+        let scale = (u32_to_f32(size.0) / width).min(u32_to_f32(size.1) / height);
+        let left = cell.0 + (u32_to_f32(size.0) - width * scale) / 2.0;
+        let top = cell.1 + (u32_to_f32(size.1) - height * scale) / 2.0;
+
+        // Anything drawn past the edges of the image stays out of the
+        // neighbouring cells. The clip is in the image's own units.
+        let rect = tiny_skia::Rect::from_xywh(0.0, 0.0, width, height)
+            .ok_or("Could not create a thumbnail's clip")?;
+        let mut path = usvg::Path::new(Rc::new(tiny_skia::PathBuilder::from_rect(rect)));
+        path.fill = Some(usvg::Fill::default());
+        let clip = usvg::ClipPath {
+            id: self.new_def_id("clip"),
+            ..usvg::ClipPath::default()
+        };
+        clip.root.append_kind(usvg::NodeKind::Path(path));
+
+        let group = usvg::Node::new(usvg::NodeKind::Group(usvg::Group {
+            transform: tiny_skia::Transform::from_row(scale, 0.0, 0.0, scale, left, top),
+            clip_path: Some(Rc::new(clip)),
+            ..usvg::Group::default()
+        }));
+        for node in image.export_tree().root.children() {
+            group.append(self.adopt(&node));
+        }
+        Ok(group)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Corner, FillRule, Image, Rounding, StrokeStyle, COLORS, MAX_DIMENSION};
+
+    #[test]
+    fn cells_draw_the_way_the_image_does() {
+        let mut image = Image::new(100, 50);
+        image.set_coordinate_system(Corner::BottomLeft, true);
+        image.set_rounding(Rounding::Truncate);
+        let style = StrokeStyle {
+            width: 4.0,
+            ..StrokeStyle::default()
+        };
+        let mut ends = Vec::new();
+        image
+            .draw_grid_of(2, 1, 0, |cell, _, _| {
+                ends.push(cell.draw_simple_line(10, 5, 45, 11, COLORS[1])?);
+                cell.draw_styled_line(25, 2, 0, 10, COLORS[2], &style)?;
+                Ok(())
+            })
+            .unwrap();
+        // 7.78 units along each axis, rounded down.
+        assert_eq!(ends, [(17, 12), (17, 12)]);
+
+        // The upright line is near the bottom of each cell, not the top.
+        let pixmap = image.render().unwrap();
+        for x in [25, 75] {
+            let inked = pixmap.pixel(x, 43).unwrap();
+            assert_eq!((inked.red(), inked.green(), inked.blue()), (0, 255, 255));
+            let blank = pixmap.pixel(x, 7).unwrap();
+            assert_eq!((blank.red(), blank.green(), blank.blue()), (0, 0, 0));
+        }
+    }
+
+    #[test]
+    fn cells_of_a_strict_image_are_strict() {
+        let mut image = Image::new(100, 100);
+        image.set_strict(true);
+        let result = image.draw_grid_of(2, 2, 5, |cell, col, _| {
+            // The cells are 40x40, so this line leaves the second column's.
+            cell.draw_simple_line(10, 20, 90, 10 + 30 * col as i32, COLORS[1])?;
+            Ok(())
+        });
+        assert!(result.unwrap_err().starts_with("In cell (1, 0)"));
+        assert!(image.element_ids().is_empty());
+
+        image.set_strict(false);
+        image
+            .draw_grid_of(2, 2, 5, |cell, _, _| {
+                cell.draw_simple_line(10, 20, 90, 40, COLORS[1])?;
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(image.element_ids().len(), 1);
+    }
+
+    #[test]
+    fn contact_sheets_lay_images_out_in_rows() {
+        let images: Vec<Image> = (1..=4)
+            .map(|color| {
+                let mut image = Image::new(50, 50);
+                image
+                    .fill_polygon(
+                        &[(0, 0), (50, 0), (50, 50), (0, 50)],
+                        COLORS[color],
+                        FillRule::NonZero,
+                    )
+                    .unwrap();
+                image
+            })
+            .collect();
+
+        let sheet = Image::contact_sheet(&images, 2, (100, 100), 10).unwrap();
+        assert_eq!(sheet.get_dimensions(), (230, 230));
+        assert_eq!(sheet.element_ids().len(), 4);
+
+        let pixmap = sheet.render().unwrap();
+        let rgb = |x, y| {
+            let pixel = pixmap.pixel(x, y).unwrap();
+            (pixel.red(), pixel.green(), pixel.blue())
+        };
+        for (color, (x, y)) in [(60, 60), (170, 60), (60, 170), (170, 170)]
+            .into_iter()
+            .enumerate()
+        {
+            let expected = COLORS[color + 1];
+            assert_eq!(rgb(x, y), (expected.red, expected.green, expected.blue));
+        }
+        // The padding around and between the cells.
+        for (x, y) in [(5, 5), (115, 60), (60, 115), (225, 225)] {
+            assert_eq!(rgb(x, y), (0, 0, 0), "({x}, {y})");
+        }
+    }
+
+    #[test]
+    fn contact_sheets_too_big_for_an_image_are_an_error() {
+        let images = [Image::new(10, 10)];
+        assert!(Image::contact_sheet(&images, 1, (MAX_DIMENSION, 10), 10).is_err());
+        assert!(Image::contact_sheet(&images, 1, (u32::MAX, 10), 10).is_err());
+        assert!(Image::contact_sheet(&images, 1, (MAX_DIMENSION - 20, 10), 10).is_ok());
+    }
+}
@@ -0,0 +1,116 @@
+use crate::{normalize_direction, Color, Image};
+
+impl Image {
+    /// Like `draw_simple_line`, but colored by the direction the line points
+    /// in, so that lines going the same way have the same color. This makes
+    /// direction fields and flow plots easy to read at a glance.
+    ///
+    /// The direction (normalized to 0 to 359, as in `draw_simple_line`) is
+    /// used as the hue in [`Color::from_hsv`], with the saturation and value
+    /// set by `set_direction_hue` (both 1.0 by default). So, by default:
+    ///
+    /// | Direction       | Color                         |
+    /// |-----------------|-------------------------------|
+    /// | 0 (up)          | red, `(255, 0, 0)`            |
+    /// | 60              | yellow, `(255, 255, 0)`       |
+    /// | 90 (right)      | yellow-green, `(128, 255, 0)` |
+    /// | 120             | green, `(0, 255, 0)`          |
+    /// | 180 (down)      | cyan, `(0, 255, 255)`         |
+    /// | 240             | blue, `(0, 0, 255)`           |
+    /// | 270 (left)      | violet, `(128, 0, 255)`       |
+    /// | 300             | magenta, `(255, 0, 255)`      |
+    ///
+    /// ```rs
+    /// for direction in (0..360).step_by(15) {
+    ///     image.draw_simple_line_hue_by_direction(50, 50, direction, 40)?;
+    /// }
+    /// ```
+    pub fn draw_simple_line_hue_by_direction(
+        &mut self,
+        x: i32,
+        y: i32,
+        direction: i32,
+        length: i32,
+    ) -> Result<(i32, i32), String> {
+        let color = self.direction_hue(direction);
+        self.draw_simple_line(x, y, direction, length, color)
+    }
+
+    /// Set the saturation and value used by
+    /// `draw_simple_line_hue_by_direction`, from 0.0 to 1.0 (values outside
+    /// that are clamped). Lower saturations give paler colors, and lower
+    /// values give darker ones.
+    ///
+    /// ```rs
+    /// // Pastel colors.
+    /// image.set_direction_hue(0.4, 1.0);
+    /// ```
+    pub fn set_direction_hue(&mut self, saturation: f32, value: f32) {
+        self.direction_hue = (saturation, value);
+        self.mark_changed();
+    }
+
+    /// The color `draw_simple_line_hue_by_direction` uses for `direction`.
+    fn direction_hue(&self, direction: i32) -> Color {
+        let (saturation, value) = self.direction_hue;
+        Color::from_hsv(normalize_direction(direction) as f32, saturation, value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Color, Image};
+
+    #[test]
+    fn lines_are_colored_by_the_direction_they_point() {
+        let mut image = Image::new(100, 100);
+        let table = [
+            (0, (255, 0, 0)),
+            (60, (255, 255, 0)),
+            (90, (128, 255, 0)),
+            (120, (0, 255, 0)),
+            (180, (0, 255, 255)),
+            (240, (0, 0, 255)),
+            (270, (128, 0, 255)),
+            (300, (255, 0, 255)),
+            // Wrapped around, as in `draw_simple_line`.
+            (-90, (128, 0, 255)),
+            (450, (128, 255, 0)),
+        ];
+        for (direction, _) in table {
+            image
+                .draw_simple_line_hue_by_direction(50, 50, direction, 40)
+                .unwrap();
+        }
+        let colors = image.stats().colors;
+        let expected: Vec<_> = table[..8]
+            .iter()
+            .map(|&(_, (red, green, blue))| Color::new_rgb(red, green, blue))
+            .collect();
+        assert_eq!(colors, expected);
+
+        image.set_direction_hue(0.5, 0.5);
+        image
+            .draw_simple_line_hue_by_direction(50, 50, 180, 40)
+            .unwrap();
+        assert_eq!(
+            image.stats().colors.last(),
+            Some(&Color::new_rgb(64, 128, 128))
+        );
+    }
+
+    #[test]
+    fn rendered_lines_keep_the_hue_of_their_direction() {
+        let mut image = Image::new(100, 100);
+        // A line to the right, which covers half of each of the rows either
+        // side of y = 50.
+        image
+            .draw_simple_line_hue_by_direction(10, 50, 90, 80)
+            .unwrap();
+        let pixel = image.render().unwrap().pixel(50, 50).unwrap();
+        let (red, green, blue) = (pixel.red(), pixel.green(), pixel.blue());
+        assert!(green.abs_diff(128) <= 2, "{red} {green} {blue}");
+        assert!(red.abs_diff(64) <= 2, "{red} {green} {blue}");
+        assert_eq!(blue, 0);
+    }
+}
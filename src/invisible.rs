@@ -0,0 +1,217 @@
+use crate::palette::distance_squared;
+use crate::{Color, ElementId, Image};
+use resvg::usvg;
+
+/// How far (as a straight line in RGB space, where black to white is about
+/// 442) a color must be from the background to count as visible on it.
+const MIN_VISIBLE_DISTANCE: f32 = 16.0;
+
+/// Options for what happens when the image is saved, set with
+/// `Image::set_save_options`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SaveOptions {
+    /// Warn when saving an image with elements that can't be seen against
+    /// the background, as found by `Image::invisible_elements`. The warning
+    /// is in the `warnings` of the [`SaveReport`](crate::SaveReport) from
+    /// the `_report` save methods, like `save_png_report`.
+    pub warn_invisible: bool,
+}
+
+impl Image {
+    /// The elements that can't really be seen, because they are drawn in
+    /// (nearly) the same color as the background, in the order they were
+    /// drawn. Black lines on the default black background are the classic
+    /// example.
+    ///
+    /// An element is listed if every stroke and fill in it is a solid color
+    /// within a small distance of the background color. Anything drawn
+    /// partly transparent is blended with the background first, so a faint
+    /// enough line counts as invisible even in a different color. Elements
+    /// with gradients or pictures are never listed, and neither are elements
+    /// that draw nothing at all. Only the background is compared against, not
+    /// other elements an element is drawn on top of.
+    ///
+    /// ```rs
+    /// let mut image = Image::new(100, 100);
+    /// image.draw_simple_line(10, 10, 90, 50, COLORS[0])?;
+    /// image.draw_simple_line(10, 20, 90, 50, COLORS[7])?;
+    /// assert_eq!(image.invisible_elements(), vec![image.element_ids()[0]]);
+    /// ```
+    pub fn invisible_elements(&self) -> Vec<ElementId> {
+        let background = match *self.background().borrow() {
+            usvg::NodeKind::Path(ref path) => {
+                path.fill.as_ref().and_then(|fill| match fill.paint {
+                    usvg::Paint::Color(color) => Some(Color::from_usvg(color)),
+                    _ => None,
+                })
+            }
+            _ => None,
+        };
+        let Some(background) = background else {
+            return Vec::new();
+        };
+
+        self.elements
+            .iter()
+            .filter(|(_, element)| is_invisible(element, background))
+            .map(|&(id, _)| id)
+            .collect()
+    }
+
+    /// Choose what happens when the image is saved (see [`SaveOptions`]).
+    ///
+    /// Any warnings the options ask for are returned in the
+    /// [`SaveReport`](crate::SaveReport) of the `_report` save methods, like
+    /// `save_png_report` and `png_bytes_report`, rather than printed.
+    ///
+    /// ```rs
+    /// image.set_save_options(SaveOptions {
+    ///     warn_invisible: true,
+    /// });
+    /// image.draw_simple_line(10, 10, 90, 50, COLORS[0])?;
+    /// let report = image.save_png_report("image.png")?;
+    /// assert_eq!(
+    ///     report.warnings,
+    ///     ["1 element is drawn in the background color, so it can't be seen"]
+    /// );
+    /// ```
+    pub fn set_save_options(&mut self, options: SaveOptions) {
+        self.save_options = options;
+        self.mark_changed();
+    }
+
+    /// The options set by `set_save_options`.
+    pub fn save_options(&self) -> SaveOptions {
+        self.save_options
+    }
+
+    /// Check that the image can be saved. Every way of saving the image
+    /// starts with this.
+    pub(crate) fn prepare_to_save(&self) -> Result<(), String> {
+        self.check_not_dry_run()?;
+        if let Some(watermark) = self.watermark_node() {
+            self.check_fonts(&watermark)?;
+        }
+        Ok(())
+    }
+
+    /// The warnings the save options ask for about saving the image as it is.
+    pub(crate) fn save_warnings(&self) -> Vec<String> {
+        if !self.save_options.warn_invisible {
+            return Vec::new();
+        }
+        match self.invisible_elements().len() {
+            0 => Vec::new(),
+            1 => vec!["1 element is drawn in the background color, so it can't be seen".into()],
+            count => vec![format!(
+                "{count} elements are drawn in the background color, so they can't be seen"
+            )],
+        }
+    }
+}
+
+/// Whether everything `element` paints is too close to `background` to see.
+fn is_invisible(element: &usvg::Node, background: Color) -> bool {
+    let mut painted = false;
+    for node in element.descendants() {
+        let kind = node.borrow();
+        let path = match *kind {
+            usvg::NodeKind::Group(_) => continue,
+            usvg::NodeKind::Path(ref path) => path,
+            usvg::NodeKind::Image(_) | usvg::NodeKind::Text(_) => return false,
+        };
+        if path.visibility != usvg::Visibility::Visible {
+            continue;
+        }
+        let opacity: f32 = node
+            .ancestors()
+            .map(|ancestor| match *ancestor.borrow() {
+                usvg::NodeKind::Group(ref group) => group.opacity.get(),
+                _ => 1.0,
+            })
+            .product();
+
+        let fill = path
+            .fill
+            .as_ref()
+            .map(|fill| (&fill.paint, fill.opacity.get()));
+        let stroke = path
+            .stroke
+            .as_ref()
+            .map(|stroke| (&stroke.paint, stroke.opacity.get()));
+        for (paint, paint_opacity) in fill.into_iter().chain(stroke) {
+            let usvg::Paint::Color(color) = *paint else {
+                return false;
+            };
+            // Blending with the background moves the color towards it, in
+            // proportion to how transparent it is.
+            let distance = (distance_squared(Color::from_usvg(color), background) as f32).sqrt();
+            if distance * opacity * paint_opacity >= MIN_VISIBLE_DISTANCE {
+                return false;
+            }
+            painted = true;
+        }
+    }
+    painted
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Image, Rgba, SaveOptions, COLORS};
+
+    #[test]
+    fn black_on_black_is_invisible() {
+        let mut image = Image::new(100, 100);
+        image.draw_simple_line(10, 10, 90, 50, COLORS[0]).unwrap();
+        image.draw_simple_line(10, 20, 90, 50, COLORS[7]).unwrap();
+        image
+            .draw_simple_line(10, 30, 90, 50, Rgba::from_straight(255, 255, 255, 5))
+            .unwrap();
+        let ids = image.element_ids();
+        assert_eq!(image.invisible_elements(), vec![ids[0], ids[2]]);
+    }
+
+    #[test]
+    fn translucent_lines_are_invisible_when_faint_enough() {
+        let mut image = Image::new(100, 100);
+        for alpha in [2, 5, 10, 40, 255] {
+            image
+                .draw_simple_line(10, 10, 90, 50, Rgba::from_straight(255, 255, 255, alpha))
+                .unwrap();
+        }
+        let ids = image.element_ids();
+        assert_eq!(image.invisible_elements(), vec![ids[0], ids[1]]);
+    }
+
+    #[test]
+    fn saving_warns_about_invisible_elements_when_asked() {
+        let mut image = Image::new(100, 100);
+        image.draw_simple_line(10, 10, 90, 50, COLORS[0]).unwrap();
+        image.draw_simple_line(10, 20, 90, 50, COLORS[7]).unwrap();
+        let (_, report) = image.png_bytes_report().unwrap();
+        assert!(report.warnings.is_empty());
+
+        image.set_save_options(SaveOptions {
+            warn_invisible: true,
+        });
+        let (_, report) = image.png_bytes_report().unwrap();
+        assert_eq!(
+            report.warnings,
+            ["1 element is drawn in the background color, so it can't be seen"]
+        );
+
+        image
+            .draw_simple_line(10, 30, 90, 50, Rgba::from_straight(255, 255, 255, 5))
+            .unwrap();
+        let path = std::env::temp_dir().join("unsvg-invisible-test.svg");
+        let report = image.save_svg_report(&path).unwrap();
+        assert_eq!(
+            report.warnings,
+            ["2 elements are drawn in the background color, so they can't be seen"]
+        );
+
+        image.set_save_options(SaveOptions::default());
+        let (_, report) = image.png_bytes_report().unwrap();
+        assert!(report.warnings.is_empty());
+    }
+}
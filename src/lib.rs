@@ -25,21 +25,133 @@
 //! values. This design decision was made to ensure consistent, deterministic
 //! behaviour for all coordinate inputs, which is not a given when using floats
 //! due to float imprecision.
+//!
+//! # Strokes
+//!
+//! Unless a [`StrokeStyle`] says otherwise, every line `unsvg` draws (with
+//! `draw_simple_line`, `draw_convex_hull`, `draw_multicolor_path` and so on)
+//! is stroked the same way, following the SVG defaults:
+//!
+//! | Property    | Default | Meaning |
+//! |-------------|---------|---------|
+//! | Width       | 1       | The line covers half a unit either side of its path, so a horizontal line at `y = 10` covers `9.5..10.5`, half of two rows of pixels. |
+//! | Caps        | Butt    | The line stops exactly at its end points, without extending past them. |
+//! | Joins       | Miter   | Where segments meet, the outer edges carry on until they meet in a sharp point. |
+//! | Miter limit | 4       | Points more than 4 widths long, measured from the inside of the corner (so corners sharper than about 29 degrees), are cut off flat instead. |
+//! | Dashes      | None    | The line is solid. |
+//! | Opacity     | 1       | The line is opaque, unless drawn with a translucent [`Rgba`]. |
+//!
+//! For example, a zigzag through `(0, 10)`, `(50, 0)` and `(0, 1)` turns by
+//! almost 180 degrees at `(50, 0)`, so with the default miter limit its point
+//! is cut off a little past the corner, rather than reaching far off to the
+//! right. Since these are also the defaults in the SVG standard, saved SVGs
+//! leave them out, and only write the properties that differ.
+//!
+//! # Features
+//!
+//! - `json`: reading and writing drawings as JSON, with
+//!   `Image::to_scene_json` and `Image::from_scene_json`, and writing
+//!   drawing statistics as JSON, with `Image::stats_json`, and drawing
+//!   GeoJSON data, with `Image::draw_geojson`.
 
 use num_traits::cast;
-use resvg::usvg::{NodeExt, TreeWriting, XmlOptions};
+use resvg::usvg::NodeExt;
 use resvg::{tiny_skia, usvg};
+use std::collections::BTreeMap;
 use std::rc::Rc;
 
-pub use resvg::usvg::Color;
+mod alignment;
+mod analysis;
+mod anchors;
+mod ansi;
+mod appender;
+mod blend;
+mod blit;
+mod blur;
+mod canvas;
+mod checkerboard;
+mod color_matrix;
+mod contour;
+mod coords;
+mod diff;
+mod dilate;
+mod dry_run;
+mod elements;
+mod eps;
+mod eraser;
+mod export;
+mod finalize;
+mod frame;
+pub mod generate;
+mod generation;
+#[cfg(feature = "json")]
+mod geojson;
+mod geometry;
+mod grid;
+mod hue;
+mod invisible;
+mod load;
+mod mask;
+mod memory;
+mod morph;
+mod outline;
+mod output;
+mod palette;
+mod phash;
+mod raycast;
+mod recolor;
+mod scale;
+#[cfg(feature = "json")]
+mod scene;
+mod segments;
+mod shapes;
+mod smil;
+mod snapshot;
+#[cfg(feature = "json")]
+mod stats_json;
+mod strict;
+mod stroke_pattern;
+mod svg;
+mod text;
+mod tiled;
+mod timestamps;
+mod transform;
+pub mod types;
+mod verify;
+mod watermark;
+
+pub use alignment::PixelAlignment;
+pub use analysis::{stats_from_svg, DrawStats};
+pub use anchors::{IntoPoint, Named};
+pub use appender::SvgAppender;
+pub use blend::BlendSpace;
+pub use canvas::{Anchor, Margins};
+pub use color_matrix::ColorMatrix;
+pub use diff::{diff_elements, ElementDiff};
+pub use export::{ExportProfile, Overlays};
+pub use finalize::FinalizedImage;
+pub use geometry::{circle_through, convex_hull, simplify_polyline, tangent_directions};
+pub use invisible::SaveOptions;
+pub use mask::MaskFit;
+pub use output::{OutputFormat, PngColorType, PngOptions, SaveReport, SavedPaths};
+pub use palette::{build_shared_palette, nearest_color, ColorCycle};
+pub use segments::{optimize_segment_order, Segment};
+pub use snapshot::ImageSnapshot;
+pub use svg::SvgOptions;
+pub use timestamps::Timestamp;
+pub use types::{
+    Color, ElementId, FillRule, IntoColor, LineCap, LineStyle, Rgba, Rounding, StrokePattern,
+    StrokeStyle,
+};
+pub use watermark::Corner;
 
 /// This contains 16 simple colors which users can select from.
 /// These correspond to the 16 colors available in the original Logo language.
 /// The colors are:
 ///  - Black
 ///  - Blue
-///  - Green
 ///  - Cyan
+///  - Green
 ///  - Red
 ///  - Magenta
 ///  - Yellow
@@ -52,6 +164,9 @@ pub use resvg::usvg::Color;
 ///  - Purple
 ///  - Orange
 ///  - Grey
+///
+/// Anywhere a color is expected, you can also use an index into this list, or
+/// one of the names in [`COLOR_NAMES`] (see [`IntoColor`]).
 pub static COLORS: [Color; 16] = [
     Color {
         red: 0,
@@ -135,6 +250,12 @@ pub static COLORS: [Color; 16] = [
     },
 ];
 
+/// The names of the colors in [`COLORS`], in the same order.
+pub static COLOR_NAMES: [&str; 16] = [
+    "black", "blue", "cyan", "green", "red", "magenta", "yellow", "white", "brown", "tan",
+    "forest", "aqua", "salmon", "purple", "orange", "grey",
+];
+
 fn u32_to_f32(num: u32) -> f32 {
     cast(num).unwrap_or_else(|| panic!("failed to convert u32 '{num}' to f32"))
 }
@@ -147,10 +268,20 @@ fn i32_to_f32(num: i32) -> f32 {
     cast(num).unwrap_or_else(|| panic!("failed to convert i32 '{num}' to f32"))
 }
 
-fn f32_to_i32(num: f32) -> i32 {
-    cast(num.round()).unwrap_or_else(|| panic!("failed to convert f32 '{num}' to i32"))
+/// Round to an i32 with `rounding`. Values too big or small for an i32
+/// saturate at `i32::MAX` or `i32::MIN`, instead of overflowing.
+fn f32_to_i32_saturating(num: f32, rounding: Rounding) -> i32 {
+    cast(rounding.apply(num)).unwrap_or(if num > 0.0 { i32::MAX } else { i32::MIN })
 }
 
+/// Like `f32_to_i32_saturating`, for an `f64`.
+fn f64_to_i32_saturating(num: f64, rounding: Rounding) -> i32 {
+    cast(rounding.apply_f64(num)).unwrap_or(if num > 0.0 { i32::MAX } else { i32::MIN })
+}
+
+/// Below this size, an `f32` holds every multiple of 1/256 exactly.
+const F32_PRECISE_LIMIT: f64 = 65536.0;
+
 /// Normalize a direction values in degrees to within [0, 360).
 fn normalize_direction(direction: i32) -> i32 {
     let normalized = direction % 360;
@@ -163,23 +294,172 @@ fn normalize_direction(direction: i32) -> i32 {
 
 /// Tells you where a line will end, given a starting point, direction, and length.
 /// This is used by `draw_simple_line` to get the end point of a line.
+///
+/// `draw_simple_line` works its end point out with this very function, not
+/// a copy of it, so the point it returns is always exactly what this
+/// returns for the same arguments, including when the start point is the
+/// end of an earlier line. The only exceptions are images that change how
+/// end points are found, with `set_clamp_to_bounds`, `set_rounding` or
+/// `set_coordinate_system`.
+///
+/// A negative length goes backwards, in the opposite direction: it gives
+/// exactly the same end point as adding 180 to the direction and using the
+/// positive length.
+///
+/// If the end point would be beyond the range of an `i32`, its coordinates
+/// saturate at `i32::MAX` or `i32::MIN`, so a long chain of lines that keeps
+/// going in one direction stops at the edge of the coordinate space instead
+/// of wrapping around or panicking.
+///
+/// The end point is worked out in these steps:
+///  1. The start point is converted to `f32`, and rounded to the nearest
+///     1/256 of a unit.
+///  2. The direction is normalized to 0 to 359 degrees (after adding 180 for
+///     a negative length), and converted to radians, measured from the right.
+///  3. The end point is the start point plus the `f32` cosine and sine of
+///     the direction times the length, again rounded to the nearest 1/256.
+///  4. Each coordinate is rounded to a whole number with [`Rounding::Nearest`]
+///     (halves away from zero). `get_end_coordinates_rounded` can round in
+///     other ways.
+///
+/// Step 3 means results can differ very slightly from exact maths: for
+/// example, direction 30 and length 1 gives an x of exactly 0.5 (rounded to
+/// 1), even though the `f32` cosine is a tiny bit less than 0.5.
+///
+/// An `f32` can only hold coordinates to 1/256 of a unit up to 65536, so if
+/// the start point or length is 65536 or more, steps 1 and 3 use `f64`
+/// instead (with the same `f32` cosine and sine). Lines far out on a large
+/// canvas end in the same place, relative to their start, as lines near the
+/// origin.
+///
+/// ```rs
+/// // 70.7107 to the right and up, rounded to the nearest unit.
+/// assert_eq!(get_end_coordinates(0, 0, 45, 100), (71, -71));
+///
+/// // Going "up" by -100 goes down instead.
+/// assert_eq!(get_end_coordinates(0, 0, 0, -100), (0, 100));
+/// assert_eq!(get_end_coordinates(0, 0, 0, -100), get_end_coordinates(0, 0, 180, 100));
+///
+/// let end = get_end_coordinates(i32::MAX - 10, 0, 90, i32::MAX);
+/// assert_eq!(end, (i32::MAX, 0));
+/// ```
 pub fn get_end_coordinates(x: i32, y: i32, direction: i32, length: i32) -> (i32, i32) {
-    let x = i32_to_f32(x);
-    let y = i32_to_f32(y);
-    let length = i32_to_f32(length);
+    get_end_coordinates_rounded(x, y, direction, length, Rounding::Nearest)
+}
+
+/// Like [`get_end_coordinates`], but choosing how the end point is rounded to
+/// whole units in its last step.
+///
+/// ```rs
+/// assert_eq!(get_end_coordinates_rounded(0, 0, 45, 100, Rounding::Nearest), (71, -71));
+/// assert_eq!(get_end_coordinates_rounded(0, 0, 45, 100, Rounding::Truncate), (70, -70));
+/// assert_eq!(get_end_coordinates_rounded(0, 0, 45, 100, Rounding::HalfToEven), (71, -71));
+/// ```
+pub fn get_end_coordinates_rounded(
+    x: i32,
+    y: i32,
+    direction: i32,
+    length: i32,
+    rounding: Rounding,
+) -> (i32, i32) {
+    let x = f64::from(x);
+    let y = f64::from(y);
+    let length = f64::from(length);
 
     let (end_x, end_y) = get_end_coordinates_precise(x, y, direction, length);
 
-    let end_x = f32_to_i32(end_x);
-    let end_y = f32_to_i32(end_y);
+    let end_x = f64_to_i32_saturating(end_x, rounding);
+    let end_y = f64_to_i32_saturating(end_y, rounding);
+
+    (end_x, end_y)
+}
+
+/// Where a line drawn from (`x`, `y`) has got to after fraction `t` of its
+/// length, for animating a line being drawn: draw a line from (`x`, `y`) to
+/// this point in each frame, with `t` going from 0.0 to 1.0.
+///
+/// `t` is clamped to 0.0 to 1.0 (and NaN counts as 0.0), so `t` of 0.0 gives
+/// the start point, and 1.0 gives the same end point as
+/// [`get_end_coordinates`]. Points in between are worked out and rounded the
+/// same way, as if the line were `t` times as long.
+///
+/// ```rs
+/// assert_eq!(partial_line(0, 0, 90, 100, 0.5), (50, 0));
+///
+/// // 30 frames of a line being drawn.
+/// for frame in 1..=30 {
+///     let end = partial_line(10, 10, 135, 80, frame as f32 / 30.0);
+///     // ...draw a line from (10, 10) to `end` on this frame's image.
+/// }
+/// ```
+pub fn partial_line(x: i32, y: i32, direction: i32, length: i32, t: f32) -> (i32, i32) {
+    let t = if t > 0.0 { t.min(1.0) } else { 0.0 };
+    let (end_x, end_y) = get_end_coordinates_precise(
+        f64::from(x),
+        f64::from(y),
+        direction,
+        f64::from(length) * f64::from(t),
+    );
+    (
+        f64_to_i32_saturating(end_x, Rounding::Nearest),
+        f64_to_i32_saturating(end_y, Rounding::Nearest),
+    )
+}
+
+/// Where a line ends, to the nearest 1/256 of a unit.
+///
+/// This is worked out in `f32`, as `get_end_coordinates` documents, while
+/// every value is small enough for an `f32` to hold it to 1/256 of a unit.
+/// Further out than that (on canvases wider than 65536, say), the start
+/// point and the sums are held in `f64` instead. The line's offset from its
+/// start is still worked out as it would be from the origin, so a line ends
+/// in the same place relative to its start wherever it is drawn.
+fn get_end_coordinates_precise(x: f64, y: f64, direction: i32, length: f64) -> (f64, f64) {
+    let small = |value: f64| value.abs() < F32_PRECISE_LIMIT;
+    if small(x) && small(y) && small(length) {
+        let (end_x, end_y) =
+            get_end_coordinates_precise_f32(x as f32, y as f32, direction, length as f32);
+        return (f64::from(end_x), f64::from(end_y));
+    }
+
+    let (offset_x, offset_y) = if small(length) {
+        let (offset_x, offset_y) =
+            get_end_coordinates_precise_f32(0.0, 0.0, direction, length as f32);
+        (f64::from(offset_x), f64::from(offset_y))
+    } else {
+        let (direction, length) = if length < 0.0 {
+            (
+                normalize_direction(normalize_direction(direction) + 180),
+                -length,
+            )
+        } else {
+            (normalize_direction(direction), length)
+        };
+        // The same f32 angle as for shorter lines.
+        let direction_rad = ((direction as f32) - 90.0).to_radians();
+        (
+            f64::from(direction_rad.cos()) * length,
+            f64::from(direction_rad.sin()) * length,
+        )
+    };
+
+    let end_x = quantize_f64(quantize_f64(x) + offset_x);
+    let end_y = quantize_f64(quantize_f64(y) + offset_y);
 
     (end_x, end_y)
 }
 
-fn get_end_coordinates_precise(x: f32, y: f32, direction: i32, length: f32) -> (f32, f32) {
+fn get_end_coordinates_precise_f32(x: f32, y: f32, direction: i32, length: f32) -> (f32, f32) {
     let x = quantize(x);
     let y = quantize(y);
-    let direction = normalize_direction(direction);
+    let (direction, length) = if length < 0.0 {
+        (
+            normalize_direction(normalize_direction(direction) + 180),
+            -length,
+        )
+    } else {
+        (normalize_direction(direction), length)
+    };
 
     // directions start at 0 degrees being straight up, and go clockwise.
     // we need to add 90 degrees to make 0 degrees straight right.
@@ -193,21 +473,137 @@ fn get_end_coordinates_precise(x: f32, y: f32, direction: i32, length: f32) -> (
 
 /// This represents an image that's being constructed. Use the `new` function
 /// to create one.
-#[derive(Clone)]
 pub struct Image {
     width: u32,
     height: u32,
     tree: usvg::Tree,
+    elements: Vec<(ElementId, usvg::Node)>,
+    next_element_id: u64,
+    clips: Vec<Rc<usvg::ClipPath>>,
+    mask: Option<Rc<usvg::Mask>>,
+    clamp_to_bounds: bool,
+    blend_space: BlendSpace,
+    next_def_id: u32,
+    animations: Vec<(ElementId, smil::Translation)>,
+    direction_hue: (f32, f32),
+    watermark: Option<watermark::Watermark>,
+    frame: Option<frame::Frame>,
+    rounding: Rounding,
+    fonts: Option<Rc<usvg::fontdb::Database>>,
+    dry_run: bool,
+    coordinate_system: (Corner, bool),
+    strict: bool,
+    timestamps: Option<timestamps::Timestamps>,
+    generation: u64,
+    memory_limit: Option<usize>,
+    element_bytes: usize,
+    save_options: SaveOptions,
+    anchors: BTreeMap<String, (i32, i32)>,
+    pixel_alignment: PixelAlignment,
+    units_per_step: u32,
 }
 
 fn quantize(x: f32) -> f32 {
     (x * 256.0).round() / 256.0
 }
 
+fn quantize_f64(x: f64) -> f64 {
+    (x * 256.0).round() / 256.0
+}
+
+impl Clone for Image {
+    /// Cloning an image copies everything that has been drawn on it, so that
+    /// drawing on the clone never changes the original (and vice versa).
+    fn clone(&self) -> Image {
+        let root = usvg::Node::new(usvg::NodeKind::Group(usvg::Group::default()));
+        root.append(self.background().make_deep_copy());
+
+        let elements = self
+            .elements
+            .iter()
+            .map(|(id, node)| {
+                let copy = node.make_deep_copy();
+                root.append(copy.clone());
+                (*id, copy)
+            })
+            .collect();
+
+        Image {
+            width: self.width,
+            height: self.height,
+            tree: usvg::Tree {
+                size: self.tree.size,
+                view_box: self.tree.view_box,
+                root,
+            },
+            elements,
+            next_element_id: self.next_element_id,
+            clips: self.clips.clone(),
+            mask: self.mask.clone(),
+            clamp_to_bounds: self.clamp_to_bounds,
+            blend_space: self.blend_space,
+            next_def_id: self.next_def_id,
+            animations: self.animations.clone(),
+            direction_hue: self.direction_hue,
+            watermark: self.watermark.clone(),
+            frame: self.frame,
+            rounding: self.rounding,
+            fonts: self.fonts.clone(),
+            dry_run: self.dry_run,
+            coordinate_system: self.coordinate_system,
+            strict: self.strict,
+            timestamps: self.timestamps.clone(),
+            generation: self.generation,
+            memory_limit: self.memory_limit,
+            element_bytes: self.element_bytes,
+            save_options: self.save_options,
+            anchors: self.anchors.clone(),
+            pixel_alignment: self.pixel_alignment,
+            units_per_step: self.units_per_step,
+        }
+    }
+}
+
+/// The largest width or height an image can have, in units.
+///
+/// Every whole-unit coordinate up to this size is held exactly, so SVG
+/// output is accurate right up to the edges of the largest image. A PNG has
+/// one pixel per unit, and rendering one needs 4 bytes per pixel: as big as
+/// this limit allows, that is far more memory than most computers have, so
+/// rendering returns an error (instead of crashing) when the memory can't be
+/// had.
+pub const MAX_DIMENSION: u32 = 1_000_000;
+
 impl Image {
     /// Creates an image.
+    ///
+    /// Panics if `width` or `height` is 0 or more than [`MAX_DIMENSION`]; use
+    /// `try_new` to get an error instead.
     pub fn new(width: u32, height: u32) -> Image {
-        let size = usvg::Size::from_wh(width as f32, height as f32).unwrap();
+        Image::try_new(width, height).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Creates an image, or returns an error if `width` or `height` is 0 or
+    /// more than [`MAX_DIMENSION`].
+    ///
+    /// ```rs
+    /// let image = Image::try_new(MAX_DIMENSION, 600)?;
+    /// assert!(Image::try_new(MAX_DIMENSION + 1, 600).is_err());
+    /// ```
+    pub fn try_new(width: u32, height: u32) -> Result<Image, String> {
+        if width == 0 || height == 0 {
+            return Err(format!(
+                "An image must be at least 1x1, but {width}x{height} was asked for"
+            ));
+        }
+        if width > MAX_DIMENSION || height > MAX_DIMENSION {
+            return Err(format!(
+                "An image can be at most {MAX_DIMENSION}x{MAX_DIMENSION}, but {width}x{height} was asked for"
+            ));
+        }
+
+        let size = usvg::Size::from_wh(width as f32, height as f32)
+            .ok_or_else(|| format!("Could not make a {width}x{height} image"))?;
         let tree = usvg::Tree {
             size,
             view_box: usvg::ViewBox {
@@ -217,7 +613,7 @@ impl Image {
             root: usvg::Node::new(usvg::NodeKind::Group(usvg::Group::default())),
         };
 
-        let fill = usvg::Fill::from_paint(usvg::Paint::Color(usvg::Color::black()));
+        let fill = usvg::Fill::from_paint(Color::black().paint());
         let mut path = usvg::Path::new(Rc::from(tiny_skia::PathBuilder::from_rect(
             size.to_non_zero_rect(0.0, 0.0).to_rect(),
         )));
@@ -225,15 +621,71 @@ impl Image {
 
         tree.root.append_kind(usvg::NodeKind::Path(path));
 
-        Image {
+        Ok(Image {
             width,
             height,
             tree,
-        }
+            elements: Vec::new(),
+            next_element_id: 0,
+            clips: Vec::new(),
+            mask: None,
+            clamp_to_bounds: false,
+            blend_space: BlendSpace::Srgb,
+            next_def_id: 0,
+            animations: Vec::new(),
+            direction_hue: (1.0, 1.0),
+            watermark: None,
+            frame: None,
+            rounding: Rounding::Nearest,
+            fonts: None,
+            dry_run: false,
+            coordinate_system: (Corner::TopLeft, false),
+            strict: false,
+            timestamps: None,
+            generation: 0,
+            memory_limit: None,
+            element_bytes: 0,
+            save_options: SaveOptions::default(),
+            anchors: BTreeMap::new(),
+            pixel_alignment: PixelAlignment::None,
+            units_per_step: 1,
+        })
+    }
+
+    /// A `width` by `height` image with nothing drawn on it, that draws the
+    /// way this one does, for drawing part of this image on separately.
+    ///
+    /// The drawing settings, anchors, memory use and timestamps carry over,
+    /// as does the next element id, so that ids handed out by the part never
+    /// clash with this image's. Clips, masks, animations and anything that
+    /// is only added when saving (frames, watermarks, save options) do not.
+    pub(crate) fn blank_like(&self, width: u32, height: u32) -> Result<Image, String> {
+        let mut image = Image::try_new(width, height)?;
+        image.next_element_id = self.next_element_id;
+        image.clamp_to_bounds = self.clamp_to_bounds;
+        image.blend_space = self.blend_space;
+        image.next_def_id = self.next_def_id;
+        image.direction_hue = self.direction_hue;
+        image.rounding = self.rounding;
+        image.fonts = self.fonts.clone();
+        image.dry_run = self.dry_run;
+        image.coordinate_system = self.coordinate_system;
+        image.strict = self.strict;
+        image.timestamps = self.timestamps.clone();
+        image.memory_limit = self.memory_limit;
+        image.element_bytes = self.element_bytes;
+        image.anchors = self.anchors.clone();
+        image.pixel_alignment = self.pixel_alignment;
+        image.units_per_step = self.units_per_step;
+        Ok(image)
     }
 
     /// Get the size of the image as a tuple of (width, height).
     ///
+    /// This is the size of the saved image, in units of the SVG (or pixels
+    /// of the PNG). For an image made with `new_scaled`, that isn't the size
+    /// in the steps draw calls use, which is `get_dimensions_in_steps`.
+    ///
     /// ```rs
     /// let image = Image::new(100, 100);
     /// let (width, height) = image.get_dimensions();
@@ -251,52 +703,791 @@ impl Image {
     /// image.save_png("image.png");
     /// ```
     pub fn save_png<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), String> {
-        let rtree = resvg::Tree::from_usvg(&self.tree);
+        self.prepare_to_save()?;
+        self.render()?.save_png(path).map_err(|e| e.to_string())
+    }
 
-        let pixmap_size = rtree.size.to_int_size();
-        let mut pixmap = tiny_skia::Pixmap::new(pixmap_size.width(), pixmap_size.height()).unwrap();
-        rtree.render(tiny_skia::Transform::default(), &mut pixmap.as_mut());
-        pixmap.save_png(path).map_err(|e| e.to_string())
+    /// Render the image to a pixmap, as it would be saved by `save_png`.
+    fn render(&self) -> Result<tiny_skia::Pixmap, String> {
+        self.check_not_dry_run()?;
+        match self.blend_space {
+            BlendSpace::Srgb => render_tree(&self.export_tree()),
+            BlendSpace::Linear => self.render_linear(),
+        }
     }
 
     /// Save the image to a file.
     ///
+    /// To choose how the SVG is written, use `save_svg_with_options`.
+    ///
     /// ```rs
     /// let image = Image::new(100, 100);
     /// image.save_svg("image.svg");
     /// ```
     pub fn save_svg<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), String> {
-        std::fs::write(path, self.tree.to_string(&XmlOptions::default())).map_err(|e| e.to_string())
+        self.save_svg_with_options(path, &SvgOptions::default())
+    }
+
+    /// Choose whether coordinates outside the image are moved onto its edge.
+    ///
+    /// By default, anything drawn outside the image is kept as-is, and is simply
+    /// cut off by the edge of the image when it is displayed: a line that
+    /// leaves the image keeps its direction and length, and the point it
+    /// returns may be outside the image.
+    ///
+    /// With clamping turned on, every coordinate is clamped to the nearest
+    /// point of the image *before* drawing, which changes the geometry
+    /// itself. A line that would leave the image instead ends on its edge
+    /// (which can change the line's direction), and the clamped end point is
+    /// what gets returned, so a following line starts from the edge.
+    ///
+    /// Clamping is silent: a line that was too long is simply drawn shorter,
+    /// and nothing tells you it happened. If you'd rather find out, use
+    /// [`Image::set_strict`] instead, which returns an `Err` for a line that
+    /// leaves the image and draws nothing. With both turned on, strict mode
+    /// wins: the line is checked before it would be clamped, so it is
+    /// rejected rather than shortened.
+    ///
+    /// ```rs
+    /// let mut image = Image::new(100, 100);
+    /// image.set_clamp_to_bounds(true);
+    /// let end = image.draw_simple_line(50, 50, 90, 100, COLORS[1])?;
+    /// assert_eq!(end, (100, 50));
+    /// ```
+    pub fn set_clamp_to_bounds(&mut self, clamp: bool) {
+        self.clamp_to_bounds = clamp;
+        self.mark_changed();
+    }
+
+    /// Choose how the end points of lines are rounded to whole units (see
+    /// [`Rounding`]). The default, `Rounding::Nearest`, matches
+    /// [`get_end_coordinates`].
+    ///
+    /// ```rs
+    /// image.set_rounding(Rounding::Truncate);
+    /// let end = image.draw_simple_line(0, 0, 45, 100, COLORS[1])?;
+    /// assert_eq!(end, (70, -70));
+    /// ```
+    pub fn set_rounding(&mut self, rounding: Rounding) {
+        self.rounding = rounding;
+        self.mark_changed();
+    }
+
+    /// Clamp a point onto the image if `set_clamp_to_bounds` is turned on.
+    fn clamp_point(&self, (x, y): (i32, i32)) -> (i32, i32) {
+        if !self.clamp_to_bounds {
+            return (x, y);
+        }
+
+        let (min_x, min_y, max_x, max_y) = self.drawing_area();
+        (x.clamp(min_x, max_x), y.clamp(min_y, max_y))
     }
 
     /// Draw a line on the image, taking a starting point, direction, length, and color.
     /// We return the end point of the line as a tuple of (x, y).
+    ///
+    /// A negative length draws the line backwards from the starting point, as
+    /// if the direction were turned around by 180 degrees (see
+    /// [`get_end_coordinates`]).
+    ///
+    /// The color can be an opaque [`Color`], a translucent [`Rgba`], or an index
+    /// or name from the [`COLORS`] palette (see [`IntoColor`]).
     pub fn draw_simple_line(
         &mut self,
         x: i32,
         y: i32,
         direction: i32,
         length: i32,
-        color: Color,
+        color: impl IntoColor,
+    ) -> Result<(i32, i32), String> {
+        self.draw_styled_line(x, y, direction, length, color, &StrokeStyle::default())
+    }
+
+    /// Like `draw_simple_line`, but choosing the width, dash pattern, end
+    /// caps and [`StrokePattern`] of the line with a [`StrokeStyle`].
+    ///
+    /// ```rs
+    /// let dashed = StrokeStyle {
+    ///     width: 2.0,
+    ///     style: LineStyle::Dashed,
+    ///     ..StrokeStyle::default()
+    /// };
+    /// image.draw_styled_line(10, 10, 90, 50, COLORS[1], &dashed)?;
+    /// ```
+    pub fn draw_styled_line(
+        &mut self,
+        x: i32,
+        y: i32,
+        direction: i32,
+        length: i32,
+        color: impl IntoColor,
+        style: &StrokeStyle,
     ) -> Result<(i32, i32), String> {
-        let (end_x, end_y) = get_end_coordinates(x, y, direction, length);
+        let color = color.into_color()?;
+        let stroke = style.to_usvg(color)?;
+        self.check_strict_line(x, y, direction, length)?;
+        let end = self.line_end(x, y, direction, length);
+        let (x, y) = self.clamp_point((x, y));
+        let (end_x, end_y) = self.clamp_point(end);
 
-        let paint = usvg::Paint::Color(color);
-        let mut path = tiny_skia::PathBuilder::new();
-        path.move_to(i32_to_f32(x), i32_to_f32(y));
-        path.line_to(i32_to_f32(end_x), i32_to_f32(end_y));
+        let path = style.pattern.outline(
+            &[
+                (i32_to_f32(x), i32_to_f32(y)),
+                (i32_to_f32(end_x), i32_to_f32(end_y)),
+            ],
+            style.width,
+        )?;
 
         let mut path = usvg::Path::new(
             path.finish()
                 .ok_or("Could not draw line".to_string())?
                 .into(),
         );
-        let mut stroke = usvg::Stroke::default();
-        stroke.paint = paint;
         path.stroke = Some(stroke);
 
-        self.tree.root.append_kind(usvg::NodeKind::Path(path));
+        self.append_element(usvg::NodeKind::Path(path))?;
 
         Ok((end_x, end_y))
     }
+
+    /// Build an animation of the image being drawn, one draw call at a time.
+    ///
+    /// This returns `frame_count` images. The first frame only contains the
+    /// background, the last frame contains everything drawn on this image, and
+    /// the draw calls in between are spread as evenly as possible across the
+    /// other frames. If `frame_count` is 1, the only frame is the finished
+    /// image.
+    ///
+    /// ```rs
+    /// let frames = image.animate(30);
+    /// for (i, frame) in frames.iter().enumerate() {
+    ///     frame.save_png(format!("frame_{i:02}.png"))?;
+    /// }
+    /// ```
+    pub fn animate(&self, frame_count: usize) -> Vec<Image> {
+        let element_count = self.elements.len();
+
+        (0..frame_count)
+            .map(|frame| {
+                let shown = if frame_count == 1 {
+                    element_count
+                } else {
+                    frame * element_count / (frame_count - 1)
+                };
+
+                let mut image = self.clone();
+                for (_, node) in image.elements.drain(shown..) {
+                    node.detach();
+                }
+                image
+            })
+            .collect()
+    }
+
+    /// Restrict everything drawn from now on to the inside of a polygon, until
+    /// the matching call to `pop_clip`.
+    ///
+    /// The polygon is closed automatically, so the last point does not need to
+    /// repeat the first. Clips compose: while several clips are pushed, only
+    /// the area inside all of them can be drawn on.
+    ///
+    /// Self-intersecting polygons clip using the even-odd rule: a point is
+    /// inside the polygon if a ray from it crosses the polygon's edges an odd
+    /// number of times. For example, the inner pentagon of a pentagram is
+    /// *outside* the clip, while its five points are inside.
+    ///
+    /// ```rs
+    /// let mut image = Image::new(100, 100);
+    /// image.push_clip_polygon(&[(50, 0), (100, 100), (0, 100)])?;
+    /// // Only the part of this line inside the triangle is drawn.
+    /// image.draw_simple_line(0, 75, 90, 100, COLORS[1])?;
+    /// image.pop_clip()?;
+    /// ```
+    pub fn push_clip_polygon(&mut self, points: &[(i32, i32)]) -> Result<(), String> {
+        if points.len() < 3 {
+            return Err(format!(
+                "A clip polygon needs at least 3 points, but {} were given",
+                points.len()
+            ));
+        }
+
+        let mut builder = tiny_skia::PathBuilder::new();
+        builder.move_to(i32_to_f32(points[0].0), i32_to_f32(points[0].1));
+        for &(x, y) in &points[1..] {
+            builder.line_to(i32_to_f32(x), i32_to_f32(y));
+        }
+        builder.close();
+
+        let mut path = usvg::Path::new(
+            builder
+                .finish()
+                .ok_or("Could not create clip polygon".to_string())?
+                .into(),
+        );
+        path.fill = Some(usvg::Fill {
+            rule: usvg::FillRule::EvenOdd,
+            ..usvg::Fill::default()
+        });
+
+        let clip = usvg::ClipPath {
+            id: self.new_def_id("clip"),
+            ..usvg::ClipPath::default()
+        };
+        clip.root.append_kind(usvg::NodeKind::Path(path));
+        self.clips.push(Rc::new(clip));
+        self.mark_changed();
+
+        Ok(())
+    }
+
+    /// Remove the most recently pushed clip, so that it no longer applies to
+    /// anything drawn afterwards. Returns an error if no clip is active.
+    pub fn pop_clip(&mut self) -> Result<(), String> {
+        self.clips
+            .pop()
+            .ok_or("There is no clip to pop".to_string())?;
+        self.mark_changed();
+        Ok(())
+    }
+
+    /// The top-level nodes added by draw calls, in drawing order.
+    fn elements(&self) -> impl Iterator<Item = usvg::Node> + '_ {
+        self.elements.iter().map(|(_, node)| node.clone())
+    }
+
+    /// Add a drawn element to the image, inside any active clips.
+    /// Returns an error if it would take the image over its memory limit.
+    fn append_element(&mut self, kind: usvg::NodeKind) -> Result<ElementId, String> {
+        self.append_element_node(usvg::Node::new(kind))
+    }
+
+    fn append_element_node(&mut self, mut node: usvg::Node) -> Result<ElementId, String> {
+        // Each clip gets its own group, so nested clips intersect. The most
+        // recently pushed clip is the innermost group.
+        for clip in self.clips.iter().rev() {
+            let group = usvg::Node::new(usvg::NodeKind::Group(usvg::Group {
+                clip_path: Some(clip.clone()),
+                ..usvg::Group::default()
+            }));
+            group.append(node);
+            node = group;
+        }
+        let bytes = self.check_memory_limit(&node)?;
+        if !self.dry_run {
+            self.tree.root.append(node.clone());
+        }
+
+        self.next_element_id += 1;
+        let id = ElementId(self.next_element_id);
+        self.elements.push((id, node));
+        self.element_bytes += bytes;
+        self.record_timestamp(id);
+        self.mark_changed();
+        Ok(id)
+    }
+
+    /// A new, unique id for a clip path or mask definition.
+    fn new_def_id(&mut self, kind: &str) -> String {
+        self.next_def_id += 1;
+        format!("{kind}{}", self.next_def_id)
+    }
+
+    /// Deep copy nodes from another image, giving the clip paths and masks they
+    /// use new ids, so they can't clash with this image's definitions.
+    fn adopt(&mut self, node: &usvg::Node) -> usvg::Node {
+        let mut renamed = Renamed::default();
+        self.adopt_with(node, &mut renamed)
+    }
+
+    fn adopt_with(&mut self, node: &usvg::Node, renamed: &mut Renamed) -> usvg::Node {
+        let copy = node.make_deep_copy();
+        for descendant in copy.descendants() {
+            if let usvg::NodeKind::Group(ref mut group) = *descendant.borrow_mut() {
+                // Ids of animated elements only mean something in their own image.
+                group.id.clear();
+            }
+        }
+        self.rename_defs(&copy, renamed);
+        copy
+    }
+
+    /// Give the clip paths and masks used by `node` and its descendants new
+    /// ids, in place. `node` must be a copy that nothing else uses.
+    fn rename_defs(&mut self, node: &usvg::Node, renamed: &mut Renamed) {
+        for descendant in node.descendants() {
+            if let usvg::NodeKind::Group(ref mut group) = *descendant.borrow_mut() {
+                if let Some(clip) = group.clip_path.take() {
+                    group.clip_path = Some(self.adopt_clip(&clip, renamed));
+                }
+                if let Some(mask) = group.mask.take() {
+                    group.mask = Some(self.adopt_mask(&mask, renamed));
+                }
+            }
+        }
+    }
+
+    fn adopt_clip(
+        &mut self,
+        clip: &Rc<usvg::ClipPath>,
+        renamed: &mut Renamed,
+    ) -> Rc<usvg::ClipPath> {
+        if let Some((_, new)) = renamed.clips.iter().find(|(old, _)| Rc::ptr_eq(old, clip)) {
+            return new.clone();
+        }
+
+        let new = Rc::new(usvg::ClipPath {
+            id: self.new_def_id("clip"),
+            units: clip.units,
+            transform: clip.transform,
+            clip_path: clip
+                .clip_path
+                .as_ref()
+                .map(|inner| self.adopt_clip(inner, renamed)),
+            root: self.adopt_with(&clip.root, renamed),
+        });
+        renamed.clips.push((clip.clone(), new.clone()));
+        new
+    }
+
+    fn adopt_mask(&mut self, mask: &Rc<usvg::Mask>, renamed: &mut Renamed) -> Rc<usvg::Mask> {
+        if let Some((_, new)) = renamed.masks.iter().find(|(old, _)| Rc::ptr_eq(old, mask)) {
+            return new.clone();
+        }
+
+        let new = Rc::new(usvg::Mask {
+            id: self.new_def_id("mask"),
+            units: mask.units,
+            content_units: mask.content_units,
+            rect: mask.rect,
+            kind: mask.kind,
+            mask: mask
+                .mask
+                .as_ref()
+                .map(|inner| self.adopt_mask(inner, renamed)),
+            root: self.adopt_with(&mask.root, renamed),
+        });
+        renamed.masks.push((mask.clone(), new.clone()));
+        new
+    }
+
+    /// The background of the image, which is always the first child of the root.
+    fn background(&self) -> usvg::Node {
+        self.tree
+            .root
+            .first_child()
+            .expect("an image always has a background")
+    }
+
+    /// Build the tree that is actually saved, applying anything that only
+    /// takes effect on output (like the image's mask, frame and watermark).
+    fn export_tree(&self) -> usvg::Tree {
+        let mut tree = self.export_tree_of(Some(self.background()), self.elements());
+        self.finish_export(&mut tree);
+        tree
+    }
+
+    /// Add what is drawn on top of everything when saving (the frame, then
+    /// the watermark) to a tree being exported, and turn any text in the tree
+    /// into paths.
+    fn finish_export(&self, tree: &mut usvg::Tree) {
+        for node in self.frame_node().into_iter().chain(self.watermark_node()) {
+            tree.root.append(node);
+        }
+        self.convert_text(tree);
+    }
+
+    /// Like `export_tree`, but only including some of the image's nodes, and
+    /// without the frame or watermark.
+    fn export_tree_of(
+        &self,
+        background: Option<usvg::Node>,
+        elements: impl Iterator<Item = usvg::Node>,
+    ) -> usvg::Tree {
+        let root = usvg::Node::new(usvg::NodeKind::Group(usvg::Group::default()));
+        if let Some(background) = background {
+            root.append(background.make_deep_copy());
+        }
+
+        let content = match self.mask {
+            Some(ref mask) => root.append_kind(usvg::NodeKind::Group(usvg::Group {
+                mask: Some(mask.clone()),
+                ..usvg::Group::default()
+            })),
+            None => root.clone(),
+        };
+        let transform = self.coordinate_transform();
+        let content = if transform.is_identity() {
+            content
+        } else {
+            content.append_kind(usvg::NodeKind::Group(usvg::Group {
+                transform,
+                ..usvg::Group::default()
+            }))
+        };
+        for element in elements {
+            let copy = element.make_deep_copy();
+            content.append(copy.clone());
+            self.align_to_pixels(&copy);
+        }
+
+        usvg::Tree {
+            size: self.tree.size,
+            view_box: self.tree.view_box,
+            root,
+        }
+    }
+}
+
+/// Render a tree to a new pixmap the size of the tree.
+fn render_tree(tree: &usvg::Tree) -> Result<tiny_skia::Pixmap, String> {
+    let rtree = resvg::Tree::from_usvg(tree);
+
+    let pixmap_size = rtree.size.to_int_size();
+    let mut pixmap = new_pixmap(pixmap_size.width(), pixmap_size.height())?;
+    rtree.render(tiny_skia::Transform::default(), &mut pixmap.as_mut());
+    Ok(pixmap)
+}
+
+/// Make a new, transparent pixmap, or an error if there isn't enough memory
+/// for it.
+pub(crate) fn new_pixmap(width: u32, height: u32) -> Result<tiny_skia::Pixmap, String> {
+    check_pixmap_memory(width, height)?;
+    tiny_skia::Pixmap::new(width, height)
+        .ok_or_else(|| format!("Could not allocate a {width}x{height} image"))
+}
+
+/// Check there is enough memory for a `width` by `height` pixmap, before
+/// making it: failing to allocate it would abort the program.
+pub(crate) fn check_pixmap_memory(width: u32, height: u32) -> Result<(), String> {
+    let bytes = u64::from(width) * u64::from(height) * 4;
+    let enough =
+        usize::try_from(bytes).is_ok_and(|bytes| Vec::<u8>::new().try_reserve_exact(bytes).is_ok());
+    if !enough {
+        return Err(format!(
+            "Not enough memory to render a {width}x{height} image, which needs {bytes} bytes"
+        ));
+    }
+    Ok(())
+}
+
+/// Clip paths and masks that have already been copied by `Image::adopt`, so
+/// that definitions shared between several nodes stay shared.
+#[derive(Default)]
+struct Renamed {
+    clips: Vec<(Rc<usvg::ClipPath>, Rc<usvg::ClipPath>)>,
+    masks: Vec<(Rc<usvg::Mask>, Rc<usvg::Mask>)>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The opaque color of a pixel.
+    fn rgb(pixmap: &tiny_skia::Pixmap, x: u32, y: u32) -> (u8, u8, u8) {
+        let pixel = pixmap.pixel(x, y).unwrap();
+        (pixel.red(), pixel.green(), pixel.blue())
+    }
+
+    #[test]
+    fn clip_polygons_clip_pngs_and_svgs() {
+        let mut image = Image::new(100, 100);
+        image
+            .push_clip_polygon(&[(50, 0), (100, 100), (0, 100)])
+            .unwrap();
+        let style = StrokeStyle {
+            width: 6.0,
+            ..StrokeStyle::default()
+        };
+        for y in (5..100).step_by(10) {
+            image
+                .draw_styled_line(0, y, 90, 100, COLORS[7], &style)
+                .unwrap();
+        }
+        for x in (5..100).step_by(10) {
+            image
+                .draw_styled_line(x, 0, 180, 100, COLORS[7], &style)
+                .unwrap();
+        }
+        image.pop_clip().unwrap();
+        assert!(image.pop_clip().is_err());
+
+        let svg = image.svg_string(&SvgOptions::default());
+        for pixmap in [
+            image.render().unwrap(),
+            render_tree(&snapshot::parse_svg(&svg).unwrap()).unwrap(),
+        ] {
+            // Grid lines inside the triangle, and nothing outside it.
+            assert_eq!(rgb(&pixmap, 50, 85), (255, 255, 255));
+            assert_eq!(rgb(&pixmap, 45, 25), (255, 255, 255));
+            assert_eq!(rgb(&pixmap, 5, 25), (0, 0, 0));
+            assert_eq!(rgb(&pixmap, 95, 45), (0, 0, 0));
+        }
+    }
+
+    #[test]
+    fn animation_goes_from_background_to_everything() {
+        let mut image = Image::new(100, 100);
+        for x in (10..60).step_by(10) {
+            image.draw_simple_line(x, 0, 180, 50, COLORS[1]).unwrap();
+        }
+        let frames = image.animate(3);
+        assert_eq!(frames.len(), 3);
+        let counts: Vec<_> = frames
+            .iter()
+            .map(|frame| frame.element_ids().len())
+            .collect();
+        assert_eq!(counts, [0, 2, 5]);
+
+        let first = frames[0].render().unwrap();
+        assert!(first
+            .pixels()
+            .iter()
+            .all(|pixel| (pixel.red(), pixel.green(), pixel.blue()) == (0, 0, 0)));
+        assert_eq!(
+            frames[2].svg_string(&SvgOptions::default()),
+            image.svg_string(&SvgOptions::default())
+        );
+    }
+
+    #[test]
+    fn clamped_lines_end_on_the_border() {
+        let mut image = Image::new(100, 80);
+        image.set_clamp_to_bounds(true);
+        assert_eq!(
+            image.draw_simple_line(50, 50, 90, 100, COLORS[1]),
+            Ok((100, 50))
+        );
+        assert_eq!(
+            image.draw_simple_line(-20, 40, 180, 100, COLORS[1]),
+            Ok((0, 80))
+        );
+        let ends: Vec<_> = image
+            .to_segments(0.1)
+            .iter()
+            .map(|segment| (segment.start, segment.end))
+            .collect();
+        assert_eq!(
+            ends,
+            [((50.0, 50.0), (100.0, 50.0)), ((0.0, 40.0), (0.0, 80.0))]
+        );
+
+        image.set_clamp_to_bounds(false);
+        assert_eq!(
+            image.draw_simple_line(50, 50, 90, 100, COLORS[1]),
+            Ok((150, 50))
+        );
+    }
+
+    #[test]
+    fn each_rounding_mode_rounds_halves_its_own_way() {
+        // Direction 30 ends exactly half a length to the right, and
+        // direction 210 half a length to the left.
+        let cases = [
+            ((30, 1), [(1, -1), (0, 0), (0, -1)]),
+            ((30, 5), [(3, -4), (2, -4), (2, -4)]),
+            ((30, 7), [(4, -6), (3, -6), (4, -6)]),
+            ((210, 5), [(-3, 4), (-2, 4), (-2, 4)]),
+            ((45, 100), [(71, -71), (70, -70), (71, -71)]),
+            ((90, 10), [(10, 0), (10, 0), (10, 0)]),
+        ];
+        let modes = [Rounding::Nearest, Rounding::Truncate, Rounding::HalfToEven];
+        for ((direction, length), ends) in cases {
+            for (rounding, end) in modes.into_iter().zip(ends) {
+                assert_eq!(
+                    get_end_coordinates_rounded(0, 0, direction, length, rounding),
+                    end,
+                    "{direction} {length} {rounding:?}"
+                );
+
+                // Drawing rounds the same way, from where the line starts.
+                let end = get_end_coordinates_rounded(50, 50, direction, length, rounding);
+                let mut image = Image::new(100, 100);
+                image.set_rounding(rounding);
+                assert_eq!(
+                    image.draw_simple_line(50, 50, direction, length, COLORS[1]),
+                    Ok(end)
+                );
+                let segment = image.to_segments(0.1).pop().unwrap();
+                assert_eq!(segment.end, (end.0 as f32, end.1 as f32));
+            }
+        }
+        assert_eq!(get_end_coordinates(0, 0, 30, 5), (3, -4));
+        // The end point is rounded, not the distance moved, so truncating
+        // goes towards zero from wherever the line ends up.
+        assert_eq!(
+            get_end_coordinates_rounded(50, 50, 30, 1, Rounding::Truncate),
+            (50, 49)
+        );
+    }
+
+    #[test]
+    fn strict_mode_rejects_lines_clamping_would_shorten() {
+        let mut image = Image::new(100, 80);
+        image.set_clamp_to_bounds(true);
+        image.set_strict(true);
+        assert!(image.draw_simple_line(50, 50, 90, 100, COLORS[1]).is_err());
+        assert!(image.element_ids().is_empty());
+    }
+
+    #[test]
+    fn chained_lines_saturate() {
+        let mut image = Image::new(50, 40);
+        // The `f32` cosine of 90 degrees isn't quite 0, so the coordinate
+        // across the line can drift a little, but never wraps.
+        for (direction, edge) in [
+            (90, (Some(i32::MAX), None)),
+            (180, (None, Some(i32::MAX))),
+            (270, (Some(i32::MIN), None)),
+            (0, (None, Some(i32::MIN))),
+        ] {
+            let mut end = (0, 0);
+            for _ in 0..10 {
+                end = image
+                    .draw_simple_line(end.0, end.1, direction, i32::MAX, COLORS[1])
+                    .unwrap();
+            }
+            let (x, y) = edge;
+            assert_eq!(x.unwrap_or(end.0), end.0);
+            assert_eq!(y.unwrap_or(end.1), end.1);
+            assert!(x.is_some() || end.0.abs() < 10_000, "{end:?}");
+            assert!(y.is_some() || end.1.abs() < 10_000, "{end:?}");
+        }
+        assert_eq!(
+            get_end_coordinates(i32::MAX - 10, 0, 90, i32::MAX),
+            (i32::MAX, 0)
+        );
+        image.render().unwrap();
+    }
+
+    #[test]
+    fn partial_lines_stop_part_way() {
+        assert_eq!(partial_line(0, 0, 90, 100, 0.5), (50, 0));
+        assert_eq!(partial_line(10, 10, 180, 40, 0.5), (10, 30));
+        // 40 units along a diagonal is 28.28 across and down.
+        assert_eq!(partial_line(10, 10, 135, 80, 0.5), (38, 38));
+        assert_eq!(partial_line(10, 10, 135, 80, 0.25), (24, 24));
+        // Halfway along a negative length goes back the other way.
+        assert_eq!(partial_line(50, 50, 90, -40, 0.5), (30, 50));
+
+        for (direction, length) in [(0, 100), (37, 61), (135, 80), (300, 7)] {
+            assert_eq!(partial_line(10, 10, direction, length, 0.0), (10, 10));
+            assert_eq!(
+                partial_line(10, 10, direction, length, 1.0),
+                get_end_coordinates(10, 10, direction, length)
+            );
+        }
+    }
+
+    #[test]
+    fn partial_lines_clamp_t() {
+        let end = get_end_coordinates(10, 10, 135, 80);
+        assert_eq!(partial_line(10, 10, 135, 80, 2.0), end);
+        assert_eq!(partial_line(10, 10, 135, 80, f32::INFINITY), end);
+        assert_eq!(partial_line(10, 10, 135, 80, -1.0), (10, 10));
+        assert_eq!(partial_line(10, 10, 135, 80, f32::NEG_INFINITY), (10, 10));
+        assert_eq!(partial_line(10, 10, 135, 80, f32::NAN), (10, 10));
+    }
+
+    #[test]
+    fn images_can_be_up_to_max_dimension() {
+        for (width, height) in [
+            (MAX_DIMENSION, 1),
+            (1, MAX_DIMENSION),
+            (MAX_DIMENSION, MAX_DIMENSION),
+        ] {
+            let image = Image::try_new(width, height).unwrap();
+            assert_eq!(image.get_dimensions(), (width, height));
+        }
+        for (width, height) in [
+            (MAX_DIMENSION + 1, 1),
+            (1, MAX_DIMENSION + 1),
+            (u32::MAX, u32::MAX),
+        ] {
+            let Err(e) = Image::try_new(width, height) else {
+                panic!("{width}x{height} was allowed");
+            };
+            assert!(e.contains("at most 1000000x1000000"), "{e}");
+        }
+        for (width, height) in [(0, 1), (1, 0)] {
+            assert!(Image::try_new(width, height).is_err());
+        }
+    }
+
+    #[test]
+    fn svgs_stay_exact_at_max_dimension() {
+        let mut image = Image::try_new(MAX_DIMENSION, MAX_DIMENSION).unwrap();
+        let far = MAX_DIMENSION as i32;
+        let end = image
+            .draw_simple_line(far - 10, far - 1, 90, 7, COLORS[1])
+            .unwrap();
+        assert_eq!(end, (far - 3, far - 1));
+        // 1000 units along a diagonal is 707.1 across and up.
+        let end = image
+            .draw_simple_line(far - 1000, far - 1000, 45, 1000, COLORS[1])
+            .unwrap();
+        assert_eq!(end, (far - 293, far - 1707));
+        let end = image.draw_simple_line(0, 5, 90, far, COLORS[1]).unwrap();
+        assert_eq!(end, (far, 5));
+
+        let svg = image.svg_string(&svg::SvgOptions::default());
+        assert!(svg.contains(r#"width="1000000" height="1000000""#), "{svg}");
+        assert!(svg.contains("M 999990 999999 L 999997 999999"), "{svg}");
+        assert!(svg.contains("M 0 5 L 1000000 5"), "{svg}");
+    }
+
+    #[test]
+    fn pngs_at_max_dimension_render_or_fail_cleanly() {
+        let mut image = Image::try_new(MAX_DIMENSION, 4).unwrap();
+        image
+            .draw_simple_line(MAX_DIMENSION as i32 - 10, 0, 180, 4, COLORS[7])
+            .unwrap();
+        let pixmap = image.render().unwrap();
+        assert_eq!((pixmap.width(), pixmap.height()), (MAX_DIMENSION, 4));
+        let lit = pixmap.pixel(MAX_DIMENSION - 10, 2).unwrap();
+        assert!(lit.red() > 64, "{lit:?}");
+        assert_eq!(pixmap.pixel(MAX_DIMENSION - 20, 2).unwrap().red(), 0);
+
+        // Far more pixels than there is memory for.
+        let image = Image::try_new(MAX_DIMENSION, MAX_DIMENSION).unwrap();
+        let Err(e) = image.png_bytes() else {
+            panic!("a {MAX_DIMENSION}x{MAX_DIMENSION} PNG was made");
+        };
+        assert!(e.starts_with("Not enough memory"), "{e}");
+    }
+
+    #[test]
+    fn negative_lengths_draw_the_opposite_way() {
+        // Direction 0 is up, so a negative length goes down.
+        assert_eq!(get_end_coordinates(50, 50, 0, -100), (50, 150));
+        assert_eq!(get_end_coordinates(50, 50, 180, 100), (50, 150));
+        assert_eq!(get_end_coordinates(50, 50, 90, -30), (20, 50));
+        assert_eq!(get_end_coordinates(50, 50, 45, -40), (22, 78));
+        for direction in [0, 37, 90, 135, 200, 300, -45] {
+            for length in [1, 17, 100, 1000] {
+                assert_eq!(
+                    get_end_coordinates(50, 50, direction, -length),
+                    get_end_coordinates(50, 50, direction + 180, length),
+                    "{direction} degrees, {length} long"
+                );
+            }
+        }
+
+        // The line drawn is the same one too, from the same start.
+        let mut backwards = Image::new(200, 200);
+        let end = backwards
+            .draw_simple_line(50, 50, 0, -100, COLORS[1])
+            .unwrap();
+        assert_eq!(end, (50, 150));
+        let mut forwards = Image::new(200, 200);
+        forwards
+            .draw_simple_line(50, 50, 180, 100, COLORS[1])
+            .unwrap();
+        let ends = |image: &Image| {
+            let segments = image.to_segments(0.1);
+            assert_eq!(segments.len(), 1);
+            (segments[0].start, segments[0].end)
+        };
+        assert_eq!(ends(&backwards), ((50.0, 50.0), (50.0, 150.0)));
+        assert_eq!(ends(&forwards), ends(&backwards));
+    }
 }
@@ -0,0 +1,348 @@
+use crate::snapshot::parse_svg;
+use crate::{Corner, Image, Renamed};
+use resvg::{tiny_skia, usvg};
+
+/// How far (in units) the edges of a background can be from the edges of
+/// the image, to allow for rounding when the viewBox is scaled.
+const EDGE_TOLERANCE: f32 = 1.0 / 256.0;
+
+impl Image {
+    /// Load an image from an SVG file, such as one written by `save_svg`.
+    /// See `from_svg_str` for how the file is read.
+    ///
+    /// ```rs
+    /// image.save_svg("drawing.svg")?;
+    /// let mut copy = Image::from_svg_file("drawing.svg")?;
+    /// copy.draw_simple_line(10, 10, 90, 50, COLORS[1])?;
+    /// ```
+    pub fn from_svg_file<P: AsRef<std::path::Path>>(path: P) -> Result<Image, String> {
+        let svg = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Image::from_svg_str(&svg)
+    }
+
+    /// Load an image from the contents of an SVG file, such as one written
+    /// by `save_svg`, so that more can be drawn on it.
+    ///
+    /// Loading a saved image gives back an image that behaves like the
+    /// original: it has the same size and background, the same coordinate
+    /// system (from `set_coordinate_system`) and mask (from `set_mask`), and
+    /// each thing drawn on the original is one element, in the same order,
+    /// so element counts line up (though the elements get new ids). Saving
+    /// the loaded image again gives the same drawing.
+    /// A frame or watermark that was saved in the file can't be told apart
+    /// from the drawing, so each becomes an ordinary element.
+    ///
+    /// Other SVG files can be loaded too, as long as they start with a
+    /// rectangle filling the whole image, which is used as the background.
+    /// The file's viewBox is applied to everything in it, so the image's
+    /// units are the file's pixels.
+    ///
+    /// Returns an error for anything that can't be kept when the image is
+    /// saved again, rather than quietly leaving it out: animations, text
+    /// (which is only drawn correctly with the right fonts, so should be
+    /// turned into paths first), a size that isn't a whole number of
+    /// pixels or is more than [`MAX_DIMENSION`](crate::MAX_DIMENSION), or a
+    /// file with no background.
+    ///
+    /// ```rs
+    /// let copy = Image::from_svg_str(&std::fs::read_to_string("drawing.svg")?)?;
+    /// assert_eq!(copy.element_ids().len(), image.element_ids().len());
+    /// ```
+    pub fn from_svg_str(svg: &str) -> Result<Image, String> {
+        // These are dropped when parsing, so they would be lost on saving.
+        if svg.contains("<animate") || svg.contains("<set ") {
+            return Err("The SVG is animated, and animations can't be loaded".into());
+        }
+
+        let tree = parse_svg(svg)?;
+        let (width, height) = (tree.size.width(), tree.size.height());
+        if width.fract() != 0.0 || height.fract() != 0.0 {
+            return Err(format!(
+                "The SVG is {width}x{height}, but images can only be a whole number of pixels across"
+            ));
+        }
+        let (width, height) = (width as u32, height as u32);
+        if width == 0 || height == 0 {
+            return Err(format!("The SVG is {width}x{height}, so it has no area"));
+        }
+        if tree
+            .root
+            .descendants()
+            .any(|node| matches!(*node.borrow(), usvg::NodeKind::Text(_)))
+        {
+            return Err("The SVG has text, which can't be loaded: turn it into paths first".into());
+        }
+
+        let mut image = Image::try_new(width, height)?;
+        let mut nodes: Vec<usvg::Node> = tree.root.children().collect();
+        let view_box =
+            usvg::utils::view_box_to_transform(tree.view_box.rect, tree.view_box.aspect, tree.size);
+
+        let background = nodes
+            .first()
+            .and_then(|node| background_fill(node, view_box, width, height))
+            .ok_or("The SVG has no background: it must start with a filled rectangle covering the whole image")?;
+        if let usvg::NodeKind::Path(ref mut path) = *image.background().borrow_mut() {
+            path.fill = Some(background);
+        }
+        nodes.remove(0);
+        if !view_box.is_identity() {
+            nodes = nodes
+                .into_iter()
+                .map(|node| pre_transform(node, view_box))
+                .collect();
+        }
+
+        // Look inside the group for the mask, and then the group for the
+        // coordinate system, in that order, as `export_tree` adds them.
+        let mut renamed = Renamed::default();
+        if let Some(first) = nodes.first().cloned() {
+            if let usvg::NodeKind::Group(ref group) = *first.borrow() {
+                if let Some(ref mask) = group.mask {
+                    if group.clip_path.is_none() && group.transform.is_identity() {
+                        image.mask = Some(image.adopt_mask(mask, &mut renamed));
+                        nodes.splice(0..1, first.children());
+                    }
+                }
+            }
+        }
+        if let Some(first) = nodes.first().cloned() {
+            if let usvg::NodeKind::Group(ref group) = *first.borrow() {
+                if let Some(system) = coordinate_system_of(group, width, height) {
+                    image.coordinate_system = system;
+                    nodes.splice(0..1, first.children());
+                }
+            }
+        }
+
+        for node in nodes {
+            let node = image.adopt_with(&node, &mut renamed);
+            image.append_element_node(node)?;
+        }
+        Ok(image)
+    }
+}
+
+/// Apply `transform` to `node`, before its own transform.
+///
+/// Anything other than a group is put in a new group with the transform,
+/// since that is how usvg reads a transformed shape back from a saved file,
+/// so the image saves the same way after it is loaded again.
+fn pre_transform(node: usvg::Node, transform: tiny_skia::Transform) -> usvg::Node {
+    if let usvg::NodeKind::Group(ref mut group) = *node.borrow_mut() {
+        group.transform = transform.pre_concat(group.transform);
+    }
+    if matches!(*node.borrow(), usvg::NodeKind::Group(_)) {
+        return node;
+    }
+
+    let group = usvg::Node::new(usvg::NodeKind::Group(usvg::Group {
+        transform,
+        ..usvg::Group::default()
+    }));
+    group.append(node);
+    group
+}
+
+/// The fill of `node`, if it is a background as drawn by `Image::new`: a
+/// filled rectangle with no outline covering exactly the whole image once
+/// the file's `view_box` transform is applied.
+fn background_fill(
+    node: &usvg::Node,
+    view_box: tiny_skia::Transform,
+    width: u32,
+    height: u32,
+) -> Option<usvg::Fill> {
+    let usvg::NodeKind::Path(ref path) = *node.borrow() else {
+        return None;
+    };
+    if path.stroke.is_some() || path.data.points().len() != 4 {
+        return None;
+    }
+    let transform = view_box.pre_concat(path.transform);
+    let bounds = (*path.data).clone().transform(transform)?.bounds();
+    let near = |a: f32, b: f32| (a - b).abs() <= EDGE_TOLERANCE;
+    let covers = near(bounds.left(), 0.0)
+        && near(bounds.top(), 0.0)
+        && near(bounds.right(), width as f32)
+        && near(bounds.bottom(), height as f32);
+    // A rotated or skewed square could have the same bounds.
+    let straight = transform.kx == 0.0 && transform.ky == 0.0;
+    if covers && straight {
+        path.fill.clone()
+    } else {
+        None
+    }
+}
+
+/// The coordinate system whose transform (from `Image::coordinate_transform`)
+/// is the transform of `group`, unless it is the default or `group` does
+/// anything else.
+fn coordinate_system_of(group: &usvg::Group, width: u32, height: u32) -> Option<(Corner, bool)> {
+    let transform = group.transform;
+    let plain = group.clip_path.is_none()
+        && group.mask.is_none()
+        && group.opacity.get() == 1.0
+        && group.filters.is_empty();
+    if !plain
+        || transform.is_identity()
+        || (transform.sx, transform.kx, transform.ky) != (1.0, 0.0, 0.0)
+        || transform.sy.abs() != 1.0
+    {
+        return None;
+    }
+
+    // The offset is 0, or the whole width or height of the image.
+    let at_far_edge =
+        |offset: f32, size: u32| (offset == 0.0 || offset == size as f32).then_some(offset != 0.0);
+    let right = at_far_edge(transform.tx, width)?;
+    let bottom = at_far_edge(transform.ty, height)?;
+    let origin = match (right, bottom) {
+        (false, false) => Corner::TopLeft,
+        (true, false) => Corner::TopRight,
+        (false, true) => Corner::BottomLeft,
+        (true, true) => Corner::BottomRight,
+    };
+    Some((origin, transform.sy < 0.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Corner, FillRule, Image, LineCap, LineStyle, MaskFit, StrokeStyle, SvgOptions, COLORS,
+    };
+
+    fn svg(image: &Image) -> String {
+        image.svg_string(&SvgOptions::default())
+    }
+
+    /// Check that `image` loads back from its SVG as the same image, which
+    /// saves as the same SVG and carries on drawing the same way.
+    fn assert_round_trips(image: &mut Image) {
+        let saved = svg(image);
+        let mut loaded = Image::from_svg_str(&saved).unwrap();
+        assert_eq!(svg(&loaded), saved);
+        assert_eq!(loaded.element_ids().len(), image.element_ids().len());
+        assert_eq!(loaded.coordinate_system(), image.coordinate_system());
+        assert_eq!(loaded.get_dimensions(), image.get_dimensions());
+        assert!(loaded.render().unwrap().pixels() == image.render().unwrap().pixels());
+
+        let end = image.draw_simple_line(5, 5, 135, 20, COLORS[14]).unwrap();
+        assert_eq!(loaded.draw_simple_line(5, 5, 135, 20, COLORS[14]), Ok(end));
+        assert_eq!(svg(&loaded), svg(image));
+        assert_eq!(loaded.element_ids().len(), image.element_ids().len());
+    }
+
+    #[test]
+    fn shapes_and_styles_round_trip() {
+        let mut image = Image::new(100, 80);
+        image.draw_simple_line(10, 10, 90, 50, COLORS[1]).unwrap();
+        let style = StrokeStyle {
+            width: 3.0,
+            style: LineStyle::Dashed,
+            cap: LineCap::Round,
+            ..StrokeStyle::default()
+        };
+        image
+            .draw_styled_polyline(&[(10, 70), (40, 40), (70, 70)], COLORS[3], &style)
+            .unwrap();
+        image.draw_ring(70, 30, 20, 10, COLORS[4]).unwrap();
+        image
+            .fill_polygon(&[(0, 0), (30, 0), (0, 30)], COLORS[5], FillRule::EvenOdd)
+            .unwrap();
+        image
+            .draw_connected_dots(&[(20, 50), (50, 20)], 4, COLORS[7], COLORS[2])
+            .unwrap();
+        assert_round_trips(&mut image);
+    }
+
+    #[test]
+    fn clips_masks_and_coordinate_systems_round_trip() {
+        let mut image = Image::new(120, 60);
+        // Groups with their own transforms and clips.
+        image
+            .draw_grid_of(3, 1, 2, |cell, col, _| {
+                let length = 40 + 20 * i32::try_from(col).unwrap();
+                cell.draw_simple_line(0, 20, 90, length, COLORS[7])?;
+                Ok(())
+            })
+            .unwrap();
+        assert_round_trips(&mut image);
+
+        let mut mask = Image::new(120, 60);
+        mask.fill_polygon(
+            &[(0, 0), (60, 0), (60, 60), (0, 60)],
+            COLORS[7],
+            FillRule::NonZero,
+        )
+        .unwrap();
+        for origin in [Corner::TopLeft, Corner::BottomLeft, Corner::TopRight] {
+            let mut image = Image::new(120, 60);
+            image.set_coordinate_system(origin, true);
+            image.set_mask(&mask, MaskFit::Error).unwrap();
+            let (min_x, min_y, _, _) = image.drawing_area();
+            image
+                .draw_simple_line(min_x + 10, min_y + 30, 90, 100, COLORS[4])
+                .unwrap();
+            assert_round_trips(&mut image);
+        }
+    }
+
+    #[test]
+    fn other_svgs_are_normalized_when_loaded() {
+        // A viewBox at half the size, and a gradient.
+        let svg_text = r##"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="60" viewBox="0 0 50 30">
+            <defs>
+                <linearGradient id="fade"><stop offset="0" stop-color="red"/><stop offset="1" stop-color="blue"/></linearGradient>
+            </defs>
+            <rect width="50" height="30" fill="#102030"/>
+            <rect x="5" y="5" width="20" height="10" fill="url(#fade)"/>
+            <line x1="30" y1="5" x2="45" y2="25" stroke="white"/>
+        </svg>"##;
+        let mut image = Image::from_svg_str(svg_text).unwrap();
+        assert_eq!(image.get_dimensions(), (100, 60));
+        assert_eq!(image.element_ids().len(), 2);
+        // The viewBox is absorbed, so units are the file's pixels.
+        assert_eq!(
+            image.element_bounds(image.element_ids()[0]),
+            Some((10, 10, 50, 30))
+        );
+        let pixmap = image.render().unwrap();
+        let background = pixmap.pixel(90, 5).unwrap();
+        assert_eq!(
+            (background.red(), background.green(), background.blue()),
+            (0x10, 0x20, 0x30)
+        );
+        let left = pixmap.pixel(11, 20).unwrap();
+        let right = pixmap.pixel(48, 20).unwrap();
+        assert!(left.red() > left.blue() && right.blue() > right.red());
+
+        assert_round_trips(&mut image);
+    }
+
+    #[test]
+    fn what_cant_round_trip_is_rejected() {
+        let svg_text = |size: &str, body: &str| {
+            format!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" {size}><rect width="10" height="10"/>{body}</svg>"#
+            )
+        };
+        let size = r#"width="10" height="10""#;
+        assert!(Image::from_svg_str(&svg_text(size, "")).is_ok());
+
+        let rejected = [
+            svg_text(size, r#"<text x="1" y="5">hi</text>"#),
+            svg_text(
+                size,
+                r#"<line x1="0" y1="0" x2="5" y2="5" stroke="red"><animate attributeName="x2" to="9" dur="1s"/></line>"#,
+            ),
+            svg_text(r#"width="10.5" height="10""#, ""),
+            svg_text(r#"width="20" height="10""#, ""),
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="10" height="10"><line x1="0" y1="0" x2="5" y2="5" stroke="red"/></svg>"#.to_string(),
+            "not an svg".to_string(),
+        ];
+        for svg_text in rejected {
+            assert!(Image::from_svg_str(&svg_text).is_err(), "{svg_text}");
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use crate::Image;
+use resvg::{tiny_skia, usvg};
+use std::rc::Rc;
+
+/// What to do when a mask image is not the same size as the image it masks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MaskFit {
+    /// Stretch the mask so it covers the whole image.
+    Scale,
+    /// Refuse to use the mask, returning an error.
+    Error,
+}
+
+impl Image {
+    /// Fade out everything drawn on the image using another image as a mask.
+    ///
+    /// The brightness (luminance) of each point of the mask becomes the
+    /// opacity of the drawing at that point: where the mask is white the
+    /// drawing is fully visible, where it is black the drawing is hidden and
+    /// the background shows through, and shades of grey fade between the two.
+    /// The background itself is never masked.
+    ///
+    /// The mask applies to everything drawn on the image, including things
+    /// drawn after it is set. Setting a new mask replaces the old one. If the
+    /// mask is a different size to this image, `fit` decides whether it is
+    /// stretched to fit or whether an error is returned.
+    ///
+    /// ```rs
+    /// let mut mask = Image::new(100, 100);
+    /// // A grey frame around a white centre fades the edges of the drawing.
+    /// // ...
+    /// image.set_mask(&mask, MaskFit::Error)?;
+    /// ```
+    pub fn set_mask(&mut self, mask: &Image, fit: MaskFit) -> Result<(), String> {
+        self.mask = Some(self.create_mask(mask, fit)?);
+        self.mark_changed();
+        Ok(())
+    }
+
+    /// Remove the mask set by `set_mask`, if there is one.
+    pub fn clear_mask(&mut self) {
+        self.mask = None;
+        self.mark_changed();
+    }
+
+    /// Like `set_mask`, but only masks what is drawn inside `draw`.
+    ///
+    /// `draw` is given an image with the same drawing settings as this one
+    /// (like its rounding, coordinate system, strict mode and anchors), but
+    /// with nothing on it. Everything it draws is masked as a single group,
+    /// so overlapping shapes fade out together rather than showing through
+    /// each other, and the group becomes one element of this image. The ids
+    /// of elements drawn inside `draw` then no longer refer to anything, as
+    /// if those elements had been removed. If `draw` returns an error,
+    /// nothing it drew is kept.
+    ///
+    /// ```rs
+    /// image.with_mask(&mask, MaskFit::Scale, |image| {
+    ///     image.draw_simple_line(0, 50, 90, 100, COLORS[4])?;
+    ///     Ok(())
+    /// })?;
+    /// ```
+    pub fn with_mask<F>(&mut self, mask: &Image, fit: MaskFit, draw: F) -> Result<(), String>
+    where
+        F: FnOnce(&mut Image) -> Result<(), String>,
+    {
+        let mask = self.create_mask(mask, fit)?;
+
+        let mut masked = self.blank_like(self.width, self.height)?;
+        draw(&mut masked)?;
+        self.next_def_id = masked.next_def_id;
+        self.next_element_id = masked.next_element_id;
+
+        let group = usvg::Node::new(usvg::NodeKind::Group(usvg::Group {
+            mask: Some(mask),
+            ..usvg::Group::default()
+        }));
+        let elements: Vec<_> = masked.elements().collect();
+        for element in elements {
+            group.append(element);
+        }
+        self.append_element_node(group)?;
+
+        Ok(())
+    }
+
+    fn create_mask(&mut self, mask: &Image, fit: MaskFit) -> Result<Rc<usvg::Mask>, String> {
+        let (width, height) = (self.width as f32, self.height as f32);
+        let (mask_width, mask_height) = (mask.width as f32, mask.height as f32);
+
+        if (mask.width, mask.height) != (self.width, self.height) && fit == MaskFit::Error {
+            return Err(format!(
+                "The mask is {}x{}, but the image is {}x{}",
+                mask.width, mask.height, self.width, self.height
+            ));
+        }
+
+        let content = usvg::Node::new(usvg::NodeKind::Group(usvg::Group {
+            transform: tiny_skia::Transform::from_scale(width / mask_width, height / mask_height),
+            ..usvg::Group::default()
+        }));
+        // The mask's own watermark isn't part of its drawing.
+        let mut mask_tree = mask.export_tree_of(Some(mask.background()), mask.elements());
+        mask.convert_text(&mut mask_tree);
+        for node in mask_tree.root.children() {
+            content.append(self.adopt(&node));
+        }
+
+        let root = usvg::Node::new(usvg::NodeKind::Group(usvg::Group::default()));
+        root.append(content);
+
+        Ok(Rc::new(usvg::Mask {
+            id: self.new_def_id("mask"),
+            units: usvg::Units::UserSpaceOnUse,
+            content_units: usvg::Units::UserSpaceOnUse,
+            rect: self.tree.view_box.rect,
+            kind: usvg::MaskType::Luminance,
+            mask: None,
+            root,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Color, Corner, FillRule, COLORS};
+
+    /// A mask of four upright bands, from black on the left to white on the
+    /// right.
+    fn fade() -> Image {
+        let mut mask = Image::new(100, 100);
+        for band in 0..4 {
+            let grey = u8::try_from(band * 85).unwrap();
+            let (left, right) = (band * 25, band * 25 + 25);
+            mask.fill_polygon(
+                &[(left, 0), (right, 0), (right, 100), (left, 100)],
+                Color::new_rgb(grey, grey, grey),
+                FillRule::NonZero,
+            )
+            .unwrap();
+        }
+        mask
+    }
+
+    #[test]
+    fn masked_drawings_fade_with_the_mask() {
+        let mut image = Image::new(100, 100);
+        image
+            .with_mask(&fade(), MaskFit::Error, |image| {
+                image.fill_polygon(
+                    &[(0, 0), (100, 0), (100, 100), (0, 100)],
+                    Color::white(),
+                    FillRule::NonZero,
+                )
+            })
+            .unwrap();
+
+        let pixmap = image.render().unwrap();
+        for (band, x) in [12, 37, 62, 87].into_iter().enumerate() {
+            let expected = band as u8 * 85;
+            let pixel = pixmap.pixel(x, 50).unwrap();
+            for channel in [pixel.red(), pixel.green(), pixel.blue()] {
+                assert!(channel.abs_diff(expected) <= 2, "at x = {x}: {pixel:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn masked_drawings_draw_the_way_the_image_does() {
+        let mut image = Image::new(100, 100);
+        image.set_coordinate_system(Corner::BottomLeft, true);
+        image.set_strict(true);
+        image.set_anchor("corner", 90, 10);
+        let drawn = image.draw_simple_line(0, 0, 45, 10, COLORS[1]).unwrap();
+        assert_eq!(drawn, (7, 7));
+
+        let mut inside = None;
+        image
+            .with_mask(&fade(), MaskFit::Error, |image| {
+                assert!(image.draw_simple_line(50, 50, 0, 60, COLORS[1]).is_err());
+                assert_eq!(image.anchor("corner"), Some((90, 10)));
+                image.draw_simple_line(90, 10, 0, 80, Color::white())?;
+                inside = image.last_element();
+                Ok(())
+            })
+            .unwrap();
+
+        // The upright line is on the right, which the mask leaves visible.
+        let pixmap = image.render().unwrap();
+        let pixel = pixmap.pixel(90, 50).unwrap();
+        assert!(pixel.red() > 100, "{pixel:?}");
+
+        // Ids from inside don't come back as the ids of later elements.
+        let inside = inside.unwrap();
+        assert!(!image.element_ids().contains(&inside));
+        image.draw_simple_line(10, 10, 90, 10, COLORS[1]).unwrap();
+        assert!(!image.element_ids().contains(&inside));
+        assert_eq!(image.element_ids().len(), 3);
+    }
+}
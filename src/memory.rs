@@ -0,0 +1,205 @@
+use crate::{ElementId, Image};
+use resvg::{tiny_skia, usvg};
+use std::mem::{size_of, size_of_val};
+
+/// The memory a node of the tree takes, besides what its data points to:
+/// its data, plus 5 links to the nodes around it, 2 reference counts and a
+/// borrow flag.
+const NODE_BYTES: usize = size_of::<usvg::NodeKind>() + size_of::<[usize; 8]>();
+
+impl Image {
+    /// Roughly how many bytes of memory the image takes, including
+    /// everything drawn on it.
+    ///
+    /// This counts the shapes that make up the drawing (and any pictures in
+    /// it), which is what grows as more is drawn, but not smaller things
+    /// like clips and settings, so the real figure is a little higher. It
+    /// takes the same time however much is drawn.
+    ///
+    /// ```rs
+    /// image.draw_simple_line(10, 10, 90, 50, COLORS[1])?;
+    /// println!("About {} bytes", image.estimated_memory_bytes());
+    /// ```
+    pub fn estimated_memory_bytes(&self) -> usize {
+        size_of::<Image>() + node_bytes(&self.background()) + self.element_bytes
+    }
+
+    /// Limit how much memory the drawing can take, as measured by
+    /// `estimated_memory_bytes`, or remove the limit with `None`. There is no
+    /// limit by default.
+    ///
+    /// Once drawing something would take the image over the limit, drawing
+    /// it returns an error instead, and nothing is drawn. This keeps a
+    /// program that draws far more than expected (like one stuck in a loop)
+    /// from running out of memory. Things drawn before the limit was set are
+    /// kept, even if they are already over it.
+    ///
+    /// ```rs
+    /// // Stop at about 10 MB.
+    /// image.set_memory_limit(Some(10_000_000));
+    /// loop {
+    ///     if let Err(e) = image.draw_simple_line(10, 10, 90, 50, COLORS[1]) {
+    ///         println!("{e}");
+    ///         break;
+    ///     }
+    /// }
+    /// ```
+    pub fn set_memory_limit(&mut self, limit: Option<usize>) {
+        self.memory_limit = limit;
+        self.mark_changed();
+    }
+
+    /// The limit set by `set_memory_limit`, if there is one.
+    pub fn memory_limit(&self) -> Option<usize> {
+        self.memory_limit
+    }
+
+    /// How many bytes drawing `node` as a new element would add. Returns an
+    /// error if that would take the image over its memory limit.
+    pub(crate) fn check_memory_limit(&self, node: &usvg::Node) -> Result<usize, String> {
+        let bytes = element_bytes(node);
+        if let Some(limit) = self.memory_limit {
+            let total = self.estimated_memory_bytes().saturating_add(bytes);
+            if total > limit {
+                return Err(format!(
+                    "Drawing this would take the image to about {total} bytes, over its memory limit of {limit} bytes"
+                ));
+            }
+        }
+        Ok(bytes)
+    }
+
+    /// Work out the memory taken by the elements again, after they have been
+    /// changed in place.
+    pub(crate) fn recount_memory(&mut self) {
+        self.element_bytes = self
+            .elements
+            .iter()
+            .map(|(_, node)| element_bytes(node))
+            .sum();
+    }
+}
+
+/// The memory taken by an element: its node and everything inside it, and
+/// its place in the list of elements.
+pub(crate) fn element_bytes(node: &usvg::Node) -> usize {
+    size_of::<(ElementId, usvg::Node)>() + node_bytes(node)
+}
+
+/// The memory taken by a node and everything inside it.
+fn node_bytes(node: &usvg::Node) -> usize {
+    node.descendants()
+        .map(|node| {
+            NODE_BYTES
+                + match *node.borrow() {
+                    usvg::NodeKind::Group(ref group) => group.id.len(),
+                    usvg::NodeKind::Path(ref path) => {
+                        let dashes = path
+                            .stroke
+                            .as_ref()
+                            .and_then(|stroke| stroke.dasharray.as_ref())
+                            .map_or(0, |dashes| dashes.len() * size_of::<f32>());
+                        path.id.len()
+                            + size_of::<tiny_skia::Path>()
+                            + size_of_val(path.data.verbs())
+                            + size_of_val(path.data.points())
+                            + dashes
+                    }
+                    usvg::NodeKind::Image(ref image) => {
+                        image.id.len()
+                            + match image.kind {
+                                usvg::ImageKind::JPEG(ref data)
+                                | usvg::ImageKind::PNG(ref data)
+                                | usvg::ImageKind::GIF(ref data) => data.len(),
+                                usvg::ImageKind::SVG(ref tree) => node_bytes(&tree.root),
+                            }
+                    }
+                    usvg::NodeKind::Text(ref text) => {
+                        text.id.len()
+                            + text
+                                .chunks
+                                .iter()
+                                .map(|chunk| chunk.text.len())
+                                .sum::<usize>()
+                    }
+                }
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::svg::SvgOptions;
+    use crate::{Image, COLORS};
+
+    #[test]
+    fn drawing_takes_memory_and_removing_gives_it_back() {
+        let mut image = Image::new(100, 100);
+        let empty = image.estimated_memory_bytes();
+        image.draw_simple_line(10, 10, 90, 50, COLORS[1]).unwrap();
+        let one = image.estimated_memory_bytes();
+        assert!(one > empty);
+        image.draw_simple_line(10, 20, 90, 50, COLORS[1]).unwrap();
+        assert_eq!(image.estimated_memory_bytes() - one, one - empty);
+
+        image.remove_element(image.last_element().unwrap());
+        assert_eq!(image.estimated_memory_bytes(), one);
+    }
+
+    #[test]
+    fn drawing_over_the_limit_is_rejected() {
+        let mut image = Image::new(100, 100);
+        image.draw_simple_line(10, 10, 90, 50, COLORS[1]).unwrap();
+        let line = image.estimated_memory_bytes() - Image::new(100, 100).estimated_memory_bytes();
+
+        // Room for exactly one more line.
+        image.set_memory_limit(Some(image.estimated_memory_bytes() + line));
+        assert_eq!(
+            image.memory_limit(),
+            Some(image.estimated_memory_bytes() + line)
+        );
+        image.draw_simple_line(10, 20, 90, 50, COLORS[1]).unwrap();
+
+        let before = (
+            image.svg_string(&SvgOptions::default()),
+            image.element_ids(),
+            image.estimated_memory_bytes(),
+            image.generation(),
+        );
+        let error = image
+            .draw_simple_line(10, 30, 90, 50, COLORS[1])
+            .unwrap_err();
+        assert!(error.contains("over its memory limit"), "{error}");
+        let after = (
+            image.svg_string(&SvgOptions::default()),
+            image.element_ids(),
+            image.estimated_memory_bytes(),
+            image.generation(),
+        );
+        assert_eq!(after, before, "nothing was drawn");
+
+        image.set_memory_limit(None);
+        image.draw_simple_line(10, 30, 90, 50, COLORS[1]).unwrap();
+    }
+
+    #[test]
+    fn a_limit_keeps_what_was_already_drawn() {
+        let mut image = Image::new(100, 100);
+        for y in 0..10 {
+            image
+                .draw_simple_line(10, y * 10, 90, 50, COLORS[1])
+                .unwrap();
+        }
+        image.set_memory_limit(Some(1));
+        assert_eq!(image.element_ids().len(), 10);
+        assert!(image.draw_simple_line(10, 5, 90, 50, COLORS[1]).is_err());
+        assert!(image
+            .fill_polygon(
+                &[(0, 0), (9, 0), (0, 9)],
+                COLORS[2],
+                crate::FillRule::NonZero
+            )
+            .is_err());
+        assert_eq!(image.element_ids().len(), 10);
+    }
+}
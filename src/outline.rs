@@ -0,0 +1,236 @@
+use crate::elements::skia_stroke;
+use crate::Image;
+use resvg::usvg::NodeExt;
+use resvg::{tiny_skia, usvg};
+use std::rc::Rc;
+
+impl Image {
+    /// A copy of the image with every stroke turned into a filled outline of
+    /// the area it covers, for laser cutters and CNC tools that can only
+    /// follow filled shapes.
+    ///
+    /// The outlines follow each stroke's width, caps, joins and dashes, so
+    /// the copy looks the same as the original. Curves in a stroke are turned
+    /// into straight lines no more than `tolerance` units away from them
+    /// (0.1 is plenty for most uses). Filled shapes are left as they are,
+    /// and a shape that is both filled and stroked becomes its fill plus the
+    /// outline of its stroke, in the order they were painted. Strokes inside
+    /// a mask are left as they are, since they only decide how see-through
+    /// the drawing is.
+    ///
+    /// Returns an error if `tolerance` is not more than 0.
+    ///
+    /// ```rs
+    /// let cuttable = image.with_outlined_strokes(0.1)?;
+    /// cuttable.save_svg("cut.svg")?;
+    /// ```
+    pub fn with_outlined_strokes(&self, tolerance: f32) -> Result<Image, String> {
+        if !(tolerance > 0.0 && tolerance.is_finite()) {
+            return Err(format!(
+                "The tolerance must be more than 0, but it was {tolerance}"
+            ));
+        }
+
+        let mut image = self.clone();
+        for element in image.elements() {
+            let nodes: Vec<_> = element.descendants().collect();
+            for node in nodes {
+                outline_stroke(&node, tolerance);
+            }
+        }
+        image.recount_memory();
+        image.mark_changed();
+        Ok(image)
+    }
+}
+
+/// Replace the stroke of a path node with a filled outline. A path with both
+/// a fill and a stroke becomes a group of the two.
+fn outline_stroke(node: &usvg::Node, tolerance: f32) {
+    let transform = node.abs_transform();
+    let mut kind = node.borrow_mut();
+    let usvg::NodeKind::Path(ref mut path) = *kind else {
+        return;
+    };
+    let Some(stroke) = path.stroke.take() else {
+        return;
+    };
+
+    // tiny-skia flattens curves to within a quarter of a unit, divided by
+    // this scale, in the path's own units.
+    let scale = (transform.sx * transform.sy - transform.kx * transform.ky)
+        .abs()
+        .sqrt();
+    let resolution = scale / (4.0 * tolerance);
+    // Stroking a path doesn't dash it, so that is done first.
+    let skia = skia_stroke(&stroke);
+    let dashed = skia
+        .dash
+        .as_ref()
+        .and_then(|dash| path.data.dash(dash, resolution));
+    let Some(outline) = dashed
+        .as_ref()
+        .unwrap_or(&path.data)
+        .stroke(&skia, resolution)
+    else {
+        // The stroke covers nothing, so there is nothing to outline.
+        return;
+    };
+    let outline = usvg::Path {
+        id: String::new(),
+        transform: tiny_skia::Transform::identity(),
+        fill: Some(usvg::Fill {
+            paint: stroke.paint,
+            opacity: stroke.opacity,
+            rule: usvg::FillRule::NonZero,
+        }),
+        stroke: None,
+        paint_order: usvg::PaintOrder::FillAndStroke,
+        data: Rc::new(outline),
+        ..path.clone()
+    };
+
+    if path.fill.is_none() {
+        *path = usvg::Path {
+            id: std::mem::take(&mut path.id),
+            transform: path.transform,
+            ..outline
+        };
+        return;
+    }
+
+    let fill = usvg::Path {
+        id: String::new(),
+        transform: tiny_skia::Transform::identity(),
+        paint_order: usvg::PaintOrder::FillAndStroke,
+        ..path.clone()
+    };
+    let mut parts = [fill, outline];
+    if path.paint_order == usvg::PaintOrder::StrokeAndFill {
+        parts.reverse();
+    }
+    let group = usvg::Group {
+        id: std::mem::take(&mut path.id),
+        transform: path.transform,
+        ..usvg::Group::default()
+    };
+    *kind = usvg::NodeKind::Group(group);
+    drop(kind);
+    for part in parts {
+        node.append_kind(usvg::NodeKind::Path(part));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::svg::SvgOptions;
+    use crate::{LineCap, LineStyle, StrokeStyle, COLORS};
+
+    /// An image with thick, dashed, capped and curved strokes on it.
+    fn stroked() -> Image {
+        let mut image = Image::new(200, 200);
+        let thick = StrokeStyle {
+            width: 9.0,
+            cap: LineCap::Round,
+            ..StrokeStyle::default()
+        };
+        let dashed = StrokeStyle {
+            width: 3.0,
+            style: LineStyle::Dashed,
+            ..StrokeStyle::default()
+        };
+        image
+            .draw_styled_line(20, 20, 120, 150, COLORS[1], &thick)
+            .unwrap();
+        image
+            .draw_styled_line(20, 180, 45, 200, COLORS[4], &dashed)
+            .unwrap();
+        image
+            .draw_styled_polyline(&[(30, 100), (100, 40), (170, 100)], COLORS[3], &thick)
+            .unwrap();
+        image.draw_ring(100, 110, 60, 50, COLORS[2]).unwrap();
+        image
+            .draw_progress_arc(100, 110, 30, 6, 0.6, COLORS[7])
+            .unwrap();
+        image
+    }
+
+    #[test]
+    fn outlines_look_the_same_as_the_strokes() {
+        let image = stroked();
+        let before = image.render().unwrap();
+        let after = image.with_outlined_strokes(0.1).unwrap().render().unwrap();
+
+        let mut differing = 0;
+        for (a, b) in before.pixels().iter().zip(after.pixels()) {
+            let channels = [
+                (a.red(), b.red()),
+                (a.green(), b.green()),
+                (a.blue(), b.blue()),
+            ];
+            let most = channels.iter().map(|&(a, b)| a.abs_diff(b)).max().unwrap();
+            // Only antialiasing along the edges may differ, and only a little.
+            assert!(most < 64, "{a:?} became {b:?}");
+            if most > 8 {
+                differing += 1;
+            }
+        }
+        assert!(differing < 100, "{differing} pixels differ");
+    }
+
+    #[test]
+    fn no_strokes_are_left() {
+        let image = stroked();
+        let svg = image.svg_string(&SvgOptions::default());
+        assert!(svg.contains("stroke-width"));
+        let outlined = image.with_outlined_strokes(0.1).unwrap();
+        let svg = outlined.svg_string(&SvgOptions::default());
+        assert!(!svg.contains("stroke-width"), "{svg}");
+        assert_eq!(outlined.element_ids(), image.element_ids());
+    }
+
+    #[test]
+    fn fill_and_stroke_become_two_shapes() {
+        let rect = tiny_skia::Rect::from_xywh(20.0, 20.0, 60.0, 60.0).unwrap();
+        let mut path = usvg::Path::new(tiny_skia::PathBuilder::from_rect(rect).into());
+        path.fill = Some(usvg::Fill::from_paint(COLORS[4].paint()));
+        path.stroke = Some(usvg::Stroke {
+            paint: COLORS[7].paint(),
+            width: usvg::NonZeroPositiveF32::new(10.0).unwrap(),
+            ..usvg::Stroke::default()
+        });
+        let mut image = Image::new(100, 100);
+        let id = image.append_node(usvg::NodeKind::Path(path)).unwrap();
+
+        let outlined = image.with_outlined_strokes(0.1).unwrap();
+        let node = outlined.element_node(id).unwrap();
+        let fills: Vec<_> = node
+            .descendants()
+            .filter_map(|node| match *node.borrow() {
+                usvg::NodeKind::Path(ref path) => {
+                    assert!(path.stroke.is_none());
+                    Some(path.fill.as_ref().unwrap().paint.clone())
+                }
+                _ => None,
+            })
+            .collect();
+        // The fill first, then the stroke's outline over it.
+        assert_eq!(fills, [COLORS[4].paint(), COLORS[7].paint()]);
+
+        // The stroke's inner half still covers the edge of the fill.
+        let pixmap = outlined.render().unwrap();
+        let white = pixmap.pixel(22, 50).unwrap();
+        assert_eq!((white.red(), white.green()), (255, 255));
+        let red = pixmap.pixel(50, 50).unwrap();
+        assert_eq!((red.red(), red.green()), (255, 0));
+    }
+
+    #[test]
+    fn tolerance_must_be_positive() {
+        let image = stroked();
+        for tolerance in [0.0, -1.0, f32::NAN, f32::INFINITY] {
+            assert!(image.with_outlined_strokes(tolerance).is_err());
+        }
+    }
+}
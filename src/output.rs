@@ -0,0 +1,754 @@
+use crate::blend::blend_layers_linear;
+use crate::{f32_to_u32, new_pixmap, render_tree, u32_to_f32, BlendSpace, Image, SvgOptions};
+use resvg::tiny_skia;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// A kind of file an image can be saved as.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum OutputFormat {
+    /// An SVG file, as written by `save_svg`.
+    Svg,
+    /// A PNG file, as written by `save_png`.
+    Png,
+}
+
+impl OutputFormat {
+    /// The file extension for this format, without the dot.
+    pub fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Svg => "svg",
+            OutputFormat::Png => "png",
+        }
+    }
+}
+
+/// The files written by `save_all` or `save_formats`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SavedPaths {
+    /// Where the SVG file was written, if one was asked for.
+    pub svg: Option<PathBuf>,
+    /// Where the PNG file was written, if one was asked for.
+    pub png: Option<PathBuf>,
+}
+
+/// What was produced by one of the `_report` save methods, like
+/// `save_png_report`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SaveReport {
+    /// The width of the saved image, in pixels.
+    pub width: u32,
+    /// The height of the saved image, in pixels.
+    pub height: u32,
+    /// The size of the encoded file, in bytes.
+    pub bytes: u64,
+    /// How long it took to render and encode the image. This doesn't include
+    /// writing the file to disk.
+    pub render_time: Duration,
+    /// Anything about the image that the image's save options ask to be
+    /// warned about (see `SaveOptions`), one message each.
+    pub warnings: Vec<String>,
+}
+
+/// Options for how `save_png_with_options` writes a PNG file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PngOptions {
+    /// The resolution to record in the file, in pixels per inch, so that
+    /// printing it (or placing it in a document) gives it the right physical
+    /// size. A 600x300 image at 300 DPI prints 2 inches wide and 1 inch tall.
+    ///
+    /// This doesn't change the pixels themselves. With `None`, no resolution
+    /// is recorded, and programs pick their own (often 72 or 96 DPI).
+    pub dpi: Option<u32>,
+    /// How the pixels are stored in the file.
+    pub color_type: PngColorType,
+}
+
+/// How a PNG file stores its pixels, from [`PngOptions`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum PngColorType {
+    /// Red, green, blue and alpha, 8 bits each. Every image can be saved
+    /// this way.
+    #[default]
+    Rgba8,
+    /// Shades of grey, 8 bits each, from how bright each color looks (using
+    /// the same weights as `ColorMatrix::grayscale`). Any transparency is
+    /// dropped.
+    Gray8,
+    /// A palette of the colors used, with each pixel stored as its number in
+    /// the palette. This makes much smaller files for drawings with few
+    /// colors: with 16 colors or fewer, each pixel takes at most 4 bits.
+    ///
+    /// A PNG palette holds at most 256 colors, so saving an image with more
+    /// returns an error. The edges of lines are smoothed with in-between
+    /// colors, so even a drawing in a few colors can have more than it seems.
+    Indexed,
+}
+
+/// How many centimetres are in an inch, as PNG files record resolution in
+/// pixels per metre.
+const CM_PER_INCH: f64 = 2.54;
+
+/// Encode a rendered image as a PNG file.
+pub(crate) fn encode_png(
+    pixmap: &tiny_skia::Pixmap,
+    options: &PngOptions,
+) -> Result<Vec<u8>, String> {
+    let pixels: Vec<[u8; 4]> = pixmap
+        .pixels()
+        .iter()
+        .map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect();
+
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, pixmap.width(), pixmap.height());
+    let data = match options.color_type {
+        PngColorType::Rgba8 => {
+            encoder.set_color(png::ColorType::Rgba);
+            encoder.set_depth(png::BitDepth::Eight);
+            pixels.concat()
+        }
+        PngColorType::Gray8 => {
+            encoder.set_color(png::ColorType::Grayscale);
+            encoder.set_depth(png::BitDepth::Eight);
+            pixels
+                .iter()
+                .map(|&[red, green, blue, _]| {
+                    let grey = 0.2126 * f32::from(red)
+                        + 0.7152 * f32::from(green)
+                        + 0.0722 * f32::from(blue);
+                    grey.round().clamp(0.0, 255.0) as u8
+                })
+                .collect()
+        }
+        PngColorType::Indexed => index_pixels(&mut encoder, &pixels, pixmap.width())?,
+    };
+    if let Some(dpi) = options.dpi {
+        if dpi == 0 {
+            return Err("A PNG's resolution must be at least 1 DPI".into());
+        }
+        let per_metre = (f64::from(dpi) * 100.0 / CM_PER_INCH).round() as u32;
+        encoder.set_pixel_dims(Some(png::PixelDimensions {
+            xppu: per_metre,
+            yppu: per_metre,
+            unit: png::Unit::Meter,
+        }));
+    }
+
+    let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+    writer.write_image_data(&data).map_err(|e| e.to_string())?;
+    writer.finish().map_err(|e| e.to_string())?;
+    Ok(png)
+}
+
+/// Set `encoder` up to write `pixels` (in rows `width` long) with a palette,
+/// and return the rows of palette numbers to write. The palette numbers are
+/// packed into as few bits as the palette allows.
+fn index_pixels(
+    encoder: &mut png::Encoder<'_, &mut Vec<u8>>,
+    pixels: &[[u8; 4]],
+    width: u32,
+) -> Result<Vec<u8>, String> {
+    let mut palette: Vec<[u8; 4]> = Vec::new();
+    let mut indices: HashMap<[u8; 4], u8> = HashMap::new();
+    let mut numbers = Vec::with_capacity(pixels.len());
+    for &pixel in pixels {
+        let number = match indices.get(&pixel) {
+            Some(&number) => number,
+            None => {
+                let number = u8::try_from(palette.len()).map_err(|_| {
+                    "The image has more than 256 colors, so it can't be saved with a palette"
+                        .to_string()
+                })?;
+                palette.push(pixel);
+                indices.insert(pixel, number);
+                number
+            }
+        };
+        numbers.push(number);
+    }
+
+    let (depth, bits) = match palette.len() {
+        0..=2 => (png::BitDepth::One, 1),
+        3..=4 => (png::BitDepth::Two, 2),
+        5..=16 => (png::BitDepth::Four, 4),
+        _ => (png::BitDepth::Eight, 8),
+    };
+    encoder.set_color(png::ColorType::Indexed);
+    encoder.set_depth(depth);
+    encoder.set_palette(
+        palette
+            .iter()
+            .flat_map(|&[red, green, blue, _]| [red, green, blue])
+            .collect::<Vec<u8>>(),
+    );
+    if palette.iter().any(|&[.., alpha]| alpha < u8::MAX) {
+        encoder.set_trns(
+            palette
+                .iter()
+                .map(|&[.., alpha]| alpha)
+                .collect::<Vec<u8>>(),
+        );
+    }
+
+    // Each row starts on a new byte, with the first pixel in the highest bits.
+    let per_byte = 8 / bits;
+    Ok(numbers
+        .chunks(width as usize)
+        .flat_map(|row| {
+            row.chunks(per_byte).map(|byte_numbers| {
+                byte_numbers
+                    .iter()
+                    .enumerate()
+                    .fold(0, |byte, (i, &number)| {
+                        byte | number << (8 - bits * (i + 1))
+                    })
+            })
+        })
+        .collect())
+}
+
+impl Image {
+    /// Save the image to a PNG file, choosing how it is written.
+    ///
+    /// ```rs
+    /// // 3000x3000 pixels, to print 10 inches square.
+    /// let image = Image::new(3000, 3000);
+    /// let options = PngOptions {
+    ///     dpi: Some(300),
+    ///     ..PngOptions::default()
+    /// };
+    /// image.save_png_with_options("print.png", &options)?;
+    ///
+    /// // A small file, for a drawing in a few colors.
+    /// let options = PngOptions {
+    ///     color_type: PngColorType::Indexed,
+    ///     ..PngOptions::default()
+    /// };
+    /// image.save_png_with_options("small.png", &options)?;
+    /// ```
+    pub fn save_png_with_options<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: &PngOptions,
+    ) -> Result<(), String> {
+        self.prepare_to_save()?;
+        let png = encode_png(&self.render()?, options)?;
+        std::fs::write(path, png).map_err(|e| e.to_string())
+    }
+
+    /// Save the image as several PNG files at different scales, such as 1x,
+    /// 2x and 3x versions of an icon, and return the paths written.
+    ///
+    /// Each file is named after `base_path`, with `@` and the scale added
+    /// before the extension: with a `base_path` of `icon.png` and scales of
+    /// 1, 2 and 1.5, the files are `icon@1x.png`, `icon@2x.png` and
+    /// `icon@1.5x.png`. A scale of 2 makes an image twice as wide and twice
+    /// as tall, rounded to whole pixels.
+    ///
+    /// The drawing is prepared for saving once, and then drawn at each scale
+    /// from its shapes, rather than by stretching the pixels of one PNG, so
+    /// every size is as sharp as it can be.
+    ///
+    /// Returns an error if a scale isn't more than 0, or would make the image
+    /// less than 1 pixel across. Files for earlier scales may already have
+    /// been written when there is an error.
+    ///
+    /// ```rs
+    /// let image = Image::new(100, 100);
+    /// let paths = image.save_png_multi("icon.png", &[1.0, 2.0])?;
+    /// // icon@1x.png is 100x100, and icon@2x.png is 200x200.
+    /// assert_eq!(paths.len(), 2);
+    /// ```
+    pub fn save_png_multi<P: AsRef<Path>>(
+        &self,
+        base_path: P,
+        scales: &[f32],
+    ) -> Result<Vec<PathBuf>, String> {
+        self.prepare_to_save()?;
+        let base_path = base_path.as_ref();
+        let stem = base_path
+            .file_stem()
+            .ok_or_else(|| format!("{} isn't a file name", base_path.display()))?
+            .to_string_lossy();
+        let extension = base_path
+            .extension()
+            .map(|extension| format!(".{}", extension.to_string_lossy()))
+            .unwrap_or_default();
+
+        // Any trees after the first are blended onto it in linear light.
+        let trees = match self.blend_space {
+            BlendSpace::Srgb => vec![self.export_tree()],
+            BlendSpace::Linear => self.linear_layers(),
+        };
+        let (first, rest) = trees.split_first().ok_or("There is nothing to render")?;
+        let first = resvg::Tree::from_usvg(first);
+        let rest: Vec<_> = rest.iter().map(resvg::Tree::from_usvg).collect();
+
+        let mut paths = Vec::new();
+        for &scale in scales {
+            if !(scale > 0.0 && scale.is_finite()) {
+                return Err(format!("A scale must be more than 0, but it was {scale}"));
+            }
+            let size = |length: u32| {
+                let scaled = (u32_to_f32(length) * scale).round();
+                (scaled >= 1.0 && scaled < u32::MAX as f32).then(|| f32_to_u32(scaled))
+            };
+            let (Some(width), Some(height)) = (size(self.width), size(self.height)) else {
+                return Err(format!(
+                    "At a scale of {scale}, the image would be less than 1 pixel across, or too big to save"
+                ));
+            };
+
+            let mut pixmap = new_pixmap(width, height)?;
+            let transform = tiny_skia::Transform::from_scale(scale, scale);
+            first.render(transform, &mut pixmap.as_mut());
+            blend_layers_linear(&mut pixmap, transform, &rest);
+
+            let path = base_path.with_file_name(format!("{stem}@{scale}x{extension}"));
+            pixmap.save_png(&path).map_err(|e| e.to_string())?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+
+    /// The image encoded as a PNG file, as `save_png` would write it.
+    ///
+    /// ```rs
+    /// let png = image.png_bytes()?;
+    /// assert!(png.starts_with(b"\x89PNG"));
+    /// ```
+    pub fn png_bytes(&self) -> Result<Vec<u8>, String> {
+        self.prepare_to_save()?;
+        self.render()?.encode_png().map_err(|e| e.to_string())
+    }
+
+    /// Like `png_bytes`, but also reporting the size of the image, the
+    /// number of bytes and how long encoding took.
+    ///
+    /// ```rs
+    /// let (png, report) = image.png_bytes_report()?;
+    /// assert_eq!(report.bytes, png.len() as u64);
+    /// ```
+    pub fn png_bytes_report(&self) -> Result<(Vec<u8>, SaveReport), String> {
+        let start = Instant::now();
+        let png = self.png_bytes()?;
+        let report = self.report(png.len(), start.elapsed());
+        Ok((png, report))
+    }
+
+    /// Like `save_png`, but returning a [`SaveReport`] of what was saved, for
+    /// logging or showing to the user.
+    ///
+    /// ```rs
+    /// let report = image.save_png_report("image.png")?;
+    /// println!("Saved {}x{} ({} bytes) in {:?}", report.width, report.height, report.bytes, report.render_time);
+    /// ```
+    pub fn save_png_report<P: AsRef<Path>>(&self, path: P) -> Result<SaveReport, String> {
+        let (png, report) = self.png_bytes_report()?;
+        std::fs::write(path, png).map_err(|e| e.to_string())?;
+        Ok(report)
+    }
+
+    /// Like `save_svg`, but returning a [`SaveReport`] of what was saved. The
+    /// render time is the time taken to write out the SVG.
+    pub fn save_svg_report<P: AsRef<Path>>(&self, path: P) -> Result<SaveReport, String> {
+        self.prepare_to_save()?;
+        let start = Instant::now();
+        let svg = self.svg_string(&SvgOptions::default());
+        let report = self.report(svg.len(), start.elapsed());
+        std::fs::write(path, svg).map_err(|e| e.to_string())?;
+        Ok(report)
+    }
+
+    fn report(&self, bytes: usize, render_time: Duration) -> SaveReport {
+        SaveReport {
+            width: self.width,
+            height: self.height,
+            bytes: bytes as u64,
+            render_time,
+            warnings: self.save_warnings(),
+        }
+    }
+
+    /// Save each layer of the image as its own PNG in the directory `dir`,
+    /// for compositing in another program, and return the paths written.
+    ///
+    /// The first layer, `layer0.png`, is the background. Each element then
+    /// gets its own layer, `layer1.png`, `layer2.png` and so on, in the order
+    /// they were drawn, with everything else transparent. If a watermark is
+    /// set, it is the last layer. Drawing every layer on top of the one before
+    /// gives the same picture as `save_png`.
+    ///
+    /// `dir` is created if it doesn't exist yet. Existing files with the same
+    /// names are overwritten.
+    ///
+    /// ```rs
+    /// image.draw_simple_line(10, 10, 90, 50, COLORS[1])?;
+    /// image.draw_simple_line(10, 20, 90, 50, COLORS[4])?;
+    /// let paths = image.save_layers_png(Path::new("layers"))?;
+    /// assert_eq!(paths.len(), 3);
+    /// ```
+    pub fn save_layers_png(&self, dir: &Path) -> Result<Vec<PathBuf>, String> {
+        self.prepare_to_save()?;
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+
+        let mut paths = Vec::new();
+        for (index, layer) in self.linear_layers().iter().enumerate() {
+            let path = dir.join(format!("layer{index}.png"));
+            render_tree(layer)?
+                .save_png(&path)
+                .map_err(|e| format!("Could not save {}: {e}", path.display()))?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+
+    /// Save the image as both `<stem>.svg` and `<stem>.png`.
+    ///
+    /// This is the same as `save_formats` with both formats; see there for
+    /// how the stem and errors are handled.
+    ///
+    /// ```rs
+    /// let saved = image.save_all("drawing")?;
+    /// assert_eq!(saved.svg, Some(PathBuf::from("drawing.svg")));
+    /// assert_eq!(saved.png, Some(PathBuf::from("drawing.png")));
+    /// ```
+    pub fn save_all<P: AsRef<Path>>(&self, stem: P) -> Result<SavedPaths, String> {
+        self.save_formats(stem, &[OutputFormat::Svg, OutputFormat::Png])
+    }
+
+    /// Save the image in each of `formats`, named `<stem>.<extension>`, and
+    /// return the paths that were written.
+    ///
+    /// The stem must not already end in `.svg` or `.png` (in any case), since
+    /// that almost always means the extension was added by mistake; this is
+    /// an error, rather than silently writing `drawing.svg.png`. Any other
+    /// dots in the stem are kept, so `drawing.v2` becomes `drawing.v2.svg`.
+    ///
+    /// Files are written in the order given, and saving stops at the first
+    /// one that fails. The error says which files had already been written,
+    /// so you know what is on disk.
+    ///
+    /// ```rs
+    /// let saved = image.save_formats("drawing", &[OutputFormat::Png])?;
+    /// assert_eq!(saved.svg, None);
+    /// ```
+    pub fn save_formats<P: AsRef<Path>>(
+        &self,
+        stem: P,
+        formats: &[OutputFormat],
+    ) -> Result<SavedPaths, String> {
+        let stem = stem.as_ref();
+        if let Some(extension) = stem.extension().and_then(|extension| extension.to_str()) {
+            if ["svg", "png"].contains(&extension.to_ascii_lowercase().as_str()) {
+                return Err(format!(
+                    "The stem {} already has an extension: leave it off, and it will be added for each format",
+                    stem.display()
+                ));
+            }
+        }
+
+        let mut saved = SavedPaths::default();
+        let mut written: Vec<PathBuf> = Vec::new();
+        for &format in formats {
+            let mut path = stem.as_os_str().to_owned();
+            path.push(".");
+            path.push(format.extension());
+            let path = PathBuf::from(path);
+
+            let result = match format {
+                OutputFormat::Svg => self.save_svg(&path),
+                OutputFormat::Png => self.save_png(&path),
+            };
+            if let Err(error) = result {
+                let written = match written.as_slice() {
+                    [] => "nothing was saved".to_string(),
+                    paths => {
+                        let paths: Vec<_> = paths
+                            .iter()
+                            .map(|path| path.display().to_string())
+                            .collect();
+                        format!("{} was saved", paths.join(" and "))
+                    }
+                };
+                return Err(format!(
+                    "Could not save {}: {error} ({written})",
+                    path.display()
+                ));
+            }
+
+            written.push(path.clone());
+            match format {
+                OutputFormat::Svg => saved.svg = Some(path),
+                OutputFormat::Png => saved.png = Some(path),
+            }
+        }
+
+        Ok(saved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encode_png, PngColorType, PngOptions};
+    use crate::{Image, OutputFormat, Rgba, SavedPaths, StrokeStyle, COLORS};
+    use resvg::tiny_skia;
+
+    /// A `width` pixel wide pixmap with `colors` in it, row by row.
+    fn pixmap_of(width: u32, colors: &[[u8; 4]]) -> tiny_skia::Pixmap {
+        let height = colors.len() as u32 / width;
+        let mut pixmap = tiny_skia::Pixmap::new(width, height).unwrap();
+        for (pixel, &[red, green, blue, alpha]) in pixmap.pixels_mut().iter_mut().zip(colors) {
+            *pixel = tiny_skia::ColorU8::from_rgba(red, green, blue, alpha).premultiply();
+        }
+        pixmap
+    }
+
+    /// Encode `pixmap` with `color_type`, and read it back as the PNG's
+    /// header and its pixels, expanded to 8 bits per channel.
+    fn round_trip(
+        pixmap: &tiny_skia::Pixmap,
+        color_type: PngColorType,
+    ) -> (png::ColorType, png::BitDepth, Vec<u8>) {
+        let options = PngOptions {
+            color_type,
+            ..PngOptions::default()
+        };
+        let bytes = encode_png(pixmap, &options).unwrap();
+        let mut decoder = png::Decoder::new(bytes.as_slice());
+        decoder.set_transformations(png::Transformations::EXPAND);
+        let mut reader = decoder.read_info().unwrap();
+        let (stored, depth) = (reader.info().color_type, reader.info().bit_depth);
+        let mut data = vec![0; reader.output_buffer_size()];
+        let frame = reader.next_frame(&mut data).unwrap();
+        data.truncate(frame.buffer_size());
+        (stored, depth, data)
+    }
+
+    const COLORS_USED: [[u8; 4]; 4] = [
+        [0, 0, 0, 255],
+        [255, 255, 255, 255],
+        [255, 0, 0, 255],
+        [0, 0, 255, 128],
+    ];
+
+    #[test]
+    fn rgba8_keeps_every_channel() {
+        let pixmap = pixmap_of(2, &COLORS_USED);
+        let (stored, depth, data) = round_trip(&pixmap, PngColorType::Rgba8);
+        assert_eq!(
+            (stored, depth),
+            (png::ColorType::Rgba, png::BitDepth::Eight)
+        );
+        assert_eq!(data, COLORS_USED.concat());
+    }
+
+    #[test]
+    fn gray8_weighs_each_color_by_how_bright_it_looks() {
+        let pixmap = pixmap_of(2, &COLORS_USED);
+        let (stored, depth, data) = round_trip(&pixmap, PngColorType::Gray8);
+        assert_eq!(
+            (stored, depth),
+            (png::ColorType::Grayscale, png::BitDepth::Eight)
+        );
+        // Black, white, 0.2126 * 255 and 0.0722 * 255, without the alpha.
+        assert_eq!(data, [0, 255, 54, 18]);
+    }
+
+    #[test]
+    fn indexed_uses_as_few_bits_as_it_can() {
+        let pixmap = pixmap_of(2, &COLORS_USED);
+        let (stored, depth, data) = round_trip(&pixmap, PngColorType::Indexed);
+        assert_eq!(
+            (stored, depth),
+            (png::ColorType::Indexed, png::BitDepth::Two)
+        );
+        // Expanded back, the palette and its transparency give every pixel.
+        assert_eq!(data, COLORS_USED.concat());
+
+        // An odd width, so each row ends part way through a byte.
+        let two_colors: Vec<_> = (0..15).map(|i| COLORS_USED[i % 3 / 2]).collect();
+        let pixmap = pixmap_of(5, &two_colors);
+        let (_, depth, data) = round_trip(&pixmap, PngColorType::Indexed);
+        assert_eq!(depth, png::BitDepth::One);
+        let opaque: Vec<_> = two_colors
+            .iter()
+            .flat_map(|&[r, g, b, _]| [r, g, b])
+            .collect();
+        assert_eq!(data, opaque, "with no transparency, there's no alpha");
+
+        let many: Vec<_> = (0..16).map(|i| [i * 16, 0, 0, 255]).collect();
+        let (_, depth, _) = round_trip(&pixmap_of(4, &many), PngColorType::Indexed);
+        assert_eq!(depth, png::BitDepth::Four);
+        let many: Vec<_> = (0..=255).map(|i| [i, 0, 0, 255]).collect();
+        let (_, depth, data) = round_trip(&pixmap_of(16, &many), PngColorType::Indexed);
+        assert_eq!(depth, png::BitDepth::Eight);
+        assert_eq!(
+            data,
+            many.iter()
+                .flat_map(|&[r, g, b, _]| [r, g, b])
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn indexed_refuses_more_than_256_colors() {
+        let colors: Vec<_> = (0..257)
+            .map(|i| [(i % 256) as u8, (i / 256) as u8, 0, 255])
+            .collect();
+        let options = PngOptions {
+            color_type: PngColorType::Indexed,
+            ..PngOptions::default()
+        };
+        let error = encode_png(&pixmap_of(257, &colors), &options).unwrap_err();
+        assert!(error.contains("more than 256 colors"), "{error}");
+    }
+
+    #[test]
+    fn reports_give_the_size_of_the_file_written() {
+        let mut image = Image::new(120, 80);
+        image.draw_simple_line(10, 10, 135, 80, COLORS[1]).unwrap();
+
+        let dir = std::env::temp_dir().join("unsvg-report-test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let png_report = image.save_png_report(dir.join("image.png")).unwrap();
+        let svg_report = image.save_svg_report(dir.join("image.svg")).unwrap();
+        let png = std::fs::read(dir.join("image.png")).unwrap();
+        let svg_size = std::fs::metadata(dir.join("image.svg")).unwrap().len();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!((png_report.width, png_report.height), (120, 80));
+        assert_eq!(png_report.bytes, png.len() as u64);
+        assert_eq!(png, image.png_bytes().unwrap());
+        assert_eq!((svg_report.width, svg_report.height), (120, 80));
+        assert_eq!(svg_report.bytes, svg_size);
+
+        let (bytes, report) = image.png_bytes_report().unwrap();
+        assert_eq!(report.bytes, bytes.len() as u64);
+    }
+
+    #[test]
+    fn each_layer_holds_one_element_and_together_they_make_the_image() {
+        let mut image = Image::new(40, 40);
+        let style = StrokeStyle {
+            width: 10.0,
+            ..StrokeStyle::default()
+        };
+        image
+            .draw_styled_line(10, 0, 180, 40, COLORS[1], &style)
+            .unwrap();
+        image
+            .draw_styled_line(0, 25, 90, 40, Rgba::from_straight(255, 0, 0, 128), &style)
+            .unwrap();
+
+        let dir = std::env::temp_dir().join("unsvg-layers-test");
+        let paths = image.save_layers_png(&dir).unwrap();
+        assert_eq!(
+            paths,
+            ["layer0.png", "layer1.png", "layer2.png"].map(|name| dir.join(name))
+        );
+        let layers: Vec<_> = paths
+            .iter()
+            .map(|path| tiny_skia::Pixmap::load_png(path).unwrap())
+            .collect();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let rgba = |layer: &tiny_skia::Pixmap, x, y| {
+            let pixel = layer.pixel(x, y).unwrap();
+            [pixel.red(), pixel.green(), pixel.blue(), pixel.alpha()]
+        };
+        assert_eq!(rgba(&layers[0], 10, 10), [0, 0, 0, 255]);
+        assert_eq!(rgba(&layers[1], 10, 10), [0, 0, 255, 255]);
+        assert_eq!(rgba(&layers[1], 30, 10), [0, 0, 0, 0]);
+        assert_eq!(rgba(&layers[2], 30, 25), [128, 0, 0, 128]);
+        assert_eq!(rgba(&layers[2], 30, 10), [0, 0, 0, 0]);
+
+        let mut composed = layers[0].clone();
+        for layer in &layers[1..] {
+            composed.draw_pixmap(
+                0,
+                0,
+                layer.as_ref(),
+                &tiny_skia::PixmapPaint::default(),
+                tiny_skia::Transform::identity(),
+                None,
+            );
+        }
+        let saved = image.render().unwrap();
+        for (a, b) in composed.pixels().iter().zip(saved.pixels()) {
+            assert!(
+                a.red().abs_diff(b.red()) <= 1
+                    && a.green().abs_diff(b.green()) <= 1
+                    && a.blue().abs_diff(b.blue()) <= 1,
+                "{a:?} {b:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn save_all_writes_each_format_next_to_the_stem() {
+        let mut image = Image::new(100, 100);
+        image.draw_simple_line(10, 10, 135, 80, COLORS[1]).unwrap();
+
+        let dir = std::env::temp_dir().join("unsvg-save-all-test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let saved = image.save_all(dir.join("drawing.v2")).unwrap();
+        assert_eq!(
+            saved,
+            SavedPaths {
+                svg: Some(dir.join("drawing.v2.svg")),
+                png: Some(dir.join("drawing.v2.png")),
+            }
+        );
+        image.save_svg(dir.join("expected.svg")).unwrap();
+        image.save_png(dir.join("expected.png")).unwrap();
+        let read = |name: &str| std::fs::read(dir.join(name)).unwrap();
+        assert_eq!(read("drawing.v2.svg"), read("expected.svg"));
+        assert_eq!(read("drawing.v2.png"), read("expected.png"));
+
+        assert!(image.save_all(dir.join("drawing.SVG")).is_err());
+        assert!(!dir.join("drawing.SVG.svg").exists());
+
+        // A directory in the way of the PNG stops saving after the SVG.
+        std::fs::create_dir_all(dir.join("blocked.png")).unwrap();
+        let error = image
+            .save_formats(dir.join("blocked"), &[OutputFormat::Svg, OutputFormat::Png])
+            .unwrap_err();
+        let blocked_svg = dir.join("blocked.svg");
+        let svg_written = blocked_svg.exists();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(svg_written);
+        assert!(
+            error.ends_with(&format!("({} was saved)", blocked_svg.display())),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn each_scale_is_saved_at_its_own_size() {
+        let mut image = Image::new(100, 100);
+        image.draw_simple_line(10, 10, 135, 80, COLORS[1]).unwrap();
+
+        let dir = std::env::temp_dir().join("unsvg-multi-test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let paths = image
+            .save_png_multi(dir.join("icon.png"), &[1.0, 2.0])
+            .unwrap();
+        assert_eq!(paths, [dir.join("icon@1x.png"), dir.join("icon@2x.png")]);
+
+        let sizes: Vec<_> = paths
+            .iter()
+            .map(|path| {
+                let pixmap = tiny_skia::Pixmap::load_png(path).unwrap();
+                (pixmap.width(), pixmap.height())
+            })
+            .collect();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(sizes, [(100, 100), (200, 200)]);
+    }
+}
@@ -0,0 +1,557 @@
+use crate::{Color, Image};
+use resvg::{tiny_skia, usvg};
+
+/// The squared distance between two colors, treating red, green and blue as
+/// coordinates in space.
+pub(crate) fn distance_squared(a: Color, b: Color) -> u32 {
+    let channel = |a: u8, b: u8| u32::from(a.abs_diff(b)).pow(2);
+    channel(a.red, b.red) + channel(a.green, b.green) + channel(a.blue, b.blue)
+}
+
+/// Find the color in `palette` closest to `color`.
+///
+/// Colors are compared by their straight-line distance in RGB space. If two
+/// palette colors are equally close, the one that comes first in the palette
+/// is chosen. Returns `None` if the palette is empty.
+///
+/// ```rs
+/// let nearest = nearest_color(Color::new_rgb(250, 10, 10), &COLORS);
+/// assert_eq!(nearest, Some(COLORS[4]));
+/// ```
+pub fn nearest_color(color: Color, palette: &[Color]) -> Option<Color> {
+    nearest_index(color, palette).map(|index| palette[index])
+}
+
+/// The position in `palette` of the color closest to `color`, as in
+/// [`nearest_color`].
+fn nearest_index(color: Color, palette: &[Color]) -> Option<usize> {
+    (0..palette.len()).min_by_key(|&index| distance_squared(color, palette[index]))
+}
+
+/// Build one palette of at most `max_colors` colors for several images, so
+/// they can all be saved as indexed images (like the frames of a GIF)
+/// sharing it.
+///
+/// The palette holds each image's background color, followed by the colors
+/// of the fills and strokes drawn on them, each color once. If there are
+/// more than `max_colors`, the backgrounds are kept first (as they cover
+/// the most of each image), then the colors used by the most fills and
+/// strokes across all the images; colors used equally often keep the order
+/// they first appear in. Anything drawn in a color that is left out is
+/// shown in the nearest color that is kept, by `Image::palette_indices`.
+///
+/// Only solid colors are counted: gradients and pictures add no colors.
+///
+/// ```rs
+/// let palette = build_shared_palette(&frames, 256);
+/// for frame in &frames {
+///     let indices = frame.palette_indices(&palette)?;
+///     // ...write `indices` and `palette` as a GIF frame.
+/// }
+/// ```
+pub fn build_shared_palette(images: &[Image], max_colors: usize) -> Vec<Color> {
+    let mut backgrounds: Vec<Color> = Vec::new();
+    for image in images {
+        let background = match *image.background().borrow() {
+            usvg::NodeKind::Path(ref path) => {
+                path.fill.as_ref().and_then(|fill| match fill.paint {
+                    usvg::Paint::Color(color) => Some(Color::from_usvg(color)),
+                    _ => None,
+                })
+            }
+            _ => None,
+        };
+        if let Some(background) = background.filter(|color| !backgrounds.contains(color)) {
+            backgrounds.push(background);
+        }
+    }
+
+    // Each drawn color, with how many fills and strokes use it.
+    let mut drawn: Vec<(Color, usize)> = Vec::new();
+    for image in images {
+        for element in image.elements() {
+            for node in element.descendants() {
+                let usvg::NodeKind::Path(ref path) = *node.borrow() else {
+                    continue;
+                };
+                let fills = path.fill.iter().map(|fill| &fill.paint);
+                for paint in fills.chain(path.stroke.iter().map(|stroke| &stroke.paint)) {
+                    let usvg::Paint::Color(color) = *paint else {
+                        continue;
+                    };
+                    let color = Color::from_usvg(color);
+                    match drawn.iter_mut().find(|(seen, _)| *seen == color) {
+                        Some((_, uses)) => *uses += 1,
+                        None => drawn.push((color, 1)),
+                    }
+                }
+            }
+        }
+    }
+    drawn.retain(|(color, _)| !backgrounds.contains(color));
+    // A stable sort, so ties keep the order they first appear in.
+    drawn.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+    backgrounds
+        .into_iter()
+        .chain(drawn.into_iter().map(|(color, _)| color))
+        .take(max_colors)
+        .collect()
+}
+
+/// An endless iterator over the colors of a palette, starting again from the
+/// first color after the last, for giving each part of a drawing the next
+/// color in turn.
+///
+/// `ColorCycle::excluding` leaves out colors too close to the background,
+/// so nothing is drawn in a color that can't be seen. Colors are compared
+/// the same way as in [`nearest_color`].
+///
+/// ```rust
+/// use unsvg::{Color, ColorCycle, Image, COLORS};
+///
+/// fn main() -> Result<(), String> {
+///     let mut image = Image::new(200, 200);
+///     // The background is black, so leave black out of the cycle.
+///     let mut colors = ColorCycle::excluding(&COLORS, Color::black(), 1.0)?;
+///     for spoke in 0..32 {
+///         let color = colors.next().unwrap();
+///         image.draw_simple_line(100, 100, spoke * 360 / 32, 90, color)?;
+///     }
+///
+///     let used = image.stats().colors;
+///     assert_eq!(used.len(), 15);
+///     assert!(!used.contains(&Color::black()));
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ColorCycle {
+    colors: Vec<Color>,
+    next: usize,
+}
+
+impl ColorCycle {
+    /// Cycle through every color in `palette`, in order.
+    ///
+    /// Returns an error if the palette is empty.
+    pub fn new(palette: &[Color]) -> Result<ColorCycle, String> {
+        if palette.is_empty() {
+            return Err("The palette must contain at least one color".into());
+        }
+        Ok(ColorCycle {
+            colors: palette.to_vec(),
+            next: 0,
+        })
+    }
+
+    /// Cycle through the colors in `palette`, in order, skipping any that
+    /// are less than `min_distance` from `background`. The distance is the
+    /// straight-line distance in RGB space, with each channel from 0 to 255,
+    /// so black and white are about 441.7 apart.
+    ///
+    /// Returns an error if `min_distance` isn't a number, or if no colors are
+    /// left.
+    ///
+    /// ```rs
+    /// let colors = ColorCycle::excluding(&COLORS, Color::white(), 100.0)?;
+    /// ```
+    pub fn excluding(
+        palette: &[Color],
+        background: Color,
+        min_distance: f32,
+    ) -> Result<ColorCycle, String> {
+        if min_distance.is_nan() {
+            return Err("The minimum distance must be a number".into());
+        }
+        let colors: Vec<Color> = palette
+            .iter()
+            .copied()
+            .filter(|&color| (distance_squared(color, background) as f32).sqrt() >= min_distance)
+            .collect();
+        if colors.is_empty() {
+            return Err(format!(
+                "Every color in the palette is closer than {min_distance} to the background"
+            ));
+        }
+        Ok(ColorCycle { colors, next: 0 })
+    }
+
+    /// The color `next` will return, without moving on.
+    pub fn peek(&self) -> Color {
+        self.colors[self.next]
+    }
+
+    /// Go back to the first color.
+    pub fn reset(&mut self) {
+        self.next = 0;
+    }
+}
+
+impl Iterator for ColorCycle {
+    type Item = Color;
+
+    /// The next color, which is always `Some`, since the cycle never ends.
+    fn next(&mut self) -> Option<Color> {
+        let color = self.peek();
+        self.next = (self.next + 1) % self.colors.len();
+        Some(color)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::MAX, None)
+    }
+}
+
+/// Replace the color of each pixel with the nearest palette color, keeping
+/// its alpha.
+fn quantize_pixmap(pixmap: &mut tiny_skia::Pixmap, palette: &[Color]) -> Result<(), String> {
+    if palette.is_empty() {
+        return Err("The palette must contain at least one color".into());
+    }
+
+    for pixel in pixmap.pixels_mut() {
+        let straight = pixel.demultiply();
+        let color = Color::new_rgb(straight.red(), straight.green(), straight.blue());
+        let nearest = nearest_color(color, palette).expect("the palette is not empty");
+
+        *pixel = tiny_skia::ColorU8::from_rgba(
+            nearest.red,
+            nearest.green,
+            nearest.blue,
+            straight.alpha(),
+        )
+        .premultiply();
+    }
+
+    Ok(())
+}
+
+/// Like `quantize_pixmap`, but using Floyd-Steinberg dithering: the
+/// difference between each pixel and its palette color is spread onto the
+/// pixels to its right and below, which are still to be done.
+fn dither_pixmap(pixmap: &mut tiny_skia::Pixmap, palette: &[Color]) -> Result<(), String> {
+    if palette.is_empty() {
+        return Err("The palette must contain at least one color".into());
+    }
+
+    let (width, height) = (pixmap.width() as usize, pixmap.height() as usize);
+    let mut wanted: Vec<[f32; 3]> = pixmap
+        .pixels()
+        .iter()
+        .map(|pixel| {
+            let straight = pixel.demultiply();
+            [straight.red(), straight.green(), straight.blue()].map(f32::from)
+        })
+        .collect();
+
+    for y in 0..height {
+        for x in 0..width {
+            let index = y * width + x;
+            let [red, green, blue] = wanted[index].map(|channel| channel.clamp(0.0, 255.0));
+            let color = Color::new_rgb(red.round() as u8, green.round() as u8, blue.round() as u8);
+            let nearest = nearest_color(color, palette).expect("the palette is not empty");
+            let error = [
+                red - f32::from(nearest.red),
+                green - f32::from(nearest.green),
+                blue - f32::from(nearest.blue),
+            ];
+
+            let mut spread = |dx: isize, dy: usize, weight: f32| {
+                let Some(x) = x.checked_add_signed(dx).filter(|&x| x < width) else {
+                    return;
+                };
+                if y + dy < height {
+                    let target = &mut wanted[(y + dy) * width + x];
+                    for (channel, error) in target.iter_mut().zip(error) {
+                        *channel += error * weight;
+                    }
+                }
+            };
+            spread(1, 0, 7.0 / 16.0);
+            spread(-1, 1, 3.0 / 16.0);
+            spread(0, 1, 5.0 / 16.0);
+            spread(1, 1, 1.0 / 16.0);
+
+            let pixel = &mut pixmap.pixels_mut()[index];
+            let alpha = pixel.demultiply().alpha();
+            *pixel = tiny_skia::ColorU8::from_rgba(nearest.red, nearest.green, nearest.blue, alpha)
+                .premultiply();
+        }
+    }
+
+    Ok(())
+}
+
+impl Image {
+    /// Render the image and give the position in `palette` of each pixel's
+    /// color, row by row from the top left, for saving the image in an
+    /// indexed format. Each pixel gets the palette color nearest to it (see
+    /// [`nearest_color`]), so anti-aliased edges take the nearest of the
+    /// colors on either side. Transparency is ignored.
+    ///
+    /// Returns an error if the palette is empty or has more than 256 colors,
+    /// or for a dry-run image.
+    ///
+    /// ```rs
+    /// let palette = build_shared_palette(&[image.clone()], 16);
+    /// let indices = image.palette_indices(&palette)?;
+    /// assert_eq!(indices.len(), 100 * 100);
+    /// ```
+    pub fn palette_indices(&self, palette: &[Color]) -> Result<Vec<u8>, String> {
+        if palette.is_empty() {
+            return Err("The palette must contain at least one color".into());
+        }
+        if palette.len() > 256 {
+            return Err(format!(
+                "A palette can have at most 256 colors, but this one has {}",
+                palette.len()
+            ));
+        }
+
+        let pixmap = self.render()?;
+        let indices = pixmap
+            .pixels()
+            .iter()
+            .map(|pixel| {
+                let straight = pixel.demultiply();
+                let color = Color::new_rgb(straight.red(), straight.green(), straight.blue());
+                let index = nearest_index(color, palette).expect("the palette is not empty");
+                index as u8
+            })
+            .collect();
+        Ok(indices)
+    }
+
+    /// Save the image to a PNG file, using only the colors in `palette`.
+    ///
+    /// The image is rendered as usual, and then every pixel is replaced with
+    /// the nearest color in the palette (see [`nearest_color`]). This changes
+    /// the colors of the image itself, giving a limited-palette look, with the
+    /// anti-aliased edges of lines snapping to one palette color or another.
+    ///
+    /// ```rs
+    /// image.save_png_palette("retro.png", &COLORS)?;
+    /// ```
+    pub fn save_png_palette<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+        palette: &[Color],
+    ) -> Result<(), String> {
+        self.prepare_to_save()?;
+        let mut pixmap = self.render()?;
+        quantize_pixmap(&mut pixmap, palette)?;
+        pixmap.save_png(path).map_err(|e| e.to_string())
+    }
+
+    /// Save the image to a PNG file using only the colors in `palette`, like
+    /// `save_png_palette`, but with dithering.
+    ///
+    /// Rather than snapping each pixel to its nearest palette color, the
+    /// difference is passed on to the neighbouring pixels (Floyd-Steinberg
+    /// dithering), so areas of in-between colors become a fine mix of palette
+    /// colors that looks like the original color from a distance. Smooth
+    /// gradients and anti-aliased edges keep their appearance, rather than
+    /// turning into bands of flat color. The result is always the same for
+    /// the same image and palette.
+    ///
+    /// Returns an error if the palette is empty.
+    ///
+    /// ```rs
+    /// // Black and white only, with greys drawn as a mix of the two.
+    /// image.save_png_dithered("dithered.png", &[Color::black(), Color::white()])?;
+    /// ```
+    pub fn save_png_dithered<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+        palette: &[Color],
+    ) -> Result<(), String> {
+        self.prepare_to_save()?;
+        let mut pixmap = self.render()?;
+        dither_pixmap(&mut pixmap, palette)?;
+        pixmap.save_png(path).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Rgba, StrokeStyle, COLORS};
+
+    /// The opaque colors of the pixels in a PNG file.
+    fn png_colors(path: &std::path::Path) -> Vec<Color> {
+        let pixmap = tiny_skia::Pixmap::load_png(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        pixmap
+            .pixels()
+            .iter()
+            .map(|pixel| Color::new_rgb(pixel.red(), pixel.green(), pixel.blue()))
+            .collect()
+    }
+
+    #[test]
+    fn quantized_pngs_only_use_the_palette() {
+        assert_eq!(
+            nearest_color(Color::new_rgb(250, 10, 10), &COLORS),
+            Some(COLORS[4])
+        );
+
+        let mut image = Image::new(50, 50);
+        for direction in [100, 135, 170] {
+            let orange = Rgba::from_straight(200, 100, 30, 100);
+            image.draw_simple_line(5, 5, direction, 60, orange).unwrap();
+        }
+        let path = std::env::temp_dir().join("unsvg-palette-test.png");
+        image.save_png_palette(&path, &COLORS).unwrap();
+        let colors = png_colors(&path);
+        assert!(colors.iter().all(|color| COLORS.contains(color)));
+        assert!(colors.iter().any(|&color| color != COLORS[0]));
+    }
+
+    #[test]
+    fn dithering_mixes_palette_colors_to_look_like_the_original() {
+        let mut image = Image::new(40, 40);
+        let style = StrokeStyle {
+            width: 40.0,
+            ..StrokeStyle::default()
+        };
+        let grey = Color::new_rgb(128, 128, 128);
+        image
+            .draw_styled_line(20, 0, 180, 40, grey, &style)
+            .unwrap();
+
+        let palette = [Color::black(), Color::white()];
+        let path = std::env::temp_dir().join("unsvg-dither-test.png");
+        image.save_png_dithered(&path, &palette).unwrap();
+        let colors = png_colors(&path);
+        assert!(colors.iter().all(|color| palette.contains(color)));
+        let white = colors
+            .iter()
+            .filter(|&&color| color == Color::white())
+            .count();
+        let share = white as f32 / colors.len() as f32;
+        assert!((share - 128.0 / 255.0).abs() < 0.02, "{share}");
+
+        // The same every time.
+        image.save_png_dithered(&path, &palette).unwrap();
+        assert_eq!(png_colors(&path), colors);
+
+        // Without dithering, the grey is all one color.
+        image.save_png_palette(&path, &palette).unwrap();
+        let flat = png_colors(&path);
+        assert!(flat.iter().all(|&color| color == flat[0]));
+
+        assert!(image.save_png_dithered(&path, &[]).is_err());
+        assert!(!path.exists());
+    }
+
+    /// A black image and a white one, with lines drawn on them.
+    fn frames() -> [Image; 2] {
+        let mut black = Image::new(40, 40);
+        black.draw_simple_line(0, 5, 90, 40, COLORS[4]).unwrap();
+        black.draw_simple_line(0, 15, 90, 40, COLORS[1]).unwrap();
+        black.draw_simple_line(0, 25, 90, 40, COLORS[1]).unwrap();
+        // Drawn in the other image's background.
+        black.draw_simple_line(0, 35, 90, 40, COLORS[7]).unwrap();
+
+        let mut white = Image::from_svg_str(
+            r##"<svg width="40" height="40" viewBox="0 0 40 40" xmlns="http://www.w3.org/2000/svg">
+                <rect width="40" height="40" fill="#ffffff"/>
+            </svg>"##,
+        )
+        .unwrap();
+        // 2 wide, so the lines cover whole pixels.
+        let wide = StrokeStyle {
+            width: 2.0,
+            ..StrokeStyle::default()
+        };
+        white
+            .draw_styled_line(0, 5, 90, 40, COLORS[3], &wide)
+            .unwrap();
+        white
+            .draw_styled_line(0, 15, 90, 40, COLORS[1], &wide)
+            .unwrap();
+        white
+            .draw_styled_line(0, 25, 90, 40, COLORS[3], &wide)
+            .unwrap();
+        white
+            .draw_styled_line(0, 35, 90, 40, COLORS[4], &wide)
+            .unwrap();
+        [black, white]
+    }
+
+    #[test]
+    fn shared_palettes_put_backgrounds_then_the_most_used_colors() {
+        let frames = frames();
+        // Blue is used 3 times, red and green twice each (red first), and
+        // white only as a background.
+        let everything = [COLORS[0], COLORS[7], COLORS[1], COLORS[4], COLORS[3]];
+        assert_eq!(build_shared_palette(&frames, 256), everything);
+        assert_eq!(build_shared_palette(&frames, 5), everything);
+        assert_eq!(build_shared_palette(&frames, 3), everything[..3]);
+        assert_eq!(build_shared_palette(&frames, 1), [COLORS[0]]);
+        assert_eq!(build_shared_palette(&frames, 0), []);
+        assert_eq!(build_shared_palette(&[], 16), []);
+
+        // Each background once, even when images share one.
+        let same = [frames[0].clone(), frames[0].clone()];
+        assert_eq!(
+            build_shared_palette(&same, 16),
+            [COLORS[0], COLORS[1], COLORS[4], COLORS[7]]
+        );
+    }
+
+    #[test]
+    fn colors_left_out_of_a_shared_palette_become_the_nearest_kept() {
+        let [_, white] = frames();
+        let palette = build_shared_palette(&frames(), 4);
+        assert!(!palette.contains(&COLORS[3]));
+        let indices = white.palette_indices(&palette).unwrap();
+        assert_eq!(indices.len(), 40 * 40);
+        // The background, then the green, blue and red lines.
+        let at = |x: usize, y: usize| palette[usize::from(indices[y * 40 + x])];
+        assert_eq!(at(20, 0), COLORS[7]);
+        assert_eq!(at(20, 5), nearest_color(COLORS[3], &palette).unwrap());
+        assert_eq!(at(20, 15), COLORS[1]);
+        assert_eq!(at(20, 35), COLORS[4]);
+    }
+
+    #[test]
+    fn color_cycles_start_again_after_the_last_color() {
+        let mut colors = ColorCycle::new(&COLORS[1..4]).unwrap();
+        assert_eq!(colors.peek(), COLORS[1]);
+        let taken: Vec<Color> = colors.by_ref().take(7).collect();
+        assert_eq!(
+            taken,
+            [COLORS[1], COLORS[2], COLORS[3], COLORS[1], COLORS[2], COLORS[3], COLORS[1]]
+        );
+        assert_eq!(colors.peek(), COLORS[2]);
+        colors.reset();
+        assert_eq!(colors.next(), Some(COLORS[1]));
+        assert!(ColorCycle::new(&[]).is_err());
+    }
+
+    #[test]
+    fn color_cycles_can_leave_out_colors_near_the_background() {
+        let palette = [
+            Color::black(),
+            COLORS[4],
+            Color::new_rgb(250, 250, 250),
+            COLORS[1],
+        ];
+        let colors = ColorCycle::excluding(&palette, Color::white(), 100.0).unwrap();
+        assert_eq!(
+            colors.take(4).collect::<Vec<_>>(),
+            [Color::black(), COLORS[4], COLORS[1], Color::black()]
+        );
+
+        // Exactly the minimum distance away is far enough.
+        let grey = Color::new_rgb(155, 255, 255);
+        let colors = ColorCycle::excluding(&[grey], Color::white(), 100.0).unwrap();
+        assert_eq!(colors.peek(), grey);
+
+        assert!(ColorCycle::excluding(&palette, Color::white(), 1000.0).is_err());
+        assert!(ColorCycle::excluding(&palette, Color::white(), f32::NAN).is_err());
+        assert!(ColorCycle::excluding(&[], Color::white(), 0.0).is_err());
+    }
+}
@@ -0,0 +1,210 @@
+use crate::{u32_to_f32, Image};
+use resvg::tiny_skia;
+
+/// The size (in pixels) of the square the image is shrunk to for hashing.
+const SAMPLE_SIZE: usize = 32;
+
+/// How many of the lowest frequencies (across and down) make up the hash.
+const HASH_SIZE: usize = 8;
+
+impl Image {
+    /// A perceptual hash of the image: a fingerprint of how it looks, for
+    /// finding drawings that look alike, such as near-duplicates in a
+    /// gallery.
+    ///
+    /// Unlike an ordinary hash, similar-looking images get similar hashes.
+    /// Compare two hashes by counting the bits that differ (their Hamming
+    /// distance): 0 means the images look much the same, up to about 10
+    /// means they are probably variations of one drawing, and unrelated
+    /// images usually differ in around half of the 64 bits.
+    ///
+    /// The image is rendered in greyscale at 32x32 pixels (so its size and
+    /// shape don't matter), and the hash records which of its coarsest
+    /// patterns of light and dark (the lowest 8x8 frequencies of a discrete
+    /// cosine transform) are stronger than the median. Fine details, like the
+    /// exact width of lines, barely change it. It is always rendered with
+    /// sRGB blending, whatever `set_blend_space` chose.
+    ///
+    /// Returns an error for a dry-run image, which can't be rendered.
+    ///
+    /// ```rs
+    /// let distance = (image.phash()? ^ other.phash()?).count_ones();
+    /// if distance <= 10 {
+    ///     println!("These look like the same drawing");
+    /// }
+    /// ```
+    pub fn phash(&self) -> Result<u64, String> {
+        self.check_not_dry_run()?;
+        let mut pixmap = tiny_skia::Pixmap::new(SAMPLE_SIZE as u32, SAMPLE_SIZE as u32)
+            .expect("the sample size is not 0");
+        let size = u32_to_f32(SAMPLE_SIZE as u32);
+        let transform = tiny_skia::Transform::from_scale(
+            size / u32_to_f32(self.width),
+            size / u32_to_f32(self.height),
+        );
+        resvg::Tree::from_usvg(&self.export_tree()).render(transform, &mut pixmap.as_mut());
+
+        let grey: Vec<f32> = pixmap
+            .pixels()
+            .iter()
+            .map(|pixel| {
+                0.299 * f32::from(pixel.red())
+                    + 0.587 * f32::from(pixel.green())
+                    + 0.114 * f32::from(pixel.blue())
+            })
+            .collect();
+
+        let frequencies = low_frequencies(&grey);
+        // The first frequency is the overall brightness, which says nothing
+        // about the picture, so it is left out of the median.
+        let mut sorted = frequencies[1..].to_vec();
+        sorted.sort_by(f32::total_cmp);
+        let median = sorted[sorted.len() / 2];
+
+        Ok(frequencies
+            .iter()
+            .enumerate()
+            .filter(|&(_, &frequency)| frequency > median)
+            .fold(0, |hash, (bit, _)| hash | 1 << bit))
+    }
+}
+
+/// The lowest `HASH_SIZE` x `HASH_SIZE` coefficients of the 2D discrete
+/// cosine transform of a `SAMPLE_SIZE` x `SAMPLE_SIZE` greyscale image.
+fn low_frequencies(grey: &[f32]) -> Vec<f32> {
+    // cosines[u][x] is how much pixel x counts towards frequency u.
+    let cosines: Vec<Vec<f32>> = (0..HASH_SIZE)
+        .map(|u| {
+            (0..SAMPLE_SIZE)
+                .map(|x| {
+                    let angle = std::f32::consts::PI * (2 * x + 1) as f32 * u as f32
+                        / (2 * SAMPLE_SIZE) as f32;
+                    angle.cos()
+                })
+                .collect()
+        })
+        .collect();
+
+    // Transform each row, and then each column of the result.
+    let rows: Vec<f32> = (0..SAMPLE_SIZE)
+        .flat_map(|y| {
+            let cosines = &cosines;
+            (0..HASH_SIZE).map(move |u| {
+                (0..SAMPLE_SIZE)
+                    .map(|x| grey[y * SAMPLE_SIZE + x] * cosines[u][x])
+                    .sum::<f32>()
+            })
+        })
+        .collect();
+    (0..HASH_SIZE)
+        .flat_map(|v| {
+            let (cosines, rows) = (&cosines, &rows);
+            (0..HASH_SIZE).map(move |u| {
+                (0..SAMPLE_SIZE)
+                    .map(|y| rows[y * HASH_SIZE + u] * cosines[v][y])
+                    .sum::<f32>()
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FillRule, Image, StrokeStyle, COLORS};
+
+    /// A house with a door and a chimney, and the sun in the sky, `size`
+    /// pixels across, drawn with lines `width` wide and moved `nudge` pixels
+    /// right and down.
+    fn house(size: u32, width: f32, nudge: i32) -> Image {
+        let mut image = Image::new(size, size);
+        let at = |value: i32| value * size as i32 / 100 + nudge;
+        let style = StrokeStyle {
+            width,
+            ..StrokeStyle::default()
+        };
+        let points = [
+            (20, 90),
+            (20, 45),
+            (50, 15),
+            (65, 30),
+            (65, 18),
+            (72, 18),
+            (72, 37),
+            (80, 45),
+            (80, 90),
+            (20, 90),
+        ];
+        let points: Vec<(i32, i32)> = points.iter().map(|&(x, y)| (at(x), at(y))).collect();
+        image
+            .draw_styled_polyline(&points, COLORS[7], &style)
+            .unwrap();
+        image
+            .fill_polygon(
+                &[
+                    (at(42), at(90)),
+                    (at(42), at(65)),
+                    (at(58), at(65)),
+                    (at(58), at(90)),
+                ],
+                COLORS[7],
+                FillRule::NonZero,
+            )
+            .unwrap();
+        let radius = size * 8 / 100;
+        image
+            .draw_ring(at(15), at(15), radius, radius / 2, COLORS[7])
+            .unwrap();
+        image
+    }
+
+    fn distance(a: &Image, b: &Image) -> u32 {
+        (a.phash().unwrap() ^ b.phash().unwrap()).count_ones()
+    }
+
+    #[test]
+    fn the_same_drawing_hashes_alike_at_any_size() {
+        let small = house(100, 2.0, 0);
+        assert_eq!(small.phash(), small.phash());
+        assert_eq!(distance(&small, &house(100, 2.0, 0)), 0);
+        let sizes = [
+            distance(&small, &house(300, 6.0, 0)),
+            distance(&small, &house(64, 1.3, 0)),
+        ];
+        assert!(sizes.iter().all(|&d| d <= 4), "{sizes:?}");
+    }
+
+    #[test]
+    fn variations_hash_closer_than_unrelated_drawings() {
+        let original = house(100, 2.0, 0);
+        // Thicker lines, or moved a little.
+        let variations = [
+            distance(&original, &house(100, 4.0, 0)),
+            distance(&original, &house(100, 2.0, 1)),
+            distance(&original, &house(100, 2.0, -2)),
+        ];
+        assert!(variations.iter().all(|&d| d <= 10), "{variations:?}");
+
+        let mut bars = Image::new(100, 100);
+        for y in (10..100).step_by(20) {
+            bars.draw_styled_line(
+                0,
+                y,
+                90,
+                100,
+                COLORS[7],
+                &StrokeStyle {
+                    width: 8.0,
+                    ..StrokeStyle::default()
+                },
+            )
+            .unwrap();
+        }
+        let unrelated = [
+            distance(&original, &bars),
+            distance(&original, &Image::new(100, 100)),
+        ];
+        assert!(unrelated.iter().all(|&d| d >= 20), "{unrelated:?}");
+
+        assert!(Image::new_dry_run(100, 100).phash().is_err());
+    }
+}
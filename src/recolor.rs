@@ -0,0 +1,168 @@
+use crate::{Color, Image};
+use resvg::usvg::NodeExt;
+use resvg::{tiny_skia, usvg};
+use std::rc::Rc;
+
+/// Replace every solid fill and stroke color in a node (and everything inside
+/// it) with `f` of that color. Opacity is kept as it is.
+pub(crate) fn recolor(node: &usvg::Node, f: &dyn Fn(Color) -> Color) {
+    let map = |paint: &mut usvg::Paint| {
+        if let usvg::Paint::Color(ref mut color) = *paint {
+            *color = f(Color::from_usvg(*color)).to_usvg();
+        }
+    };
+
+    for descendant in node.descendants() {
+        if let usvg::NodeKind::Path(ref mut path) = *descendant.borrow_mut() {
+            if let Some(ref mut fill) = path.fill {
+                map(&mut fill.paint);
+            }
+            if let Some(ref mut stroke) = path.stroke {
+                map(&mut stroke.paint);
+            }
+        }
+    }
+}
+
+impl Image {
+    /// A copy of the image with every color replaced by its complement
+    /// (see [`Color::inverted`]), including the background. This is handy
+    /// for making a dark-mode version of a drawing.
+    ///
+    /// The colors of the shapes themselves are changed, rather than the
+    /// finished picture, so the inverted image is still an ordinary SVG that
+    /// can be drawn on and edited. Translucent shapes keep their opacity.
+    ///
+    /// ```rs
+    /// let mut image = Image::new(100, 100);
+    /// image.draw_simple_line(10, 10, 90, 50, COLORS[4])?;
+    /// // A cyan line on a white background.
+    /// image.inverted().save_png("inverted.png")?;
+    /// ```
+    pub fn inverted(&self) -> Image {
+        let image = self.clone();
+        recolor(&image.background(), &Color::inverted);
+        for element in image.elements() {
+            recolor(&element, &Color::inverted);
+        }
+        image
+    }
+
+    /// Change the colors of everything inside a rectangle of the image,
+    /// given as `(min_x, min_y, max_x, max_y)`.
+    ///
+    /// `f` is called with each color used in the image (including the
+    /// background) and returns the color to use instead. Only the part of the
+    /// image inside the rectangle changes: a recolored copy of the drawing,
+    /// cut down to the rectangle, is added on top of it as a new element. That
+    /// element can be removed again like any other, and the SVG stays
+    /// editable.
+    ///
+    /// Returns an error if the rectangle has no area.
+    ///
+    /// ```rs
+    /// // Invert the left half of the image.
+    /// image.map_region_colors((0, 0, 50, 100), Color::inverted)?;
+    /// ```
+    pub fn map_region_colors<F>(&mut self, rect: (i32, i32, i32, i32), f: F) -> Result<(), String>
+    where
+        F: Fn(Color) -> Color,
+    {
+        let (min_x, min_y, max_x, max_y) = rect;
+        let region =
+            usvg::NonZeroRect::from_ltrb(min_x as f32, min_y as f32, max_x as f32, max_y as f32)
+                .ok_or_else(|| format!("The region {rect:?} has no area"))?;
+
+        let mut path =
+            usvg::Path::new(Rc::new(tiny_skia::PathBuilder::from_rect(region.to_rect())));
+        path.fill = Some(usvg::Fill::default());
+        let clip = usvg::ClipPath {
+            id: self.new_def_id("clip"),
+            ..usvg::ClipPath::default()
+        };
+        clip.root.append_kind(usvg::NodeKind::Path(path));
+
+        let group = usvg::Node::new(usvg::NodeKind::Group(usvg::Group {
+            clip_path: Some(Rc::new(clip)),
+            ..usvg::Group::default()
+        }));
+        // The background is in the image's own coordinates, not the drawing's.
+        let background = group.append_kind(usvg::NodeKind::Group(usvg::Group {
+            transform: self.coordinate_transform().invert().unwrap_or_default(),
+            ..usvg::Group::default()
+        }));
+        background.append(self.background().make_deep_copy());
+        for element in self.elements() {
+            group.append(element.make_deep_copy());
+        }
+        for node in group.descendants() {
+            if let usvg::NodeKind::Group(ref mut copy) = *node.borrow_mut() {
+                // Ids of animated elements must stay unique.
+                copy.id.clear();
+            }
+        }
+        recolor(&group, &f);
+
+        self.append_element_node(group)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Color, Corner, Image, StrokeStyle, SvgOptions, COLORS};
+
+    #[test]
+    fn recolored_background_stays_in_the_region() {
+        let mut image = Image::new(100, 100);
+        image.set_coordinate_system(Corner::TopRight, false);
+        image
+            .map_region_colors((-100, 0, -50, 50), Color::inverted)
+            .unwrap();
+
+        let pixmap = image.render().unwrap();
+        let color = |x, y| {
+            let pixel = pixmap.pixel(x, y).unwrap();
+            [pixel.red(), pixel.green(), pixel.blue()]
+        };
+        // The region is the top-left quarter of the image.
+        let outside = color(75, 75);
+        let inverted = outside.map(|channel| 255 - channel);
+        assert_eq!(color(25, 25), inverted);
+        assert_eq!(color(75, 25), outside);
+        assert_eq!(color(25, 75), outside);
+    }
+
+    #[test]
+    fn recolored_copies_have_no_animation_ids() {
+        let mut image = Image::new(100, 100);
+        image.draw_simple_line(10, 50, 90, 20, COLORS[4]).unwrap();
+        let line = image.last_element().unwrap();
+        image.animate_translation(line, (60, 0), 2.0, true).unwrap();
+        image
+            .map_region_colors((0, 0, 100, 100), Color::inverted)
+            .unwrap();
+
+        let svg = image.svg_string(&SvgOptions::default());
+        assert_eq!(svg.matches("id=\"animated").count(), 1);
+    }
+
+    #[test]
+    fn inverting_makes_the_background_white_and_red_cyan() {
+        let mut image = Image::new(100, 100);
+        let style = StrokeStyle {
+            width: 5.0,
+            ..StrokeStyle::default()
+        };
+        image
+            .draw_styled_line(10, 50, 90, 80, COLORS[4], &style)
+            .unwrap();
+        let inverted = image.inverted().render().unwrap();
+        let rgb = |x, y| {
+            let pixel = inverted.pixel(x, y).unwrap();
+            (pixel.red(), pixel.green(), pixel.blue())
+        };
+        assert_eq!(rgb(50, 20), (255, 255, 255));
+        assert_eq!(rgb(50, 50), (0, 255, 255));
+    }
+}
@@ -0,0 +1,86 @@
+use crate::Image;
+
+impl Image {
+    /// Creates an image where every unit of drawing (a "step") is
+    /// `units_per_step` units of the saved image, so a program can draw on a
+    /// coarse grid of whole steps without multiplying every coordinate.
+    ///
+    /// The image is `width_steps * units_per_step` by
+    /// `height_steps * units_per_step` units, which is the size of the saved
+    /// SVG and (in pixels) PNG, and what `get_dimensions` returns. Everything
+    /// given to and returned by draw calls is in steps instead: points,
+    /// lengths and end points, and line widths and every other size too, so
+    /// the drawing looks the same as on an unscaled image `width_steps` by
+    /// `height_steps`, just bigger. Areas like `content_bounds` are in
+    /// steps, while anything that describes the saved image itself, like
+    /// `overflow` and `to_segments`, is in units.
+    ///
+    /// Returns an error if `units_per_step` is 0, or if the image would be
+    /// bigger than `Image::try_new` allows.
+    ///
+    /// ```rs
+    /// // 50 by 50 steps of 4 units each, saved as 200 by 200.
+    /// let mut image = Image::new_scaled(50, 50, 4)?;
+    /// let end = image.draw_simple_line(10, 10, 90, 20, COLORS[1])?;
+    /// assert_eq!(end, (30, 10));
+    /// assert_eq!(image.get_dimensions(), (200, 200));
+    /// assert_eq!(image.get_dimensions_in_steps(), (50, 50));
+    /// ```
+    pub fn new_scaled(
+        width_steps: u32,
+        height_steps: u32,
+        units_per_step: u32,
+    ) -> Result<Image, String> {
+        if units_per_step == 0 {
+            return Err("There must be at least 1 unit per step".into());
+        }
+        let scaled = |steps: u32| {
+            steps.checked_mul(units_per_step).ok_or_else(|| {
+                format!(
+                    "A {width_steps}x{height_steps} image of {units_per_step} units per step is too big"
+                )
+            })
+        };
+
+        let mut image = Image::try_new(scaled(width_steps)?, scaled(height_steps)?)?;
+        image.units_per_step = units_per_step;
+        Ok(image)
+    }
+
+    /// How many units of the saved image each step of drawing is, as chosen
+    /// by `new_scaled`. This is 1 for any other image.
+    pub fn units_per_step(&self) -> u32 {
+        self.units_per_step
+    }
+
+    /// Get the size of the image in steps, the units draw calls use, as a
+    /// tuple of (width, height). This is the size from `get_dimensions`
+    /// divided by `units_per_step` (rounding down, if the canvas has since
+    /// been resized to a size between whole steps).
+    pub fn get_dimensions_in_steps(&self) -> (u32, u32) {
+        (
+            self.width / self.units_per_step,
+            self.height / self.units_per_step,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Image, Segment, COLORS};
+
+    #[test]
+    fn segments_are_in_units() {
+        let mut image = Image::new_scaled(10, 10, 10).unwrap();
+        assert_eq!(image.draw_simple_line(1, 1, 90, 5, COLORS[1]), Ok((6, 1)));
+        assert_eq!(
+            image.to_segments(0.1),
+            vec![Segment {
+                start: (10.0, 10.0),
+                end: (60.0, 10.0),
+                color: COLORS[1],
+                width: 10.0,
+            }]
+        );
+    }
+}
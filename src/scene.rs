@@ -0,0 +1,684 @@
+use crate::{Color, Image};
+use resvg::{tiny_skia, usvg};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+/// The version of the scene format `to_scene_json` writes, which goes up
+/// whenever the format changes in a way older readers can't handle.
+const SCENE_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct Scene {
+    version: u32,
+    width: u32,
+    height: u32,
+    #[serde(default = "one_unit", skip_serializing_if = "is_one_unit")]
+    units_per_step: u32,
+    background: String,
+    elements: Vec<Primitive>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    anchors: BTreeMap<String, [i32; 2]>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Primitive {
+    #[serde(flatten)]
+    shape: Shape,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    transform: Option<[f32; 6]>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timestamp: Option<SceneTimestamp>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SceneTimestamp {
+    sequence: u64,
+    elapsed_secs: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Shape {
+    Line {
+        from: [f32; 2],
+        to: [f32; 2],
+        stroke: Stroke,
+    },
+    Polyline {
+        points: Vec<[f32; 2]>,
+        closed: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        stroke: Option<Stroke>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        fill: Option<Fill>,
+    },
+    Path {
+        commands: Vec<Command>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        stroke: Option<Stroke>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        fill: Option<Fill>,
+    },
+    Group {
+        #[serde(default = "opaque", skip_serializing_if = "is_opaque")]
+        opacity: f32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        clip: Option<Clip>,
+        children: Vec<Primitive>,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Command {
+    MoveTo([f32; 2]),
+    LineTo([f32; 2]),
+    QuadTo([f32; 4]),
+    CubicTo([f32; 6]),
+    Close,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Clip {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    transform: Option<[f32; 6]>,
+    shapes: Vec<Primitive>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Stroke {
+    color: String,
+    #[serde(default = "opaque", skip_serializing_if = "is_opaque")]
+    opacity: f32,
+    width: f32,
+    cap: Cap,
+    join: Join,
+    miter_limit: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    dashes: Option<Vec<f32>>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    dash_offset: f32,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Cap {
+    Butt,
+    Round,
+    Square,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Join {
+    Miter,
+    Round,
+    Bevel,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Fill {
+    color: String,
+    #[serde(default = "opaque", skip_serializing_if = "is_opaque")]
+    opacity: f32,
+    rule: Rule,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Rule {
+    NonZero,
+    EvenOdd,
+}
+
+fn opaque() -> f32 {
+    1.0
+}
+
+fn is_opaque(opacity: &f32) -> bool {
+    *opacity == 1.0
+}
+
+fn is_zero(value: &f32) -> bool {
+    *value == 0.0
+}
+
+fn one_unit() -> u32 {
+    1
+}
+
+fn is_one_unit(units: &u32) -> bool {
+    *units == 1
+}
+
+impl Image {
+    /// The drawing as JSON: a list of the shapes drawn, with their points
+    /// and styles, which is easier for other programs to read than SVG.
+    /// `Image::from_scene_json` turns it back into an image. This needs the
+    /// `json` feature.
+    ///
+    /// The scene is an object with the `version` of the format (currently
+    /// 1), the image's `width` and `height`, its `units_per_step` (if it
+    /// isn't 1, as from `new_scaled`), the `background` color, and the
+    /// `elements` drawn, in order. Colors are written `"#rrggbb"`, and points
+    /// as `[x, y]`. Each element has a `type`:
+    ///
+    /// - `"line"`: a straight line, `from` one point `to` another, with a
+    ///   `stroke`.
+    /// - `"polyline"`: straight lines through a list of `points`, `closed`
+    ///   back to the first point or not, with a `stroke`, a `fill` or both.
+    /// - `"path"`: anything else with a `stroke` or `fill`, as a list of
+    ///   `commands`, each one of `{"move_to": [x, y]}`, `{"line_to": [x, y]}`,
+    ///   `{"quad_to": [x1, y1, x, y]}`, `{"cubic_to": [x1, y1, x2, y2, x, y]}`
+    ///   or `"close"`.
+    /// - `"group"`: a list of `children`, with an `opacity` (if it isn't 1)
+    ///   and a `clip` (if it has one) made of `shapes`.
+    ///
+    /// A stroke has a `color`, `opacity` (if it isn't 1), `width`, `cap`
+    /// (`"butt"`, `"round"` or `"square"`), `join` (`"miter"`, `"round"` or
+    /// `"bevel"`), `miter_limit`, and `dashes` and `dash_offset` if it is
+    /// dashed. A fill has a `color`, `opacity` (if it isn't 1) and `rule`
+    /// (`"non_zero"` or `"even_odd"`). Any element or clip can also have a
+    /// `transform`, as the six numbers of an SVG `matrix(a b c d e f)`.
+    /// Elements with a timestamp (see `enable_timestamps`) also have a
+    /// `timestamp`, with its `sequence` number and `elapsed_secs`, which
+    /// `from_scene_json` ignores. If any anchors are set (see `set_anchor`),
+    /// the scene also has `anchors`, an object from each name to its point.
+    ///
+    /// Points are in the coordinates they were drawn with (see
+    /// `set_coordinate_system`). Only shapes are included: text, pictures,
+    /// gradients and masks are left out, and so are the image's settings,
+    /// like its coordinate system, mask, frame and watermark.
+    ///
+    /// ```rs
+    /// let mut image = Image::new(100, 100);
+    /// image.draw_simple_line(10, 10, 90, 50, COLORS[1])?;
+    /// let json = image.to_scene_json();
+    /// assert!(json.contains(r#""type":"line","from":[10.0,10.0],"to":[60.0,10.0]"#));
+    /// ```
+    pub fn to_scene_json(&self) -> String {
+        let background = match *self.background().borrow() {
+            usvg::NodeKind::Path(ref path) => {
+                path.fill.as_ref().and_then(|fill| solid(&fill.paint))
+            }
+            _ => None,
+        };
+        let scene = Scene {
+            version: SCENE_VERSION,
+            width: self.width,
+            height: self.height,
+            units_per_step: self.units_per_step,
+            background: hex(background.unwrap_or_else(Color::black)),
+            elements: self
+                .elements
+                .iter()
+                .filter_map(|&(id, ref node)| {
+                    let mut primitive = primitive(node)?;
+                    primitive.timestamp =
+                        self.element_timestamp(id).map(|timestamp| SceneTimestamp {
+                            sequence: timestamp.sequence,
+                            elapsed_secs: timestamp.elapsed.as_secs_f64(),
+                        });
+                    Some(primitive)
+                })
+                .collect(),
+            anchors: self
+                .anchors
+                .iter()
+                .map(|(name, &(x, y))| (name.clone(), [x, y]))
+                .collect(),
+        };
+        serde_json::to_string(&scene).expect("a scene can always be written as JSON")
+    }
+
+    /// Build an image from the JSON written by `to_scene_json`, with
+    /// everything in the scene drawn on it, one element per element in the
+    /// scene. This needs the `json` feature.
+    ///
+    /// Returns an error if the JSON doesn't describe a scene, if it is from a
+    /// newer version of `unsvg`, if the image would have no area or be too
+    /// big (see [`MAX_DIMENSION`](crate::MAX_DIMENSION)), or if a shape
+    /// can't be drawn (such as a path with a single point, or a stroke that
+    /// isn't more than 0 wide).
+    ///
+    /// ```rs
+    /// let copy = Image::from_scene_json(&image.to_scene_json())?;
+    /// assert_eq!(copy.element_ids().len(), image.element_ids().len());
+    /// ```
+    pub fn from_scene_json(json: &str) -> Result<Image, String> {
+        let scene: Scene = serde_json::from_str(json).map_err(|e| format!("Invalid scene: {e}"))?;
+        if scene.version > SCENE_VERSION {
+            return Err(format!(
+                "The scene is version {}, but only up to version {SCENE_VERSION} can be read",
+                scene.version
+            ));
+        }
+        if scene.width == 0 || scene.height == 0 {
+            return Err(format!(
+                "An image must be at least 1x1, but the scene is {}x{}",
+                scene.width, scene.height
+            ));
+        }
+        if scene.units_per_step == 0 {
+            return Err("There must be at least 1 unit per step".into());
+        }
+
+        let mut image = Image::try_new(scene.width, scene.height)?;
+        image.units_per_step = scene.units_per_step;
+        let background = parse_color(&scene.background)?;
+        if let usvg::NodeKind::Path(ref mut path) = *image.background().borrow_mut() {
+            path.fill = Some(usvg::Fill::from_paint(background.paint()));
+        }
+        for (index, element) in scene.elements.iter().enumerate() {
+            let node = image
+                .scene_node(element)
+                .map_err(|e| format!("In element {index}: {e}"))?;
+            image.append_element_node(node)?;
+        }
+        for (name, [x, y]) in scene.anchors {
+            image.set_anchor(&name, x, y);
+        }
+        Ok(image)
+    }
+
+    /// Build the node for a primitive read from a scene.
+    fn scene_node(&mut self, primitive: &Primitive) -> Result<usvg::Node, String> {
+        let transform = primitive
+            .transform
+            .map_or_else(tiny_skia::Transform::default, matrix);
+        if !transform.is_finite() {
+            return Err("A transform must be finite".into());
+        }
+
+        let (data, stroke, fill) = match primitive.shape {
+            Shape::Line {
+                from,
+                to,
+                ref stroke,
+            } => {
+                let mut builder = tiny_skia::PathBuilder::new();
+                builder.move_to(from[0], from[1]);
+                builder.line_to(to[0], to[1]);
+                (builder.finish(), Some(stroke), None)
+            }
+            Shape::Polyline {
+                ref points,
+                closed,
+                ref stroke,
+                ref fill,
+            } => {
+                let mut builder = tiny_skia::PathBuilder::new();
+                for (index, point) in points.iter().enumerate() {
+                    if index == 0 {
+                        builder.move_to(point[0], point[1]);
+                    } else {
+                        builder.line_to(point[0], point[1]);
+                    }
+                }
+                if closed {
+                    builder.close();
+                }
+                (builder.finish(), stroke.as_ref(), fill.as_ref())
+            }
+            Shape::Path {
+                ref commands,
+                ref stroke,
+                ref fill,
+            } => {
+                let mut builder = tiny_skia::PathBuilder::new();
+                for command in commands {
+                    match *command {
+                        Command::MoveTo([x, y]) => builder.move_to(x, y),
+                        Command::LineTo([x, y]) => builder.line_to(x, y),
+                        Command::QuadTo([x1, y1, x, y]) => builder.quad_to(x1, y1, x, y),
+                        Command::CubicTo([x1, y1, x2, y2, x, y]) => {
+                            builder.cubic_to(x1, y1, x2, y2, x, y)
+                        }
+                        Command::Close => builder.close(),
+                    }
+                }
+                (builder.finish(), stroke.as_ref(), fill.as_ref())
+            }
+            Shape::Group {
+                opacity,
+                ref clip,
+                ref children,
+            } => {
+                if !(0.0..=1.0).contains(&opacity) {
+                    return Err(format!(
+                        "A group's opacity must be between 0 and 1, but it was {opacity}"
+                    ));
+                }
+                let clip_path = match *clip {
+                    Some(ref clip) => Some(self.scene_clip(clip)?),
+                    None => None,
+                };
+                let group = usvg::Node::new(usvg::NodeKind::Group(usvg::Group {
+                    transform,
+                    opacity: usvg::Opacity::new_clamped(opacity),
+                    clip_path,
+                    ..usvg::Group::default()
+                }));
+                for child in children {
+                    group.append(self.scene_node(child)?);
+                }
+                return Ok(group);
+            }
+        };
+
+        let data = data.ok_or("A path needs at least two points")?;
+        let mut path = usvg::Path::new(Rc::new(data));
+        path.transform = transform;
+        path.stroke = stroke.map(scene_stroke).transpose()?;
+        path.fill = fill.map(scene_fill).transpose()?;
+        Ok(usvg::Node::new(usvg::NodeKind::Path(path)))
+    }
+
+    fn scene_clip(&mut self, clip: &Clip) -> Result<Rc<usvg::ClipPath>, String> {
+        let transform = clip
+            .transform
+            .map_or_else(tiny_skia::Transform::default, matrix);
+        if !transform.is_finite() {
+            return Err("A clip's transform must be finite".into());
+        }
+        let clip_path = usvg::ClipPath {
+            id: self.new_def_id("clip"),
+            transform,
+            ..usvg::ClipPath::default()
+        };
+        for shape in &clip.shapes {
+            clip_path.root.append(self.scene_node(shape)?);
+        }
+        Ok(Rc::new(clip_path))
+    }
+}
+
+/// The primitive for a node, or `None` if it is something a scene can't
+/// describe.
+fn primitive(node: &usvg::Node) -> Option<Primitive> {
+    let transform = node.borrow().transform();
+    let shape = match *node.borrow() {
+        usvg::NodeKind::Path(ref path) => path_shape(path)?,
+        usvg::NodeKind::Group(ref group) => Shape::Group {
+            opacity: group.opacity.get(),
+            clip: group.clip_path.as_ref().map(|clip| Clip {
+                transform: row(clip.transform),
+                shapes: clip
+                    .root
+                    .children()
+                    .filter_map(|child| primitive(&child))
+                    .collect(),
+            }),
+            children: node
+                .children()
+                .filter_map(|child| primitive(&child))
+                .collect(),
+        },
+        usvg::NodeKind::Image(_) | usvg::NodeKind::Text(_) => return None,
+    };
+    Some(Primitive {
+        shape,
+        transform: row(transform),
+        timestamp: None,
+    })
+}
+
+fn path_shape(path: &usvg::Path) -> Option<Shape> {
+    let stroke = path.stroke.as_ref().and_then(stroke);
+    let fill = path.fill.as_ref().and_then(fill);
+    if stroke.is_none() && fill.is_none() {
+        return None;
+    }
+
+    // Find the points of a single run of straight lines, if that's all it is.
+    let mut points = Vec::new();
+    let mut closed = false;
+    for (index, segment) in path.data.segments().enumerate() {
+        match segment {
+            tiny_skia::PathSegment::MoveTo(point) if index == 0 => points.push([point.x, point.y]),
+            tiny_skia::PathSegment::LineTo(point) if !closed => points.push([point.x, point.y]),
+            tiny_skia::PathSegment::Close if !closed => closed = true,
+            _ => {
+                points.clear();
+                break;
+            }
+        }
+    }
+
+    Some(match (&points[..], closed, stroke, fill) {
+        (&[from, to], false, Some(stroke), None) => Shape::Line { from, to, stroke },
+        ([_, _, ..], _, stroke, fill) => Shape::Polyline {
+            points,
+            closed,
+            stroke,
+            fill,
+        },
+        (_, _, stroke, fill) => Shape::Path {
+            commands: path
+                .data
+                .segments()
+                .map(|segment| match segment {
+                    tiny_skia::PathSegment::MoveTo(p) => Command::MoveTo([p.x, p.y]),
+                    tiny_skia::PathSegment::LineTo(p) => Command::LineTo([p.x, p.y]),
+                    tiny_skia::PathSegment::QuadTo(p1, p) => {
+                        Command::QuadTo([p1.x, p1.y, p.x, p.y])
+                    }
+                    tiny_skia::PathSegment::CubicTo(p1, p2, p) => {
+                        Command::CubicTo([p1.x, p1.y, p2.x, p2.y, p.x, p.y])
+                    }
+                    tiny_skia::PathSegment::Close => Command::Close,
+                })
+                .collect(),
+            stroke,
+            fill,
+        },
+    })
+}
+
+fn stroke(stroke: &usvg::Stroke) -> Option<Stroke> {
+    Some(Stroke {
+        color: hex(solid(&stroke.paint)?),
+        opacity: stroke.opacity.get(),
+        width: stroke.width.get(),
+        cap: match stroke.linecap {
+            usvg::LineCap::Butt => Cap::Butt,
+            usvg::LineCap::Round => Cap::Round,
+            usvg::LineCap::Square => Cap::Square,
+        },
+        join: match stroke.linejoin {
+            usvg::LineJoin::Miter => Join::Miter,
+            usvg::LineJoin::Round => Join::Round,
+            usvg::LineJoin::Bevel => Join::Bevel,
+        },
+        miter_limit: stroke.miterlimit.get(),
+        dashes: stroke.dasharray.clone(),
+        dash_offset: stroke.dashoffset,
+    })
+}
+
+fn fill(fill: &usvg::Fill) -> Option<Fill> {
+    Some(Fill {
+        color: hex(solid(&fill.paint)?),
+        opacity: fill.opacity.get(),
+        rule: match fill.rule {
+            usvg::FillRule::NonZero => Rule::NonZero,
+            usvg::FillRule::EvenOdd => Rule::EvenOdd,
+        },
+    })
+}
+
+fn scene_stroke(stroke: &Stroke) -> Result<usvg::Stroke, String> {
+    let width = usvg::StrokeWidth::new(stroke.width).ok_or_else(|| {
+        format!(
+            "A stroke must be more than 0 wide, but it was {}",
+            stroke.width
+        )
+    })?;
+    if stroke.miter_limit < 1.0 {
+        return Err(format!(
+            "A stroke's miter limit must be at least 1, but it was {}",
+            stroke.miter_limit
+        ));
+    }
+    Ok(usvg::Stroke {
+        paint: parse_color(&stroke.color)?.paint(),
+        opacity: opacity(stroke.opacity)?,
+        width,
+        linecap: match stroke.cap {
+            Cap::Butt => usvg::LineCap::Butt,
+            Cap::Round => usvg::LineCap::Round,
+            Cap::Square => usvg::LineCap::Square,
+        },
+        linejoin: match stroke.join {
+            Join::Miter => usvg::LineJoin::Miter,
+            Join::Round => usvg::LineJoin::Round,
+            Join::Bevel => usvg::LineJoin::Bevel,
+        },
+        miterlimit: usvg::StrokeMiterlimit::new(stroke.miter_limit),
+        dasharray: stroke.dashes.clone(),
+        dashoffset: stroke.dash_offset,
+    })
+}
+
+fn scene_fill(fill: &Fill) -> Result<usvg::Fill, String> {
+    Ok(usvg::Fill {
+        paint: parse_color(&fill.color)?.paint(),
+        opacity: opacity(fill.opacity)?,
+        rule: match fill.rule {
+            Rule::NonZero => usvg::FillRule::NonZero,
+            Rule::EvenOdd => usvg::FillRule::EvenOdd,
+        },
+    })
+}
+
+fn opacity(opacity: f32) -> Result<usvg::Opacity, String> {
+    if !(0.0..=1.0).contains(&opacity) {
+        return Err(format!(
+            "An opacity must be between 0 and 1, but it was {opacity}"
+        ));
+    }
+    Ok(usvg::Opacity::new_clamped(opacity))
+}
+
+/// The color of a paint, if it is a single solid color.
+fn solid(paint: &usvg::Paint) -> Option<Color> {
+    match *paint {
+        usvg::Paint::Color(color) => Some(Color::from_usvg(color)),
+        _ => None,
+    }
+}
+
+pub(crate) fn hex(color: Color) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.red, color.green, color.blue)
+}
+
+fn parse_color(text: &str) -> Result<Color, String> {
+    let invalid = || format!("A color must be written as \"#rrggbb\", but it was {text:?}");
+    let digits = text.strip_prefix('#').ok_or_else(invalid)?;
+    if digits.len() != 6 || !digits.is_ascii() {
+        return Err(invalid());
+    }
+    let channel = |at: usize| u8::from_str_radix(&digits[at..at + 2], 16).map_err(|_| invalid());
+    Ok(Color {
+        red: channel(0)?,
+        green: channel(2)?,
+        blue: channel(4)?,
+    })
+}
+
+/// A transform as the numbers of an SVG `matrix`, or `None` if it does nothing.
+fn row(transform: tiny_skia::Transform) -> Option<[f32; 6]> {
+    let tiny_skia::Transform {
+        sx,
+        ky,
+        kx,
+        sy,
+        tx,
+        ty,
+    } = transform;
+    (!transform.is_identity()).then_some([sx, ky, kx, sy, tx, ty])
+}
+
+fn matrix([sx, ky, kx, sy, tx, ty]: [f32; 6]) -> tiny_skia::Transform {
+    tiny_skia::Transform::from_row(sx, ky, kx, sy, tx, ty)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FillRule, Image, LineCap, LineStyle, StrokeStyle, COLORS};
+
+    #[test]
+    fn scenes_load_back_into_the_same_drawing() {
+        let mut image = Image::new_scaled(60, 50, 2).unwrap();
+        image.draw_simple_line(5, 5, 120, 40, COLORS[1]).unwrap();
+        let dashed = StrokeStyle {
+            width: 3.0,
+            style: LineStyle::DashDot,
+            cap: LineCap::Round,
+            ..StrokeStyle::default()
+        };
+        image
+            .draw_styled_polyline(&[(5, 45), (20, 25), (35, 45)], COLORS[3], &dashed)
+            .unwrap();
+        image.draw_ring(45, 15, 10, 5, COLORS[4]).unwrap();
+        image
+            .fill_polygon(
+                &[(40, 30), (58, 48), (40, 48), (58, 30)],
+                COLORS[2],
+                FillRule::EvenOdd,
+            )
+            .unwrap();
+        image
+            .draw_connected_dots(&[(10, 30), (25, 10), (30, 20)], 3, COLORS[7], COLORS[15])
+            .unwrap();
+        // Groups, each with a transform and a clip.
+        image
+            .draw_grid_of(2, 1, 2, |cell, col, _| {
+                let length = 20 + 20 * i32::try_from(col).unwrap();
+                cell.draw_simple_line(0, 10, 90, length, COLORS[7])?;
+                Ok(())
+            })
+            .unwrap();
+        image.set_anchor("corner", 60, 50);
+
+        let json = image.to_scene_json();
+        for part in [
+            r#""type":"group""#,
+            r#""clip""#,
+            r#""dashes""#,
+            r#""anchors""#,
+        ] {
+            assert!(json.contains(part), "{part} is missing from {json}");
+        }
+        let copy = Image::from_scene_json(&json).unwrap();
+        assert_eq!(copy.element_ids().len(), image.element_ids().len());
+        assert_eq!(copy.to_scene_json(), json);
+
+        let (original, loaded) = (image.render().unwrap(), copy.render().unwrap());
+        assert_eq!((loaded.width(), loaded.height()), (120, 100));
+        assert!(original.pixels().iter().any(|pixel| pixel.red() > 0));
+        assert!(original.pixels() == loaded.pixels());
+    }
+
+    #[test]
+    fn scenes_that_cant_be_drawn_are_errors() {
+        let scene = |elements: &str| {
+            format!(
+                r##"{{"version":1,"width":10,"height":10,"background":"#000000","elements":[{elements}]}}"##
+            )
+        };
+        assert!(Image::from_scene_json(&scene("")).is_ok());
+        assert!(Image::from_scene_json(&scene("").replace(":1,", ":2,")).is_err());
+        assert!(Image::from_scene_json(&scene("").replace("10,", "0,")).is_err());
+        assert!(Image::from_scene_json("[]").is_err());
+
+        let line = r##"{"type":"line","from":[1.0,1.0],"to":[9.0,9.0],"stroke":{"color":"#ff0000","width":WIDTH,"cap":"butt","join":"miter","miter_limit":4.0}}"##;
+        let image = Image::from_scene_json(&scene(&line.replace("WIDTH", "2.0"))).unwrap();
+        assert_eq!(image.element_ids().len(), 1);
+        let Err(error) = Image::from_scene_json(&scene(&line.replace("WIDTH", "0.0"))) else {
+            panic!("a stroke 0 wide was drawn");
+        };
+        assert!(error.starts_with("In element 0:"), "{error}");
+    }
+}
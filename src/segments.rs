@@ -0,0 +1,614 @@
+use crate::elements::skia_stroke;
+use crate::{Color, Image};
+use resvg::usvg::NodeExt;
+use resvg::{tiny_skia, usvg};
+
+/// The smallest curve tolerance `to_segments` uses, in units.
+const MIN_TOLERANCE: f32 = 0.01;
+
+/// The most times a curve is cut in half when flattening it, so that no
+/// curve becomes more than 1024 pieces.
+const MAX_DEPTH: u32 = 10;
+
+/// A straight piece of a stroked line, from `Image::to_segments`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Segment {
+    /// Where the segment starts.
+    pub start: (f32, f32),
+    /// Where the segment ends.
+    pub end: (f32, f32),
+    /// The color of the line.
+    pub color: Color,
+    /// The width of the line, in units.
+    pub width: f32,
+}
+
+impl Image {
+    /// Every stroked line on the image as a list of straight segments, for
+    /// pen plotters and other tools that only draw straight lines.
+    ///
+    /// Segments are in drawing order, and each line's segments follow it from
+    /// start to end, so a plotter can draw each line without lifting the pen.
+    /// Curves are split into straight pieces that are never more than
+    /// `curve_tolerance` units from the real curve (a tolerance smaller than
+    /// 0.01 is treated as 0.01), with more pieces where a curve bends
+    /// sharply and fewer where it is nearly straight. Dashed lines become one
+    /// segment per dash.
+    ///
+    /// Only what a pen would draw is included: filled areas, the background,
+    /// and pictures made of pixels are left out, and so are clips, masks,
+    /// opacity and line caps. Lines not drawn in a solid color are left out,
+    /// too. Coordinates and widths are as the lines appear on the saved
+    /// image, including any transforms and the coordinate system chosen with
+    /// `set_coordinate_system`.
+    ///
+    /// ```rs
+    /// image.draw_simple_line(10, 10, 90, 50, COLORS[1])?;
+    /// let segments = image.to_segments(0.1);
+    /// assert_eq!(segments, vec![Segment {
+    ///     start: (10.0, 10.0),
+    ///     end: (60.0, 10.0),
+    ///     color: COLORS[1],
+    ///     width: 1.0,
+    /// }]);
+    /// ```
+    pub fn to_segments(&self, curve_tolerance: f32) -> Vec<Segment> {
+        let tolerance = if curve_tolerance >= MIN_TOLERANCE {
+            curve_tolerance
+        } else {
+            MIN_TOLERANCE
+        };
+
+        let mut segments = Vec::new();
+        for element in self.elements() {
+            for node in element.descendants() {
+                let usvg::NodeKind::Path(ref path) = *node.borrow() else {
+                    continue;
+                };
+                let Some(ref stroke) = path.stroke else {
+                    continue;
+                };
+                let usvg::Paint::Color(color) = stroke.paint else {
+                    continue;
+                };
+
+                let dashed = skia_stroke(stroke)
+                    .dash
+                    .and_then(|dash| path.data.dash(&dash, 1.0));
+                let transform = self.coordinate_transform().pre_concat(node.abs_transform());
+                let (scale_x, scale_y) = transform.get_scale();
+                let width = stroke.width.get() * (scale_x * scale_y).sqrt();
+
+                flatten(
+                    dashed.as_ref().unwrap_or(&path.data),
+                    tolerance,
+                    |start, end| {
+                        let (mut start, mut end) = (start, end);
+                        transform.map_point(&mut start);
+                        transform.map_point(&mut end);
+                        segments.push(Segment {
+                            start: (start.x, start.y),
+                            end: (end.x, end.y),
+                            color: Color::from_usvg(color),
+                            width,
+                        });
+                    },
+                );
+            }
+        }
+        segments
+    }
+}
+
+/// Reorder `segments` so that a pen plotter drawing them in order, starting
+/// with its pen at `start`, spends less time moving with the pen lifted.
+/// Returns the total distance the pen travels between segments, before and
+/// after reordering.
+///
+/// This picks the segments greedily: after each segment, the next one is
+/// whichever undrawn segment has an end closest to where the pen is, and it
+/// is turned around (swapping `start` and `end`) if the pen is closer to its
+/// end. Lines made of several segments stay together, since each segment
+/// starts where the last one finished. Segments the same distance away are
+/// taken in their original order, so the result is always the same for the
+/// same input. The greedy choice is usually a big improvement, but isn't
+/// always the shortest possible; if it would somehow make the travel longer,
+/// the original order is kept.
+///
+/// ```rs
+/// let mut segments = image.to_segments(0.1);
+/// let (before, after) = optimize_segment_order(&mut segments, (0.0, 0.0));
+/// println!("Pen-up travel went from {before} to {after} units");
+/// ```
+pub fn optimize_segment_order(segments: &mut [Segment], start: (f32, f32)) -> (f32, f32) {
+    let before = travel(segments, start);
+
+    let mut grid = EndpointGrid::new(segments, start);
+    let mut ordered = Vec::with_capacity(segments.len());
+    let mut pen = start;
+    while let Some((index, flipped)) = grid.nearest(segments, pen) {
+        grid.remove(segments, index);
+        let mut segment = segments[index];
+        if flipped {
+            std::mem::swap(&mut segment.start, &mut segment.end);
+        }
+        pen = segment.end;
+        ordered.push(segment);
+    }
+
+    let after = travel(&ordered, start);
+    if after > before {
+        return (before, before);
+    }
+    segments.copy_from_slice(&ordered);
+    (before, after)
+}
+
+/// The total distance the pen moves between `segments`, starting at `start`.
+fn travel(segments: &[Segment], start: (f32, f32)) -> f32 {
+    let mut pen = start;
+    let mut total = 0.0;
+    for segment in segments {
+        total += distance_squared(pen, segment.start).sqrt();
+        pen = segment.end;
+    }
+    total
+}
+
+fn distance_squared(a: (f32, f32), b: (f32, f32)) -> f32 {
+    (a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)
+}
+
+/// The ends of a list of segments, sorted into square cells so the nearest
+/// one to a point can be found without checking every segment.
+struct EndpointGrid {
+    origin: (f32, f32),
+    cell_size: f32,
+    cols: usize,
+    rows: usize,
+    /// The indices of the segments with an end in each cell, row by row.
+    cells: Vec<Vec<usize>>,
+}
+
+impl EndpointGrid {
+    /// A grid covering `extra` and the ends of every segment, with about one
+    /// segment per cell.
+    fn new(segments: &[Segment], extra: (f32, f32)) -> EndpointGrid {
+        let points = segments
+            .iter()
+            .flat_map(|segment| [segment.start, segment.end])
+            .chain(std::iter::once(extra))
+            .filter(|point| point.0.is_finite() && point.1.is_finite());
+        let (mut min, mut max) = (extra, extra);
+        for point in points {
+            min = (min.0.min(point.0), min.1.min(point.1));
+            max = (max.0.max(point.0), max.1.max(point.1));
+        }
+
+        let side = (segments.len() as f32).sqrt().ceil().max(1.0);
+        let cell_size = ((max.0 - min.0).max(max.1 - min.1) / side).max(1.0);
+        let cols = ((max.0 - min.0) / cell_size).floor() as usize + 1;
+        let rows = ((max.1 - min.1) / cell_size).floor() as usize + 1;
+
+        let mut grid = EndpointGrid {
+            origin: min,
+            cell_size,
+            cols,
+            rows,
+            cells: vec![Vec::new(); cols * rows],
+        };
+        for (index, segment) in segments.iter().enumerate() {
+            let start = grid.cell_of(segment.start);
+            let end = grid.cell_of(segment.end);
+            grid.cells[start].push(index);
+            if end != start {
+                grid.cells[end].push(index);
+            }
+        }
+        grid
+    }
+
+    /// The column and row of the cell containing `point`, clamped onto the grid.
+    fn cell_position(&self, point: (f32, f32)) -> (usize, usize) {
+        let col = ((point.0 - self.origin.0) / self.cell_size).floor() as usize;
+        let row = ((point.1 - self.origin.1) / self.cell_size).floor() as usize;
+        (col.min(self.cols - 1), row.min(self.rows - 1))
+    }
+
+    fn cell_of(&self, point: (f32, f32)) -> usize {
+        let (col, row) = self.cell_position(point);
+        row * self.cols + col
+    }
+
+    /// Take a segment out of the grid, once it has been drawn.
+    fn remove(&mut self, segments: &[Segment], index: usize) {
+        for point in [segments[index].start, segments[index].end] {
+            let cell = self.cell_of(point);
+            self.cells[cell].retain(|&other| other != index);
+        }
+    }
+
+    /// The segment with an end nearest to `point`, and whether that end is
+    /// its `end` (so it should be turned around). Ties go to the segment
+    /// that comes first, and then to its `start`.
+    fn nearest(&self, segments: &[Segment], point: (f32, f32)) -> Option<(usize, bool)> {
+        let (col, row) = self.cell_position(point);
+        let mut best: Option<(f32, usize, bool)> = None;
+
+        // Search outwards one ring of cells at a time. Every end not yet
+        // checked is at least `(ring - 1) * cell_size` away, so once
+        // something closer than that has been found, it is the nearest.
+        for ring in 0..=self.cols.max(self.rows) {
+            if let Some((nearest, ..)) = best {
+                let reach = ring.saturating_sub(1) as f32 * self.cell_size;
+                if nearest < reach * reach {
+                    break;
+                }
+            }
+
+            let rows = row.saturating_sub(ring)..=(row + ring).min(self.rows - 1);
+            for other_row in rows {
+                let cols = col.saturating_sub(ring)..=(col + ring).min(self.cols - 1);
+                for other_col in cols {
+                    if other_row.abs_diff(row).max(other_col.abs_diff(col)) != ring {
+                        continue;
+                    }
+                    for &index in &self.cells[other_row * self.cols + other_col] {
+                        let segment = &segments[index];
+                        for (flipped, end) in [(false, segment.start), (true, segment.end)] {
+                            let distance = distance_squared(point, end);
+                            let closer = best.is_none_or(|(nearest, best_index, best_flipped)| {
+                                distance
+                                    .total_cmp(&nearest)
+                                    .then(index.cmp(&best_index))
+                                    .then(flipped.cmp(&best_flipped))
+                                    .is_lt()
+                            });
+                            if closer {
+                                best = Some((distance, index, flipped));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        best.map(|(_, index, flipped)| (index, flipped))
+    }
+}
+
+/// Call `line` with the start and end of each straight piece of `path`, with
+/// curves split into pieces within `tolerance` of the curve.
+pub(crate) fn flatten(path: &tiny_skia::Path, tolerance: f32, mut line: impl FnMut(Point, Point)) {
+    let mut start = Point::zero();
+    let mut current = Point::zero();
+
+    for segment in path.segments() {
+        match segment {
+            tiny_skia::PathSegment::MoveTo(to) => {
+                start = to;
+                current = to;
+            }
+            tiny_skia::PathSegment::LineTo(to) => {
+                line(current, to);
+                current = to;
+            }
+            tiny_skia::PathSegment::QuadTo(control, to) => {
+                // A cubic curve with these control points is the same curve.
+                let control1 = lerp(current, control, 2.0 / 3.0);
+                let control2 = lerp(to, control, 2.0 / 3.0);
+                flatten_cubic([current, control1, control2, to], tolerance, 0, &mut line);
+                current = to;
+            }
+            tiny_skia::PathSegment::CubicTo(control1, control2, to) => {
+                flatten_cubic([current, control1, control2, to], tolerance, 0, &mut line);
+                current = to;
+            }
+            tiny_skia::PathSegment::Close => {
+                if current != start {
+                    line(current, start);
+                }
+                current = start;
+            }
+        }
+    }
+}
+
+type Point = tiny_skia::Point;
+
+/// The length of `a - 2b + c`, which measures how sharply a curve bends.
+fn second_difference(a: Point, b: Point, c: Point) -> f32 {
+    (a.x - 2.0 * b.x + c.x).hypot(a.y - 2.0 * b.y + c.y)
+}
+
+/// Split a cubic curve into straight pieces within `tolerance` of it, by
+/// cutting it in half until each half is flat enough. This puts more pieces
+/// where the curve bends sharply, and fewer where it is nearly straight.
+///
+/// A curve whose control points have second differences of at most `d` is
+/// within `3d / 4` of the line between its ends.
+fn flatten_cubic<F: FnMut(Point, Point)>(
+    [p0, p1, p2, p3]: [Point; 4],
+    tolerance: f32,
+    depth: u32,
+    line: &mut F,
+) {
+    let bend = second_difference(p0, p1, p2).max(second_difference(p1, p2, p3));
+    // Each half bends a quarter as much, so this always ends, but a huge
+    // curve with a tiny tolerance could still take too many pieces.
+    if depth >= MAX_DEPTH || 0.75 * bend <= tolerance || !bend.is_finite() {
+        line(p0, p3);
+        return;
+    }
+
+    let (a, b, c) = (lerp(p0, p1, 0.5), lerp(p1, p2, 0.5), lerp(p2, p3, 0.5));
+    let (d, e) = (lerp(a, b, 0.5), lerp(b, c, 0.5));
+    let middle = lerp(d, e, 0.5);
+    flatten_cubic([p0, a, d, middle], tolerance, depth + 1, line);
+    flatten_cubic([middle, e, c, p3], tolerance, depth + 1, line);
+}
+
+fn lerp(a: Point, b: Point, t: f32) -> Point {
+    Point::from_xy(a.x + (b.x - a.x) * t, a.y + (b.y - a.y) * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{flatten, optimize_segment_order, Point};
+    use crate::{Corner, Image, LineStyle, Segment, StrokeStyle, COLORS};
+    use resvg::{tiny_skia, usvg};
+
+    /// Draw each segment on its own, as a plotter would.
+    fn plot(segments: &[Segment], width: u32, height: u32) -> Image {
+        let mut image = Image::new(width, height);
+        for segment in segments {
+            let mut builder = tiny_skia::PathBuilder::new();
+            builder.move_to(segment.start.0, segment.start.1);
+            builder.line_to(segment.end.0, segment.end.1);
+            let mut path = usvg::Path::new(builder.finish().unwrap().into());
+            path.stroke = Some(usvg::Stroke {
+                paint: segment.color.paint(),
+                width: usvg::NonZeroPositiveF32::new(segment.width).unwrap(),
+                ..usvg::Stroke::default()
+            });
+            image.append_node(usvg::NodeKind::Path(path)).unwrap();
+        }
+        image
+    }
+
+    #[test]
+    fn plotting_the_segments_draws_the_image_again() {
+        let mut image = Image::new(100, 100);
+        image.draw_simple_line(10, 10, 90, 80, COLORS[1]).unwrap();
+        image.draw_simple_line(10, 20, 135, 60, COLORS[4]).unwrap();
+        let dashed = StrokeStyle {
+            width: 3.0,
+            style: LineStyle::Dashed,
+            ..StrokeStyle::default()
+        };
+        image
+            .draw_styled_line(90, 10, 180, 80, COLORS[3], &dashed)
+            .unwrap();
+        image.draw_wave(10, 80, 10, 40, 2.0, COLORS[7]).unwrap();
+
+        let segments = image.to_segments(0.05);
+        assert_eq!(segments[0].start, (10.0, 10.0));
+        assert_eq!(segments[0].end, (90.0, 10.0));
+        // 80 units of dashes 12 long, 9 apart.
+        let dashes = segments.iter().filter(|segment| segment.color == COLORS[3]);
+        assert_eq!(dashes.count(), 4);
+
+        let original = image.render().unwrap();
+        let plotted = plot(&segments, 100, 100).render().unwrap();
+        let worst = original
+            .pixels()
+            .iter()
+            .zip(plotted.pixels())
+            .map(|(a, b)| {
+                a.red()
+                    .abs_diff(b.red())
+                    .max(a.green().abs_diff(b.green()))
+                    .max(a.blue().abs_diff(b.blue()))
+            })
+            .max();
+        // Only where the pieces of the wave overlap at their ends, where the
+        // edges are drawn twice, do the pixels differ at all.
+        assert!(worst.unwrap() <= 40, "{worst:?}");
+    }
+
+    /// The greedy order `optimize_segment_order` describes, found by
+    /// checking every segment at every step.
+    fn greedy_order(segments: &[Segment], start: (f32, f32)) -> Vec<Segment> {
+        let mut left: Vec<Segment> = segments.to_vec();
+        let mut ordered = Vec::new();
+        let mut pen = start;
+        while !left.is_empty() {
+            let distance =
+                |point: (f32, f32)| (point.0 - pen.0).powi(2) + (point.1 - pen.1).powi(2);
+            let mut best = (f32::INFINITY, 0, false);
+            for (index, segment) in left.iter().enumerate() {
+                for (flipped, end) in [(false, segment.start), (true, segment.end)] {
+                    if distance(end) < best.0 {
+                        best = (distance(end), index, flipped);
+                    }
+                }
+            }
+            let mut segment = left.remove(best.1);
+            if best.2 {
+                std::mem::swap(&mut segment.start, &mut segment.end);
+            }
+            pen = segment.end;
+            ordered.push(segment);
+        }
+        ordered
+    }
+
+    /// `count` segments between points on a small grid, so that many ends
+    /// are the same distance apart.
+    fn grid_segments(count: usize, seed: u32) -> Vec<Segment> {
+        let mut state = seed;
+        let mut next = || {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (state >> 24) as f32 % 20.0 * 5.0
+        };
+        (0..count)
+            .map(|_| Segment {
+                start: (next(), next()),
+                end: (next(), next()),
+                color: COLORS[1],
+                width: 1.0,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn segments_are_ordered_the_same_way_every_time() {
+        for seed in 0..20 {
+            let segments = grid_segments(300, seed);
+            let mut first = segments.clone();
+            let (before, after) = optimize_segment_order(&mut first, (0.0, 0.0));
+            assert!(after <= before);
+            assert_eq!(first, greedy_order(&segments, (0.0, 0.0)), "seed {seed}");
+
+            let mut second = segments.clone();
+            assert_eq!(
+                optimize_segment_order(&mut second, (0.0, 0.0)),
+                (before, after)
+            );
+            assert_eq!(second, first);
+        }
+
+        // Ties go to the segment that comes first, then to its start.
+        let mut segments = vec![
+            Segment {
+                start: (10.0, 0.0),
+                end: (20.0, 0.0),
+                color: COLORS[1],
+                width: 1.0,
+            },
+            Segment {
+                start: (0.0, 10.0),
+                end: (0.0, 20.0),
+                color: COLORS[4],
+                width: 1.0,
+            },
+            Segment {
+                start: (-10.0, 0.0),
+                end: (0.0, -10.0),
+                color: COLORS[2],
+                width: 1.0,
+            },
+        ];
+        optimize_segment_order(&mut segments, (0.0, 0.0));
+        let colors: Vec<_> = segments.iter().map(|segment| segment.color).collect();
+        // Every segment has an end 10 from the start, and from (20, 0) the
+        // second segment's start and the third's end are both next nearest.
+        assert_eq!(colors, [COLORS[1], COLORS[4], COLORS[2]]);
+        assert_eq!(segments[1].start, (0.0, 10.0));
+    }
+
+    #[test]
+    fn segments_are_in_image_coordinates() {
+        let mut image = Image::new(100, 100);
+        image.set_coordinate_system(Corner::BottomLeft, true);
+        image.draw_simple_line(10, 10, 0, 50, COLORS[1]).unwrap();
+        assert_eq!(
+            image.to_segments(0.1),
+            vec![Segment {
+                start: (10.0, 90.0),
+                end: (10.0, 40.0),
+                color: COLORS[1],
+                width: 1.0,
+            }]
+        );
+    }
+
+    /// The straight pieces `flatten` splits a single cubic curve into.
+    fn pieces(curve: [(f32, f32); 4], tolerance: f32) -> Vec<(Point, Point)> {
+        let [p0, p1, p2, p3] = curve;
+        let mut builder = tiny_skia::PathBuilder::new();
+        builder.move_to(p0.0, p0.1);
+        builder.cubic_to(p1.0, p1.1, p2.0, p2.1, p3.0, p3.1);
+        let mut pieces = Vec::new();
+        flatten(&builder.finish().unwrap(), tolerance, |start, end| {
+            pieces.push((start, end));
+        });
+        pieces
+    }
+
+    /// How far `point` is from the nearest of `pieces`.
+    fn distance_to(pieces: &[(Point, Point)], point: Point) -> f32 {
+        pieces
+            .iter()
+            .map(|&(a, b)| {
+                let (dx, dy) = (b.x - a.x, b.y - a.y);
+                let along = ((point.x - a.x) * dx + (point.y - a.y) * dy) / (dx * dx + dy * dy);
+                let t = if along.is_finite() {
+                    along.clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                (a.x + dx * t - point.x).hypot(a.y + dy * t - point.y)
+            })
+            .fold(f32::INFINITY, f32::min)
+    }
+
+    fn point_on(curve: [(f32, f32); 4], t: f32) -> Point {
+        let s = 1.0 - t;
+        let weights = [s * s * s, 3.0 * s * s * t, 3.0 * s * t * t, t * t * t];
+        let x = (0..4).map(|i| weights[i] * curve[i].0).sum();
+        let y = (0..4).map(|i| weights[i] * curve[i].1).sum();
+        Point::from_xy(x, y)
+    }
+
+    #[test]
+    fn curves_stay_within_the_tolerance() {
+        let curve = [(0.0, 0.0), (100.0, 0.0), (100.0, 100.0), (0.0, 100.0)];
+        for tolerance in [0.01, 0.1, 1.0] {
+            let pieces = pieces(curve, tolerance);
+            assert_eq!(pieces[0].0, Point::from_xy(0.0, 0.0));
+            assert_eq!(pieces.last().unwrap().1, Point::from_xy(0.0, 100.0));
+            for pair in pieces.windows(2) {
+                assert_eq!(pair[0].1, pair[1].0, "the pieces are joined up");
+            }
+            for step in 0..=1000 {
+                let point = point_on(curve, step as f32 / 1000.0);
+                let distance = distance_to(&pieces, point);
+                assert!(distance <= tolerance * 1.01, "{distance} at {tolerance}");
+            }
+        }
+    }
+
+    #[test]
+    fn tighter_tolerances_take_more_pieces() {
+        let curve = [(0.0, 0.0), (100.0, 0.0), (100.0, 100.0), (0.0, 100.0)];
+        let counts: Vec<_> = [1.0, 0.1, 0.01]
+            .iter()
+            .map(|&tolerance| pieces(curve, tolerance).len())
+            .collect();
+        assert!(counts[0] < counts[1] && counts[1] < counts[2], "{counts:?}");
+    }
+
+    #[test]
+    fn more_pieces_where_curves_bend_more() {
+        // The same length, but one barely bends and the other is a U turn.
+        let gentle = pieces([(0.0, 0.0), (33.0, 2.0), (66.0, 2.0), (100.0, 0.0)], 0.1);
+        let sharp = pieces([(0.0, 0.0), (0.0, 65.0), (50.0, 65.0), (50.0, 0.0)], 0.1);
+        assert!(
+            gentle.len() * 4 <= sharp.len(),
+            "{} vs {}",
+            gentle.len(),
+            sharp.len()
+        );
+
+        // A curve with its control points on a line is drawn as one piece.
+        let straight = pieces([(0.0, 0.0), (30.0, 30.0), (60.0, 60.0), (90.0, 90.0)], 0.01);
+        assert_eq!(straight.len(), 1);
+    }
+
+    #[test]
+    fn huge_curves_take_at_most_1024_pieces() {
+        let curve = [(0.0, 0.0), (1e6, 0.0), (1e6, 1e6), (0.0, 1e6)];
+        assert_eq!(pieces(curve, 0.01).len(), 1024);
+    }
+}
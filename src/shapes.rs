@@ -0,0 +1,1420 @@
+use crate::{
+    convex_hull, i32_to_f32, u32_to_f32, Color, ElementId, FillRule, Image, IntoColor, StrokeStyle,
+};
+use resvg::usvg::NodeExt;
+use resvg::{tiny_skia, usvg};
+
+/// The most dashes `draw_varying_dashed_line` will draw, so that a pattern
+/// with tiny dashes and gaps can't run (almost) forever.
+const MAX_DASHES: usize = 1_000_000;
+
+/// The most cycles `draw_wave` will draw, so that a tiny wavelength can't
+/// make a path too big to draw.
+const MAX_WAVE_CYCLES: f32 = 250_000.0;
+
+/// Turn a finished path builder into a path node's data.
+fn finish(builder: tiny_skia::PathBuilder, what: &str) -> Result<usvg::Path, String> {
+    builder
+        .finish()
+        .map(|path| usvg::Path::new(path.into()))
+        .ok_or_else(|| format!("Could not draw {what}"))
+}
+
+/// The point at `angle` degrees around a circle, with 0 degrees being straight
+/// up and angles increasing clockwise (like directions in `draw_simple_line`).
+fn circle_point(center: (f32, f32), radius: f32, angle: f32) -> (f32, f32) {
+    let (sin, cos) = angle.to_radians().sin_cos();
+    (center.0 + radius * sin, center.1 - radius * cos)
+}
+
+/// The sine and cosine of `quarters` quarter turns, exact at whole quarter
+/// turns so a wave's peaks and troughs land exactly on its amplitude.
+fn quarter_sin_cos(quarters: f32) -> (f32, f32) {
+    let quarters = quarters.rem_euclid(4.0);
+    match quarters {
+        0.0 => (0.0, 1.0),
+        1.0 => (1.0, 0.0),
+        2.0 => (0.0, -1.0),
+        3.0 => (-1.0, 0.0),
+        _ => (quarters * std::f32::consts::FRAC_PI_2).sin_cos(),
+    }
+}
+
+/// Add an arc of a circle to `builder`, going from angle `start` to angle
+/// `end` (in degrees, as in `circle_point`). The builder must already be at
+/// the starting point of the arc.
+fn arc_to(
+    builder: &mut tiny_skia::PathBuilder,
+    center: (f32, f32),
+    radius: f32,
+    start: f32,
+    end: f32,
+) {
+    // Each cubic curve covers at most a quarter of the circle.
+    let segments = ((end - start).abs() / 90.0).ceil().max(1.0);
+    let sweep = (end - start) / segments;
+    // How far the control points sit along the tangents, for a close fit.
+    let handle = 4.0 / 3.0 * (sweep.to_radians() / 4.0).tan() * radius;
+
+    for i in 0..segments as u32 {
+        let from = start + sweep * i as f32;
+        let to = from + sweep;
+        let (x0, y0) = circle_point(center, radius, from);
+        let (x1, y1) = circle_point(center, radius, to);
+        let (sin0, cos0) = from.to_radians().sin_cos();
+        let (sin1, cos1) = to.to_radians().sin_cos();
+        builder.cubic_to(
+            x0 + handle * cos0,
+            y0 + handle * sin0,
+            x1 - handle * cos1,
+            y1 - handle * sin1,
+            x1,
+            y1,
+        );
+    }
+}
+
+impl Image {
+    /// Draw a grid of `cols` by `rows` square cells, each `cell_size` units
+    /// wide, with its top-left corner at `origin`.
+    ///
+    /// `cells` is called with the column and row of each cell, and returns the
+    /// color to fill it with, or `None` to leave it empty. If `grid_lines` is
+    /// given, lines of that color are drawn between (and around) all the cells.
+    ///
+    /// All the cells of one color are drawn as a single shape, so even very
+    /// large grids only add one path per color to the image. Cells outside the
+    /// image are cut off as usual.
+    ///
+    /// ```rs
+    /// // A checkerboard.
+    /// image.draw_cell_grid((0, 0), 8, 8, 10, &|col, row| {
+    ///     ((col + row) % 2 == 0).then_some(COLORS[7])
+    /// }, None)?;
+    /// ```
+    pub fn draw_cell_grid(
+        &mut self,
+        origin: (i32, i32),
+        cols: u32,
+        rows: u32,
+        cell_size: u32,
+        cells: &dyn Fn(u32, u32) -> Option<Color>,
+        grid_lines: Option<Color>,
+    ) -> Result<(), String> {
+        if cols == 0 || rows == 0 || cell_size == 0 {
+            return Err("A cell grid needs at least one row, column and unit of cell size".into());
+        }
+
+        let (x, y) = (origin.0 as f32, origin.1 as f32);
+        let size = cell_size as f32;
+        let bounds = tiny_skia::Rect::from_xywh(x, y, cols as f32 * size, rows as f32 * size)
+            .ok_or("Could not draw cell grid")?;
+        self.check_strict_bounds("cell grid", bounds)?;
+
+        // One path per color, in the order the colors first appear.
+        let mut fills: Vec<(Color, tiny_skia::PathBuilder)> = Vec::new();
+        for row in 0..rows {
+            let top = y + row as f32 * size;
+
+            // Neighbouring cells of the same color in a row become one rect.
+            let mut col = 0;
+            while col < cols {
+                let Some(color) = cells(col, row) else {
+                    col += 1;
+                    continue;
+                };
+
+                let start = col;
+                col += 1;
+                while col < cols && cells(col, row) == Some(color) {
+                    col += 1;
+                }
+
+                let rect = tiny_skia::Rect::from_xywh(
+                    x + start as f32 * size,
+                    top,
+                    (col - start) as f32 * size,
+                    size,
+                )
+                .ok_or("Could not draw cell grid")?;
+
+                match fills.iter_mut().find(|(fill, _)| *fill == color) {
+                    Some((_, builder)) => builder.push_rect(rect),
+                    None => {
+                        let mut builder = tiny_skia::PathBuilder::new();
+                        builder.push_rect(rect);
+                        fills.push((color, builder));
+                    }
+                }
+            }
+        }
+
+        let group = usvg::Node::new(usvg::NodeKind::Group(usvg::Group::default()));
+        for (color, builder) in fills {
+            let mut path = finish(builder, "cell grid")?;
+            path.fill = Some(usvg::Fill::from_paint(color.paint()));
+            group.append(usvg::Node::new(usvg::NodeKind::Path(path)));
+        }
+
+        if let Some(color) = grid_lines {
+            let (width, height) = (cols as f32 * size, rows as f32 * size);
+            let mut builder = tiny_skia::PathBuilder::new();
+            for col in 0..=cols {
+                builder.move_to(x + col as f32 * size, y);
+                builder.line_to(x + col as f32 * size, y + height);
+            }
+            for row in 0..=rows {
+                builder.move_to(x, y + row as f32 * size);
+                builder.line_to(x + width, y + row as f32 * size);
+            }
+
+            let mut path = finish(builder, "cell grid lines")?;
+            path.stroke = Some(usvg::Stroke {
+                paint: color.paint(),
+                ..usvg::Stroke::default()
+            });
+            group.append(usvg::Node::new(usvg::NodeKind::Path(path)));
+        }
+
+        self.append_element_node(group)?;
+        Ok(())
+    }
+
+    /// Draw part of a ring, like a circular progress bar, showing `fraction`
+    /// (from 0.0 to 1.0) of a full circle.
+    ///
+    /// The ring is centred on (`cx`, `cy`) and is `thickness` units thick,
+    /// half inside and half outside a circle of `radius`. It starts at the
+    /// top of the circle and goes clockwise, so a fraction of 0.25 fills the
+    /// top-right quarter. Fractions outside 0.0 to 1.0 are clamped, and a
+    /// fraction of 0.0 draws nothing.
+    ///
+    /// ```rs
+    /// // 75% complete.
+    /// image.draw_progress_arc(50, 50, 40, 10, 0.75, COLORS[3])?;
+    /// ```
+    pub fn draw_progress_arc(
+        &mut self,
+        cx: i32,
+        cy: i32,
+        radius: u32,
+        thickness: u32,
+        fraction: f32,
+        color: impl IntoColor,
+    ) -> Result<(), String> {
+        let color = color.into_color()?;
+        if thickness == 0 {
+            return Err("A progress arc must be at least 1 unit thick".into());
+        }
+        if fraction.is_nan() {
+            return Err("The fraction of a progress arc must be a number".into());
+        }
+        self.check_strict_fraction(fraction)?;
+
+        let fraction = fraction.clamp(0.0, 1.0);
+        if fraction == 0.0 {
+            return Ok(());
+        }
+
+        let center = (i32_to_f32(cx), i32_to_f32(cy));
+        let outer = u32_to_f32(radius) + u32_to_f32(thickness) / 2.0;
+        let inner = (u32_to_f32(radius) - u32_to_f32(thickness) / 2.0).max(0.0);
+        let end = fraction * 360.0;
+
+        // Around the outside clockwise, then back around the inside.
+        let mut builder = tiny_skia::PathBuilder::new();
+        let (x, y) = circle_point(center, outer, 0.0);
+        builder.move_to(x, y);
+        arc_to(&mut builder, center, outer, 0.0, end);
+        let (x, y) = circle_point(center, inner, end);
+        builder.line_to(x, y);
+        if inner > 0.0 {
+            arc_to(&mut builder, center, inner, end, 0.0);
+        }
+        builder.close();
+
+        let mut path = finish(builder, "progress arc")?;
+        self.check_strict_bounds("progress arc", path.data.bounds())?;
+        path.fill = Some(usvg::Fill {
+            paint: color.paint(),
+            opacity: color.opacity(),
+            ..usvg::Fill::default()
+        });
+        self.append_element(usvg::NodeKind::Path(path))?;
+        Ok(())
+    }
+
+    /// Draw the outline of the convex hull of `points`: the smallest convex
+    /// polygon that contains all of them, like a rubber band stretched around
+    /// the points. Returns the corners of the hull, as from [`convex_hull`].
+    ///
+    /// Duplicate points, and points on an edge of the hull, are not corners.
+    /// If every point is on one line, the hull is just a line between the two
+    /// furthest points.
+    ///
+    /// Returns an error if there are fewer than 2 different points.
+    ///
+    /// ```rs
+    /// let hull = image.draw_convex_hull(&[(10, 10), (90, 10), (50, 40), (90, 90), (10, 90)], COLORS[2])?;
+    /// assert_eq!(hull, vec![(10, 10), (90, 10), (90, 90), (10, 90)]);
+    /// ```
+    pub fn draw_convex_hull(
+        &mut self,
+        points: &[(i32, i32)],
+        color: impl IntoColor,
+    ) -> Result<Vec<(i32, i32)>, String> {
+        let color = color.into_color()?;
+        self.check_strict_points("convex hull", points)?;
+        let hull = convex_hull(points);
+        if hull.len() < 2 {
+            return Err("A convex hull needs at least 2 different points".into());
+        }
+
+        let mut builder = tiny_skia::PathBuilder::new();
+        builder.move_to(i32_to_f32(hull[0].0), i32_to_f32(hull[0].1));
+        for &(x, y) in &hull[1..] {
+            builder.line_to(i32_to_f32(x), i32_to_f32(y));
+        }
+        builder.close();
+
+        let mut path = finish(builder, "convex hull")?;
+        path.stroke = Some(usvg::Stroke {
+            paint: color.paint(),
+            opacity: color.opacity(),
+            ..usvg::Stroke::default()
+        });
+        self.append_element(usvg::NodeKind::Path(path))?;
+        Ok(hull)
+    }
+
+    /// Draw a line through each of `points` in turn, stroked with `style`.
+    ///
+    /// The segments are joined at each point with mitered corners, so
+    /// `style.miter_limit` chooses how sharp corners look. Unlike drawing each
+    /// segment with `draw_styled_line`, dashes and patterns carry on
+    /// around corners.
+    ///
+    /// Returns an error if there are fewer than 2 points, or if the style
+    /// isn't valid.
+    ///
+    /// ```rs
+    /// // A zigzag with its sharp points cut off.
+    /// let style = StrokeStyle {
+    ///     width: 4.0,
+    ///     miter_limit: 1.0,
+    ///     ..StrokeStyle::default()
+    /// };
+    /// image.draw_styled_polyline(&[(10, 90), (30, 10), (50, 90), (70, 10)], COLORS[1], &style)?;
+    /// ```
+    pub fn draw_styled_polyline(
+        &mut self,
+        points: &[(i32, i32)],
+        color: impl IntoColor,
+        style: &StrokeStyle,
+    ) -> Result<(), String> {
+        let stroke = style.to_usvg(color.into_color()?)?;
+        let [_, rest @ ..] = points else {
+            return Err("A polyline needs at least 2 points, but none were given".into());
+        };
+        if rest.is_empty() {
+            return Err("A polyline needs at least 2 points, but 1 was given".into());
+        }
+        self.check_strict_points("polyline", points)?;
+
+        let points: Vec<(f32, f32)> = points
+            .iter()
+            .map(|&(x, y)| (i32_to_f32(x), i32_to_f32(y)))
+            .collect();
+        let builder = style.pattern.outline(&points, style.width)?;
+
+        let mut path = finish(builder, "polyline")?;
+        path.stroke = Some(stroke);
+        self.append_element(usvg::NodeKind::Path(path))?;
+        Ok(())
+    }
+
+    /// Draw a line through each of `points` in turn, with a dot on every
+    /// point, to show where a drawing went and in what order (such as every
+    /// point a turtle visited).
+    ///
+    /// The dots are `dot_diameter` units across, in `dot_color`, except for
+    /// the first point, which gets a dot twice as wide so that it's clear
+    /// where the line starts. Later dots are drawn over the first, so a path
+    /// that comes back to where it started still shows a dot there. A single
+    /// point draws just its starting dot.
+    ///
+    /// However many points there are, the line and the dots add at most
+    /// three paths to the image, as a single element.
+    ///
+    /// Returns an error if there are no points, or if `dot_diameter` isn't
+    /// more than 0.
+    ///
+    /// ```rs
+    /// image.draw_connected_dots(&[(10, 10), (90, 10), (50, 80)], 4, COLORS[0], COLORS[4])?;
+    /// ```
+    pub fn draw_connected_dots(
+        &mut self,
+        points: &[(i32, i32)],
+        dot_diameter: i32,
+        line_color: impl IntoColor,
+        dot_color: impl IntoColor,
+    ) -> Result<(), String> {
+        let (line_color, dot_color) = (line_color.into_color()?, dot_color.into_color()?);
+        let [(x, y), rest @ ..] = points else {
+            return Err("Connected dots need at least 1 point, but none were given".into());
+        };
+        if dot_diameter <= 0 {
+            return Err(format!(
+                "The dots must be more than 0 across, but they were {dot_diameter}"
+            ));
+        }
+        self.check_strict_points("connected dots", points)?;
+
+        let group = usvg::Node::new(usvg::NodeKind::Group(usvg::Group::default()));
+        if !rest.is_empty() {
+            let mut builder = tiny_skia::PathBuilder::new();
+            builder.move_to(i32_to_f32(*x), i32_to_f32(*y));
+            for &(x, y) in rest {
+                builder.line_to(i32_to_f32(x), i32_to_f32(y));
+            }
+            let mut path = finish(builder, "connected dots")?;
+            path.stroke = Some(usvg::Stroke {
+                paint: line_color.paint(),
+                opacity: line_color.opacity(),
+                ..usvg::Stroke::default()
+            });
+            group.append_kind(usvg::NodeKind::Path(path));
+        }
+
+        let radius = i32_to_f32(dot_diameter) / 2.0;
+        let dots = [(&points[..1], radius * 2.0), (rest, radius)];
+        for (centres, radius) in dots {
+            if centres.is_empty() {
+                continue;
+            }
+            let mut builder = tiny_skia::PathBuilder::new();
+            for &(x, y) in centres {
+                builder.push_circle(i32_to_f32(x), i32_to_f32(y), radius);
+            }
+            let mut path = finish(builder, "connected dots")?;
+            path.fill = Some(usvg::Fill {
+                paint: dot_color.paint(),
+                opacity: dot_color.opacity(),
+                rule: usvg::FillRule::NonZero,
+            });
+            group.append_kind(usvg::NodeKind::Path(path));
+        }
+
+        self.append_element_node(group)?;
+        Ok(())
+    }
+
+    /// Like `draw_styled_line`, but with dashes that change along the line,
+    /// such as dashes that get longer, gaps that close up, or a Morse-code
+    /// rhythm.
+    ///
+    /// `pattern` chooses each dash in turn: it is given how far along the
+    /// line the dash starts (0.0 at the start of the line, up to 1.0 at the
+    /// end), and returns the length of the dash and of the gap after it, in
+    /// units. Each dash is a separate short stroke with `style`'s width and
+    /// caps, so round caps turn short dashes into dots. The last dash is cut
+    /// off at the end of the line. `style.style` and `style.pattern` are
+    /// ignored, since `pattern` takes their place. The whole line is one
+    /// element.
+    ///
+    /// Returns an error if a dash isn't more than 0 long, a gap is less than
+    /// 0 long, the line would need more than 1,000,000 dashes, or the style
+    /// isn't valid.
+    ///
+    /// ```rs
+    /// // Dashes 4 units long, with gaps growing from 1 to 9 units.
+    /// image.draw_varying_dashed_line(10, 50, 90, 80, COLORS[1], &StrokeStyle::default(), |t| {
+    ///     (4.0, 1.0 + 8.0 * t)
+    /// })?;
+    /// ```
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_varying_dashed_line(
+        &mut self,
+        x: i32,
+        y: i32,
+        direction: i32,
+        length: i32,
+        color: impl IntoColor,
+        style: &StrokeStyle,
+        mut pattern: impl FnMut(f32) -> (f32, f32),
+    ) -> Result<(i32, i32), String> {
+        let mut stroke = style.to_usvg(color.into_color()?)?;
+        stroke.dasharray = None;
+        self.check_strict_line(x, y, direction, length)?;
+        let end = self.line_end(x, y, direction, length);
+        let (x, y) = self.clamp_point((x, y));
+        let (end_x, end_y) = self.clamp_point(end);
+
+        let (start, end) = (
+            (i32_to_f32(x), i32_to_f32(y)),
+            (i32_to_f32(end_x), i32_to_f32(end_y)),
+        );
+        let total = (end.0 - start.0).hypot(end.1 - start.1);
+        // How far along the line a distance is, from 0.0 to 1.0.
+        let fraction = |at: f32| if total > 0.0 { at / total } else { 0.0 };
+        let point_at = |at: f32| {
+            let t = fraction(at);
+            (
+                start.0 + (end.0 - start.0) * t,
+                start.1 + (end.1 - start.1) * t,
+            )
+        };
+
+        let mut builder = tiny_skia::PathBuilder::new();
+        let (mut at, mut dashes) = (0.0, 0);
+        // A line of length 0 still gets one (empty) dash, as it would from
+        // `draw_styled_line`.
+        while at < total || dashes == 0 {
+            let (dash, gap) = pattern(fraction(at));
+            if !(dash > 0.0 && dash.is_finite()) {
+                return Err(format!(
+                    "A dash must be more than 0 long, but it was {dash}"
+                ));
+            }
+            if !(gap >= 0.0 && gap.is_finite()) {
+                return Err(format!("A gap must be at least 0 long, but it was {gap}"));
+            }
+            dashes += 1;
+            if dashes > MAX_DASHES {
+                return Err(format!(
+                    "The line would need more than {MAX_DASHES} dashes: use longer dashes or gaps"
+                ));
+            }
+
+            let (from, to) = (point_at(at), point_at((at + dash).min(total)));
+            builder.move_to(from.0, from.1);
+            builder.line_to(to.0, to.1);
+            at += dash + gap;
+        }
+
+        let mut path = finish(builder, "dashed line")?;
+        path.stroke = Some(stroke);
+        self.append_element(usvg::NodeKind::Path(path))?;
+        Ok((end_x, end_y))
+    }
+
+    /// Draw construction lines through the centre of the image: one across
+    /// its whole width, and one down its whole height. They are 1 unit wide,
+    /// and are one element, so they can be removed with `remove_element`
+    /// before saving the finished drawing.
+    ///
+    /// The lines go through the exact centre, at half the width and half the
+    /// height. For an odd size, that is the middle of the centre pixel, so
+    /// the line covers that column (or row) of pixels exactly. For an even
+    /// size, it is the edge between the two middle pixels, so the line
+    /// covers half of each, and looks a little lighter and blurrier.
+    ///
+    /// ```rs
+    /// let guides = image.draw_center_guides(COLORS[7])?;
+    /// // ... draw, lining things up with the guides ...
+    /// image.remove_element(guides);
+    /// image.save_png("finished.png")?;
+    /// ```
+    pub fn draw_center_guides(&mut self, color: impl IntoColor) -> Result<ElementId, String> {
+        let (min_x, min_y, max_x, max_y) = self.drawing_area();
+        let (left, top, right, bottom) = (
+            i32_to_f32(min_x),
+            i32_to_f32(min_y),
+            i32_to_f32(max_x),
+            i32_to_f32(max_y),
+        );
+        let (center_x, center_y) = ((left + right) / 2.0, (top + bottom) / 2.0);
+        self.draw_guides(
+            &[
+                (left, center_y, right, center_y),
+                (center_x, top, center_x, bottom),
+            ],
+            color,
+        )
+    }
+
+    /// Draw construction lines from corner to corner of the image, crossing
+    /// at its centre. They are 1 unit wide, and are one element, so they can
+    /// be removed with `remove_element` before saving the finished drawing.
+    ///
+    /// ```rs
+    /// let guides = image.draw_diagonals(COLORS[7])?;
+    /// ```
+    pub fn draw_diagonals(&mut self, color: impl IntoColor) -> Result<ElementId, String> {
+        let (min_x, min_y, max_x, max_y) = self.drawing_area();
+        let (left, top, right, bottom) = (
+            i32_to_f32(min_x),
+            i32_to_f32(min_y),
+            i32_to_f32(max_x),
+            i32_to_f32(max_y),
+        );
+        self.draw_guides(
+            &[(left, top, right, bottom), (right, top, left, bottom)],
+            color,
+        )
+    }
+
+    /// Draw straight lines, each given as `(x1, y1, x2, y2)`, as a single
+    /// path, for `draw_center_guides` and `draw_diagonals`.
+    fn draw_guides(
+        &mut self,
+        lines: &[(f32, f32, f32, f32)],
+        color: impl IntoColor,
+    ) -> Result<ElementId, String> {
+        let color = color.into_color()?;
+        let mut builder = tiny_skia::PathBuilder::new();
+        for &(x1, y1, x2, y2) in lines {
+            builder.move_to(x1, y1);
+            builder.line_to(x2, y2);
+        }
+
+        let mut path = finish(builder, "guides")?;
+        path.stroke = Some(usvg::Stroke {
+            paint: color.paint(),
+            opacity: color.opacity(),
+            ..usvg::Stroke::default()
+        });
+        self.append_element(usvg::NodeKind::Path(path))
+    }
+
+    /// Draw a line through `points`, with each segment in its own color:
+    /// `colors[0]` from `points[0]` to `points[1]`, `colors[1]` from
+    /// `points[1]` to `points[2]`, and so on.
+    ///
+    /// Neighbouring segments of the same color are drawn as one line, so
+    /// they join neatly at corners. Where the color changes, the segments
+    /// just meet end to end. The whole path is one element.
+    ///
+    /// Returns an error if there are fewer than 2 points, or if there isn't
+    /// exactly one color per segment (one fewer than the number of points),
+    /// or if any color isn't valid.
+    ///
+    /// ```rs
+    /// // Red across the top, then blue down the side.
+    /// image.draw_multicolor_path(&[(10, 10), (90, 10), (90, 90)], &[COLORS[4], COLORS[1]])?;
+    /// ```
+    pub fn draw_multicolor_path<C: IntoColor + Copy>(
+        &mut self,
+        points: &[(i32, i32)],
+        colors: &[C],
+    ) -> Result<(), String> {
+        if points.len() < 2 {
+            return Err(format!(
+                "A path needs at least 2 points, but {} were given",
+                points.len()
+            ));
+        }
+        if colors.len() != points.len() - 1 {
+            return Err(format!(
+                "A path needs one color per segment, but there are {} segments and {} colors",
+                points.len() - 1,
+                colors.len()
+            ));
+        }
+        let colors = colors
+            .iter()
+            .map(|&color| color.into_color())
+            .collect::<Result<Vec<_>, _>>()?;
+        self.check_strict_points("path", points)?;
+
+        let group = usvg::Node::new(usvg::NodeKind::Group(usvg::Group::default()));
+        let mut start = 0;
+        while start < colors.len() {
+            let color = colors[start];
+            let end = colors[start..]
+                .iter()
+                .position(|&other| other != color)
+                .map_or(colors.len(), |run| start + run);
+
+            let mut builder = tiny_skia::PathBuilder::new();
+            builder.move_to(i32_to_f32(points[start].0), i32_to_f32(points[start].1));
+            for &(x, y) in &points[start + 1..=end] {
+                builder.line_to(i32_to_f32(x), i32_to_f32(y));
+            }
+
+            let mut path = finish(builder, "path")?;
+            path.stroke = Some(usvg::Stroke {
+                paint: color.paint(),
+                opacity: color.opacity(),
+                ..usvg::Stroke::default()
+            });
+            group.append_kind(usvg::NodeKind::Path(path));
+            start = end;
+        }
+
+        self.append_element_node(group)?;
+        Ok(())
+    }
+
+    /// Fill the polygon with corners at `points`, in order. The polygon is
+    /// closed automatically, so the last point does not need to repeat the
+    /// first.
+    ///
+    /// `rule` decides what is inside a polygon whose edges cross each other
+    /// (see [`FillRule`]). For example, a pentagram drawn with one line
+    /// through every second point of a pentagon is solid with
+    /// `FillRule::NonZero`, but has a pentagon-shaped hole in the middle with
+    /// `FillRule::EvenOdd`.
+    ///
+    /// Returns an error if there are fewer than 3 points.
+    ///
+    /// ```rs
+    /// let star = [(50, 5), (76, 85), (8, 35), (92, 35), (24, 85)];
+    /// image.fill_polygon(&star, COLORS[6], FillRule::EvenOdd)?;
+    /// ```
+    pub fn fill_polygon(
+        &mut self,
+        points: &[(i32, i32)],
+        color: impl IntoColor,
+        rule: FillRule,
+    ) -> Result<(), String> {
+        let color = color.into_color()?;
+        if points.len() < 3 {
+            return Err(format!(
+                "A polygon needs at least 3 points, but {} were given",
+                points.len()
+            ));
+        }
+        self.check_strict_points("polygon", points)?;
+
+        let mut builder = tiny_skia::PathBuilder::new();
+        builder.move_to(i32_to_f32(points[0].0), i32_to_f32(points[0].1));
+        for &(x, y) in &points[1..] {
+            builder.line_to(i32_to_f32(x), i32_to_f32(y));
+        }
+        builder.close();
+
+        let mut path = finish(builder, "polygon")?;
+        path.fill = Some(usvg::Fill {
+            paint: color.paint(),
+            opacity: color.opacity(),
+            rule: rule.to_usvg(),
+        });
+        self.append_element(usvg::NodeKind::Path(path))?;
+        Ok(())
+    }
+
+    /// Fill the ring between two circles centred on (`cx`, `cy`), like a
+    /// target or a gauge. With an `inner_radius` of 0, this is a filled
+    /// circle.
+    ///
+    /// The ring is a single shape with a hole in it, so the background (and
+    /// anything drawn earlier) shows through the middle.
+    ///
+    /// Returns an error unless `inner_radius` is less than `outer_radius`.
+    ///
+    /// ```rs
+    /// // A red ring 10 units wide.
+    /// image.draw_ring(50, 50, 40, 30, COLORS[4])?;
+    /// ```
+    pub fn draw_ring(
+        &mut self,
+        cx: i32,
+        cy: i32,
+        outer_radius: u32,
+        inner_radius: u32,
+        color: impl IntoColor,
+    ) -> Result<(), String> {
+        let color = color.into_color()?;
+        if inner_radius >= outer_radius {
+            return Err(format!(
+                "A ring's inner radius must be less than its outer radius, but they were {inner_radius} and {outer_radius}"
+            ));
+        }
+
+        let (x, y) = (i32_to_f32(cx), i32_to_f32(cy));
+        let mut builder = tiny_skia::PathBuilder::new();
+        builder.push_circle(x, y, u32_to_f32(outer_radius));
+        if inner_radius > 0 {
+            builder.push_circle(x, y, u32_to_f32(inner_radius));
+        }
+
+        let mut path = finish(builder, "ring")?;
+        self.check_strict_bounds("ring", path.data.bounds())?;
+        path.fill = Some(usvg::Fill {
+            paint: color.paint(),
+            opacity: color.opacity(),
+            rule: usvg::FillRule::EvenOdd,
+        });
+        self.append_element(usvg::NodeKind::Path(path))?;
+        Ok(())
+    }
+
+    /// Draw a filled band that follows `points`, with a different width at
+    /// each point, like a brush stroke that thickens and thins.
+    ///
+    /// `widths[i]` is the width of the band at `points[i]`, and the width
+    /// changes smoothly along each segment between the points. At corners,
+    /// the edges of the band meet in a point, like a mitred stroke.
+    ///
+    /// Returns an error if there are fewer than 2 points, if `points` and
+    /// `widths` have different lengths, if any width is negative, or if all
+    /// the points are the same.
+    ///
+    /// ```rs
+    /// // A band that widens from 2 units to 10 units.
+    /// image.draw_ribbon(&[(10, 50), (90, 50)], &[2.0, 10.0], COLORS[5])?;
+    /// ```
+    pub fn draw_ribbon(
+        &mut self,
+        points: &[(i32, i32)],
+        widths: &[f32],
+        color: impl IntoColor,
+    ) -> Result<(), String> {
+        let color = color.into_color()?;
+        if points.len() < 2 {
+            return Err(format!(
+                "A ribbon needs at least 2 points, but {} were given",
+                points.len()
+            ));
+        }
+        if points.len() != widths.len() {
+            return Err(format!(
+                "A ribbon needs one width per point, but there are {} points and {} widths",
+                points.len(),
+                widths.len()
+            ));
+        }
+        if let Some(width) = widths
+            .iter()
+            .find(|width| !(**width >= 0.0 && width.is_finite()))
+        {
+            return Err(format!(
+                "Ribbon widths must be 0 or more, but one was {width}"
+            ));
+        }
+
+        let points: Vec<(f32, f32)> = points
+            .iter()
+            .map(|&(x, y)| (i32_to_f32(x), i32_to_f32(y)))
+            .collect();
+
+        // The direction of each segment, with zero-length segments taking
+        // the direction of the nearest segment before (or else after) them.
+        let mut directions: Vec<Option<(f32, f32)>> = points
+            .windows(2)
+            .map(|pair| {
+                let (dx, dy) = (pair[1].0 - pair[0].0, pair[1].1 - pair[0].1);
+                let length = dx.hypot(dy);
+                (length > 0.0).then(|| (dx / length, dy / length))
+            })
+            .collect();
+        let first = directions
+            .iter()
+            .flatten()
+            .copied()
+            .next()
+            .ok_or("A ribbon needs at least two different points")?;
+        let mut previous = first;
+        for direction in &mut directions {
+            previous = *direction.get_or_insert(previous);
+        }
+        let directions: Vec<(f32, f32)> = directions.into_iter().flatten().collect();
+
+        // How far each side of the band is from each point, at right angles
+        // to the path: the average of the neighbouring segments' normals,
+        // lengthened at corners so the band keeps its width.
+        let offsets: Vec<(f32, f32)> = (0..points.len())
+            .map(|i| {
+                let before = directions[i.saturating_sub(1)];
+                let after = directions[i.min(directions.len() - 1)];
+                let (nx, ny) = (-(before.1 + after.1), before.0 + after.0);
+                let length = nx.hypot(ny);
+                let half_width = widths[i] / 2.0;
+                if length < 1e-3 {
+                    // The path doubles straight back on itself.
+                    return (-before.1 * half_width, before.0 * half_width);
+                }
+                // Normalised, and then divided by the cosine of half the turn.
+                let (nx, ny) = (nx / length, ny / length);
+                let cos = (nx * -after.1 + ny * after.0).max(0.25);
+                (nx * half_width / cos, ny * half_width / cos)
+            })
+            .collect();
+
+        let mut builder = tiny_skia::PathBuilder::new();
+        let sides = points.iter().zip(&offsets);
+        for (i, (&(x, y), &(ox, oy))) in sides.clone().enumerate() {
+            if i == 0 {
+                builder.move_to(x + ox, y + oy);
+            } else {
+                builder.line_to(x + ox, y + oy);
+            }
+        }
+        for (&(x, y), &(ox, oy)) in sides.rev() {
+            builder.line_to(x - ox, y - oy);
+        }
+        builder.close();
+
+        let mut path = finish(builder, "ribbon")?;
+        self.check_strict_bounds("ribbon", path.data.bounds())?;
+        path.fill = Some(usvg::Fill {
+            paint: color.paint(),
+            opacity: color.opacity(),
+            ..usvg::Fill::default()
+        });
+        self.append_element(usvg::NodeKind::Path(path))?;
+        Ok(())
+    }
+
+    /// Draw a sine wave going right from `x_start`, centred on the line
+    /// `y = y_center`, for `cycles` whole (or part) waves of `wavelength`
+    /// units each.
+    ///
+    /// The wave starts at `y_center` going up, reaches its peak at
+    /// `y_center - amplitude` a quarter of a wavelength along, comes back
+    /// down through `y_center` to its trough at `y_center + amplitude`, and
+    /// so on. A negative `amplitude` starts going down instead. The wave is
+    /// one smooth curve, made of a curve for each quarter of a wavelength
+    /// that meets the peaks and troughs exactly.
+    ///
+    /// Returns an error if `wavelength` isn't more than 0, or if `cycles`
+    /// isn't a number more than 0 (and at most 250,000).
+    ///
+    /// ```rs
+    /// // Three waves across a 300 by 100 image.
+    /// image.draw_wave(0, 50, 40, 100, 3.0, COLORS[2])?;
+    /// ```
+    pub fn draw_wave(
+        &mut self,
+        x_start: i32,
+        y_center: i32,
+        amplitude: i32,
+        wavelength: i32,
+        cycles: f32,
+        color: impl IntoColor,
+    ) -> Result<(), String> {
+        let stroke = StrokeStyle::default().to_usvg(color.into_color()?)?;
+        if wavelength <= 0 {
+            return Err(format!(
+                "The wavelength of a wave must be more than 0, but it is {wavelength}"
+            ));
+        }
+        if !(cycles > 0.0 && cycles <= MAX_WAVE_CYCLES) {
+            return Err(format!(
+                "A wave must have more than 0 and at most {MAX_WAVE_CYCLES} cycles, but it has {cycles}"
+            ));
+        }
+
+        let (x_start, y_center) = (i32_to_f32(x_start), i32_to_f32(y_center));
+        let amplitude = i32_to_f32(amplitude);
+        let quarter = i32_to_f32(wavelength) / 4.0;
+        // The wave at `t` quarters along, and its slope there.
+        let wave = |t: f32| {
+            let (sin, cos) = quarter_sin_cos(t);
+            let slope = -amplitude * cos * std::f32::consts::FRAC_PI_2 / quarter;
+            (x_start + t * quarter, y_center - amplitude * sin, slope)
+        };
+
+        // Each piece is a cubic curve with the same ends and slopes at its
+        // ends as the wave.
+        let mut builder = tiny_skia::PathBuilder::new();
+        let (x, y, _) = wave(0.0);
+        builder.move_to(x, y);
+        let end = cycles * 4.0;
+        let mut from = 0.0;
+        while from < end {
+            let to = (from + 1.0).min(end);
+            let (x0, y0, slope0) = wave(from);
+            let (x1, y1, slope1) = wave(to);
+            let handle = (x1 - x0) / 3.0;
+            builder.cubic_to(
+                x0 + handle,
+                y0 + handle * slope0,
+                x1 - handle,
+                y1 - handle * slope1,
+                x1,
+                y1,
+            );
+            from = to;
+        }
+
+        let mut path = finish(builder, "wave")?;
+        self.check_strict_bounds("wave", path.data.bounds())?;
+        path.stroke = Some(stroke);
+        self.append_element(usvg::NodeKind::Path(path))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Corner, FillRule, Image, StrokeStyle, COLORS};
+    use resvg::usvg;
+
+    /// The number of paths an element is drawn with.
+    fn path_count(image: &Image) -> usize {
+        let node = image.element_node(image.last_element().unwrap()).unwrap();
+        let count = node
+            .descendants()
+            .filter(|node| matches!(*node.borrow(), usvg::NodeKind::Path(_)))
+            .count();
+        count
+    }
+
+    #[test]
+    fn huge_cell_grids_are_drawn_with_one_path_per_color() {
+        let mut image = Image::new(1000, 1000);
+        image
+            .draw_cell_grid(
+                (0, 0),
+                1000,
+                1000,
+                1,
+                &|col, row| match (col + row) % 3 {
+                    0 => Some(COLORS[1]),
+                    1 => Some(COLORS[4]),
+                    _ => None,
+                },
+                Some(COLORS[7]),
+            )
+            .unwrap();
+        // One path for each color, and one for the grid lines.
+        assert_eq!(path_count(&image), 3);
+        // The grid lines stick half a unit out, rounded outwards.
+        assert_eq!(
+            image.element_bounds(image.last_element().unwrap()),
+            Some((-1, -1, 1001, 1001))
+        );
+    }
+
+    #[test]
+    fn multicolor_path_takes_any_kind_of_color() {
+        let points = [(10, 10), (90, 10), (90, 90)];
+        let mut by_color = Image::new(100, 100);
+        by_color
+            .draw_multicolor_path(&points, &[COLORS[4], COLORS[1]])
+            .unwrap();
+        let mut by_index = Image::new(100, 100);
+        by_index.draw_multicolor_path(&points, &[4, 1]).unwrap();
+        assert_eq!(by_index.to_segments(0.1), by_color.to_segments(0.1));
+
+        assert!(by_index.draw_multicolor_path(&points, &[4, 99]).is_err());
+        assert_eq!(by_index.element_ids().len(), 1);
+    }
+
+    /// The positions of the pixels that aren't black.
+    fn lit_pixels(image: &Image) -> Vec<(u32, u32)> {
+        let pixmap = image.render().unwrap();
+        (0..pixmap.height())
+            .flat_map(|y| (0..pixmap.width()).map(move |x| (x, y)))
+            .filter(|&(x, y)| pixmap.pixel(x, y).unwrap().red() > 128)
+            .collect()
+    }
+
+    #[test]
+    fn progress_arc_fills_its_fraction_of_the_ring() {
+        let arc = |fraction| {
+            let mut image = Image::new(100, 100);
+            image
+                .draw_progress_arc(50, 50, 40, 10, fraction, COLORS[7])
+                .unwrap();
+            image
+        };
+
+        let ring = lit_pixels(&arc(1.0)).len() as f32;
+        let quarter = lit_pixels(&arc(0.25));
+        let share = quarter.len() as f32 / ring;
+        assert!((0.23..0.27).contains(&share), "{share}");
+        assert!(quarter.iter().all(|&(x, y)| x >= 50 && y < 50));
+
+        let empty = arc(0.0);
+        assert_eq!(empty.last_element(), None);
+        assert!(lit_pixels(&empty).is_empty());
+    }
+
+    #[test]
+    fn ribbons_widen_along_their_points_and_keep_their_width_at_corners() {
+        let mut image = Image::new(100, 100);
+        image
+            .draw_ribbon(&[(10, 50), (90, 50)], &[2.0, 18.0], COLORS[7])
+            .unwrap();
+        let lit = lit_pixels(&image);
+        let column = |x| lit.iter().filter(|&&(lit_x, _)| lit_x == x).count();
+        // 2.2 and 17.8 units wide in the middle of the first and last columns.
+        assert!((2..=3).contains(&column(10)), "{}", column(10));
+        assert!((17..=18).contains(&column(89)), "{}", column(89));
+        assert_eq!(column(50), 10);
+        assert_eq!(column(9), 0);
+        assert_eq!(column(90), 0);
+
+        let mut image = Image::new(100, 100);
+        image
+            .draw_ribbon(&[(20, 20), (80, 20), (80, 80)], &[10.0; 3], COLORS[7])
+            .unwrap();
+        // The outside of the corner is mitred, and the ends are cut square.
+        assert_eq!(
+            image.element_bounds(image.last_element().unwrap()),
+            Some((20, 15, 85, 80))
+        );
+        let lit = lit_pixels(&image);
+        assert!(lit.contains(&(84, 15)) && lit.contains(&(75, 24)));
+        assert!(!lit.contains(&(74, 25)));
+
+        assert!(image.draw_ribbon(&[(10, 10)], &[1.0], COLORS[7]).is_err());
+        assert!(image
+            .draw_ribbon(&[(10, 10), (20, 10)], &[1.0], COLORS[7])
+            .is_err());
+        assert!(image
+            .draw_ribbon(&[(10, 10), (20, 10)], &[1.0, -1.0], COLORS[7])
+            .is_err());
+        assert!(image
+            .draw_ribbon(&[(10, 10), (10, 10)], &[1.0, 1.0], COLORS[7])
+            .is_err());
+        assert_eq!(image.element_ids().len(), 1);
+    }
+
+    #[test]
+    fn convex_hulls_leave_out_points_inside_them() {
+        let mut image = Image::new(100, 100);
+        let hull = image
+            .draw_convex_hull(
+                &[
+                    (50, 50),
+                    (90, 90),
+                    (10, 10),
+                    (50, 10),
+                    (90, 10),
+                    (10, 90),
+                    (10, 10),
+                ],
+                COLORS[2],
+            )
+            .unwrap();
+        assert_eq!(hull, [(10, 10), (90, 10), (90, 90), (10, 90)]);
+
+        let segments: Vec<_> = image
+            .to_segments(0.1)
+            .into_iter()
+            .map(|segment| (segment.start, segment.end))
+            .collect();
+        assert_eq!(
+            segments,
+            [
+                ((10.0, 10.0), (90.0, 10.0)),
+                ((90.0, 10.0), (90.0, 90.0)),
+                ((90.0, 90.0), (10.0, 90.0)),
+                ((10.0, 90.0), (10.0, 10.0)),
+            ]
+        );
+
+        assert!(image
+            .draw_convex_hull(&[(5, 5), (5, 5)], COLORS[2])
+            .is_err());
+        assert_eq!(image.element_ids().len(), 1);
+    }
+
+    #[test]
+    fn rings_show_what_is_under_them_through_the_middle() {
+        let mut image = Image::new(100, 100);
+        image.draw_simple_line(0, 50, 90, 100, COLORS[1]).unwrap();
+        image.draw_ring(50, 50, 40, 30, COLORS[7]).unwrap();
+        let pixmap = image.render().unwrap();
+        let pixel = |x, y| {
+            let pixel = pixmap.pixel(x, y).unwrap();
+            (pixel.red(), pixel.blue())
+        };
+
+        // The band, where the line is hidden under the ring.
+        assert_eq!(pixel(50, 15), (255, 255));
+        assert_eq!(pixel(15, 50).0, 255);
+        assert_eq!(pixel(85, 49).0, 255);
+        // The hole, where the line shows through, and outside the ring.
+        assert_eq!(pixel(50, 50).0, 0);
+        assert!(pixel(50, 50).1 > 64);
+        assert_eq!(pixel(50, 25), (0, 0));
+        assert_eq!(pixel(50, 5), (0, 0));
+        assert_eq!(pixel(95, 49).0, 0);
+
+        let band = lit_pixels(&image).len() as f32;
+        let area = std::f32::consts::PI * (40.0 * 40.0 - 30.0 * 30.0);
+        assert!((band - area).abs() < area * 0.02, "{band} {area}");
+
+        assert!(image.draw_ring(50, 50, 30, 30, COLORS[7]).is_err());
+        assert!(image.draw_ring(50, 50, 20, 30, COLORS[7]).is_err());
+        assert_eq!(image.element_ids().len(), 2);
+    }
+
+    #[test]
+    fn pentagrams_have_a_hole_only_with_even_odd() {
+        let star = [(50, 5), (76, 85), (8, 35), (92, 35), (24, 85)];
+        let filled = |rule| {
+            let mut image = Image::new(100, 100);
+            image.fill_polygon(&star, COLORS[7], rule).unwrap();
+            let pixmap = image.render().unwrap();
+            move |x, y| pixmap.pixel(x, y).unwrap().red() > 128
+        };
+
+        let non_zero = filled(FillRule::NonZero);
+        let even_odd = filled(FillRule::EvenOdd);
+        // The middle, the tip of a point, and outside the star.
+        assert!(non_zero(50, 50) && !even_odd(50, 50));
+        assert!(non_zero(50, 15) && even_odd(50, 15));
+        assert!(non_zero(15, 38) && even_odd(15, 38));
+        assert!(!non_zero(10, 80) && !even_odd(10, 80));
+
+        let mut image = Image::new(100, 100);
+        assert!(image
+            .fill_polygon(&[(10, 10), (20, 20)], COLORS[7], FillRule::NonZero)
+            .is_err());
+        assert_eq!(image.last_element(), None);
+    }
+
+    #[test]
+    fn varying_dashes_have_the_gaps_they_were_given() {
+        let mut image = Image::new(100, 100);
+        let style = StrokeStyle {
+            width: 2.0,
+            ..StrokeStyle::default()
+        };
+        // Dashes 4 long, with gaps of 2 until half way, and then of 6.
+        let end = image
+            .draw_varying_dashed_line(0, 50, 90, 100, COLORS[7], &style, |t| {
+                (4.0, if t < 0.5 { 2.0 } else { 6.0 })
+            })
+            .unwrap();
+        assert_eq!(end, (100, 50));
+        assert_eq!(path_count(&image), 1);
+
+        // The runs of unlit columns along the line, as (start, length).
+        let lit: Vec<u32> = lit_pixels(&image)
+            .into_iter()
+            .filter(|&(_, y)| y == 50)
+            .map(|(x, _)| x)
+            .collect();
+        let mut gaps: Vec<(u32, u32)> = Vec::new();
+        for pair in lit.windows(2) {
+            if pair[1] > pair[0] + 1 {
+                gaps.push((pair[0] + 1, pair[1] - pair[0] - 1));
+            }
+        }
+        // Dashes start at 0, 6, 12, ... 48, then 54, 64, 74, 84 and 94.
+        let mut expected: Vec<(u32, u32)> = (0..9).map(|i| (4 + 6 * i, 2)).collect();
+        expected.extend((0..4).map(|i| (58 + 10 * i, 6)));
+        assert_eq!(gaps, expected);
+        assert_eq!(lit.last(), Some(&97));
+    }
+
+    #[test]
+    fn varying_dashes_must_have_length() {
+        let mut image = Image::new(100, 100);
+        let style = StrokeStyle::default();
+        let mut draw = |pattern: fn(f32) -> (f32, f32)| {
+            image.draw_varying_dashed_line(0, 50, 90, 100, COLORS[7], &style, pattern)
+        };
+        assert!(draw(|_| (0.0, 1.0)).is_err());
+        assert!(draw(|_| (1.0, -1.0)).is_err());
+        assert!(draw(|_| (f32::NAN, 1.0)).is_err());
+        assert!(draw(|t| (1.0, if t > 0.5 { f32::INFINITY } else { 1.0 })).is_err());
+        // More than a million dashes.
+        assert!(draw(|_| (0.00005, 0.0)).is_err());
+        // A gap of 0 is one long dash.
+        assert_eq!(draw(|_| (10.0, 0.0)), Ok((100, 50)));
+        assert_eq!(image.element_ids().len(), 1);
+    }
+
+    #[test]
+    fn connected_dots_mark_every_point_and_the_start() {
+        let mut image = Image::new(100, 100);
+        image
+            .draw_connected_dots(&[(20, 20), (80, 20), (80, 80)], 6, COLORS[1], COLORS[4])
+            .unwrap();
+        assert_eq!(path_count(&image), 3);
+
+        let pixmap = image.render().unwrap();
+        let color = |x, y| {
+            let pixel = pixmap.pixel(x, y).unwrap();
+            (pixel.red() > 64, pixel.blue() > 64)
+        };
+        // The first dot is twice as wide as the others.
+        assert_eq!(color(25, 20), (true, false));
+        assert_eq!(color(82, 20), (true, false));
+        assert_eq!(color(84, 20), (false, false));
+        assert_eq!(color(80, 77), (true, false));
+        // The line goes between the dots, in order.
+        assert_eq!(color(50, 20), (false, true));
+        assert_eq!(color(80, 50), (false, true));
+        assert_eq!(color(50, 50), (false, false));
+
+        // One point is just its starting dot.
+        let mut image = Image::new(100, 100);
+        image
+            .draw_connected_dots(&[(50, 50)], 6, COLORS[1], COLORS[4])
+            .unwrap();
+        assert_eq!(path_count(&image), 1);
+        assert_eq!(
+            image.element_bounds(image.last_element().unwrap()),
+            Some((44, 44, 56, 56))
+        );
+
+        // However many points there are.
+        let points: Vec<(i32, i32)> = (0..1000).map(|i| (i % 100, i / 10)).collect();
+        image
+            .draw_connected_dots(&points, 2, COLORS[1], COLORS[4])
+            .unwrap();
+        assert_eq!(path_count(&image), 3);
+
+        assert!(image
+            .draw_connected_dots(&[], 2, COLORS[1], COLORS[4])
+            .is_err());
+        assert!(image
+            .draw_connected_dots(&[(1, 1)], 0, COLORS[1], COLORS[4])
+            .is_err());
+        assert_eq!(image.element_ids().len(), 2);
+    }
+
+    #[test]
+    fn center_guides_cross_at_the_exact_centre() {
+        // An odd size, so the guides cover the middle pixels exactly.
+        let mut image = Image::new(101, 51);
+        image.draw_simple_line(10, 10, 90, 20, COLORS[4]).unwrap();
+        let before = image.svg_string(&Default::default());
+        let guides = image.draw_center_guides(COLORS[7]).unwrap();
+        let pixmap = image.render().unwrap();
+        let red = |x, y| pixmap.pixel(x, y).unwrap().red();
+        for x in [0, 20, 80, 100] {
+            assert_eq!((red(x, 24), red(x, 25), red(x, 26)), (0, 255, 0), "x = {x}");
+        }
+        for y in [0, 10, 40, 50] {
+            assert_eq!((red(49, y), red(50, y), red(51, y)), (0, 255, 0), "y = {y}");
+        }
+        assert!(image.remove_element(guides));
+        assert_eq!(image.svg_string(&Default::default()), before);
+
+        // An even size, so each guide is split over the two middle pixels,
+        // wherever the origin is.
+        let mut image = Image::new(100, 50);
+        image.set_coordinate_system(Corner::BottomRight, true);
+        image.draw_center_guides(COLORS[7]).unwrap();
+        let pixmap = image.render().unwrap();
+        // Whether each pixel is lit about half way.
+        let half = |x, y| (120..=136).contains(&pixmap.pixel(x, y).unwrap().red());
+        assert_eq!(
+            (half(10, 23), half(10, 24), half(10, 25), half(10, 26)),
+            (false, true, true, false)
+        );
+        assert_eq!(
+            (half(48, 10), half(49, 10), half(50, 10), half(51, 10)),
+            (false, true, true, false)
+        );
+        assert_eq!(pixmap.pixel(10, 23).unwrap().red(), 0);
+    }
+
+    #[test]
+    fn diagonals_go_from_corner_to_corner() {
+        let mut image = Image::new(100, 100);
+        image.set_coordinate_system(Corner::BottomLeft, true);
+        let diagonals = image.draw_diagonals(COLORS[7]).unwrap();
+        assert_eq!(image.element_ids(), [diagonals]);
+        assert_eq!(path_count(&image), 1);
+
+        let lit = lit_pixels(&image);
+        for i in [0, 25, 50, 75, 99] {
+            assert!(lit.contains(&(i, i)), "({i}, {i})");
+            assert!(lit.contains(&(99 - i, i)), "({}, {i})", 99 - i);
+        }
+        assert!(!lit.contains(&(10, 50)));
+        assert!(!lit.contains(&(50, 10)));
+        assert!(lit
+            .iter()
+            .all(|&(x, y)| x.abs_diff(y) <= 1 || (x + y).abs_diff(99) <= 1));
+    }
+
+    /// The points the last element's path goes through, at the ends of its
+    /// lines and curves.
+    fn path_points(image: &Image) -> Vec<(f32, f32)> {
+        let node = image.element_node(image.last_element().unwrap()).unwrap();
+        let usvg::NodeKind::Path(ref path) = *node.borrow() else {
+            panic!("the element isn't a path");
+        };
+        path.data
+            .segments()
+            .filter_map(|segment| match segment {
+                resvg::tiny_skia::PathSegment::MoveTo(p)
+                | resvg::tiny_skia::PathSegment::LineTo(p)
+                | resvg::tiny_skia::PathSegment::QuadTo(_, p)
+                | resvg::tiny_skia::PathSegment::CubicTo(_, _, p) => Some((p.x, p.y)),
+                resvg::tiny_skia::PathSegment::Close => None,
+            })
+            .collect()
+    }
+
+    fn assert_near(actual: &[(f32, f32)], expected: &[(f32, f32)]) {
+        assert_eq!(actual.len(), expected.len(), "{actual:?}");
+        for (&(x, y), &(ex, ey)) in actual.iter().zip(expected) {
+            assert!((x - ex).abs() < 1e-3 && (y - ey).abs() < 1e-3, "{actual:?}");
+        }
+    }
+
+    #[test]
+    fn waves_meet_their_peaks_and_troughs() {
+        let mut image = Image::new(300, 100);
+        image.draw_wave(0, 50, 40, 100, 2.0, COLORS[2]).unwrap();
+        // Up to the peak, down through the middle to the trough, and back.
+        let cycle = [(25.0, 10.0), (50.0, 50.0), (75.0, 90.0), (100.0, 50.0)];
+        let mut expected = vec![(0.0, 50.0)];
+        expected.extend(cycle);
+        expected.extend(cycle.map(|(x, y)| (x + 100.0, y)));
+        assert_near(&path_points(&image), &expected);
+
+        // A negative amplitude goes down first.
+        image.draw_wave(0, 50, -40, 100, 1.0, COLORS[2]).unwrap();
+        assert_near(
+            &path_points(&image),
+            &[
+                (0.0, 50.0),
+                (25.0, 90.0),
+                (50.0, 50.0),
+                (75.0, 10.0),
+                (100.0, 50.0),
+            ],
+        );
+
+        // Part of a cycle stops part way up.
+        image.draw_wave(10, 50, 40, 100, 0.125, COLORS[2]).unwrap();
+        let up = 40.0 * std::f32::consts::FRAC_1_SQRT_2;
+        assert_near(&path_points(&image), &[(10.0, 50.0), (22.5, 50.0 - up)]);
+    }
+
+    #[test]
+    fn waves_follow_a_sine_curve() {
+        let mut image = Image::new(300, 100);
+        image.draw_wave(0, 50, 40, 100, 3.0, COLORS[2]).unwrap();
+        let segments = image.to_segments(0.01);
+        assert_eq!(segments.first().unwrap().start, (0.0, 50.0));
+        for segment in segments {
+            let (x, y) = segment.start;
+            let sine = 50.0 - 40.0 * (x / 100.0 * std::f32::consts::TAU).sin();
+            // Cubic curves are close to a sine wave (within 1.25% of the
+            // amplitude), but not exactly one.
+            assert!((y - sine).abs() < 0.5, "{y} at {x}, not {sine}");
+        }
+
+        // The line is 1 wide, so the peaks at y = 10 also light half of row
+        // 9, and the troughs at y = 90 half of row 90.
+        let pixmap = image.render().unwrap();
+        let lit_rows: Vec<u32> = (0..100)
+            .filter(|&y| (0..300).any(|x| pixmap.pixel(x, y).unwrap().green() > 64))
+            .collect();
+        assert_eq!(lit_rows.first(), Some(&9));
+        assert_eq!(lit_rows.last(), Some(&90));
+    }
+
+    #[test]
+    fn waves_need_a_wavelength_and_cycles() {
+        let mut image = Image::new(300, 100);
+        for (wavelength, cycles) in [
+            (0, 1.0),
+            (-100, 1.0),
+            (100, 0.0),
+            (100, -1.0),
+            (100, f32::NAN),
+            (100, 250_001.0),
+        ] {
+            assert!(image
+                .draw_wave(0, 50, 40, wavelength, cycles, COLORS[2])
+                .is_err());
+        }
+        assert!(image.element_ids().is_empty());
+    }
+}
@@ -0,0 +1,155 @@
+use crate::{ElementId, Image};
+use resvg::usvg;
+
+/// A movement of an element, played when its SVG is viewed in a browser.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Translation {
+    offset: (i32, i32),
+    duration: f32,
+    repeat: bool,
+}
+
+/// The id given to the group wrapped around an animated element.
+fn group_id(id: ElementId) -> String {
+    format!("animated{}", id.0)
+}
+
+impl Image {
+    /// Make an element slide by `offset` over `duration_secs` seconds when
+    /// the SVG is viewed in a web browser, using an SVG (SMIL) animation.
+    ///
+    /// If `repeat` is true, the element jumps back and slides again forever;
+    /// otherwise it slides once and stays at the end. Animating an element
+    /// again replaces its previous animation.
+    ///
+    /// Only SVG output is animated. PNGs, and SVG viewers that don't support
+    /// animation, show the element where it was drawn.
+    ///
+    /// Returns an error if the element doesn't exist, or if the duration
+    /// isn't more than 0.
+    ///
+    /// ```rs
+    /// image.draw_simple_line(10, 50, 90, 20, COLORS[4])?;
+    /// let line = image.last_element().unwrap();
+    /// // Slide 60 units to the right every 2 seconds.
+    /// image.animate_translation(line, (60, 0), 2.0, true)?;
+    /// image.save_svg("moving.svg")?;
+    /// ```
+    pub fn animate_translation(
+        &mut self,
+        id: ElementId,
+        offset: (i32, i32),
+        duration_secs: f32,
+        repeat: bool,
+    ) -> Result<(), String> {
+        if !(duration_secs > 0.0 && duration_secs.is_finite()) {
+            return Err(format!(
+                "An animation must last more than 0 seconds, but the duration was {duration_secs}"
+            ));
+        }
+        let index = self
+            .elements
+            .iter()
+            .position(|&(other, _)| other == id)
+            .ok_or_else(|| format!("There is no element with id {id:?}"))?;
+
+        let translation = Translation {
+            offset,
+            duration: duration_secs,
+            repeat,
+        };
+        self.mark_changed();
+        if let Some((_, existing)) = self.animations.iter_mut().find(|(other, _)| *other == id) {
+            *existing = translation;
+            return Ok(());
+        }
+
+        // The animation needs an element to attach to, so the element is
+        // wrapped in a group with an id.
+        let node = self.elements[index].1.clone();
+        let group = usvg::Node::new(usvg::NodeKind::Group(usvg::Group {
+            id: group_id(id),
+            ..usvg::Group::default()
+        }));
+        node.insert_before(group.clone());
+        node.detach();
+        group.append(node);
+        self.elements[index].1 = group;
+        self.recount_memory();
+
+        self.animations.push((id, translation));
+        Ok(())
+    }
+}
+
+/// Add the `<animateTransform>` for each animation to a saved SVG.
+pub(crate) fn add_animations(svg: &str, animations: &[(ElementId, Translation)]) -> String {
+    let mut svg = svg.to_string();
+
+    for (id, translation) in animations {
+        let attribute = format!(" id=\"{}\"", group_id(*id));
+        let Some(start) = svg.find(&format!("<g{attribute}")) else {
+            continue;
+        };
+        let Some(end) = svg[start..].find('>').map(|end| start + end + 1) else {
+            continue;
+        };
+
+        let indent = &svg[svg[..start].rfind('\n').map_or(0, |line| line + 1)..start];
+        let (x, y) = translation.offset;
+        let timing = if translation.repeat {
+            "repeatCount=\"indefinite\""
+        } else {
+            "fill=\"freeze\""
+        };
+        let animation = format!(
+            "\n{indent}    <animateTransform attributeName=\"transform\" type=\"translate\" from=\"0 0\" to=\"{x} {y}\" dur=\"{}s\" {timing}/>",
+            translation.duration
+        );
+        svg.insert_str(end, &animation);
+    }
+
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::snapshot::parse_svg;
+    use crate::{Image, SvgOptions, COLORS};
+
+    #[test]
+    fn animations_are_added_to_the_svg_and_it_still_parses() {
+        let mut image = Image::new(100, 100);
+        image.draw_simple_line(10, 50, 90, 20, COLORS[4]).unwrap();
+        let line = image.last_element().unwrap();
+        image.draw_simple_line(10, 70, 90, 20, COLORS[1]).unwrap();
+        let still = image.render().unwrap();
+
+        image.animate_translation(line, (60, 0), 2.0, true).unwrap();
+        let svg = image.svg_string(&SvgOptions::default());
+        let animation = "<animateTransform attributeName=\"transform\" type=\"translate\" from=\"0 0\" to=\"60 0\" dur=\"2s\" repeatCount=\"indefinite\"/>";
+        assert_eq!(svg.matches("<animateTransform").count(), 1);
+        let group = svg.find(&format!("<g id=\"animated{}\"", line.0)).unwrap();
+        let inside = &svg[group..];
+        assert!(inside.find(animation).unwrap() < inside.find("</g>").unwrap());
+
+        // Animating again replaces the animation.
+        image
+            .animate_translation(line, (0, -5), 0.5, false)
+            .unwrap();
+        let svg = image.svg_string(&SvgOptions::default());
+        assert_eq!(svg.matches("<animateTransform").count(), 1);
+        assert!(svg.contains("to=\"0 -5\" dur=\"0.5s\" fill=\"freeze\"/>"));
+
+        // The SVG parses back, and shows the element where it was drawn.
+        let tree = parse_svg(&svg).unwrap();
+        assert_eq!(tree.size.width(), 100.0);
+        assert_eq!(image.render().unwrap(), still);
+        assert_eq!(image.verify_render_consistency(), Ok(()));
+
+        assert!(image.animate_translation(line, (1, 1), 0.0, true).is_err());
+        assert!(image
+            .animate_translation(line, (1, 1), f32::NAN, true)
+            .is_err());
+    }
+}
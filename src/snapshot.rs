@@ -0,0 +1,174 @@
+use crate::blend::render_layers_linear;
+use crate::{render_tree, BlendSpace, Image, SvgOptions};
+use resvg::usvg::{self, TreeParsing, TreeWriting, XmlOptions};
+use std::sync::Arc;
+
+/// A frozen copy of what an image looked like at one moment, made by
+/// `Image::snapshot`.
+///
+/// A snapshot is completely separate from the image it was taken from, so
+/// drawing on the image afterwards never changes it. Snapshots are cheap to
+/// clone (clones share the same data), can be compared with `==` to see
+/// whether anything changed between them, and can be sent to other threads,
+/// so a whole series of them can be rendered in parallel.
+///
+/// A snapshot renders with the [`BlendSpace`] the image had when it was
+/// taken, so saving it gives the same PNG the image would have given then.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImageSnapshot {
+    width: u32,
+    height: u32,
+    svg: Arc<str>,
+    /// With linear blending, the background and then each element as its
+    /// own SVG, so they can be blended together when rendering.
+    linear_layers: Option<Arc<[String]>>,
+}
+
+impl ImageSnapshot {
+    /// Get the size of the snapshot as a tuple of (width, height).
+    pub fn get_dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// The snapshot as the contents of an SVG file.
+    pub fn svg(&self) -> &str {
+        &self.svg
+    }
+
+    /// Save the snapshot to a PNG file.
+    pub fn save_png<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), String> {
+        let pixmap = match self.linear_layers {
+            Some(ref layers) => {
+                let trees = layers
+                    .iter()
+                    .map(|layer| parse_svg(layer))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let (background, elements) = trees
+                    .split_first()
+                    .ok_or("There is no background to render")?;
+                render_layers_linear(background, elements.iter().cloned())?
+            }
+            None => render_tree(&parse_svg(&self.svg)?)?,
+        };
+        pixmap.save_png(path).map_err(|e| e.to_string())
+    }
+
+    /// Save the snapshot to an SVG file.
+    pub fn save_svg<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), String> {
+        std::fs::write(path, self.svg()).map_err(|e| e.to_string())
+    }
+}
+
+/// Parse an SVG written by this crate back into a tree.
+pub(crate) fn parse_svg(svg: &str) -> Result<usvg::Tree, String> {
+    usvg::Tree::from_str(svg, &usvg::Options::default()).map_err(|e| e.to_string())
+}
+
+impl Image {
+    /// The layers from `linear_layers`, as separate SVGs.
+    fn linear_layer_svgs(&self) -> Vec<String> {
+        self.linear_layers()
+            .iter()
+            .map(|tree| tree.to_string(&XmlOptions::default()))
+            .collect()
+    }
+
+    /// Take a snapshot of everything drawn on the image so far.
+    ///
+    /// Returns an error for a dry-run image, which has nothing to save.
+    ///
+    /// ```rs
+    /// let mut snapshots = Vec::new();
+    /// for step in 0..1000 {
+    ///     // ... draw one step of the simulation ...
+    ///     if step % 100 == 0 {
+    ///         snapshots.push(image.snapshot()?);
+    ///     }
+    /// }
+    ///
+    /// std::thread::scope(|scope| {
+    ///     for (i, snapshot) in snapshots.iter().enumerate() {
+    ///         scope.spawn(move || snapshot.save_png(format!("step_{i:02}.png")));
+    ///     }
+    /// });
+    /// ```
+    pub fn snapshot(&self) -> Result<ImageSnapshot, String> {
+        self.check_not_dry_run()?;
+        Ok(ImageSnapshot {
+            width: self.width,
+            height: self.height,
+            svg: self.svg_string(&SvgOptions::default()).into(),
+            linear_layers: (self.blend_space == BlendSpace::Linear)
+                .then(|| self.linear_layer_svgs().into()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BlendSpace, Image, Rgba, StrokeStyle, COLORS};
+
+    /// Save a PNG with `save` and read back the bytes.
+    fn png_bytes(name: &str, save: impl FnOnce(&std::path::Path) -> Result<(), String>) -> Vec<u8> {
+        let path = std::env::temp_dir().join(format!("unsvg_snapshot_{name}.png"));
+        save(&path).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn snapshots_blend_the_way_the_image_does() {
+        for space in [BlendSpace::Srgb, BlendSpace::Linear] {
+            let mut image = Image::new(20, 20);
+            image.set_blend_space(space);
+            let style = StrokeStyle {
+                width: 10.0,
+                ..StrokeStyle::default()
+            };
+            for color in [
+                Rgba::from_straight(255, 0, 0, 128),
+                Rgba::from_straight(0, 0, 255, 128),
+            ] {
+                image
+                    .draw_styled_line(10, 0, 180, 20, color, &style)
+                    .unwrap();
+            }
+
+            let snapshot = image.snapshot().unwrap();
+            let name = format!("{space:?}");
+            assert_eq!(
+                png_bytes(&format!("{name}_snapshot"), |path| snapshot.save_png(path)),
+                png_bytes(&format!("{name}_image"), |path| image.save_png(path)),
+                "{space:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn snapshots_keep_what_was_drawn_when_they_were_taken() {
+        for space in [BlendSpace::Srgb, BlendSpace::Linear] {
+            let mut image = Image::new(20, 20);
+            image.set_blend_space(space);
+            image.draw_simple_line(5, 0, 180, 20, COLORS[1]).unwrap();
+            let before = png_bytes(&format!("{space:?}_before"), |path| image.save_png(path));
+            let snapshot = image.snapshot().unwrap();
+
+            image.draw_simple_line(15, 0, 180, 20, COLORS[4]).unwrap();
+            assert_ne!(image.snapshot().unwrap(), snapshot);
+            assert_eq!(
+                png_bytes(&format!("{space:?}_later"), |path| snapshot.save_png(path)),
+                before,
+                "{space:?}"
+            );
+            let svg = std::env::temp_dir().join(format!("unsvg_snapshot_{space:?}.svg"));
+            snapshot.save_svg(&svg).unwrap();
+            assert_eq!(std::fs::read_to_string(&svg).unwrap(), snapshot.svg());
+            assert_eq!(
+                snapshot.svg().matches("<path").count() + 1,
+                image.snapshot().unwrap().svg().matches("<path").count()
+            );
+            std::fs::remove_file(&svg).unwrap();
+        }
+    }
+}
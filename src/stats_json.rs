@@ -0,0 +1,118 @@
+use crate::scene::hex;
+use crate::{DrawStats, Image};
+use serde::Serialize;
+
+/// The version of the format `stats_json` writes, which goes up whenever
+/// fields are renamed, removed or change meaning. Adding a field doesn't
+/// change it.
+const STATS_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct Stats {
+    version: u32,
+    element_count: usize,
+    ink_length: f32,
+    bounds: Option<Bounds>,
+    colors: Vec<String>,
+    render_cost: u64,
+}
+
+#[derive(Serialize)]
+struct Bounds {
+    min_x: i32,
+    min_y: i32,
+    max_x: i32,
+    max_y: i32,
+}
+
+impl DrawStats {
+    /// The statistics as JSON, in the format described in `Image::stats_json`.
+    /// This needs the `json` feature.
+    pub fn to_json(&self) -> String {
+        let stats = Stats {
+            version: STATS_VERSION,
+            element_count: self.element_count,
+            ink_length: self.ink_length,
+            bounds: self.bounds.map(|(min_x, min_y, max_x, max_y)| Bounds {
+                min_x,
+                min_y,
+                max_x,
+                max_y,
+            }),
+            colors: self.colors.iter().map(|&color| hex(color)).collect(),
+            render_cost: self.render_cost,
+        };
+        serde_json::to_string(&stats).expect("statistics can always be written as JSON")
+    }
+}
+
+impl Image {
+    /// The image's [`DrawStats`] as JSON, for loading into other tools like
+    /// dashboards. This needs the `json` feature.
+    ///
+    /// The JSON is one object, with its fields always in this order:
+    ///
+    /// - `version`: the version of this format, currently 1. It only goes up
+    ///   when a field is renamed, removed or changes meaning, so readers
+    ///   should check it, but should ignore fields they don't know about.
+    /// - `element_count`: the number of elements drawn.
+    /// - `ink_length`: the total length of every stroked line, in units.
+    /// - `bounds`: the area covered by everything drawn, as an object with
+    ///   `min_x`, `min_y`, `max_x` and `max_y`, or `null` if nothing has been
+    ///   drawn.
+    /// - `colors`: every fill and stroke color used, as `"#rrggbb"` strings,
+    ///   in the order they first appear.
+    /// - `render_cost`: the estimate of how much work rendering takes.
+    ///
+    /// See [`DrawStats`] for what each of these measures. To get the same
+    /// JSON for a saved SVG file, use `stats_from_svg(svg)?.to_json()`.
+    ///
+    /// ```rs
+    /// let mut image = Image::new(100, 100);
+    /// image.draw_simple_line(10, 10, 90, 50, COLORS[1])?;
+    /// image.draw_simple_line(10, 20, 90, 30, COLORS[4])?;
+    /// assert_eq!(
+    ///     image.stats_json(),
+    ///     r##"{"version":1,"element_count":2,"ink_length":80.0,"bounds":{"min_x":10,"min_y":9,"max_x":60,"max_y":21},"colors":["#0000ff","#ff0000"],"render_cost":160}"##,
+    /// );
+    /// ```
+    pub fn stats_json(&self) -> String {
+        self.stats().to_json()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{stats_from_svg, FillRule, Image, COLORS};
+
+    #[test]
+    fn stats_json_is_pinned_for_a_known_drawing() {
+        let mut image = Image::new(100, 100);
+        image.draw_simple_line(10, 10, 90, 50, COLORS[1]).unwrap();
+        image.draw_simple_line(10, 20, 180, 30, COLORS[4]).unwrap();
+        image
+            .fill_polygon(
+                &[(70, 70), (90, 70), (80, 90)],
+                COLORS[3],
+                FillRule::NonZero,
+            )
+            .unwrap();
+        let json = image.stats_json();
+        assert_eq!(
+            json,
+            r##"{"version":1,"element_count":3,"ink_length":80.0,"bounds":{"min_x":9,"min_y":9,"max_x":90,"max_y":90},"colors":["#0000ff","#ff0000","#00ff00"],"render_cost":560}"##
+        );
+
+        // The same from the saved SVG.
+        let svg = image.svg_string(&Default::default());
+        assert_eq!(stats_from_svg(&svg).unwrap().to_json(), json);
+    }
+
+    #[test]
+    fn stats_json_of_nothing_has_no_bounds() {
+        assert_eq!(
+            Image::new(10, 10).stats_json(),
+            r#"{"version":1,"element_count":0,"ink_length":0.0,"bounds":null,"colors":[],"render_cost":0}"#
+        );
+    }
+}
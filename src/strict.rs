@@ -0,0 +1,341 @@
+use crate::{i32_to_f32, normalize_direction, Image};
+use resvg::tiny_skia;
+
+impl Image {
+    /// Turn strict mode on or off. It is off by default.
+    ///
+    /// Some draw calls quietly do something reasonable with questionable
+    /// arguments. In strict mode, they return an error explaining what was
+    /// wrong instead, so that mistakes show up straight away rather than as
+    /// a drawing that looks a bit off. The checks are:
+    ///
+    /// - A line that starts or ends outside the image, which would otherwise
+    ///   be cut off at the edge (or moved onto it, with
+    ///   `set_clamp_to_bounds`). Points on the edge are fine.
+    /// - A line with a length of 0, which draws nothing.
+    /// - A line with a negative length, which is otherwise drawn backwards.
+    /// - A line's direction outside 0 to 359, which is otherwise wrapped
+    ///   around (so 370 is the same as 10).
+    /// - A point of a polyline, path, polygon or convex hull (or a dot of
+    ///   `draw_connected_dots`) outside the image, which would otherwise be
+    ///   cut off.
+    /// - A shape that goes outside the image, which would otherwise be cut
+    ///   off. This applies to rings, progress arcs, ribbons, waves, cell
+    ///   grids and GeoJSON drawings, and only counts the shape itself, not
+    ///   the width of its outline.
+    /// - A progress arc's fraction outside 0.0 to 1.0, which is otherwise
+    ///   clamped.
+    ///
+    /// The line checks apply to `draw_simple_line`, `draw_styled_line`,
+    /// `draw_varying_dashed_line`, `draw_line_pct`, the lines drawn by
+    /// `l_system`, and everything that draws through them (like
+    /// `draw_simple_line_from`). Nothing is drawn when a check fails. The
+    /// watermark isn't drawn until the image is saved, so it isn't checked.
+    ///
+    /// ```rs
+    /// let mut image = Image::new(100, 100);
+    /// image.set_strict(true);
+    /// assert!(image.draw_simple_line(50, 50, 90, 80, COLORS[1]).is_err());
+    /// assert!(image.draw_simple_line(50, 50, 450, 10, COLORS[1]).is_err());
+    /// image.draw_simple_line(50, 50, 90, 50, COLORS[1])?;
+    /// ```
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+        self.mark_changed();
+    }
+
+    /// Whether strict mode is on, from `set_strict`.
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// In strict mode, returns an error if a line from (`x`, `y`) with this
+    /// direction and length is questionable.
+    pub(crate) fn check_strict_line(
+        &self,
+        x: i32,
+        y: i32,
+        direction: i32,
+        length: i32,
+    ) -> Result<(), String> {
+        if !self.strict {
+            return Ok(());
+        }
+
+        if !(0..360).contains(&direction) {
+            return Err(format!(
+                "Strict mode: the direction {direction} is outside 0 to 359 (it would be the same as {})",
+                normalize_direction(direction)
+            ));
+        }
+        if length == 0 {
+            return Err("Strict mode: a line with a length of 0 draws nothing".into());
+        }
+        if length < 0 {
+            return Err(format!(
+                "Strict mode: the length {length} is negative (the line would be drawn backwards, in direction {})",
+                normalize_direction(direction.saturating_add(180))
+            ));
+        }
+
+        let (end_x, end_y) = self.line_end(x, y, direction, length);
+        if !self.is_on_image(x, y) || !self.is_on_image(end_x, end_y) {
+            return Err(format!(
+                "Strict mode: the line from ({x}, {y}) to ({end_x}, {end_y}) goes outside the image, which is {}",
+                self.describe_area()
+            ));
+        }
+        Ok(())
+    }
+
+    /// In strict mode, returns an error if any of the points of a `what`
+    /// (like "polygon") are outside the image.
+    pub(crate) fn check_strict_points(
+        &self,
+        what: &str,
+        points: &[(i32, i32)],
+    ) -> Result<(), String> {
+        if !self.strict {
+            return Ok(());
+        }
+
+        match points.iter().find(|&&(x, y)| !self.is_on_image(x, y)) {
+            Some((x, y)) => Err(format!(
+                "Strict mode: the {what}'s point ({x}, {y}) is outside the image, which is {}",
+                self.describe_area()
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// In strict mode, returns an error if a `what` (like "ring") that
+    /// covers `bounds`, in drawing coordinates, goes outside the image.
+    pub(crate) fn check_strict_bounds(
+        &self,
+        what: &str,
+        bounds: tiny_skia::Rect,
+    ) -> Result<(), String> {
+        if !self.strict {
+            return Ok(());
+        }
+
+        let (min_x, min_y, max_x, max_y) = self.drawing_area();
+        // Curves are made of floats, so allow them to reach just past the
+        // edge.
+        let slack = 1e-3;
+        if bounds.left() < i32_to_f32(min_x) - slack
+            || bounds.top() < i32_to_f32(min_y) - slack
+            || bounds.right() > i32_to_f32(max_x) + slack
+            || bounds.bottom() > i32_to_f32(max_y) + slack
+        {
+            return Err(format!(
+                "Strict mode: the {what} reaches from ({}, {}) to ({}, {}), outside the image, which is {}",
+                bounds.left(),
+                bounds.top(),
+                bounds.right(),
+                bounds.bottom(),
+                self.describe_area()
+            ));
+        }
+        Ok(())
+    }
+
+    fn is_on_image(&self, x: i32, y: i32) -> bool {
+        let (min_x, min_y, max_x, max_y) = self.drawing_area();
+        (min_x..=max_x).contains(&x) && (min_y..=max_y).contains(&y)
+    }
+
+    /// The corners of the image, for error messages.
+    fn describe_area(&self) -> String {
+        let (min_x, min_y, max_x, max_y) = self.drawing_area();
+        format!("from ({min_x}, {min_y}) to ({max_x}, {max_y})")
+    }
+
+    /// In strict mode, returns an error if a progress arc's fraction would be
+    /// clamped.
+    pub(crate) fn check_strict_fraction(&self, fraction: f32) -> Result<(), String> {
+        if self.strict && !(0.0..=1.0).contains(&fraction) {
+            return Err(format!(
+                "Strict mode: a progress arc's fraction must be from 0.0 to 1.0, but it was {fraction}"
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FillRule, Image, StrokeStyle, COLORS};
+
+    type Draw = fn(&mut Image) -> Result<(), String>;
+
+    /// Each check, with a draw call that passes it on a 100x100 image and
+    /// one that fails it.
+    fn cases() -> Vec<(&'static str, Draw, Draw)> {
+        vec![
+            (
+                "line off the image",
+                |image| image.draw_simple_line(50, 50, 90, 40, 1).map(drop),
+                |image| image.draw_simple_line(50, 50, 90, 80, 1).map(drop),
+            ),
+            (
+                "direction past 359",
+                |image| image.draw_simple_line(50, 50, 10, 20, 1).map(drop),
+                |image| image.draw_simple_line(50, 50, 370, 20, 1).map(drop),
+            ),
+            (
+                "length of 0",
+                |image| image.draw_simple_line(50, 50, 90, 1, 1).map(drop),
+                |image| image.draw_simple_line(50, 50, 90, 0, 1).map(drop),
+            ),
+            (
+                "negative length",
+                |image| image.draw_simple_line(50, 50, 270, 20, 1).map(drop),
+                |image| image.draw_simple_line(50, 50, 90, -20, 1).map(drop),
+            ),
+            (
+                "styled line off the image",
+                |image| {
+                    let style = StrokeStyle::default();
+                    image.draw_styled_line(0, 0, 135, 100, 1, &style).map(drop)
+                },
+                |image| {
+                    let style = StrokeStyle::default();
+                    image.draw_styled_line(0, 0, 315, 100, 1, &style).map(drop)
+                },
+            ),
+            (
+                "varying dashed line off the image",
+                |image| {
+                    let style = StrokeStyle::default();
+                    image
+                        .draw_varying_dashed_line(10, 10, 180, 80, 1, &style, |_| (5.0, 5.0))
+                        .map(drop)
+                },
+                |image| {
+                    let style = StrokeStyle::default();
+                    image
+                        .draw_varying_dashed_line(10, 10, 180, 95, 1, &style, |_| (5.0, 5.0))
+                        .map(drop)
+                },
+            ),
+            (
+                "line by fractions off the image",
+                |image| image.draw_line_pct(0.1, 0.5, 90, 0.5, 1).map(drop),
+                |image| image.draw_line_pct(0.5, 0.5, 90, 0.5, 1).map(drop),
+            ),
+            (
+                "polyline off the image",
+                |image| {
+                    let style = StrokeStyle::default();
+                    image.draw_styled_polyline(&[(10, 10), (100, 100)], 1, &style)
+                },
+                |image| {
+                    let style = StrokeStyle::default();
+                    image.draw_styled_polyline(&[(10, 10), (101, 100)], 1, &style)
+                },
+            ),
+            (
+                "line between points off the image",
+                |image| image.draw_line_between((0, 0), (100, 50), 1),
+                |image| image.draw_line_between((0, -1), (100, 50), 1),
+            ),
+            (
+                "connected dots off the image",
+                |image| image.draw_connected_dots(&[(10, 10), (90, 90)], 4, 1, 2),
+                |image| image.draw_connected_dots(&[(10, 10), (90, 190)], 4, 1, 2),
+            ),
+            (
+                "path off the image",
+                |image| image.draw_multicolor_path(&[(10, 10), (90, 10), (90, 90)], &[1, 2]),
+                |image| image.draw_multicolor_path(&[(10, 10), (-90, 10), (90, 90)], &[1, 2]),
+            ),
+            (
+                "polygon off the image",
+                |image| image.fill_polygon(&[(0, 0), (100, 0), (50, 100)], 1, FillRule::NonZero),
+                |image| image.fill_polygon(&[(0, 0), (100, 0), (50, 200)], 1, FillRule::NonZero),
+            ),
+            (
+                "convex hull off the image",
+                |image| {
+                    image
+                        .draw_convex_hull(&[(10, 10), (90, 10), (50, 90)], 1)
+                        .map(drop)
+                },
+                |image| {
+                    image
+                        .draw_convex_hull(&[(10, 10), (190, 10), (50, 90)], 1)
+                        .map(drop)
+                },
+            ),
+            (
+                "ring off the image",
+                |image| image.draw_ring(50, 50, 50, 30, 1),
+                |image| image.draw_ring(50, 50, 51, 30, 1),
+            ),
+            (
+                "progress arc off the image",
+                |image| image.draw_progress_arc(50, 50, 40, 10, 1.0, 1),
+                |image| image.draw_progress_arc(50, 50, 46, 10, 1.0, 1),
+            ),
+            (
+                "progress arc fraction past 1.0",
+                |image| image.draw_progress_arc(50, 50, 40, 10, 1.0, 1),
+                |image| image.draw_progress_arc(50, 50, 40, 10, 1.5, 1),
+            ),
+            (
+                "ribbon off the image",
+                |image| image.draw_ribbon(&[(10, 50), (90, 50)], &[2.0, 100.0], 1),
+                |image| image.draw_ribbon(&[(10, 50), (90, 50)], &[2.0, 102.0], 1),
+            ),
+            (
+                "wave off the image",
+                |image| image.draw_wave(0, 50, 50, 50, 2.0, 1),
+                |image| image.draw_wave(0, 50, 51, 50, 2.0, 1),
+            ),
+            (
+                "cell grid off the image",
+                |image| image.draw_cell_grid((0, 0), 10, 10, 10, &|_, _| Some(COLORS[1]), None),
+                |image| image.draw_cell_grid((1, 0), 10, 10, 10, &|_, _| Some(COLORS[1]), None),
+            ),
+        ]
+    }
+
+    #[test]
+    fn strict_mode_rejects_what_it_checks_and_draws_nothing() {
+        for (check, pass, fail) in cases() {
+            let mut image = Image::new(100, 100);
+            image.set_strict(true);
+            assert_eq!(pass(&mut image), Ok(()), "{check}");
+            let error = fail(&mut image).unwrap_err();
+            assert!(error.starts_with("Strict mode: "), "{check}: {error}");
+            assert_eq!(image.element_ids().len(), 1, "{check}");
+        }
+    }
+
+    #[test]
+    fn lenient_mode_draws_what_strict_mode_rejects() {
+        for (check, _, fail) in cases() {
+            let mut image = Image::new(100, 100);
+            assert_eq!(fail(&mut image), Ok(()), "{check}");
+            assert_eq!(image.element_ids().len(), 1, "{check}");
+        }
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn strict_mode_checks_geojson_drawings() {
+        let line =
+            |x: i32| format!(r#"{{"type": "LineString", "coordinates": [[10, 10], [{x}, 90]]}}"#);
+        let mut image = Image::new(100, 100);
+        image.set_strict(true);
+        let projection = |(x, y): (f64, f64)| (x as i32, y as i32);
+        image.draw_geojson(&line(100), projection, 1).unwrap();
+        assert!(image.draw_geojson(&line(101), projection, 1).is_err());
+        assert_eq!(image.element_ids().len(), 1);
+
+        image.set_strict(false);
+        image.draw_geojson(&line(101), projection, 1).unwrap();
+        assert_eq!(image.element_ids().len(), 2);
+    }
+}
@@ -0,0 +1,255 @@
+use crate::StrokePattern;
+use resvg::tiny_skia;
+
+/// The most times a pattern can repeat along one line, so that a long line
+/// with a very thin stroke can't take forever to build.
+const MAX_REPEATS: f32 = 1_000_000.0;
+
+/// The furthest a rail can be pushed out at a sharp corner, as a multiple of
+/// its distance from the line (like a stroke's miter limit).
+const RAIL_MITER_LIMIT: f32 = 4.0;
+
+impl StrokePattern {
+    /// The outline to stroke for a line through `points`, drawn with this
+    /// pattern and a stroke `width` wide.
+    ///
+    /// Returns an error if the pattern would repeat too many times.
+    pub(crate) fn outline(
+        self,
+        points: &[(f32, f32)],
+        width: f32,
+    ) -> Result<tiny_skia::PathBuilder, String> {
+        let mut builder = tiny_skia::PathBuilder::new();
+        let line = Polyline::new(points);
+        if self == StrokePattern::Plain || line.length == 0.0 {
+            add_polyline(&mut builder, points.iter().copied());
+            return Ok(builder);
+        }
+        if line.length / width > MAX_REPEATS {
+            return Err(format!(
+                "A line {} long with a stroke {width} wide would repeat its pattern too many times",
+                line.length
+            ));
+        }
+
+        match self {
+            StrokePattern::Plain => unreachable!("plain lines are drawn above"),
+            StrokePattern::Zigzag => {
+                let mut zigzag = vec![line.point(0.0, 0.0)];
+                let mut side = 1.0;
+                let mut at = width;
+                while at < line.length {
+                    zigzag.push(line.point(at, side * 2.0 * width));
+                    side = -side;
+                    at += 2.0 * width;
+                }
+                zigzag.push(line.point(line.length, 0.0));
+                add_polyline(&mut builder, zigzag.into_iter());
+            }
+            StrokePattern::Railroad => {
+                for side in [1.0, -1.0] {
+                    add_polyline(&mut builder, line.offset(side * 2.0 * width));
+                }
+                for at in repeats(2.0 * width, 4.0 * width, line.length) {
+                    add_polyline(
+                        &mut builder,
+                        [line.point(at, 3.0 * width), line.point(at, -3.0 * width)].into_iter(),
+                    );
+                }
+            }
+            StrokePattern::Ticks => {
+                add_polyline(&mut builder, points.iter().copied());
+                for at in repeats(2.0 * width, 4.0 * width, line.length) {
+                    add_polyline(
+                        &mut builder,
+                        [line.point(at, 0.0), line.point(at, 3.0 * width)].into_iter(),
+                    );
+                }
+            }
+        }
+        Ok(builder)
+    }
+}
+
+/// Distances from `first`, every `step`, up to `length`.
+fn repeats(first: f32, step: f32, length: f32) -> impl Iterator<Item = f32> {
+    (0..)
+        .map(move |i| first + step * i as f32)
+        .take_while(move |&at| at <= length)
+}
+
+/// Add a line through `points` to `builder`, as a new subpath.
+fn add_polyline(builder: &mut tiny_skia::PathBuilder, points: impl Iterator<Item = (f32, f32)>) {
+    for (i, (x, y)) in points.enumerate() {
+        if i == 0 {
+            builder.move_to(x, y);
+        } else {
+            builder.line_to(x, y);
+        }
+    }
+}
+
+/// A line through several points, for finding points along it.
+struct Polyline {
+    /// The points, without any repeated ones.
+    points: Vec<(f32, f32)>,
+    /// The distance along the line to each point.
+    distances: Vec<f32>,
+    length: f32,
+}
+
+impl Polyline {
+    fn new(points: &[(f32, f32)]) -> Polyline {
+        let mut unique: Vec<(f32, f32)> = points.to_vec();
+        unique.dedup();
+        let mut distances = Vec::with_capacity(unique.len());
+        let mut length = 0.0;
+        for (i, &(x, y)) in unique.iter().enumerate() {
+            if let Some(&(last_x, last_y)) = i.checked_sub(1).map(|last| &unique[last]) {
+                length += (x - last_x).hypot(y - last_y);
+            }
+            distances.push(length);
+        }
+        Polyline {
+            points: unique,
+            distances,
+            length,
+        }
+    }
+
+    /// The direction of segment `i` (from point `i` to point `i + 1`), as a
+    /// unit vector.
+    fn direction(&self, i: usize) -> (f32, f32) {
+        let ((x1, y1), (x2, y2)) = (self.points[i], self.points[i + 1]);
+        let length = self.distances[i + 1] - self.distances[i];
+        ((x2 - x1) / length, (y2 - y1) / length)
+    }
+
+    /// The point `at` along the line, moved `offset` to its left.
+    fn point(&self, at: f32, offset: f32) -> (f32, f32) {
+        let segment = self
+            .distances
+            .windows(2)
+            .position(|pair| at <= pair[1])
+            .unwrap_or(self.points.len() - 2);
+        let (dx, dy) = self.direction(segment);
+        let (x, y) = self.points[segment];
+        let along = at - self.distances[segment];
+        (x + dx * along + dy * offset, y + dy * along - dx * offset)
+    }
+
+    /// The points of a line running alongside this one, `offset` to its
+    /// left, with mitered corners.
+    fn offset(&self, offset: f32) -> impl Iterator<Item = (f32, f32)> + '_ {
+        let last = self.points.len() - 1;
+        (0..=last).map(move |i| {
+            // The unit vectors to the left of the segments either side.
+            let left = |segment: usize| {
+                let (dx, dy) = self.direction(segment);
+                (dy, -dx)
+            };
+            let before = left(i.saturating_sub(1));
+            let after = left(i.min(last - 1));
+            let (sum_x, sum_y) = (before.0 + after.0, before.1 + after.1);
+            let sum_length = sum_x.hypot(sum_y);
+            let normal = if sum_length > f32::EPSILON {
+                let (x, y) = (sum_x / sum_length, sum_y / sum_length);
+                // Push the corner out so that both sides stay `offset` away.
+                let scale = 1.0 / (x * before.0 + y * before.1).max(1.0 / RAIL_MITER_LIMIT);
+                (x * scale, y * scale)
+            } else {
+                // The line turns right around here.
+                before
+            };
+            let (x, y) = self.points[i];
+            (x + normal.0 * offset, y + normal.1 * offset)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::StrokePattern;
+    use resvg::tiny_skia;
+
+    /// The points of each subpath of a pattern's outline.
+    fn subpaths(pattern: StrokePattern, points: &[(f32, f32)], width: f32) -> Vec<Vec<(f32, f32)>> {
+        let path = pattern.outline(points, width).unwrap().finish().unwrap();
+        let mut subpaths: Vec<Vec<(f32, f32)>> = Vec::new();
+        for segment in path.segments() {
+            match segment {
+                tiny_skia::PathSegment::MoveTo(p) => subpaths.push(vec![(p.x, p.y)]),
+                tiny_skia::PathSegment::LineTo(p) => {
+                    subpaths.last_mut().unwrap().push((p.x, p.y));
+                }
+                _ => panic!("patterns are made of straight lines"),
+            }
+        }
+        subpaths
+    }
+
+    #[test]
+    fn zigzags_swing_twice_the_width_either_side() {
+        let zigzag = subpaths(StrokePattern::Zigzag, &[(0.0, 0.0), (20.0, 0.0)], 1.0);
+        assert_eq!(zigzag.len(), 1);
+        let points = &zigzag[0];
+        assert_eq!(points.first(), Some(&(0.0, 0.0)));
+        assert_eq!(points.last(), Some(&(20.0, 0.0)));
+        // A peak every 2 units, from 1 to 19, alternating sides.
+        let peaks = &points[1..points.len() - 1];
+        assert_eq!(peaks.len(), 10);
+        for (i, &(x, y)) in peaks.iter().enumerate() {
+            let side = if i % 2 == 0 { -1.0 } else { 1.0 };
+            assert_eq!((x, y), (1.0 + 2.0 * i as f32, side * 2.0), "peak {i}");
+        }
+
+        // Wider strokes swing further, with fewer peaks.
+        let wide = subpaths(StrokePattern::Zigzag, &[(0.0, 0.0), (20.0, 0.0)], 2.0);
+        let peaks = &wide[0][1..wide[0].len() - 1];
+        assert_eq!(peaks.len(), 5);
+        assert!(peaks.iter().all(|&(_, y)| y.abs() == 4.0));
+    }
+
+    #[test]
+    fn zigzags_follow_the_line_around_corners() {
+        let points = [(0.0, 0.0), (0.0, 10.0), (10.0, 10.0)];
+        let zigzag = subpaths(StrokePattern::Zigzag, &points, 1.0);
+        let peaks = &zigzag[0][1..zigzag[0].len() - 1];
+        // Each peak is 2 across from the segment it is on.
+        let near = |a: f32, b: f32| (a - b).abs() < 1e-4;
+        for &(x, y) in peaks {
+            let on_first = near(x.abs(), 2.0) && (0.0..=10.0).contains(&y);
+            let on_second = near((y - 10.0).abs(), 2.0) && (0.0..=10.0).contains(&x);
+            assert!(on_first || on_second, "({x}, {y})");
+        }
+        // 10 peaks at 1, 3, ... 19 along the line, on alternating sides.
+        assert_eq!(peaks.len(), 10);
+        let sides: Vec<bool> = peaks[..5].iter().map(|&(x, _)| x > 0.0).collect();
+        assert_eq!(sides, [true, false, true, false, true]);
+        let sides: Vec<bool> = peaks[5..].iter().map(|&(_, y)| y > 10.0).collect();
+        assert_eq!(sides, [true, false, true, false, true]);
+    }
+
+    #[test]
+    fn other_patterns_have_rails_and_ticks() {
+        let line = [(0.0, 0.0), (20.0, 0.0)];
+        assert_eq!(subpaths(StrokePattern::Plain, &line, 1.0), [line.to_vec()]);
+
+        let railroad = subpaths(StrokePattern::Railroad, &line, 1.0);
+        assert_eq!(railroad[0], [(0.0, -2.0), (20.0, -2.0)]);
+        assert_eq!(railroad[1], [(0.0, 2.0), (20.0, 2.0)]);
+        // Ties every 4 units from 2.
+        let ties: Vec<f32> = railroad[2..].iter().map(|tie| tie[0].0).collect();
+        assert_eq!(ties, [2.0, 6.0, 10.0, 14.0, 18.0]);
+        assert_eq!(railroad[2], [(2.0, -3.0), (2.0, 3.0)]);
+
+        let ticks = subpaths(StrokePattern::Ticks, &line, 1.0);
+        assert_eq!(ticks[0], line);
+        assert_eq!(ticks.len(), 6);
+        assert_eq!(ticks[1], [(2.0, 0.0), (2.0, -3.0)]);
+
+        assert!(StrokePattern::Zigzag
+            .outline(&[(0.0, 0.0), (2_000_000.0, 0.0)], 1.0)
+            .is_err());
+    }
+}
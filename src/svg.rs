@@ -0,0 +1,237 @@
+use crate::smil::add_animations;
+use crate::Image;
+use resvg::usvg;
+use resvg::usvg::{TreeWriting, XmlOptions};
+
+/// Options for how `save_svg_with_options` writes an SVG file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SvgOptions {
+    /// Give each distinct color a CSS class, defined once in a `<style>`
+    /// block, instead of repeating the color on every shape.
+    ///
+    /// A shape stroked with `#ff0000` gets the class `stroke-ff0000`, and
+    /// one filled with it gets `fill-ff0000`. This makes drawings with many
+    /// shapes of the same color smaller, and lets the colors be changed
+    /// later by editing (or overriding) the styles.
+    pub css_classes: bool,
+    /// Leave out the line breaks and indentation that make the file easy to
+    /// read, so it is as small as possible (for serving on the web, say).
+    pub minify: bool,
+}
+
+impl Image {
+    /// Save the image to an SVG file, choosing how it is written.
+    ///
+    /// ```rs
+    /// let options = SvgOptions {
+    ///     css_classes: true,
+    ///     ..SvgOptions::default()
+    /// };
+    /// image.save_svg_with_options("image.svg", &options)?;
+    /// ```
+    pub fn save_svg_with_options<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+        options: &SvgOptions,
+    ) -> Result<(), String> {
+        self.prepare_to_save()?;
+        std::fs::write(path, self.svg_string(options)).map_err(|e| e.to_string())
+    }
+
+    /// The contents of the SVG file `save_svg_with_options` would write.
+    pub(crate) fn svg_string(&self, options: &SvgOptions) -> String {
+        self.write_svg(&self.export_tree(), options)
+    }
+
+    /// Write out a tree exported from this image as an SVG file's contents.
+    pub(crate) fn write_svg(&self, tree: &usvg::Tree, options: &SvgOptions) -> String {
+        let mut svg = add_animations(&tree.to_string(&XmlOptions::default()), &self.animations);
+        if options.css_classes {
+            svg = use_css_classes(&svg);
+        }
+        if options.minify {
+            // Every tag is on a line of its own, and text has already been
+            // turned into paths, so no line holds whitespace that matters.
+            svg = svg.lines().map(str::trim).collect();
+        }
+        svg
+    }
+}
+
+/// Move the solid `fill` and `stroke` colors of every path in `svg` into
+/// classes, defined by a `<style>` block at the start of the document.
+fn use_css_classes(svg: &str) -> String {
+    let mut output = String::with_capacity(svg.len());
+    let mut classes: Vec<String> = Vec::new();
+
+    let mut rest = svg;
+    while let Some(start) = rest.find("<path ") {
+        let (before, tag) = rest.split_at(start);
+        let end = tag.find('>').map_or(tag.len(), |end| end + 1);
+        let (tag, after) = tag.split_at(end);
+        output.push_str(before);
+
+        let mut attributes = tag["<path".len()..].to_string();
+        let mut names = Vec::new();
+        for property in ["fill", "stroke"] {
+            let Some((color, without)) = take_color(&attributes, property) else {
+                continue;
+            };
+            let name = format!("{property}-{color}");
+            if !classes.contains(&name) {
+                classes.push(name.clone());
+            }
+            names.push(name);
+            attributes = without;
+        }
+
+        output.push_str("<path");
+        if !names.is_empty() {
+            output.push_str(&format!(" class=\"{}\"", names.join(" ")));
+        }
+        output.push_str(&attributes);
+        rest = after;
+    }
+    output.push_str(rest);
+
+    if classes.is_empty() {
+        return output;
+    }
+
+    let style: String = classes
+        .iter()
+        .map(|name| {
+            let (property, color) = name.split_once('-').expect("class names contain a dash");
+            format!("\n        .{name} {{ {property}: #{color}; }}")
+        })
+        .collect();
+
+    // The style block goes straight after the opening `<svg ...>` tag.
+    let Some(svg_end) = output
+        .find("<svg")
+        .and_then(|start| output[start..].find('>').map(|end| start + end + 1))
+    else {
+        return output;
+    };
+    output.insert_str(svg_end, &format!("\n    <style>{style}\n    </style>"));
+    output
+}
+
+/// Find a `property="#rrggbb"` attribute in a tag's attributes, returning the
+/// color's hex digits and the attributes with that one removed.
+fn take_color(attributes: &str, property: &str) -> Option<(String, String)> {
+    let prefix = format!(" {property}=\"#");
+    let start = attributes.find(&prefix)?;
+    let color_start = start + prefix.len();
+    let color = attributes.get(color_start..color_start + 6)?;
+    if !color.bytes().all(|byte| byte.is_ascii_hexdigit())
+        || attributes.as_bytes().get(color_start + 6) != Some(&b'"')
+    {
+        return None;
+    }
+
+    let without = format!("{}{}", &attributes[..start], &attributes[color_start + 7..]);
+    Some((color.to_string(), without))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{snapshot::parse_svg, Color, LineCap, StrokeStyle, COLORS};
+
+    #[test]
+    fn css_classes_are_styled_and_parse_back() {
+        let mut image = Image::new(100, 100);
+        image.draw_simple_line(10, 10, 90, 50, COLORS[4]).unwrap();
+        image.draw_simple_line(10, 20, 90, 50, COLORS[4]).unwrap();
+        image.draw_simple_line(10, 30, 90, 50, COLORS[1]).unwrap();
+        let options = SvgOptions {
+            css_classes: true,
+            ..SvgOptions::default()
+        };
+        let svg = image.svg_string(&options);
+        assert!(svg.contains("<style>"), "{svg}");
+        assert_eq!(svg.matches("class=\"stroke-ff0000\"").count(), 2, "{svg}");
+        assert!(!svg.contains("stroke=\"#ff0000\""), "{svg}");
+
+        let strokes: Vec<_> = parse_svg(&svg)
+            .unwrap()
+            .root
+            .descendants()
+            .filter_map(|node| match *node.borrow() {
+                usvg::NodeKind::Path(ref path) => match path.stroke.as_ref()?.paint {
+                    usvg::Paint::Color(color) => Some(Color::from_usvg(color)),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect();
+        assert_eq!(strokes, [COLORS[4], COLORS[4], COLORS[1]]);
+    }
+
+    /// The `<path ...>` tags of an SVG.
+    fn path_tags(svg: &str) -> Vec<&str> {
+        svg.match_indices("<path ")
+            .map(|(start, _)| {
+                let tag = &svg[start..];
+                &tag[..tag.find('>').unwrap() + 1]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn default_strokes_only_write_their_color() {
+        let mut image = Image::new(100, 100);
+        image.draw_simple_line(10, 10, 90, 50, COLORS[4]).unwrap();
+        let svg = image.svg_string(&SvgOptions::default());
+        let tags = path_tags(&svg);
+        let line = tags.last().unwrap();
+        assert!(line.contains("stroke=\"#ff0000\""), "{line}");
+        assert!(line.contains("fill=\"none\""), "{line}");
+        for attribute in [
+            "stroke-width",
+            "stroke-linecap",
+            "stroke-linejoin",
+            "stroke-miterlimit",
+            "stroke-dasharray",
+            "stroke-opacity",
+        ] {
+            assert!(!line.contains(attribute), "{line}");
+        }
+
+        // Which are the SVG defaults, so they parse back as the stroke drawn.
+        let tree = parse_svg(&svg).unwrap();
+        let path = tree.root.descendants().last().unwrap();
+        let usvg::NodeKind::Path(ref path) = *path.borrow() else {
+            panic!("the line should be a path");
+        };
+        let stroke = path.stroke.as_ref().unwrap();
+        assert_eq!(stroke.width.get(), 1.0);
+        assert_eq!(stroke.linecap, usvg::LineCap::Butt);
+        assert_eq!(stroke.linejoin, usvg::LineJoin::Miter);
+        assert_eq!(stroke.miterlimit.get(), 4.0);
+        assert_eq!(stroke.dasharray, None);
+        assert_eq!(stroke.opacity.get(), 1.0);
+    }
+
+    #[test]
+    fn strokes_that_differ_from_the_defaults_write_what_differs() {
+        let mut image = Image::new(100, 100);
+        let style = StrokeStyle {
+            width: 3.0,
+            cap: LineCap::Round,
+            miter_limit: 2.0,
+            ..StrokeStyle::default()
+        };
+        image
+            .draw_styled_polyline(&[(10, 10), (50, 50), (90, 10)], COLORS[1], &style)
+            .unwrap();
+        let svg = image.svg_string(&SvgOptions::default());
+        let tags = path_tags(&svg);
+        let line = tags.last().unwrap();
+        assert!(line.contains("stroke-width=\"3\""), "{line}");
+        assert!(line.contains("stroke-linecap=\"round\""), "{line}");
+        assert!(line.contains("stroke-miterlimit=\"2\""), "{line}");
+        assert!(!line.contains("stroke-linejoin"), "{line}");
+    }
+}
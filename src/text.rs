@@ -0,0 +1,286 @@
+use crate::Image;
+use resvg::usvg::{self, fontdb, TreeTextToPath};
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::OnceLock;
+
+/// The fonts installed on this computer, loaded the first time any text is
+/// rendered.
+fn system_fonts() -> &'static fontdb::Database {
+    static FONTS: OnceLock<fontdb::Database> = OnceLock::new();
+    FONTS.get_or_init(|| {
+        let mut fonts = fontdb::Database::new();
+        fonts.load_system_fonts();
+        fall_back_to_any_font(&mut fonts);
+        fonts
+    })
+}
+
+/// The default generic families (like Arial for sans-serif) aren't installed
+/// everywhere, so if there is no sans-serif font, use whatever font there is
+/// for sans-serif and serif text.
+fn fall_back_to_any_font(fonts: &mut fontdb::Database) {
+    let sans_serif = fontdb::Query {
+        families: &[fontdb::Family::SansSerif],
+        ..fontdb::Query::default()
+    };
+    if fonts.query(&sans_serif).is_none() {
+        let fallback = fonts
+            .faces()
+            .find_map(|face| face.families.first())
+            .map(|(family, _)| family.clone());
+        if let Some(family) = fallback {
+            fonts.set_sans_serif_family(family.clone());
+            fonts.set_serif_family(family);
+        }
+    }
+}
+
+impl Image {
+    /// Use the fonts in the font files at `paths` (like `.ttf` or `.otf`
+    /// files) for any text on the image (like a watermark), instead of the
+    /// fonts installed on this computer.
+    ///
+    /// By default, the installed fonts are loaded the first time any text is
+    /// rendered, and saving an image with text is an error if there are
+    /// none. Providing the fonts
+    /// instead means text looks the same everywhere, even on machines with
+    /// no fonts installed (like many CI servers). If none of the fonts is
+    /// sans-serif, the first one is used instead.
+    ///
+    /// Returns an error if a file can't be read, or if it has no fonts in it
+    /// that can be used, in which case the image's fonts are unchanged.
+    ///
+    /// ```rs
+    /// image.set_font_files(&["fonts/DejaVuSans.ttf"])?;
+    /// image.set_watermark("Draft", Corner::BottomRight, 0.5);
+    /// ```
+    pub fn set_font_files<P: AsRef<Path>>(&mut self, paths: &[P]) -> Result<(), String> {
+        let fonts = paths
+            .iter()
+            .map(|path| {
+                std::fs::read(path).map_err(|e| {
+                    format!(
+                        "Could not read the font file {}: {e}",
+                        path.as_ref().display()
+                    )
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        self.set_font_data(fonts)
+    }
+
+    /// Like `set_font_files`, but with the contents of the font files,
+    /// such as fonts built into the program with `include_bytes!`.
+    ///
+    /// Returns an error if any of `fonts` has no fonts in it that can be
+    /// used, in which case the image's fonts are unchanged.
+    ///
+    /// ```rs
+    /// image.set_font_data([include_bytes!("../fonts/DejaVuSans.ttf").to_vec()])?;
+    /// ```
+    pub fn set_font_data(
+        &mut self,
+        fonts: impl IntoIterator<Item = Vec<u8>>,
+    ) -> Result<(), String> {
+        let mut database = fontdb::Database::new();
+        for (index, font) in fonts.into_iter().enumerate() {
+            let before = database.len();
+            database.load_font_data(font);
+            if database.len() == before {
+                return Err(format!(
+                    "Font {} has no fonts in it that can be used",
+                    index + 1
+                ));
+            }
+        }
+        if database.is_empty() {
+            return Err("At least one font is needed".into());
+        }
+
+        self.set_font_database(database);
+        Ok(())
+    }
+
+    /// Like `set_font_files`, but with fonts already loaded into a font
+    /// database, such as one shared between several images or one with the
+    /// system fonts and some of the program's own.
+    ///
+    /// Saving an image with text is an error if `fonts` is empty.
+    ///
+    /// ```rs
+    /// let mut fonts = resvg::usvg::fontdb::Database::new();
+    /// fonts.load_fonts_dir("fonts");
+    /// image.set_font_database(fonts);
+    /// ```
+    pub fn set_font_database(&mut self, mut fonts: fontdb::Database) {
+        fall_back_to_any_font(&mut fonts);
+        self.fonts = Some(Rc::new(fonts));
+        self.mark_changed();
+    }
+
+    /// Check there are fonts to draw any text in `node` with, as otherwise
+    /// the text would be left out.
+    pub(crate) fn check_fonts(&self, node: &usvg::Node) -> Result<(), String> {
+        let has_text = node
+            .descendants()
+            .any(|node| matches!(*node.borrow(), usvg::NodeKind::Text(_)));
+        if has_text
+            && self
+                .fonts
+                .as_deref()
+                .unwrap_or_else(|| system_fonts())
+                .is_empty()
+        {
+            return Err(
+                "There are no fonts to draw the image's text with: give some with `set_font_files`"
+                    .into(),
+            );
+        }
+        Ok(())
+    }
+
+    /// Turn any text in a tree into paths, since text can't be rendered or
+    /// saved directly.
+    pub(crate) fn convert_text(&self, tree: &mut usvg::Tree) {
+        let has_text = tree
+            .root
+            .descendants()
+            .any(|node| matches!(*node.borrow(), usvg::NodeKind::Text(_)));
+        if has_text {
+            tree.convert_text(self.fonts.as_deref().unwrap_or_else(|| system_fonts()));
+        }
+    }
+}
+
+/// A single line of sans-serif text, with its baseline starting at `(x, y)`
+/// (or ending there, or centred on it, depending on `anchor`).
+pub(crate) fn text_node(
+    text: &str,
+    (x, y): (f32, f32),
+    size: f32,
+    anchor: usvg::TextAnchor,
+    fill: usvg::Fill,
+) -> Option<usvg::Node> {
+    let span = usvg::TextSpan {
+        start: 0,
+        end: text.len(),
+        fill: Some(fill),
+        stroke: None,
+        paint_order: usvg::PaintOrder::default(),
+        font: usvg::Font {
+            families: vec!["sans-serif".to_string()],
+            style: usvg::FontStyle::default(),
+            stretch: usvg::FontStretch::default(),
+            weight: 400,
+        },
+        font_size: usvg::NonZeroPositiveF32::new(size)?,
+        small_caps: false,
+        apply_kerning: true,
+        decoration: usvg::TextDecoration {
+            underline: None,
+            overline: None,
+            line_through: None,
+        },
+        dominant_baseline: usvg::DominantBaseline::default(),
+        alignment_baseline: usvg::AlignmentBaseline::default(),
+        baseline_shift: Vec::new(),
+        visibility: usvg::Visibility::default(),
+        letter_spacing: 0.0,
+        word_spacing: 0.0,
+        text_length: None,
+        length_adjust: usvg::LengthAdjust::default(),
+    };
+
+    Some(usvg::Node::new(usvg::NodeKind::Text(usvg::Text {
+        id: String::new(),
+        transform: usvg::Transform::default(),
+        rendering_mode: usvg::TextRendering::default(),
+        positions: Vec::new(),
+        rotate: Vec::new(),
+        writing_mode: usvg::WritingMode::LeftToRight,
+        chunks: vec![usvg::TextChunk {
+            x: Some(x),
+            y: Some(y),
+            anchor,
+            spans: vec![span],
+            text_flow: usvg::TextFlow::Linear,
+            text: text.to_string(),
+        }],
+    })))
+}
+
+/// A sans-serif font for tests, so they draw the same text whatever fonts
+/// are installed (Tuffy, which is in the public domain).
+#[cfg(test)]
+pub(crate) fn test_font() -> Vec<u8> {
+    include_bytes!("../testdata/fonts/Tuffy.ttf").to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Corner;
+
+    /// How many pixels of the image aren't black.
+    fn lit_pixels(image: &Image) -> usize {
+        let pixmap = image.render().unwrap();
+        pixmap
+            .pixels()
+            .iter()
+            .filter(|pixel| pixel.red() > 0)
+            .count()
+    }
+
+    #[test]
+    fn text_is_drawn_in_the_fonts_given() {
+        let font = std::env::temp_dir().join("unsvg-test-font.ttf");
+        std::fs::write(&font, test_font()).unwrap();
+        let mut image = Image::new(200, 50);
+        image.set_font_files(&[&font]).unwrap();
+        assert_eq!(lit_pixels(&image), 0);
+        image.set_watermark("HHHH", Corner::TopLeft, 1.0);
+        assert!(lit_pixels(&image) > 50);
+
+        let mut from_data = Image::new(200, 50);
+        from_data.set_font_data([test_font()]).unwrap();
+        from_data.set_watermark("HHHH", Corner::TopLeft, 1.0);
+        assert!(from_data.render().unwrap() == image.render().unwrap());
+
+        let mut fonts = fontdb::Database::new();
+        fonts.load_font_data(test_font());
+        let mut from_database = Image::new(200, 50);
+        from_database.set_font_database(fonts);
+        from_database.set_watermark("HHHH", Corner::TopLeft, 1.0);
+        assert!(from_database.render().unwrap() == image.render().unwrap());
+    }
+
+    #[test]
+    fn saving_text_with_no_fonts_is_an_error() {
+        let mut image = Image::new(200, 50);
+        image.set_font_database(fontdb::Database::new());
+        assert!(image.png_bytes().is_ok());
+
+        image.set_watermark("HHHH", Corner::TopLeft, 1.0);
+        let Err(e) = image.png_bytes() else {
+            panic!("saved text with no fonts")
+        };
+        assert!(e.contains("no fonts"), "{e}");
+        let path = std::env::temp_dir().join("unsvg-no-fonts-test.svg");
+        assert!(image.save_svg(&path).is_err());
+
+        image.clear_watermark();
+        assert!(image.png_bytes().is_ok());
+    }
+
+    #[test]
+    fn fonts_that_cannot_be_used_are_an_error() {
+        let mut image = Image::new(100, 100);
+        let missing = std::env::temp_dir().join("unsvg-no-such-font.ttf");
+        assert!(image.set_font_files(&[missing]).is_err());
+        assert!(image.set_font_files::<&Path>(&[]).is_err());
+        assert!(image.set_font_data([b"not a font".to_vec()]).is_err());
+        assert!(image.set_font_data([]).is_err());
+        assert!(image.fonts.is_none());
+    }
+}
@@ -0,0 +1,697 @@
+use crate::blend::blend_layers_linear;
+use crate::elements::{node_bounds, skia_stroke};
+use crate::snapshot::parse_svg;
+use crate::{new_pixmap, BlendSpace, Color, Image};
+use resvg::usvg::{NodeExt, TreeWriting, XmlOptions};
+use resvg::{tiny_skia, usvg};
+use std::io::Write;
+
+/// How many rows of pixels `Image::rows` renders at a time.
+const ROW_STRIP: u32 = 256;
+
+/// How far past a tile the pixmap it is rendered in reaches, in pixels.
+const TILE_MARGIN: i32 = 2;
+
+impl Image {
+    /// Save the image to a PNG file, rendering it in square tiles of
+    /// `tile_size` pixels.
+    ///
+    /// The rows of tiles are shared out between as many threads as there are
+    /// processors, and each thread only holds the row of tiles it is working
+    /// on (and the one it has just finished), so this can save images (say,
+    /// 10000x10000) too big to render in one go. The PNG has exactly the
+    /// same pixels as `save_png` saves.
+    ///
+    /// A tile is rendered into a pixmap only a couple of pixels bigger than
+    /// itself when everything drawn across it has only horizontal and
+    /// vertical edges, like the background. A slanted or curved shape (or a
+    /// translucent group, or an image) comes out slightly differently when
+    /// it is cut off at the edge of a pixmap, so the whole of any such shape
+    /// that reaches into a tile is rendered with it. Memory is only saved
+    /// where the image's slanted and curved shapes are each much smaller
+    /// than the image.
+    ///
+    /// Returns an error if `tile_size` is 0.
+    ///
+    /// ```rs
+    /// let mut image = Image::new(10000, 10000);
+    /// // ...
+    /// image.save_png_tiled("huge.png", 1024)?;
+    /// ```
+    pub fn save_png_tiled<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+        tile_size: u32,
+    ) -> Result<(), String> {
+        self.prepare_to_save()?;
+        if tile_size == 0 {
+            return Err("Tiles must be at least 1 pixel wide".into());
+        }
+
+        let file = std::fs::File::create(path).map_err(|e| e.to_string())?;
+        let mut encoder = png::Encoder::new(std::io::BufWriter::new(file), self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+        let mut stream = writer.stream_writer().map_err(|e| e.to_string())?;
+
+        // Trees can't be sent between threads, so each thread parses its own
+        // copy of the image.
+        let layers: Vec<String> = render_layers(self)
+            .iter()
+            .map(|tree| tree.to_string(&XmlOptions::default()))
+            .collect();
+        let (width, height) = (self.width, self.height);
+        let tops: Vec<u32> = (0..height).step_by(tile_size as usize).collect();
+        let threads = std::thread::available_parallelism()
+            .map_or(1, |count| count.get())
+            .min(tops.len());
+
+        std::thread::scope(|scope| {
+            // Thread `i` renders every `threads`th row of tiles, starting at
+            // row `i`, so the rows can be taken from each thread in turn.
+            let receivers: Vec<_> = (0..threads)
+                .map(|thread| {
+                    let (sender, receiver) = std::sync::mpsc::sync_channel(1);
+                    let (layers, tops) = (&layers, &tops);
+                    scope.spawn(move || {
+                        let tiles = match Tiles::parse(layers, width, height) {
+                            Ok(tiles) => tiles,
+                            Err(e) => {
+                                let _ = sender.send(Err(e));
+                                return;
+                            }
+                        };
+                        for &top in tops.iter().skip(thread).step_by(threads) {
+                            // Stop if the rows are no longer wanted.
+                            if sender.send(tiles.render_row(top, tile_size)).is_err() {
+                                return;
+                            }
+                        }
+                    });
+                    receiver
+                })
+                .collect();
+
+            for index in 0..tops.len() {
+                let row = receivers[index % threads]
+                    .recv()
+                    .map_err(|_| "A thread rendering the image stopped early".to_string())??;
+                stream.write_all(&row).map_err(|e| e.to_string())?;
+            }
+            Ok::<_, String>(())
+        })?;
+
+        stream.finish().map_err(|e| e.to_string())
+    }
+
+    /// Render the image a band of `rows_per_chunk` rows at a time, from top
+    /// to bottom, and call `f` with each band. This is for streaming the
+    /// pixels somewhere (like a PNG encoder, or a GPU texture) when the
+    /// whole image would take too much memory to render at once: a
+    /// 20000x20000 image needs 1.6 GB, but a band of 256 of its rows only
+    /// needs about 20 MB.
+    ///
+    /// `f` is given the y coordinate of the band's first row, and the band's
+    /// pixels, row by row from the top left, with 4 bytes (red, green, blue,
+    /// alpha) per pixel, not premultiplied by alpha. The last band is shorter
+    /// if the image's height isn't a multiple of `rows_per_chunk`. If `f`
+    /// returns an error, rendering stops and the error is returned.
+    ///
+    /// Putting the bands together gives exactly the same pixels as
+    /// `save_png`. Only about one band's pixels are held at a time, except
+    /// that a band crossed by slanted or curved shapes renders the whole of
+    /// those shapes with it, as described for `save_png_tiled`.
+    ///
+    /// Returns an error if `rows_per_chunk` is 0, or for a dry-run image.
+    ///
+    /// ```rs
+    /// let mut encoder = png::Encoder::new(file, width, height);
+    /// encoder.set_color(png::ColorType::Rgba);
+    /// let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+    /// let mut stream = writer.stream_writer().map_err(|e| e.to_string())?;
+    /// image.render_rows(256, |_, band| stream.write_all(band).map_err(|e| e.to_string()))?;
+    /// stream.finish().map_err(|e| e.to_string())?;
+    /// ```
+    pub fn render_rows<F>(&self, rows_per_chunk: u32, mut f: F) -> Result<(), String>
+    where
+        F: FnMut(u32, &[u8]) -> Result<(), String>,
+    {
+        self.check_not_dry_run()?;
+        if rows_per_chunk == 0 {
+            return Err("Each chunk must have at least 1 row".into());
+        }
+        let tiles = Tiles::new(self);
+
+        for top in (0..self.height).step_by(rows_per_chunk as usize) {
+            let height = rows_per_chunk.min(self.height - top);
+            let band = tiles.render((0, top), (self.width, height))?;
+            f(top, &straight_bytes(&band))?;
+        }
+        Ok(())
+    }
+
+    /// The rendered image, one row of pixels at a time from top to bottom.
+    /// Each row has one color per pixel, from left to right. This is handy
+    /// for writing the image out in a format `unsvg` doesn't support.
+    ///
+    /// The image is rendered a strip of rows at a time as the rows are used,
+    /// so even very large images need not have all their pixels in memory at
+    /// once. The pixels are those `render_rows` gives. Transparency is
+    /// dropped: each color is what the pixel would look like if it were
+    /// opaque.
+    ///
+    /// If a strip can't be rendered (there isn't enough memory for it, or
+    /// this is a dry-run image), the rows stop there, so fewer rows than the
+    /// image's height means the image couldn't all be rendered.
+    ///
+    /// ```rs
+    /// for (y, row) in image.rows().enumerate() {
+    ///     for (x, color) in row.iter().enumerate() {
+    ///         println!("({x}, {y}) is {color:?}");
+    ///     }
+    /// }
+    /// ```
+    pub fn rows(&self) -> impl Iterator<Item = Vec<Color>> {
+        let (width, height) = (self.width, self.height);
+        let tiles = self.check_not_dry_run().ok().map(|()| Tiles::new(self));
+
+        (0..height)
+            .step_by(ROW_STRIP as usize)
+            .map_while(move |top| {
+                tiles
+                    .as_ref()?
+                    .render((0, top), (width, ROW_STRIP.min(height - top)))
+                    .ok()
+            })
+            .flat_map(move |strip| {
+                let rows: Vec<Vec<Color>> = strip
+                    .pixels()
+                    .chunks(width as usize)
+                    .map(|row| {
+                        row.iter()
+                            .map(|pixel| {
+                                let color = pixel.demultiply();
+                                Color {
+                                    red: color.red(),
+                                    green: color.green(),
+                                    blue: color.blue(),
+                                }
+                            })
+                            .collect()
+                    })
+                    .collect();
+                rows.into_iter()
+            })
+    }
+}
+
+/// The trees to render for an image: the first is drawn normally, and any
+/// others are blended onto it in linear light.
+fn render_layers(image: &Image) -> Vec<usvg::Tree> {
+    match image.blend_space {
+        BlendSpace::Srgb => vec![image.export_tree()],
+        BlendSpace::Linear => image.linear_layers(),
+    }
+}
+
+/// A pixmap's pixels as straight (not premultiplied) RGBA bytes.
+fn straight_bytes(pixmap: &tiny_skia::Pixmap) -> Vec<u8> {
+    pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect()
+}
+
+/// An image, ready to be rendered a part at a time.
+///
+/// Every part is rendered from the same trees, built once, just moved up and
+/// left over a smaller pixmap, so it comes out as it would rendering the
+/// whole image. The exception is where a shape is cut off by the edge of the
+/// pixmap: the renderer works the slanted and curved edges it cuts out again
+/// from where they are cut, which shifts their anti-aliasing slightly all
+/// along them. So the part of the image rendered for a tile is grown to take
+/// in every such shape that reaches into it.
+struct Tiles {
+    /// The first tree is drawn normally, and any others are blended onto it
+    /// in linear light.
+    trees: Vec<resvg::Tree>,
+    canvas: tiny_skia::IntRect,
+    /// The area of every shape that can't be cut off at the edge of a tile.
+    uncut: Vec<tiny_skia::IntRect>,
+}
+
+impl Tiles {
+    fn new(image: &Image) -> Tiles {
+        Tiles::from_trees(&render_layers(image), image.width, image.height)
+    }
+
+    /// Tiles for a `width` by `height` image, from the SVGs of its layers.
+    fn parse(layers: &[String], width: u32, height: u32) -> Result<Tiles, String> {
+        let trees = layers
+            .iter()
+            .map(|layer| parse_svg(layer))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Tiles::from_trees(&trees, width, height))
+    }
+
+    fn from_trees(trees: &[usvg::Tree], width: u32, height: u32) -> Tiles {
+        let canvas =
+            tiny_skia::IntRect::from_xywh(0, 0, width, height).expect("an image is never empty");
+        let uncut = trees
+            .iter()
+            .flat_map(|tree| tree.root.descendants())
+            .filter(|node| !can_cut(node))
+            .filter_map(|node| node_bounds(&node))
+            .filter_map(|bounds| {
+                let clamp = |value: f32, max: u32| value.clamp(-1.0, max as f32 + 1.0) as i32;
+                tiny_skia::IntRect::from_ltrb(
+                    clamp(bounds.left().floor(), width) - TILE_MARGIN,
+                    clamp(bounds.top().floor(), height) - TILE_MARGIN,
+                    clamp(bounds.right().ceil(), width) + TILE_MARGIN,
+                    clamp(bounds.bottom().ceil(), height) + TILE_MARGIN,
+                )?
+                .intersect(&canvas)
+            })
+            .collect();
+
+        Tiles {
+            trees: trees.iter().map(resvg::Tree::from_usvg).collect(),
+            canvas,
+            uncut,
+        }
+    }
+
+    /// Render the `width` by `height` part of the image with its top-left
+    /// corner at (`left`, `top`).
+    fn render(
+        &self,
+        (left, top): (u32, u32),
+        (width, height): (u32, u32),
+    ) -> Result<tiny_skia::Pixmap, String> {
+        let tile = tiny_skia::IntRect::from_xywh(left as i32, top as i32, width, height)
+            .and_then(|tile| tile.intersect(&self.canvas))
+            .ok_or_else(|| format!("There is no {width}x{height} tile at ({left}, {top})"))?;
+        let region = self.region(tile);
+
+        let mut pixmap = new_pixmap(region.width(), region.height())?;
+        let transform =
+            tiny_skia::Transform::from_translate(-(region.x() as f32), -(region.y() as f32));
+        let (first, rest) = self
+            .trees
+            .split_first()
+            .ok_or("There is nothing to render")?;
+        first.render(transform, &mut pixmap.as_mut());
+        blend_layers_linear(&mut pixmap, transform, rest);
+
+        if region == tile {
+            return Ok(pixmap);
+        }
+        tile.translate(-region.x(), -region.y())
+            .and_then(|crop| pixmap.clone_rect(crop))
+            .ok_or_else(|| "Could not crop a rendered tile".to_string())
+    }
+
+    /// The part of the image to render to get `tile`: the tile and a couple
+    /// of pixels around it (as the renderer can leave out lines it cuts off
+    /// less than a pixel or so from the edge of a small pixmap), grown to
+    /// take in every shape that can't be cut off and reaches into it, and
+    /// every such shape that reaches into that, and so on.
+    fn region(&self, tile: tiny_skia::IntRect) -> tiny_skia::IntRect {
+        let mut region = tiny_skia::IntRect::from_ltrb(
+            tile.left() - TILE_MARGIN,
+            tile.top() - TILE_MARGIN,
+            tile.right() + TILE_MARGIN,
+            tile.bottom() + TILE_MARGIN,
+        )
+        .and_then(|padded| padded.intersect(&self.canvas))
+        .unwrap_or(tile);
+        loop {
+            let grown = self
+                .uncut
+                .iter()
+                .filter(|area| area.intersect(&region).is_some())
+                .fold(region, |region, area| {
+                    tiny_skia::IntRect::from_ltrb(
+                        region.left().min(area.left()),
+                        region.top().min(area.top()),
+                        region.right().max(area.right()),
+                        region.bottom().max(area.bottom()),
+                    )
+                    .unwrap_or(region)
+                });
+            if grown == region {
+                return region;
+            }
+            region = grown;
+        }
+    }
+
+    /// Render the row of `tile_size` tiles starting `top` pixels down, and
+    /// put them together into rows of straight RGBA bytes.
+    fn render_row(&self, top: u32, tile_size: u32) -> Result<Vec<u8>, String> {
+        let (image_width, image_height) = (self.canvas.width(), self.canvas.height());
+        let height = tile_size.min(image_height - top);
+        let tiles = (0..image_width)
+            .step_by(tile_size as usize)
+            .map(|left| self.render((left, top), (tile_size.min(image_width - left), height)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut row = Vec::with_capacity(image_width as usize * height as usize * 4);
+        for y in 0..height {
+            for tile in &tiles {
+                let start = (y * tile.width()) as usize;
+                for pixel in &tile.pixels()[start..start + tile.width() as usize] {
+                    let color = pixel.demultiply();
+                    row.extend([color.red(), color.green(), color.blue(), color.alpha()]);
+                }
+            }
+        }
+        Ok(row)
+    }
+}
+
+/// Whether the renderer draws `node` the same when it is cut off at the edge
+/// of a pixmap. That is true of paths made of only horizontal and vertical
+/// edges, like the background, and of groups that aren't drawn in a layer
+/// of their own (whatever is in them is checked separately).
+fn can_cut(node: &usvg::Node) -> bool {
+    match *node.borrow() {
+        usvg::NodeKind::Group(ref group) => {
+            group.opacity.get() == 1.0
+                && group.clip_path.is_none()
+                && group.mask.is_none()
+                && group.filters.is_empty()
+        }
+        usvg::NodeKind::Path(ref path) => {
+            let transform = node.abs_transform();
+            let fill = path
+                .fill
+                .as_ref()
+                .map(|_| (*path.data).clone().transform(transform));
+            let stroke = path.stroke.as_ref().map(|stroke| {
+                path.data
+                    .stroke(&skia_stroke(stroke), 1.0)
+                    .and_then(|outline| outline.transform(transform))
+            });
+            [fill, stroke]
+                .into_iter()
+                .flatten()
+                .all(|outline| outline.is_some_and(|outline| is_rectilinear(&outline)))
+        }
+        usvg::NodeKind::Image(_) => false,
+        usvg::NodeKind::Text(_) => true,
+    }
+}
+
+/// Whether every edge of `path` is horizontal or vertical, including the
+/// edges that close each part of it when it is filled.
+fn is_rectilinear(path: &tiny_skia::Path) -> bool {
+    let straight = |from: tiny_skia::Point, to: tiny_skia::Point| from.x == to.x || from.y == to.y;
+    let (mut start, mut current) = (tiny_skia::Point::zero(), tiny_skia::Point::zero());
+    for segment in path.segments() {
+        match segment {
+            tiny_skia::PathSegment::MoveTo(point) => {
+                if !straight(current, start) {
+                    return false;
+                }
+                (start, current) = (point, point);
+            }
+            tiny_skia::PathSegment::LineTo(point) => {
+                if !straight(current, point) {
+                    return false;
+                }
+                current = point;
+            }
+            tiny_skia::PathSegment::Close => current = start,
+            tiny_skia::PathSegment::QuadTo(..) | tiny_skia::PathSegment::CubicTo(..) => {
+                return false
+            }
+        }
+    }
+    straight(current, start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Tiles;
+    use crate::{BlendSpace, Color, FillRule, Image, Rgba, StrokeStyle, COLORS};
+    use resvg::tiny_skia;
+
+    #[test]
+    fn tiled_png_matches_one_pass_render_for_straight_edges() {
+        for space in [BlendSpace::Srgb, BlendSpace::Linear] {
+            let mut image = straight_image();
+            image.set_blend_space(space);
+            assert_eq!(save_tiled(&image, 32), one_pass_render(&image), "{space:?}");
+            assert_eq!(save_tiled(&image, 7), one_pass_render(&image), "{space:?}");
+        }
+    }
+
+    #[test]
+    fn tiled_png_matches_one_pass_render_for_slanted_and_curved_edges() {
+        for space in [BlendSpace::Srgb, BlendSpace::Linear] {
+            for mut image in [slanted_image(), scattered_image()] {
+                image.set_blend_space(space);
+                for tile_size in [11, 64] {
+                    assert_eq!(
+                        save_tiled(&image, tile_size),
+                        one_pass_render(&image),
+                        "{space:?}, tiles of {tile_size}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn tiled_png_of_one_tile_matches_one_pass_render() {
+        let image = slanted_image();
+        assert_eq!(save_tiled(&image, 1000), one_pass_render(&image));
+    }
+
+    #[test]
+    fn bands_put_together_match_one_pass_render_for_straight_edges() {
+        let image = straight_image();
+        assert_eq!(render_bands(&image, 17), one_pass_render(&image));
+    }
+
+    #[test]
+    fn bands_put_together_match_one_pass_render_for_slanted_and_curved_edges() {
+        for image in [slanted_image(), scattered_image()] {
+            for rows_per_chunk in [1, 3, 17, 64, 129, 1000] {
+                assert_eq!(
+                    render_bands(&image, rows_per_chunk),
+                    one_pass_render(&image),
+                    "bands of {rows_per_chunk}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn bands_cover_every_row_once() {
+        let image = slanted_image();
+        for (rows_per_chunk, expected) in [
+            (1, (0..130).map(|top| (top, 1)).collect::<Vec<_>>()),
+            (50, vec![(0, 50), (50, 50), (100, 30)]),
+            (65, vec![(0, 65), (65, 65)]),
+            (130, vec![(0, 130)]),
+            (u32::MAX, vec![(0, 130)]),
+        ] {
+            let mut bands = Vec::new();
+            image
+                .render_rows(rows_per_chunk, |top, band| {
+                    assert_eq!(band.len() % (150 * 4), 0);
+                    bands.push((top, band.len() as u32 / (150 * 4)));
+                    Ok(())
+                })
+                .unwrap();
+            assert_eq!(bands, expected, "{rows_per_chunk} rows per chunk");
+        }
+    }
+
+    #[test]
+    fn only_slanted_shapes_make_a_tile_render_more_than_itself() {
+        let mut image = straight_image();
+        let (end_x, end_y) = image.draw_simple_line(100, 100, 45, 20, COLORS[7]).unwrap();
+        let tiles = Tiles::new(&image);
+        let tile = |left, top| tiny_skia::IntRect::from_xywh(left, top, 10, 10).unwrap();
+
+        for top in (0..130).step_by(10) {
+            assert_eq!(
+                tiles.region(tile(10, top)),
+                tiny_skia::IntRect::from_ltrb(8, (top - 2).max(0), 22, (top + 12).min(130))
+                    .unwrap()
+            );
+        }
+
+        let region = tiles.region(tile(95, 95));
+        assert!(region.left() <= 100.min(end_x) && region.right() >= 100.max(end_x));
+        assert!(region.top() <= 100.min(end_y) && region.bottom() >= 100.max(end_y));
+        let tile = tiles.render((95, 95), (10, 10)).unwrap();
+        assert_eq!((tile.width(), tile.height()), (10, 10));
+    }
+
+    #[test]
+    fn bands_stop_at_the_first_error() {
+        let image = slanted_image();
+        let mut calls = 0;
+        let result = image.render_rows(10, |top, _| {
+            calls += 1;
+            if top == 20 {
+                return Err("full".to_string());
+            }
+            Ok(())
+        });
+        assert_eq!(result, Err("full".to_string()));
+        assert_eq!(calls, 3);
+    }
+
+    #[test]
+    fn bands_of_no_rows_are_an_error() {
+        let image = slanted_image();
+        assert!(image.render_rows(0, |_, _| Ok(())).is_err());
+    }
+
+    #[test]
+    fn tiles_of_no_pixels_are_an_error() {
+        let image = slanted_image();
+        let path = std::env::temp_dir().join("unsvg-tiled-zero-test.png");
+        assert!(image.save_png_tiled(&path, 0).is_err());
+    }
+
+    #[test]
+    fn rows_match_one_pass_render() {
+        for image in [slanted_image(), scattered_image()] {
+            let (width, height) = image.get_dimensions();
+            let rows: Vec<Vec<Color>> = image.rows().collect();
+            assert_eq!(rows.len(), height as usize);
+            assert!(rows.iter().all(|row| row.len() == width as usize));
+
+            let expected = image.render().unwrap();
+            for (y, row) in rows.iter().enumerate() {
+                for (x, color) in row.iter().enumerate() {
+                    let pixel = expected.pixel(x as u32, y as u32).unwrap().demultiply();
+                    assert_eq!(
+                        [color.red, color.green, color.blue],
+                        [pixel.red(), pixel.green(), pixel.blue()],
+                        "({x}, {y})"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rows_of_a_dry_run_image_are_empty() {
+        let mut image = Image::new_dry_run(100, 100);
+        image.draw_simple_line(10, 10, 90, 50, COLORS[1]).unwrap();
+        assert_eq!(image.rows().count(), 0);
+    }
+
+    /// An image whose shapes only have horizontal and vertical edges, which
+    /// cross between tiles and bands everywhere.
+    fn straight_image() -> Image {
+        let mut image = Image::new(150, 130);
+        for (i, width) in [1.0, 2.0, 3.0, 5.0, 8.0].into_iter().enumerate() {
+            let style = StrokeStyle {
+                width,
+                ..StrokeStyle::default()
+            };
+            let offset = 13 + 27 * i as i32;
+            image
+                .draw_styled_line(3, offset, 90, 144, COLORS[i + 1], &style)
+                .unwrap();
+            image
+                .draw_styled_line(offset, 127, 0, 124, COLORS[i + 6], &style)
+                .unwrap();
+        }
+        image
+            .fill_polygon(
+                &[(20, 20), (90, 20), (90, 60), (50, 60), (50, 100), (20, 100)],
+                Rgba::from_straight(255, 255, 0, 128),
+                FillRule::NonZero,
+            )
+            .unwrap();
+        image
+    }
+
+    /// An image with slanted and curved shapes that cross between tiles every
+    /// which way.
+    fn slanted_image() -> Image {
+        let mut image = Image::new(150, 130);
+        for direction in (0..360).step_by(25) {
+            image
+                .draw_simple_line(75, 65, direction, 70, COLORS[direction as usize % 16])
+                .unwrap();
+        }
+        image.draw_ring(40, 40, 30, 8, COLORS[3]).unwrap();
+        image
+            .fill_polygon(
+                &[(-20, 5), (140, 40), (30, 150)],
+                COLORS[4],
+                FillRule::NonZero,
+            )
+            .unwrap();
+        image
+    }
+
+    /// Short slanted lines and small circles spread over the image, so most
+    /// tiles only take in a few of them.
+    fn scattered_image() -> Image {
+        let mut image = Image::new(200, 160);
+        for (index, (x, y)) in (10..200)
+            .step_by(23)
+            .flat_map(|x| (8..160).step_by(19).map(move |y| (x, y)))
+            .enumerate()
+        {
+            let color = COLORS[index % 15 + 1];
+            if index % 3 == 0 {
+                image.draw_ring(x, y, 6, 3, color).unwrap();
+            } else {
+                image
+                    .draw_simple_line(x, y, index as i32 * 37, 12, color)
+                    .unwrap();
+            }
+        }
+        image
+    }
+
+    /// Save the image with `save_png_tiled`, and read back its pixels as
+    /// straight RGBA bytes.
+    fn save_tiled(image: &Image, tile_size: u32) -> Vec<u8> {
+        let path = std::env::temp_dir().join(format!(
+            "unsvg-tiled-test-{:?}-{tile_size}.png",
+            std::thread::current().id()
+        ));
+        image.save_png_tiled(&path, tile_size).unwrap();
+        let decoder = png::Decoder::new(std::fs::File::open(&path).unwrap());
+        let mut reader = decoder.read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        reader.next_frame(&mut pixels).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        pixels
+    }
+
+    /// The image's pixels from `render_rows`, put back together.
+    fn render_bands(image: &Image, rows_per_chunk: u32) -> Vec<u8> {
+        let mut pixels = Vec::new();
+        image
+            .render_rows(rows_per_chunk, |_, band| {
+                pixels.extend_from_slice(band);
+                Ok(())
+            })
+            .unwrap();
+        pixels
+    }
+
+    /// The image's pixels, rendered in one go, as straight RGBA bytes.
+    fn one_pass_render(image: &Image) -> Vec<u8> {
+        super::straight_bytes(&image.render().unwrap())
+    }
+}
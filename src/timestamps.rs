@@ -0,0 +1,155 @@
+use crate::{ElementId, Image};
+use std::time::{Duration, Instant};
+
+/// When an element was drawn, from `Image::element_timestamp`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Timestamp {
+    /// How many elements were drawn before this one since timestamps were
+    /// turned on, so the first element drawn after that is 0.
+    pub sequence: u64,
+    /// How long after timestamps were turned on the element was drawn.
+    pub elapsed: Duration,
+}
+
+/// The timestamps recorded since `Image::enable_timestamps`.
+#[derive(Clone, Debug)]
+pub(crate) struct Timestamps {
+    start: Instant,
+    recorded: Vec<(ElementId, Timestamp)>,
+}
+
+impl Image {
+    /// Start recording when each element is drawn, for tools that replay a
+    /// drawing at the speed it was made. Once this is called, every element
+    /// drawn gets a [`Timestamp`], found with `element_timestamp`, and
+    /// `to_scene_json` includes it.
+    ///
+    /// Timestamps are off by default, so that saving the same drawing always
+    /// gives the same file. Elements drawn before this is called don't get a
+    /// timestamp. Calling it again does nothing, so the times keep counting
+    /// from the first call. Clones (including the frames from `animate`)
+    /// keep the timestamps recorded so far, and carry on from the same start.
+    ///
+    /// ```rs
+    /// image.enable_timestamps();
+    /// image.draw_simple_line(10, 10, 90, 50, COLORS[1])?;
+    /// std::thread::sleep(std::time::Duration::from_millis(100));
+    /// image.draw_simple_line(10, 20, 90, 50, COLORS[4])?;
+    ///
+    /// let ids = image.element_ids();
+    /// let first = image.element_timestamp(ids[0]).unwrap();
+    /// let second = image.element_timestamp(ids[1]).unwrap();
+    /// assert_eq!((first.sequence, second.sequence), (0, 1));
+    /// assert!(second.elapsed - first.elapsed >= std::time::Duration::from_millis(100));
+    /// ```
+    pub fn enable_timestamps(&mut self) {
+        if self.timestamps.is_none() {
+            self.timestamps = Some(Timestamps {
+                start: Instant::now(),
+                recorded: Vec::new(),
+            });
+            self.mark_changed();
+        }
+    }
+
+    /// When an element was drawn. Returns `None` if the element has been
+    /// removed, or if it was drawn before `enable_timestamps` was called.
+    pub fn element_timestamp(&self, id: ElementId) -> Option<Timestamp> {
+        self.element_node(id)?;
+        let timestamps = self.timestamps.as_ref()?;
+        timestamps
+            .recorded
+            .iter()
+            .find(|&&(other, _)| other == id)
+            .map(|&(_, timestamp)| timestamp)
+    }
+
+    /// Record that an element was just drawn, if timestamps are turned on.
+    pub(crate) fn record_timestamp(&mut self, id: ElementId) {
+        if let Some(ref mut timestamps) = self.timestamps {
+            let timestamp = Timestamp {
+                sequence: timestamps.recorded.len() as u64,
+                elapsed: timestamps.start.elapsed(),
+            };
+            timestamps.recorded.push((id, timestamp));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Image, SvgOptions, COLORS};
+    use std::time::Duration;
+
+    #[test]
+    fn elements_drawn_after_enabling_are_numbered_in_order() {
+        let mut image = Image::new(100, 100);
+        image.draw_simple_line(10, 10, 90, 50, COLORS[1]).unwrap();
+        let before = image.last_element().unwrap();
+        image.enable_timestamps();
+        for y in [20, 30, 40] {
+            image.draw_simple_line(10, y, 90, 50, COLORS[4]).unwrap();
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        // Turning them on again doesn't start again.
+        image.enable_timestamps();
+        image.draw_simple_line(10, 50, 90, 50, COLORS[4]).unwrap();
+
+        let ids = image.element_ids();
+        assert_eq!(image.element_timestamp(before), None);
+        let timestamps: Vec<_> = ids[1..]
+            .iter()
+            .map(|&id| image.element_timestamp(id).unwrap())
+            .collect();
+        let sequences: Vec<u64> = timestamps.iter().map(|t| t.sequence).collect();
+        assert_eq!(sequences, [0, 1, 2, 3]);
+        for pair in timestamps.windows(2) {
+            assert!(pair[1].elapsed >= pair[0].elapsed + Duration::from_millis(5));
+        }
+
+        // Clones keep counting, and removed elements have no timestamp.
+        let mut copy = image.clone();
+        copy.draw_simple_line(10, 60, 90, 50, COLORS[4]).unwrap();
+        let last = copy
+            .element_timestamp(copy.last_element().unwrap())
+            .unwrap();
+        assert_eq!(last.sequence, 4);
+        assert!(copy.remove_element(ids[1]));
+        assert_eq!(copy.element_timestamp(ids[1]), None);
+        assert_eq!(image.element_timestamp(ids[1]), Some(timestamps[0]));
+    }
+
+    #[test]
+    fn timestamps_dont_change_saved_files() {
+        let draw = |image: &mut Image| {
+            image.draw_simple_line(10, 10, 90, 50, COLORS[1]).unwrap();
+            image.draw_ring(50, 50, 20, 10, COLORS[4]).unwrap();
+        };
+        let mut plain = Image::new(100, 100);
+        draw(&mut plain);
+        let mut timed = Image::new(100, 100);
+        timed.enable_timestamps();
+        draw(&mut timed);
+        assert_eq!(
+            timed.svg_string(&SvgOptions::default()),
+            plain.svg_string(&SvgOptions::default())
+        );
+        assert_eq!(timed.png_bytes(), plain.png_bytes());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn scenes_include_timestamps() {
+        let mut image = Image::new(100, 100);
+        image.draw_simple_line(10, 10, 90, 50, COLORS[1]).unwrap();
+        assert!(!image.to_scene_json().contains("timestamp"));
+        image.enable_timestamps();
+        image.draw_simple_line(10, 20, 90, 50, COLORS[1]).unwrap();
+        let json = image.to_scene_json();
+        assert_eq!(
+            json.matches(r#""timestamp":{"sequence":0,"elapsed_secs":"#)
+                .count(),
+            1
+        );
+    }
+}
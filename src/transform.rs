@@ -0,0 +1,382 @@
+use crate::elements::{map_points, node_bounds};
+use crate::{normalize_direction, ElementId, Image};
+use resvg::usvg::NodeExt;
+use resvg::{tiny_skia, usvg};
+use std::rc::Rc;
+
+impl Image {
+    /// Draw a mirror image of an element, reflected across the (infinite)
+    /// line through `line_p1` and `line_p2`. The copy has the same colors and
+    /// style as the original, and the original is left where it is.
+    ///
+    /// Returns the id of the new element, or an error if the element doesn't
+    /// exist or the two points are the same (so they don't define a line).
+    ///
+    /// ```rs
+    /// image.draw_simple_line(10, 10, 90, 30, COLORS[1])?;
+    /// let line = image.last_element().unwrap();
+    /// // Reflect across the vertical line x = 50.
+    /// let mirrored = image.draw_reflected(line, (50, 0), (50, 100))?;
+    /// assert_eq!(image.element_bounds(mirrored), Some((60, 9, 90, 11)));
+    /// ```
+    pub fn draw_reflected(
+        &mut self,
+        id: ElementId,
+        line_p1: (i32, i32),
+        line_p2: (i32, i32),
+    ) -> Result<ElementId, String> {
+        if line_p1 == line_p2 {
+            return Err(format!(
+                "Can't reflect across a line through {line_p1:?} and {line_p2:?}: the points are the same"
+            ));
+        }
+
+        let (x, y) = (line_p1.0 as f32, line_p1.1 as f32);
+        let (dx, dy) = (line_p2.0 as f32 - x, line_p2.1 as f32 - y);
+
+        // Reflection across a line through the origin at angle θ is
+        // [cos 2θ, sin 2θ; sin 2θ, -cos 2θ], written without trig so
+        // axis-aligned and diagonal lines give exact results.
+        let length_squared = dx * dx + dy * dy;
+        let cos = (dx * dx - dy * dy) / length_squared;
+        let sin = 2.0 * dx * dy / length_squared;
+        let reflection = tiny_skia::Transform::from_translate(x, y)
+            .pre_concat(tiny_skia::Transform::from_row(
+                cos, sin, sin, -cos, 0.0, 0.0,
+            ))
+            .pre_translate(-x, -y);
+
+        self.append_transformed(id, reflection)
+    }
+
+    /// Draw a copy of an element with a transform applied to it.
+    pub(crate) fn append_transformed(
+        &mut self,
+        id: ElementId,
+        transform: tiny_skia::Transform,
+    ) -> Result<ElementId, String> {
+        let node = self
+            .element_node(id)
+            .ok_or_else(|| format!("There is no element with id {id:?}"))?;
+
+        let group = usvg::Node::new(usvg::NodeKind::Group(usvg::Group {
+            transform,
+            ..usvg::Group::default()
+        }));
+        group.append(node.make_deep_copy());
+        self.append_element_node(group)
+    }
+}
+
+impl Image {
+    /// Stamp copies of the most recently drawn element, each one transformed
+    /// a little more than the last, for spirograph-style patterns.
+    ///
+    /// See `repeat_element` for how the copies are transformed. Returns the
+    /// ids of the copies, or an error if nothing has been drawn yet.
+    ///
+    /// ```rs
+    /// // A star of 36 lines, 10 degrees apart, around where the first one
+    /// // starts.
+    /// image.draw_simple_line(50, 50, 0, 40, COLORS[6])?;
+    /// image.repeat_last(35, 10, (0, 0), 1.0)?;
+    /// ```
+    pub fn repeat_last(
+        &mut self,
+        times: u32,
+        rotate_degrees_per_copy: i32,
+        translate_per_copy: (i32, i32),
+        scale_per_copy: f32,
+    ) -> Result<Vec<ElementId>, String> {
+        let id = self
+            .last_element()
+            .ok_or("There is no element to repeat, since nothing has been drawn")?;
+        self.repeat_element(
+            id,
+            times,
+            rotate_degrees_per_copy,
+            translate_per_copy,
+            scale_per_copy,
+        )
+    }
+
+    /// Stamp `times` copies of an element, each one transformed a little more
+    /// than the last.
+    ///
+    /// Each copy is the previous one (starting with the original) rotated
+    /// clockwise by `rotate_degrees_per_copy` and scaled by `scale_per_copy`
+    /// around where it starts, then moved by `translate_per_copy`. So the
+    /// tenth copy is rotated ten times as far as the first, scaled by
+    /// `scale_per_copy` to the tenth power, and starts ten times
+    /// `translate_per_copy` from where the original does.
+    ///
+    /// Where an element starts is the first point of the first line or
+    /// shape in it, such as the starting point given to `draw_simple_line`.
+    /// An element with no lines or shapes in it is rotated about its centre.
+    ///
+    /// Returns the ids of the copies in order. Repeating zero times does
+    /// nothing. Returns an error if the element doesn't exist, or if
+    /// `scale_per_copy` isn't more than 0.
+    pub fn repeat_element(
+        &mut self,
+        id: ElementId,
+        times: u32,
+        rotate_degrees_per_copy: i32,
+        translate_per_copy: (i32, i32),
+        scale_per_copy: f32,
+    ) -> Result<Vec<ElementId>, String> {
+        if !(scale_per_copy > 0.0 && scale_per_copy.is_finite()) {
+            return Err(format!(
+                "The scale per copy must be more than 0, but it was {scale_per_copy}"
+            ));
+        }
+        let node = self
+            .element_node(id)
+            .ok_or_else(|| format!("There is no element with id {id:?}"))?;
+        let (x, y) = element_start(&node).unwrap_or_else(|| self.drawing_centre());
+
+        // Clockwise on the image is anticlockwise when y goes up.
+        let degrees = match self.coordinate_system() {
+            (_, true) => -normalize_direction(rotate_degrees_per_copy),
+            (_, false) => normalize_direction(rotate_degrees_per_copy),
+        };
+        let (dx, dy) = (translate_per_copy.0 as f32, translate_per_copy.1 as f32);
+
+        // Each copy is worked out from the original rather than from the
+        // copy before, so rounding errors don't build up.
+        (1..=times)
+            .map(|copy| {
+                let copies = copy as f32;
+                let transform =
+                    tiny_skia::Transform::from_translate(x + dx * copies, y + dy * copies)
+                        .pre_concat(tiny_skia::Transform::from_rotate(
+                            (i64::from(degrees) * i64::from(copy) % 360) as f32,
+                        ))
+                        .pre_scale(scale_per_copy.powf(copies), scale_per_copy.powf(copies))
+                        .pre_translate(-x, -y);
+                self.append_transformed(id, transform)
+            })
+            .collect()
+    }
+
+    /// The centre of the image, in drawing coordinates.
+    fn drawing_centre(&self) -> (f32, f32) {
+        let (min_x, min_y, max_x, max_y) = self.drawing_area();
+        (
+            (min_x as f32 + max_x as f32) / 2.0,
+            (min_y as f32 + max_y as f32) / 2.0,
+        )
+    }
+}
+
+/// Where an element starts: the first point of the first path in it, or
+/// else the centre of whatever it covers.
+fn element_start(node: &usvg::Node) -> Option<(f32, f32)> {
+    let start = node.descendants().find_map(|descendant| {
+        let transform = descendant.abs_transform();
+        match *descendant.borrow() {
+            usvg::NodeKind::Path(ref path) => {
+                let mut point = *path.data.points().first()?;
+                transform.map_point(&mut point);
+                Some((point.x, point.y))
+            }
+            _ => None,
+        }
+    });
+    start.or_else(|| {
+        let bounds = node_bounds(node)?;
+        Some((
+            (bounds.left() + bounds.right()) / 2.0,
+            (bounds.top() + bounds.bottom()) / 2.0,
+        ))
+    })
+}
+
+impl Image {
+    /// Move every point of everything drawn so far to the nearest multiple
+    /// of `spacing`, for a clean, blocky look.
+    ///
+    /// Points are snapped where they appear on the image, so copies made by
+    /// `draw_reflected` or `repeat_last` snap to the same grid as everything
+    /// else. The control points of curves are snapped too, and stroke widths
+    /// are not changed. Only things already drawn are snapped, not things
+    /// drawn afterwards. Returns an error if `spacing` is 0.
+    ///
+    /// ```rs
+    /// let mut image = Image::new(100, 100);
+    /// image.draw_simple_line(3, 3, 135, 20, COLORS[1])?;
+    /// image.snap_to_grid(5)?;
+    /// // The line now goes from (5, 5) to (15, 15).
+    /// ```
+    pub fn snap_to_grid(&mut self, spacing: u32) -> Result<(), String> {
+        if spacing == 0 {
+            return Err("The grid spacing must be at least 1".into());
+        }
+        let spacing = spacing as f32;
+        let snap = |value: f32| (value / spacing).round() * spacing;
+
+        for element in self.elements() {
+            for node in element.descendants() {
+                let transform = node.abs_transform();
+                let Some(inverse) = transform.invert() else {
+                    continue;
+                };
+
+                if let usvg::NodeKind::Path(ref mut path) = *node.borrow_mut() {
+                    let snapped = map_points(&path.data, |mut point| {
+                        transform.map_point(&mut point);
+                        point = tiny_skia::Point::from_xy(snap(point.x), snap(point.y));
+                        inverse.map_point(&mut point);
+                        point
+                    });
+                    if let Some(snapped) = snapped {
+                        path.data = Rc::new(snapped);
+                    }
+                }
+            }
+        }
+        self.recount_memory();
+        self.mark_changed();
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Corner, Image, StrokeStyle, COLORS};
+
+    fn near((x1, y1): (f32, f32), (x2, y2): (f32, f32)) -> bool {
+        (x1 - x2).abs() < 1e-3 && (y1 - y2).abs() < 1e-3
+    }
+
+    /// Where the copy of a line drawn from `start` to `end` ends up when
+    /// reflected across the line through `p1` and `p2`.
+    fn reflected(
+        start: (i32, i32),
+        end: (i32, i32),
+        p1: (i32, i32),
+        p2: (i32, i32),
+    ) -> ((f32, f32), (f32, f32)) {
+        let mut image = Image::new(100, 100);
+        image
+            .draw_styled_polyline(&[start, end], COLORS[1], &StrokeStyle::default())
+            .unwrap();
+        let line = image.last_element().unwrap();
+        image.draw_reflected(line, p1, p2).unwrap();
+        let segment = image.to_segments(0.1).pop().unwrap();
+        (segment.start, segment.end)
+    }
+
+    #[test]
+    fn reflections_across_axis_aligned_and_diagonal_lines_are_exact() {
+        // Across x = 50 and y = 40, whichever way round the points are.
+        let vertical = ((90.0, 10.0), (70.0, 30.0));
+        assert_eq!(reflected((10, 10), (30, 30), (50, 0), (50, 100)), vertical);
+        assert_eq!(reflected((10, 10), (30, 30), (50, 100), (50, 0)), vertical);
+        let horizontal = ((10.0, 70.0), (30.0, 50.0));
+        assert_eq!(
+            reflected((10, 10), (30, 30), (0, 40), (100, 40)),
+            horizontal
+        );
+
+        // Across y = x, which swaps x and y, and y = -x + 100.
+        assert_eq!(
+            reflected((10, 20), (60, 30), (0, 0), (100, 100)),
+            ((20.0, 10.0), (30.0, 60.0))
+        );
+        assert_eq!(
+            reflected((10, 20), (60, 30), (100, 0), (0, 100)),
+            ((80.0, 90.0), (70.0, 40.0))
+        );
+    }
+
+    #[test]
+    fn reflecting_across_a_point_is_an_error() {
+        let mut image = Image::new(100, 100);
+        image.draw_simple_line(10, 10, 90, 30, COLORS[1]).unwrap();
+        let line = image.last_element().unwrap();
+        assert!(image.draw_reflected(line, (50, 50), (50, 50)).is_err());
+        assert_eq!(image.element_ids(), [line]);
+    }
+
+    #[test]
+    fn thirty_six_turns_of_ten_degrees_come_back_to_the_start() {
+        let mut image = Image::new(100, 100);
+        image.draw_simple_line(30, 50, 90, 20, COLORS[1]).unwrap();
+        let copies = image.repeat_last(36, 10, (0, 0), 1.0).unwrap();
+        assert_eq!(copies.len(), 36);
+
+        let segments = image.to_segments(0.1);
+        assert_eq!(segments.len(), 37);
+        for (copy, segment) in segments.iter().enumerate() {
+            // Every copy turns about where the line starts, not the centre of
+            // the image.
+            assert!(near(segment.start, (30.0, 50.0)), "{segment:?}");
+            let angle = (copy as f32 * 10.0).to_radians();
+            let end = (30.0 + 20.0 * angle.cos(), 50.0 + 20.0 * angle.sin());
+            assert!(near(segment.end, end), "copy {copy}: {segment:?}");
+        }
+        assert!(near(segments[36].end, segments[0].end));
+    }
+
+    #[test]
+    fn copies_turn_about_where_they_have_moved_to() {
+        let mut image = Image::new(100, 100);
+        image.set_coordinate_system(Corner::BottomLeft, true);
+        image.draw_simple_line(10, 50, 90, 10, COLORS[1]).unwrap();
+        image.repeat_last(3, 90, (20, 0), 2.0).unwrap();
+
+        // Segments are where they are on the image, where clockwise from
+        // the right is down, whichever way y goes.
+        let segments: Vec<_> = image
+            .to_segments(0.1)
+            .into_iter()
+            .map(|segment| (segment.start, segment.end))
+            .collect();
+        let expected = [
+            ((10.0, 50.0), (20.0, 50.0)),
+            ((30.0, 50.0), (30.0, 70.0)),
+            ((50.0, 50.0), (10.0, 50.0)),
+            ((70.0, 50.0), (70.0, -30.0)),
+        ];
+        assert_eq!(segments.len(), expected.len());
+        for ((start, end), (expected_start, expected_end)) in segments.into_iter().zip(expected) {
+            assert!(
+                near(start, expected_start) && near(end, expected_end),
+                "{start:?} {end:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn snapping_moves_every_point_to_the_grid_where_it_is_on_the_image() {
+        let mut image = Image::new(100, 100);
+        image.draw_simple_line(3, 3, 135, 20, COLORS[1]).unwrap();
+        let line = image.last_element().unwrap();
+        // The copy is drawn with a transform, but snaps where it appears.
+        image.draw_reflected(line, (50, 0), (50, 100)).unwrap();
+        image.snap_to_grid(5).unwrap();
+
+        let segments: Vec<_> = image
+            .to_segments(0.1)
+            .into_iter()
+            .map(|segment| (segment.start, segment.end))
+            .collect();
+        let expected = [((5.0, 5.0), (15.0, 15.0)), ((95.0, 5.0), (85.0, 15.0))];
+        assert_eq!(segments.len(), expected.len());
+        for ((start, end), (expected_start, expected_end)) in segments.into_iter().zip(expected) {
+            assert!(
+                near(start, expected_start) && near(end, expected_end),
+                "{start:?} {end:?}"
+            );
+        }
+
+        // Lines drawn afterwards aren't snapped.
+        image.draw_simple_line(3, 3, 90, 20, COLORS[1]).unwrap();
+        let last = image.to_segments(0.1).pop().unwrap();
+        assert_eq!((last.start, last.end), ((3.0, 3.0), (23.0, 3.0)));
+
+        assert!(image.snap_to_grid(0).is_err());
+    }
+}
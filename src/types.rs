@@ -0,0 +1,636 @@
+//! The types used by `unsvg`'s public interface.
+//!
+//! `unsvg` is built on [`resvg`](https://docs.rs/resvg), but doesn't expose
+//! `resvg`'s types directly. Everything here is owned by `unsvg`, so upgrading
+//! the underlying `resvg` version is never a breaking change for users.
+
+use crate::{COLORS, COLOR_NAMES};
+use resvg::usvg;
+
+/// A fully opaque color, made up of red, green and blue channels.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Color {
+    /// The red channel.
+    pub red: u8,
+    /// The green channel.
+    pub green: u8,
+    /// The blue channel.
+    pub blue: u8,
+}
+
+impl Color {
+    /// Creates a color from its red, green and blue channels.
+    pub fn new_rgb(red: u8, green: u8, blue: u8) -> Color {
+        Color { red, green, blue }
+    }
+
+    /// Black, which is also the default background color.
+    pub fn black() -> Color {
+        Color::new_rgb(0, 0, 0)
+    }
+
+    /// White.
+    pub fn white() -> Color {
+        Color::new_rgb(255, 255, 255)
+    }
+
+    /// The complement of this color, with each channel flipped, so black
+    /// becomes white and red becomes cyan.
+    pub fn inverted(self) -> Color {
+        Color::new_rgb(255 - self.red, 255 - self.green, 255 - self.blue)
+    }
+
+    /// Creates a color from a hue, saturation and value (HSV).
+    ///
+    /// `hue` is in degrees around the color wheel: 0 is red, 60 yellow, 120
+    /// green, 180 cyan, 240 blue and 300 magenta, blending evenly in between.
+    /// Hues outside 0 to 360 wrap around, and a hue that isn't finite counts
+    /// as 0. `saturation` (0 is grey, 1 is fully colored) and `value` (0 is
+    /// black, 1 is fully bright) are clamped to 0.0 to 1.0. Each channel is
+    /// rounded to the nearest whole number.
+    ///
+    /// ```rs
+    /// assert_eq!(Color::from_hsv(120.0, 1.0, 1.0), Color::new_rgb(0, 255, 0));
+    /// assert_eq!(Color::from_hsv(90.0, 1.0, 1.0), Color::new_rgb(128, 255, 0));
+    /// assert_eq!(Color::from_hsv(0.0, 0.0, 0.5), Color::new_rgb(128, 128, 128));
+    /// ```
+    pub fn from_hsv(hue: f32, saturation: f32, value: f32) -> Color {
+        let unit = |x: f32| if x.is_nan() { 0.0 } else { x.clamp(0.0, 1.0) };
+        let (saturation, value) = (unit(saturation), unit(value));
+        let hue = if hue.is_finite() {
+            hue.rem_euclid(360.0) / 60.0
+        } else {
+            0.0
+        };
+
+        let chroma = value * saturation;
+        let middle = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+        let (red, green, blue) = match hue as u32 % 6 {
+            0 => (chroma, middle, 0.0),
+            1 => (middle, chroma, 0.0),
+            2 => (0.0, chroma, middle),
+            3 => (0.0, middle, chroma),
+            4 => (middle, 0.0, chroma),
+            _ => (chroma, 0.0, middle),
+        };
+
+        let lightest = value - chroma;
+        let channel = |x: f32| ((x + lightest) * 255.0).round() as u8;
+        Color::new_rgb(channel(red), channel(green), channel(blue))
+    }
+
+    pub(crate) fn from_usvg(color: usvg::Color) -> Color {
+        Color::new_rgb(color.red, color.green, color.blue)
+    }
+
+    pub(crate) fn to_usvg(self) -> usvg::Color {
+        usvg::Color::new_rgb(self.red, self.green, self.blue)
+    }
+
+    pub(crate) fn paint(self) -> usvg::Paint {
+        usvg::Paint::Color(self.to_usvg())
+    }
+}
+
+/// A color with an alpha channel, for drawing translucent shapes.
+///
+/// The red, green and blue channels can be given either as *straight* values
+/// (the color you would see if the shape were fully opaque) or as
+/// *premultiplied* values (each channel already scaled by the alpha). Mixing
+/// the two up is a common source of dark fringes around translucent shapes,
+/// so an `Rgba` can only be built through a constructor that says which one
+/// you mean. Internally the color is always stored straight, and the renderer
+/// takes care of premultiplying it.
+///
+/// ```rs
+/// // 50% transparent white.
+/// let color = Rgba::from_straight(255, 255, 255, 128);
+/// assert_eq!(color.to_premultiplied(), [128, 128, 128, 128]);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rgba {
+    red: u8,
+    green: u8,
+    blue: u8,
+    alpha: u8,
+}
+
+impl Rgba {
+    /// Creates a color from straight (not premultiplied) channel values.
+    pub fn from_straight(red: u8, green: u8, blue: u8, alpha: u8) -> Rgba {
+        Rgba {
+            red,
+            green,
+            blue,
+            alpha,
+        }
+    }
+
+    /// Creates a color from premultiplied channel values.
+    ///
+    /// Color channels greater than `alpha` are not valid premultiplied values,
+    /// and are clamped to `alpha`. A fully transparent color is always black.
+    pub fn from_premultiplied(red: u8, green: u8, blue: u8, alpha: u8) -> Rgba {
+        let unpremultiply = |channel: u8| {
+            if alpha == 0 {
+                0
+            } else {
+                let channel = u32::from(channel.min(alpha));
+                let alpha = u32::from(alpha);
+                // Rounded division, which can't exceed 255 since channel <= alpha.
+                ((channel * 255 + alpha / 2) / alpha) as u8
+            }
+        };
+
+        Rgba {
+            red: unpremultiply(red),
+            green: unpremultiply(green),
+            blue: unpremultiply(blue),
+            alpha,
+        }
+    }
+
+    /// The straight channel values, as `[red, green, blue, alpha]`.
+    pub fn to_straight(&self) -> [u8; 4] {
+        [self.red, self.green, self.blue, self.alpha]
+    }
+
+    /// The premultiplied channel values, as `[red, green, blue, alpha]`.
+    pub fn to_premultiplied(&self) -> [u8; 4] {
+        let premultiply = |channel: u8| {
+            let product = u32::from(channel) * u32::from(self.alpha);
+            ((product + 127) / 255) as u8
+        };
+
+        [
+            premultiply(self.red),
+            premultiply(self.green),
+            premultiply(self.blue),
+            self.alpha,
+        ]
+    }
+
+    /// The opaque part of the color, without its alpha.
+    pub fn color(&self) -> Color {
+        Color::new_rgb(self.red, self.green, self.blue)
+    }
+
+    pub(crate) fn paint(&self) -> usvg::Paint {
+        self.color().paint()
+    }
+
+    pub(crate) fn opacity(&self) -> usvg::Opacity {
+        usvg::Opacity::new_clamped(f32::from(self.alpha) / 255.0)
+    }
+}
+
+impl From<Color> for Rgba {
+    fn from(color: Color) -> Rgba {
+        Rgba::from_straight(color.red, color.green, color.blue, 255)
+    }
+}
+
+/// Identifies something drawn on an [`Image`](crate::Image).
+///
+/// Every draw call adds one element to the image, which can be found with
+/// [`Image::last_element`](crate::Image::last_element). Ids are never reused,
+/// so an id whose element has been removed stays invalid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ElementId(pub(crate) u64);
+
+/// Anything that can be used as a color in a draw call.
+///
+/// As well as [`Color`] and [`Rgba`], draw calls accept:
+///  - an index into [`COLORS`], as a `usize`, `u32` or `i32`, and
+///  - the name of a color in [`COLOR_NAMES`], like `"red"` (ignoring case).
+///
+/// An index that is out of range, or a name that isn't in the palette, makes
+/// the draw call return an error instead of drawing anything.
+///
+/// ```rs
+/// image.draw_simple_line(10, 10, 90, 50, 4)?;
+/// image.draw_simple_line(10, 20, 90, 50, "red")?;
+/// image.draw_simple_line(10, 30, 90, 50, COLORS[4])?;
+/// ```
+pub trait IntoColor {
+    /// Convert into a color, or describe why that isn't possible.
+    fn into_color(self) -> Result<Rgba, String>;
+}
+
+impl IntoColor for Color {
+    fn into_color(self) -> Result<Rgba, String> {
+        Ok(self.into())
+    }
+}
+
+impl IntoColor for Rgba {
+    fn into_color(self) -> Result<Rgba, String> {
+        Ok(self)
+    }
+}
+
+impl IntoColor for usize {
+    fn into_color(self) -> Result<Rgba, String> {
+        COLORS
+            .get(self)
+            .map(|&color| color.into())
+            .ok_or_else(|| index_error(self))
+    }
+}
+
+impl IntoColor for u32 {
+    fn into_color(self) -> Result<Rgba, String> {
+        usize::try_from(self)
+            .unwrap_or(usize::MAX)
+            .into_color()
+            .map_err(|_| index_error(self))
+    }
+}
+
+impl IntoColor for i32 {
+    fn into_color(self) -> Result<Rgba, String> {
+        usize::try_from(self)
+            .map_err(|_| index_error(self))?
+            .into_color()
+    }
+}
+
+fn index_error(index: impl std::fmt::Display) -> String {
+    format!(
+        "Color index {index} is out of range: valid indices are 0 to {}",
+        COLORS.len() - 1
+    )
+}
+
+impl IntoColor for &str {
+    fn into_color(self) -> Result<Rgba, String> {
+        COLOR_NAMES
+            .iter()
+            .position(|name| name.eq_ignore_ascii_case(self))
+            .map(|index| COLORS[index].into())
+            .ok_or_else(|| {
+                format!(
+                    "Unknown color name '{self}': valid names are {}",
+                    COLOR_NAMES.join(", ")
+                )
+            })
+    }
+}
+
+/// How the end point of a line is rounded to whole units, from
+/// `Image::set_rounding` or `get_end_coordinates_rounded`.
+///
+/// ```rs
+/// // Direction 30, length 1 ends exactly half a unit to the right.
+/// assert_eq!(get_end_coordinates_rounded(0, 0, 30, 1, Rounding::Nearest).0, 1);
+/// assert_eq!(get_end_coordinates_rounded(0, 0, 30, 1, Rounding::Truncate).0, 0);
+/// assert_eq!(get_end_coordinates_rounded(0, 0, 30, 1, Rounding::HalfToEven).0, 0);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Rounding {
+    /// Round to the nearest whole number, with halves rounded away from zero
+    /// (so 2.5 becomes 3, and -2.5 becomes -3). This is the default, and is
+    /// what `f32::round` does.
+    #[default]
+    Nearest,
+    /// Drop the fractional part, rounding towards zero (so 2.7 becomes 2, and
+    /// -2.7 becomes -2). This matches casting with `as i32`.
+    Truncate,
+    /// Round to the nearest whole number, with halves rounded to the nearest
+    /// even number (so 2.5 becomes 2, and 3.5 becomes 4). This is also called
+    /// banker's rounding, and is IEEE 754's default rounding mode.
+    HalfToEven,
+}
+
+impl Rounding {
+    pub(crate) fn apply(self, num: f32) -> f32 {
+        match self {
+            Rounding::Nearest => num.round(),
+            Rounding::Truncate => num.trunc(),
+            Rounding::HalfToEven => num.round_ties_even(),
+        }
+    }
+
+    /// Like `apply`, for an `f64`.
+    pub(crate) fn apply_f64(self, num: f64) -> f64 {
+        match self {
+            Rounding::Nearest => num.round(),
+            Rounding::Truncate => num.trunc(),
+            Rounding::HalfToEven => num.round_ties_even(),
+        }
+    }
+}
+
+/// How a filled shape decides which areas are inside it, which matters for
+/// shapes whose outline crosses itself.
+///
+/// For a pentagram (a five-pointed star drawn with one line, whose outline
+/// crosses itself five times), the points are inside with either rule, but
+/// the pentagon in the middle is only inside with `NonZero`, so `EvenOdd`
+/// leaves a hole there. For shapes whose outline never crosses itself, both
+/// rules fill the same area.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum FillRule {
+    /// A point is inside if the outline winds around it: drawing a ray out
+    /// from the point, the outline crosses it more times going one way
+    /// (clockwise, say) than the other. This is the SVG default.
+    #[default]
+    NonZero,
+    /// A point is inside if a ray out from it crosses the outline an odd
+    /// number of times, whichever way the outline goes.
+    EvenOdd,
+}
+
+impl FillRule {
+    pub(crate) fn to_usvg(self) -> usvg::FillRule {
+        match self {
+            FillRule::NonZero => usvg::FillRule::NonZero,
+            FillRule::EvenOdd => usvg::FillRule::EvenOdd,
+        }
+    }
+}
+
+/// A preset dash pattern for lines.
+///
+/// The lengths of the dashes and gaps are multiples of the stroke width, so a
+/// pattern looks the same whether the line is thin or thick. For a stroke of
+/// width `w`, the dash arrays (alternating dash and gap lengths) are:
+///
+/// | Style     | Dash array                 |
+/// |-----------|----------------------------|
+/// | `Solid`   | none                       |
+/// | `Dashed`  | `[4w, 3w]`                 |
+/// | `Dotted`  | `[0.05w, 2w]`              |
+/// | `DashDot` | `[4w, 2w, 0.05w, 2w]`      |
+///
+/// The dots are very short dashes, which become round dots when drawn with
+/// [`LineCap::Round`]. With the default `LineCap::Butt` they are barely
+/// visible, so dotted lines should almost always use round caps.
+///
+/// ```rs
+/// assert_eq!(LineStyle::Dotted.dash_array(2.0), Some(vec![0.1, 4.0]));
+/// assert_eq!(LineStyle::Solid.dash_array(2.0), None);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum LineStyle {
+    /// An unbroken line.
+    #[default]
+    Solid,
+    /// Long dashes.
+    Dashed,
+    /// Dots, which should be drawn with round caps.
+    Dotted,
+    /// Alternating dashes and dots.
+    DashDot,
+}
+
+impl LineStyle {
+    /// The dash array for this style with a stroke of the given width, or
+    /// `None` for a solid line.
+    pub fn dash_array(self, width: f32) -> Option<Vec<f32>> {
+        let dot = 0.05;
+        let pattern: &[f32] = match self {
+            LineStyle::Solid => return None,
+            LineStyle::Dashed => &[4.0, 3.0],
+            LineStyle::Dotted => &[dot, 2.0],
+            LineStyle::DashDot => &[4.0, 2.0, dot, 2.0],
+        };
+        Some(pattern.iter().map(|length| length * width).collect())
+    }
+}
+
+/// A repeating shape drawn along a line in place of a plain stroke, as used
+/// for railways, borders and the like on maps and diagrams.
+///
+/// Like [`LineStyle`], the sizes are multiples of the stroke width `w`, and
+/// everything is drawn with the line's stroke (so it can be dashed too):
+///
+/// | Pattern    | Shape                                                      |
+/// |------------|------------------------------------------------------------|
+/// | `Plain`    | the line itself                                            |
+/// | `Zigzag`   | a zigzag with its points `2w` either side of the line, `4w` apart along it |
+/// | `Railroad` | two rails `2w` either side of the line, with ties `6w` long every `4w` |
+/// | `Ticks`    | the line, with ticks `3w` long every `4w` on its left      |
+///
+/// The left of a line is on the left going from its start to its end, with
+/// y going down the image.
+///
+/// ```rs
+/// let railway = StrokeStyle {
+///     pattern: StrokePattern::Railroad,
+///     ..StrokeStyle::default()
+/// };
+/// image.draw_styled_polyline(&[(10, 90), (50, 50), (90, 50)], COLORS[7], &railway)?;
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum StrokePattern {
+    /// The line itself.
+    #[default]
+    Plain,
+    /// A zigzag back and forth across the line.
+    Zigzag,
+    /// Two parallel rails, joined by ties across the line.
+    Railroad,
+    /// The line, with short ticks along one side.
+    Ticks,
+}
+
+/// The shape drawn at the ends of a line (and of each dash).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum LineCap {
+    /// The line stops exactly at its end point.
+    #[default]
+    Butt,
+    /// The line ends in a half circle around its end point.
+    Round,
+    /// The line ends in a half square around its end point.
+    Square,
+}
+
+/// How a line is stroked: its width, dash pattern, end caps and corners.
+///
+/// The default is what `draw_simple_line` uses: a solid line 1 unit wide,
+/// with butt caps and a miter limit of 4 (see the [crate docs](crate#strokes)).
+///
+/// ```rs
+/// let style = StrokeStyle {
+///     width: 3.0,
+///     style: LineStyle::Dotted,
+///     cap: LineCap::Round,
+///     ..StrokeStyle::default()
+/// };
+/// image.draw_styled_line(10, 10, 90, 50, COLORS[1], &style)?;
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StrokeStyle {
+    /// The width of the line, which must be more than 0.
+    pub width: f32,
+    /// The dash pattern of the line.
+    pub style: LineStyle,
+    /// The shape of the ends of the line, and of each of its dashes.
+    pub cap: LineCap,
+    /// How far a sharp corner may stick out, as a multiple of the line's
+    /// width, before its point is cut off. It must be at least 1.
+    ///
+    /// Where two segments meet, the outer edges of the line are extended
+    /// until they meet in a point (a "miter"). The sharper the corner, the
+    /// longer the miter is: for an angle of θ between the segments, it is
+    /// `1 / sin(θ / 2)` widths from the inside of the corner to its point.
+    /// When that is more than the miter limit, the corner is cut off flat
+    /// (beveled) instead.
+    /// The default of 4 bevels corners sharper than about 29 degrees, which
+    /// stops nearly-reversing polylines from growing long spikes. Raise it
+    /// for sharper points, or use 1 to bevel every corner.
+    pub miter_limit: f32,
+    /// A repeating shape to draw along the line instead of the line itself.
+    pub pattern: StrokePattern,
+}
+
+impl Default for StrokeStyle {
+    fn default() -> StrokeStyle {
+        StrokeStyle {
+            width: 1.0,
+            style: LineStyle::Solid,
+            cap: LineCap::Butt,
+            miter_limit: 4.0,
+            pattern: StrokePattern::Plain,
+        }
+    }
+}
+
+impl StrokeStyle {
+    /// The usvg stroke for this style in the given color.
+    pub(crate) fn to_usvg(self, color: Rgba) -> Result<usvg::Stroke, String> {
+        let width = usvg::StrokeWidth::new(self.width)
+            .filter(|width| width.get().is_finite())
+            .ok_or_else(|| {
+                format!(
+                    "The stroke width must be more than 0, but it was {}",
+                    self.width
+                )
+            })?;
+        if !(self.miter_limit >= 1.0 && self.miter_limit.is_finite()) {
+            return Err(format!(
+                "The miter limit must be at least 1, but it was {}",
+                self.miter_limit
+            ));
+        }
+
+        Ok(usvg::Stroke {
+            paint: color.paint(),
+            opacity: color.opacity(),
+            width,
+            dasharray: self.style.dash_array(self.width),
+            linecap: match self.cap {
+                LineCap::Butt => usvg::LineCap::Butt,
+                LineCap::Round => usvg::LineCap::Round,
+                LineCap::Square => usvg::LineCap::Square,
+            },
+            miterlimit: usvg::StrokeMiterlimit::new(self.miter_limit),
+            ..usvg::Stroke::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Image, StrokeStyle, SvgOptions};
+
+    /// The SVG of a line drawn in `color`, or why it couldn't be drawn.
+    fn line_in(color: impl IntoColor) -> Result<String, String> {
+        let mut image = Image::new(100, 100);
+        image.draw_simple_line(10, 10, 90, 50, color)?;
+        Ok(image.svg_string(&SvgOptions::default()))
+    }
+
+    #[test]
+    fn palette_indices_must_be_in_range() {
+        let Err(e) = line_in(99usize) else {
+            panic!("drew a line in color 99")
+        };
+        assert!(e.contains("0 to 15"), "{e}");
+        assert!(line_in(16u32).unwrap_err().contains("0 to 15"));
+        assert!(line_in(-1).unwrap_err().contains("0 to 15"));
+        assert!(line_in(i32::MIN).is_err());
+
+        assert_eq!(line_in(4usize).unwrap(), line_in(COLORS[4]).unwrap());
+        assert_eq!(line_in(15u32).unwrap(), line_in(COLORS[15]).unwrap());
+        assert_eq!(line_in(0).unwrap(), line_in(COLORS[0]).unwrap());
+    }
+
+    #[test]
+    fn color_names_are_the_palette_ignoring_case() {
+        let red = line_in(COLORS[4]).unwrap();
+        for name in ["red", "Red", "RED", "rEd"] {
+            assert_eq!(line_in(name).unwrap(), red, "{name}");
+        }
+        for (name, &color) in COLOR_NAMES.iter().zip(&COLORS) {
+            assert_eq!(name.into_color(), Ok(color.into()), "{name}");
+        }
+
+        for name in ["crimson", "", "red ", "reds"] {
+            let Err(e) = line_in(name) else {
+                panic!("drew a line in '{name}'")
+            };
+            assert!(e.contains(&format!("'{name}'")), "{e}");
+        }
+    }
+
+    #[test]
+    fn colors_are_used_as_they_are() {
+        let color = Color::new_rgb(12, 34, 56);
+        assert_eq!(color.into_color(), Ok(color.into()));
+        let svg = line_in(color).unwrap();
+        assert!(svg.contains("#0c2238"), "{svg}");
+
+        let rgba = Rgba::from_straight(12, 34, 56, 78);
+        assert_eq!(rgba.into_color(), Ok(rgba));
+        assert!(line_in(rgba).is_ok());
+    }
+
+    #[test]
+    fn straight_alpha_is_premultiplied_when_rendered() {
+        let white = Rgba::from_straight(255, 255, 255, 128);
+        assert_eq!(white.to_premultiplied(), [128, 128, 128, 128]);
+        assert_eq!(
+            Rgba::from_premultiplied(64, 0, 200, 128).to_straight(),
+            [128, 0, 255, 128]
+        );
+
+        // Half-transparent white over the black background.
+        let mut image = Image::new(20, 20);
+        let style = StrokeStyle {
+            width: 10.0,
+            ..StrokeStyle::default()
+        };
+        image
+            .draw_styled_line(10, 0, 180, 20, white, &style)
+            .unwrap();
+        let pixmap = image.render().unwrap();
+        let pixel = pixmap.pixel(10, 10).unwrap();
+        assert_eq!(
+            [pixel.red(), pixel.green(), pixel.blue(), pixel.alpha()],
+            [128, 128, 128, 255]
+        );
+    }
+
+    #[test]
+    fn line_styles_scale_their_dashes_with_the_width() {
+        assert_eq!(LineStyle::Solid.dash_array(1.0), None);
+        assert_eq!(LineStyle::Dashed.dash_array(1.0), Some(vec![4.0, 3.0]));
+        assert_eq!(LineStyle::Dotted.dash_array(1.0), Some(vec![0.05, 2.0]));
+        assert_eq!(
+            LineStyle::DashDot.dash_array(1.0),
+            Some(vec![4.0, 2.0, 0.05, 2.0])
+        );
+
+        assert_eq!(LineStyle::Solid.dash_array(2.0), None);
+        assert_eq!(LineStyle::Dashed.dash_array(2.0), Some(vec![8.0, 6.0]));
+        assert_eq!(LineStyle::Dotted.dash_array(2.0), Some(vec![0.1, 4.0]));
+        assert_eq!(
+            LineStyle::DashDot.dash_array(0.5),
+            Some(vec![2.0, 1.0, 0.025, 1.0])
+        );
+    }
+}
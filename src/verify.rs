@@ -0,0 +1,143 @@
+use crate::snapshot::parse_svg;
+use crate::{render_tree, Image, SvgOptions};
+use resvg::tiny_skia::Pixmap;
+
+/// How far apart (out of 255) a channel of the two renders can be before the
+/// pixel counts as different. This allows for tiny rounding differences.
+const TOLERANCE: u8 = 2;
+
+/// How many differing pixels are described individually.
+const MAX_REPORTED: usize = 10;
+
+impl Image {
+    /// Check that the SVG this image saves looks the same as the image itself.
+    ///
+    /// The image is rendered directly, and its SVG is parsed back and
+    /// rendered too, and the two are compared pixel by pixel. If any channel
+    /// of any pixel differs by more than 2 (out of 255), this returns an
+    /// error describing the differences: a summary first, and then the
+    /// first few pixels that differ. A dry-run image, which can't be
+    /// rendered, is an error too.
+    ///
+    /// A difference means something was lost or changed when writing the
+    /// SVG, which is a bug in `unsvg`. This is quick for small images, so it
+    /// is reasonable to run in tests. Linear blending (see `set_blend_space`)
+    /// can't be saved in SVGs, so both renders use sRGB blending.
+    ///
+    /// ```rs
+    /// image.verify_render_consistency().map_err(|errors| errors.join("\n"))?;
+    /// ```
+    pub fn verify_render_consistency(&self) -> Result<(), Vec<String>> {
+        self.check_not_dry_run().map_err(|e| vec![e])?;
+        let direct = render_tree(&self.export_tree()).map_err(|e| vec![e])?;
+        let svg = self.svg_string(&SvgOptions::default());
+        let reparsed = parse_svg(&svg)
+            .and_then(|tree| render_tree(&tree))
+            .map_err(|e| vec![format!("Could not render the saved SVG: {e}")])?;
+        compare_renders(&direct, &reparsed)
+    }
+}
+
+/// Compare the image rendered directly with its saved SVG rendered, as
+/// described in `verify_render_consistency`.
+fn compare_renders(direct: &Pixmap, reparsed: &Pixmap) -> Result<(), Vec<String>> {
+    if (direct.width(), direct.height()) != (reparsed.width(), reparsed.height()) {
+        return Err(vec![format!(
+            "The image is {}x{}, but the saved SVG is {}x{}",
+            direct.width(),
+            direct.height(),
+            reparsed.width(),
+            reparsed.height()
+        )]);
+    }
+
+    let width = direct.width() as usize;
+    let mut different = 0;
+    let mut reported = Vec::new();
+    for (index, (a, b)) in direct.pixels().iter().zip(reparsed.pixels()).enumerate() {
+        let a = [a.red(), a.green(), a.blue(), a.alpha()];
+        let b = [b.red(), b.green(), b.blue(), b.alpha()];
+        if a.iter().zip(&b).all(|(a, b)| a.abs_diff(*b) <= TOLERANCE) {
+            continue;
+        }
+
+        different += 1;
+        if reported.len() < MAX_REPORTED {
+            reported.push(format!(
+                "Pixel ({}, {}) is {a:?} in the image, but {b:?} in the saved SVG",
+                index % width,
+                index / width
+            ));
+        }
+    }
+
+    if different == 0 {
+        return Ok(());
+    }
+
+    let mut errors = vec![format!(
+        "{different} of {} pixels differ between the image and its saved SVG",
+        direct.pixels().len()
+    )];
+    errors.extend(reported);
+    Err(errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::compare_renders;
+    use crate::{Image, Rgba, StrokeStyle, COLORS};
+    use resvg::tiny_skia::{Color, Pixmap};
+
+    #[test]
+    fn what_is_drawn_survives_being_saved() {
+        let mut image = Image::new(100, 100);
+        image.draw_simple_line(10, 10, 135, 80, COLORS[1]).unwrap();
+        let style = StrokeStyle {
+            width: 6.0,
+            ..StrokeStyle::default()
+        };
+        image
+            .draw_styled_line(90, 10, 180, 80, Rgba::from_straight(255, 0, 0, 100), &style)
+            .unwrap();
+        image.draw_ring(50, 50, 30, 20, COLORS[4]).unwrap();
+        assert_eq!(image.verify_render_consistency(), Ok(()));
+
+        let mut image = Image::new_dry_run(100, 100);
+        image.draw_simple_line(10, 10, 135, 80, COLORS[1]).unwrap();
+        assert!(image.verify_render_consistency().is_err());
+    }
+
+    #[test]
+    fn differences_are_counted_and_the_first_few_described() {
+        let mut direct = Pixmap::new(20, 10).unwrap();
+        direct.fill(Color::BLACK);
+        let mut reparsed = direct.clone();
+        // Within the tolerance.
+        reparsed.pixels_mut()[0] = Color::from_rgba8(2, 0, 0, 255).premultiply().to_color_u8();
+        assert_eq!(compare_renders(&direct, &reparsed), Ok(()));
+
+        for pixel in &mut reparsed.pixels_mut()[..15] {
+            *pixel = Color::from_rgba8(3, 0, 0, 255).premultiply().to_color_u8();
+        }
+        let errors = compare_renders(&direct, &reparsed).unwrap_err();
+        assert_eq!(errors.len(), 11);
+        assert_eq!(
+            errors[0],
+            "15 of 200 pixels differ between the image and its saved SVG"
+        );
+        assert_eq!(
+            errors[1],
+            "Pixel (0, 0) is [0, 0, 0, 255] in the image, but [3, 0, 0, 255] in the saved SVG"
+        );
+        assert!(errors[10].starts_with("Pixel (9, 0) "));
+
+        let smaller = Pixmap::new(10, 10).unwrap();
+        assert_eq!(
+            compare_renders(&direct, &smaller),
+            Err(vec![
+                "The image is 20x10, but the saved SVG is 10x10".to_string()
+            ])
+        );
+    }
+}
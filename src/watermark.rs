@@ -0,0 +1,199 @@
+use crate::text::text_node;
+use crate::{Color, Image};
+use resvg::usvg;
+
+/// The size of watermark text, in units.
+const WATERMARK_SIZE: f32 = 10.0;
+
+/// How far a watermark is from the edges of the image, in units.
+const WATERMARK_MARGIN: f32 = 4.0;
+
+/// A corner of the image.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Corner {
+    /// The top-left corner.
+    TopLeft,
+    /// The top-right corner.
+    TopRight,
+    /// The bottom-left corner.
+    BottomLeft,
+    /// The bottom-right corner.
+    BottomRight,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Watermark {
+    text: String,
+    corner: Corner,
+    opacity: f32,
+}
+
+impl Image {
+    /// Add a small line of grey text (like a student id) in a corner of the
+    /// image whenever it is saved, as both SVG and PNG.
+    ///
+    /// The watermark isn't drawn straight away: it is added each time the
+    /// image is saved, so it is always on top of everything, no matter when
+    /// things were drawn. It isn't an element, isn't affected by the image's
+    /// mask, and setting a new watermark replaces the old one. `opacity` goes
+    /// from 0.0 (invisible) to 1.0 (solid), and is clamped to that range.
+    ///
+    /// The text is drawn in one of the computer's sans-serif fonts, so it
+    /// looks slightly different on different computers (unless the fonts are
+    /// chosen with `set_font_files`). In saved SVGs it is made of paths,
+    /// rather than text.
+    ///
+    /// ```rs
+    /// image.set_watermark("z5555555", Corner::BottomRight, 0.5);
+    /// image.save_png("image.png")?;
+    /// ```
+    pub fn set_watermark(&mut self, text: &str, corner: Corner, opacity: f32) {
+        self.watermark = Some(Watermark {
+            text: text.to_string(),
+            corner,
+            opacity: if opacity.is_nan() {
+                0.0
+            } else {
+                opacity.clamp(0.0, 1.0)
+            },
+        });
+        self.mark_changed();
+    }
+
+    /// Stop adding the watermark set by `set_watermark` when saving.
+    pub fn clear_watermark(&mut self) {
+        self.watermark = None;
+        self.mark_changed();
+    }
+
+    /// The watermark's text, in place on the image.
+    pub(crate) fn watermark_node(&self) -> Option<usvg::Node> {
+        let watermark = self.watermark.as_ref()?;
+        let (width, height) = (self.width as f32, self.height as f32);
+        let (x, anchor) = match watermark.corner {
+            Corner::TopLeft | Corner::BottomLeft => (WATERMARK_MARGIN, usvg::TextAnchor::Start),
+            Corner::TopRight | Corner::BottomRight => {
+                (width - WATERMARK_MARGIN, usvg::TextAnchor::End)
+            }
+        };
+        // `y` is the baseline, which letters sit on: capitals reach most of
+        // the font size above it, and tails hang about a quarter below it.
+        let y = match watermark.corner {
+            Corner::TopLeft | Corner::TopRight => WATERMARK_MARGIN + WATERMARK_SIZE * 0.8,
+            Corner::BottomLeft | Corner::BottomRight => {
+                height - WATERMARK_MARGIN - WATERMARK_SIZE * 0.25
+            }
+        };
+
+        let fill = usvg::Fill {
+            paint: Color::new_rgb(128, 128, 128).paint(),
+            opacity: usvg::Opacity::new_clamped(watermark.opacity),
+            ..usvg::Fill::default()
+        };
+        text_node(&watermark.text, (x, y), WATERMARK_SIZE, anchor, fill)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::text::test_font;
+    use crate::{Corner, Image, SvgOptions, COLORS};
+
+    /// The area covered by the pixels that aren't black, and the brightest
+    /// red in it.
+    fn lit_area(image: &Image) -> Option<((u32, u32, u32, u32), u8)> {
+        let pixmap = image.render().unwrap();
+        let mut area: Option<((u32, u32, u32, u32), u8)> = None;
+        for y in 0..pixmap.height() {
+            for x in 0..pixmap.width() {
+                let red = pixmap.pixel(x, y).unwrap().red();
+                if red == 0 {
+                    continue;
+                }
+                let ((left, top, right, bottom), brightest) = area.unwrap_or(((x, y, x, y), red));
+                area = Some((
+                    (left.min(x), top.min(y), right.max(x), bottom.max(y)),
+                    brightest.max(red),
+                ));
+            }
+        }
+        area
+    }
+
+    #[test]
+    fn watermarks_are_drawn_in_their_corner_when_saving() {
+        let mut image = Image::new(200, 100);
+        image.set_font_data([test_font()]).unwrap();
+
+        for corner in [
+            Corner::TopLeft,
+            Corner::TopRight,
+            Corner::BottomLeft,
+            Corner::BottomRight,
+        ] {
+            image.set_watermark("HHHH", corner, 1.0);
+            let ((left, top, right, bottom), brightest) = lit_area(&image).unwrap();
+            let left_side = matches!(corner, Corner::TopLeft | Corner::BottomLeft);
+            let top_side = matches!(corner, Corner::TopLeft | Corner::TopRight);
+            // Within the margin and a line of text of the corner.
+            assert_eq!(left_side, left < 10 && right < 100, "{corner:?}");
+            assert_eq!(!left_side, right >= 190 && left > 100, "{corner:?}");
+            assert_eq!(top_side, top < 10 && bottom < 20, "{corner:?}");
+            assert_eq!(!top_side, bottom >= 85 && top > 75, "{corner:?}");
+            // Grey, though thin strokes don't quite cover a whole pixel.
+            assert!((96..=128).contains(&brightest), "{brightest}");
+        }
+        assert!(image.element_ids().is_empty());
+
+        image.set_watermark("HHHH", Corner::TopLeft, 1.0);
+        let (_, solid) = lit_area(&image).unwrap();
+        image.set_watermark("HHHH", Corner::TopLeft, 0.5);
+        let (_, brightest) = lit_area(&image).unwrap();
+        assert!(brightest.abs_diff(solid / 2) <= 1, "{brightest}");
+
+        image.clear_watermark();
+        assert_eq!(lit_area(&image), None);
+    }
+
+    #[test]
+    fn watermarks_are_in_saved_svgs_and_pngs() {
+        let mut image = Image::new(200, 100);
+        image.set_font_data([test_font()]).unwrap();
+        image.draw_simple_line(10, 50, 90, 100, COLORS[1]).unwrap();
+        let plain_svg = image.svg_string(&SvgOptions::default());
+        let plain_png = image.png_bytes().unwrap();
+
+        image.set_watermark("HHHH", Corner::BottomRight, 0.5);
+        let svg = image.svg_string(&SvgOptions::default());
+        assert_eq!(
+            svg.matches("<path").count(),
+            plain_svg.matches("<path").count() + 1
+        );
+        assert!(svg.contains(r#"fill-opacity="0.5""#), "{svg}");
+        let path = std::env::temp_dir().join("unsvg-watermark-test.svg");
+        image.save_svg(&path).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), svg);
+
+        let png = image.png_bytes().unwrap();
+        assert_ne!(png, plain_png);
+        let path = std::env::temp_dir().join("unsvg-watermark-test.png");
+        image.save_png(&path).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), png);
+    }
+
+    #[test]
+    fn saving_without_a_watermark_is_unchanged() {
+        let mut image = Image::new(200, 100);
+        image.draw_simple_line(10, 50, 90, 100, COLORS[1]).unwrap();
+        let (svg, png) = (
+            image.svg_string(&SvgOptions::default()),
+            image.png_bytes().unwrap(),
+        );
+
+        image.set_font_data([test_font()]).unwrap();
+        image.set_watermark("HHHH", Corner::BottomRight, 0.5);
+        image.clear_watermark();
+        assert_eq!(image.svg_string(&SvgOptions::default()), svg);
+        assert_eq!(image.png_bytes().unwrap(), png);
+    }
+}